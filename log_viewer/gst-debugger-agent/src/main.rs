@@ -0,0 +1,42 @@
+//! Runs a pipeline on the target device and streams its metrics and events
+//! over gRPC to a viewer started with `gst-debugger-gui connect HOST:PORT`.
+
+use std::net::SocketAddr;
+
+use clap::Parser;
+use gst_debugger::remote::{self, AgentInfo};
+use gst_debugger::{prepare, spawn, LaunchArgs, SecurityArgs};
+
+#[derive(Parser, Debug)]
+#[command(name = "gst-debugger-agent")]
+struct AgentArgs {
+    #[command(flatten)]
+    launch: LaunchArgs,
+
+    /// Address to accept viewers on
+    #[arg(long, default_value = "0.0.0.0:50051")]
+    listen: SocketAddr,
+
+    #[command(flatten)]
+    security: SecurityArgs,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = AgentArgs::parse();
+    let result = async {
+        let config = prepare(&args.launch).await?;
+        let info = AgentInfo {
+            pipeline: args.launch.pipeline.clone(),
+            tracing: args.launch.tracing.clone(),
+            restart_on_failure: config.restart_on_failure,
+        };
+        let (engine, output) = spawn(args.launch.pipeline.clone(), config);
+        remote::serve_agent(args.listen, &args.security, info, engine, output).await
+    }
+    .await;
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::parse::{format_clock_time, parse_duration_to_ns};
+use crate::rules::{element_matches, parse_threshold_ns, RuleResult};
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+        }
+    }
+}
+
+/// A condition in the alert language, `metric(element) op value [for duration]`,
+/// e.g. `fps(videosink) < 25 for 5s`. Interlatency takes `from->to` in place
+/// of the element, e.g. `interlatency(encoder->muxer) > 50ms`. Element names
+/// are matched like threshold rules: as a prefix, or a `*`/`?` pattern.
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    pub source: String,
+    pub metric: Metric,
+    pub element: String,
+    pub to: Option<String>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires.
+    pub duration_ns: u64,
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid alert `{}`: {}", source, reason);
+
+        let text = source.trim();
+        let (Some(open), Some(close)) = (text.find('('), text.find(')')) else {
+            return Err(invalid("expected `metric(element) < value`"));
+        };
+        if close < open {
+            return Err(invalid("expected `metric(element) < value`"));
+        }
+
+        let name = text[..open].trim();
+        let metric = Metric::from_name(name)
+            .ok_or_else(|| invalid(&format!("unknown metric `{}`", name)))?;
+
+        let target = text[open + 1..close].trim();
+        let (element, to) = match target.split_once("->") {
+            Some(_) if metric != Metric::Interlatency => {
+                return Err(invalid("only interlatency takes `from->to`"));
+            }
+            Some((from, to)) => (from.trim().to_string(), Some(to.trim().to_string())),
+            None => (target.to_string(), None),
+        };
+
+        let rest = text[close + 1..].trim();
+        let (comparison, rest) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ]
+        .into_iter()
+        .find_map(|(operator, comparison)| Some((comparison, rest.strip_prefix(operator)?)))
+        .ok_or_else(|| invalid("expected one of `<`, `<=`, `>`, `>=` after the element"))?;
+
+        let (value, duration) = match rest.split_once(" for ") {
+            Some((value, duration)) => (value.trim(), Some(duration.trim())),
+            None => (rest.trim(), None),
+        };
+        let threshold = match metric {
+            Metric::ProcTime | Metric::Interlatency | Metric::Jitter => parse_threshold_ns(value),
+            Metric::Bitrate | Metric::Framerate => value.parse().ok(),
+        }
+        .ok_or_else(|| invalid(&format!("invalid value `{}`", value)))?;
+        let duration_ns = match duration {
+            Some(duration) => parse_threshold_ns(duration)
+                .ok_or_else(|| invalid(&format!("invalid duration `{}`", duration)))?
+                as u64,
+            None => 0,
+        };
+
+        Ok(AlertRule {
+            source: text.to_string(),
+            metric,
+            element,
+            to,
+            comparison,
+            threshold,
+            duration_ns,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for AlertRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl AlertRule {
+    /// The samples of every element, or element pair for `from->to`
+    /// interlatency, the alert watches.
+    fn matching_series(&self, store: &MetricStore) -> BTreeMap<String, Vec<(u64, f64)>> {
+        let mut series: BTreeMap<String, Vec<(u64, f64)>> = BTreeMap::new();
+        if let Some(to) = &self.to {
+            for latency in store.latencies() {
+                if element_matches(&self.element, &latency.from)
+                    && element_matches(to, &latency.to)
+                    && let Some(ns) = parse_duration_to_ns(&latency.time)
+                {
+                    series
+                        .entry(format!("{}->{}", latency.from, latency.to))
+                        .or_default()
+                        .push((latency.timestamp_ns, ns as f64));
+                }
+            }
+            return series;
+        }
+
+        for stats in store.element_stats() {
+            if stats.summary(self.metric).count > 0 && element_matches(&self.element, &stats.element) {
+                let values = store.series(&stats.element, self.metric);
+                series.insert(stats.element, values);
+            }
+        }
+        series
+    }
+
+    /// Check the condition over the whole session. The alert fails if the
+    /// condition ever held for the required duration, and is still active if
+    /// it holds up to the last sample.
+    pub fn evaluate(&self, store: &MetricStore) -> Vec<RuleResult> {
+        self.matching_series(store)
+            .into_iter()
+            .map(|(element, values)| {
+                let mut since = None;
+                let mut fired_at = None;
+                let mut latest_firing = None;
+                let mut last_ns = 0;
+                for (timestamp_ns, value) in values {
+                    last_ns = timestamp_ns;
+                    if !self.comparison.holds(value, self.threshold) {
+                        since = None;
+                        continue;
+                    }
+                    let start = *since.get_or_insert(timestamp_ns);
+                    let held = timestamp_ns.saturating_sub(start) >= self.duration_ns;
+                    if held && latest_firing.is_none_or(|(fired_ns, _)| fired_ns < start) {
+                        fired_at.get_or_insert(timestamp_ns);
+                        latest_firing = Some((timestamp_ns, value));
+                    }
+                }
+                let active = since.is_some_and(|start| last_ns.saturating_sub(start) >= self.duration_ns);
+
+                let failure = fired_at.map(|fired_ns| {
+                    format!(
+                        "{}: `{}` fired at {}{}",
+                        element,
+                        self.source,
+                        format_clock_time(fired_ns),
+                        if active { " and is still active" } else { "" }
+                    )
+                });
+                RuleResult {
+                    element,
+                    check: self.source.clone(),
+                    failure,
+                    active,
+                    metric: self.metric,
+                    threshold: self.threshold,
+                    value: latest_firing.map(|(_, value)| value),
+                    timestamp_ns: latest_firing.map_or(last_ns, |(fired_ns, _)| fired_ns),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TracingData;
+    use crate::testing::{framerate, store, SECOND_NS};
+
+    fn fps(second: u64, fps: f64) -> TracingData {
+        framerate("videosink0", second * SECOND_NS, fps)
+    }
+
+    #[test]
+    fn alerts_parse_from_the_alert_language() {
+        let rule: AlertRule = "fps(videosink) < 25 for 5s".parse().unwrap();
+        assert_eq!(rule.metric, Metric::Framerate);
+        assert_eq!(rule.element, "videosink");
+        assert_eq!(rule.comparison, Comparison::Less);
+        assert_eq!(rule.threshold, 25.0);
+        assert_eq!(rule.duration_ns, 5_000_000_000);
+
+        let rule: AlertRule = "interlatency(enc->mux) >= 50ms".parse().unwrap();
+        assert_eq!(rule.to.as_deref(), Some("mux"));
+        assert_eq!(rule.comparison, Comparison::GreaterOrEqual);
+        assert_eq!(rule.threshold, 50_000_000.0);
+
+        for invalid in ["fps videosink < 25", "speed(x) < 1", "fps(a->b) < 25", "fps(x) = 25"] {
+            assert!(invalid.parse::<AlertRule>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn alerts_fire_once_the_condition_held_long_enough() {
+        let rule: AlertRule = "fps(videosink) < 25 for 2s".parse().unwrap();
+        // Too short a dip, then one long enough that recovers.
+        let dips = [30.0, 20.0, 30.0, 20.0, 20.0, 20.0, 30.0];
+        let mut store = store((0..).zip(dips).map(|(second, rate)| fps(second, rate)));
+        let results = rule.evaluate(&store);
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed());
+        assert!(!results[0].active);
+        assert_eq!(results[0].timestamp_ns, 5_000_000_000);
+
+        store.push_sample(fps(7, 10.0));
+        store.push_sample(fps(9, 10.0));
+        let results = rule.evaluate(&store);
+        assert!(results[0].active);
+        assert_eq!(results[0].value, Some(10.0));
+    }
+}
@@ -0,0 +1,244 @@
+//! Anomaly detection without hand-set thresholds: each metric of each
+//! element is compared against a rolling baseline of its own recent mean
+//! and standard deviation.
+
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use crate::parse::format_clock_time;
+use crate::rules::{duration_ns, element_matches, metric_names, RuleResult};
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+/// Samples a baseline needs before deviations from it count.
+const MIN_BASELINE_SAMPLES: usize = 10;
+
+/// The `[anomaly]` table of a rules file:
+///
+/// ```toml
+/// [anomaly]
+/// metrics = ["fps", "proctime"]
+/// sigma = 3.0
+/// for = "2s"
+/// window = "60s"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnomalyDetector {
+    /// Elements watched, as in threshold rules. Every element when omitted.
+    #[serde(default)]
+    pub element: Option<String>,
+    /// Metrics watched. Every metric when omitted.
+    #[serde(default = "all_metrics", deserialize_with = "metric_names")]
+    pub metrics: Vec<Metric>,
+    /// How many standard deviations from the mean count as a deviation.
+    #[serde(default = "default_sigma")]
+    pub sigma: f64,
+    /// How long a deviation must last before it is reported.
+    #[serde(default = "default_duration", rename = "for", deserialize_with = "duration_ns")]
+    pub duration_ns: Option<f64>,
+    /// How much recent history the baseline covers.
+    #[serde(default = "default_window", rename = "window", deserialize_with = "duration_ns")]
+    pub window_ns: Option<f64>,
+}
+
+fn all_metrics() -> Vec<Metric> {
+    Metric::ALL.to_vec()
+}
+
+fn default_sigma() -> f64 {
+    3.0
+}
+
+fn default_duration() -> Option<f64> {
+    Some(2e9)
+}
+
+fn default_window() -> Option<f64> {
+    Some(60e9)
+}
+
+/// Mean and standard deviation of the samples within the window.
+#[derive(Default)]
+struct Baseline {
+    samples: VecDeque<(u64, f64)>,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl Baseline {
+    fn push(&mut self, timestamp_ns: u64, value: f64, window_ns: u64) {
+        self.samples.push_back((timestamp_ns, value));
+        self.sum += value;
+        self.sum_squares += value * value;
+        while let Some(&(oldest_ns, oldest)) = self.samples.front()
+            && timestamp_ns.saturating_sub(oldest_ns) > window_ns
+        {
+            self.samples.pop_front();
+            self.sum -= oldest;
+            self.sum_squares -= oldest * oldest;
+        }
+    }
+
+    fn mean_and_deviation(&self) -> Option<(f64, f64)> {
+        if self.samples.len() < MIN_BASELINE_SAMPLES {
+            return None;
+        }
+        let count = self.samples.len() as f64;
+        let mean = self.sum / count;
+        let variance = (self.sum_squares / count - mean * mean).max(0.0);
+        Some((mean, variance.sqrt()))
+    }
+}
+
+/// A deviation that started at `since_ns`, and the furthest it went.
+#[derive(Clone, Copy)]
+struct Deviation {
+    since_ns: u64,
+    mean: f64,
+    value: f64,
+    sigmas: f64,
+}
+
+impl AnomalyDetector {
+    /// What the detector watches, e.g. `* FPS, ProcTime beyond 3σ for 2s`.
+    pub fn describe(&self) -> String {
+        let metrics: Vec<&str> = self.metrics.iter().map(|metric| metric.label()).collect();
+        format!(
+            "{} {} beyond {}σ for {}s",
+            self.element.as_deref().unwrap_or("*"),
+            metrics.join(", "),
+            self.sigma,
+            self.duration_ns.unwrap_or(0.0) / 1e9
+        )
+    }
+
+    /// Follow every watched series through the session. While a series
+    /// deviates, its baseline is frozen so the anomaly does not become the
+    /// new normal; a deviation that lasts the required duration fails, and
+    /// is still active if it lasts up to the last sample.
+    pub fn evaluate(&self, store: &MetricStore) -> Vec<RuleResult> {
+        let stats = store.element_stats();
+        let mut results = Vec::new();
+        for &metric in &self.metrics {
+            for element in &stats {
+                let matches = self
+                    .element
+                    .as_deref()
+                    .is_none_or(|pattern| element_matches(pattern, &element.element));
+                if matches && element.summary(metric).count > 0 {
+                    let values = store.series(&element.element, metric);
+                    results.push(self.check(&element.element, metric, &values));
+                }
+            }
+        }
+        results
+    }
+
+    fn check(&self, element: &str, metric: Metric, values: &[(u64, f64)]) -> RuleResult {
+        let duration_ns = self.duration_ns.unwrap_or(0.0) as u64;
+        let window_ns = self.window_ns.unwrap_or(0.0) as u64;
+        let mut baseline = Baseline::default();
+        let mut deviation: Option<Deviation> = None;
+        let mut reported: Option<Deviation> = None;
+        let mut last_ns = 0;
+        for &(timestamp_ns, value) in values {
+            last_ns = timestamp_ns;
+            let sigmas = baseline.mean_and_deviation().and_then(|(mean, deviation)| {
+                // A perfectly flat baseline still tolerates 1% of noise.
+                let deviation = deviation.max(mean.abs() * 0.01).max(f64::EPSILON);
+                let sigmas = (value - mean) / deviation;
+                (sigmas.abs() > self.sigma).then_some((mean, sigmas))
+            });
+            let Some((mean, sigmas)) = sigmas else {
+                deviation = None;
+                baseline.push(timestamp_ns, value, window_ns);
+                continue;
+            };
+            let current = deviation.get_or_insert(Deviation {
+                since_ns: timestamp_ns,
+                mean,
+                value,
+                sigmas,
+            });
+            if sigmas.abs() > current.sigmas.abs() {
+                current.value = value;
+                current.sigmas = sigmas;
+            }
+            if timestamp_ns.saturating_sub(current.since_ns) >= duration_ns {
+                reported = Some(*current);
+            }
+        }
+        let active = deviation
+            .as_ref()
+            .is_some_and(|current| last_ns.saturating_sub(current.since_ns) >= duration_ns);
+
+        let check = format!("{} within {}σ of its baseline", metric.label(), self.sigma);
+        let failure = reported.as_ref().map(|reported| {
+            format!(
+                "{}: {} {} was {:.1}σ {} its baseline of {} from {}{}",
+                element,
+                metric.label(),
+                metric.format(reported.value),
+                reported.sigmas.abs(),
+                if reported.sigmas < 0.0 { "below" } else { "above" },
+                metric.format(reported.mean),
+                format_clock_time(reported.since_ns),
+                if active { " and still is" } else { "" }
+            )
+        });
+        RuleResult {
+            element: element.to_string(),
+            check,
+            failure,
+            active,
+            metric,
+            threshold: reported.as_ref().map_or(0.0, |reported| reported.mean),
+            value: reported.as_ref().map(|reported| reported.value),
+            timestamp_ns: reported.map_or(last_ns, |reported| reported.since_ns),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TracingData;
+    use crate::testing::{framerate, store, SECOND_NS};
+
+    fn fps(second: u64, fps: f64) -> TracingData {
+        framerate("videosink0", second * SECOND_NS, fps)
+    }
+
+    #[test]
+    fn lasting_deviations_from_the_baseline_are_reported() {
+        let detector = AnomalyDetector {
+            element: None,
+            metrics: vec![Metric::Framerate],
+            sigma: 3.0,
+            duration_ns: Some(2e9),
+            window_ns: Some(60e9),
+        };
+        let mut store = store((0..20).map(|second| fps(second, 30.0 + (second % 2) as f64 / 2.0)));
+        // A single outlier is too short to count.
+        store.push_sample(fps(20, 10.0));
+        store.push_sample(fps(21, 30.0));
+        assert!(detector.evaluate(&store)[0].passed());
+
+        for second in 22..26 {
+            store.push_sample(fps(second, 10.0));
+        }
+        let results = detector.evaluate(&store);
+        assert!(!results[0].passed());
+        assert!(results[0].active);
+        assert_eq!(results[0].value, Some(10.0));
+        assert_eq!(results[0].timestamp_ns, 22_000_000_000);
+        assert!((results[0].threshold - 30.2).abs() < 0.1);
+
+        store.push_sample(fps(26, 30.0));
+        let results = detector.evaluate(&store);
+        assert!(!results[0].passed());
+        assert!(!results[0].active);
+    }
+}
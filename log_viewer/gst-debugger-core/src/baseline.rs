@@ -0,0 +1,321 @@
+//! Per-element statistics of a known-good run, saved so later runs of the
+//! same pipeline can be compared against it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::RuleResult;
+use crate::stats::{Aggregate, Metric};
+use crate::store::MetricStore;
+
+/// Where baselines are saved by default, one file per pipeline.
+pub const BASELINE_DIR: &str = "gst_debugger_baselines";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BaselineSummary {
+    pub count: usize,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub pipeline: String,
+    /// Summaries by element, then by metric name such as `proctime`.
+    pub elements: BTreeMap<String, BTreeMap<String, BaselineSummary>>,
+}
+
+/// How far an average may get worse than the baseline, in percent, for one
+/// metric or, without one, for every metric that has no tolerance of its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    pub metric: Option<Metric>,
+    pub percent: f64,
+}
+
+impl Tolerance {
+    /// Parse `10` for every metric or `proctime=25` for one.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (metric, percent) = match value.split_once('=') {
+            Some((name, percent)) => {
+                let metric = Metric::from_name(name.trim())
+                    .ok_or_else(|| format!("unknown metric `{}`", name.trim()))?;
+                (Some(metric), percent)
+            }
+            None => (None, value),
+        };
+        let percent = percent
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| format!("expected a percentage, got `{}`", percent.trim()))?;
+        Ok(Tolerance { metric, percent })
+    }
+
+    /// The tolerance for `metric`, `None` when it is not compared.
+    pub fn for_metric(tolerances: &[Tolerance], metric: Metric) -> Option<f64> {
+        let find = |wanted: Option<Metric>| {
+            tolerances.iter().rev().find(|tolerance| tolerance.metric == wanted)
+        };
+        find(Some(metric)).or_else(|| find(None)).map(|tolerance| tolerance.percent)
+    }
+}
+
+/// How a run's average compares with the baseline's.
+#[derive(Debug, Clone, Copy)]
+pub struct BaselineDelta {
+    pub before: f64,
+    pub after: f64,
+    /// Change relative to the baseline, in percent.
+    pub percent: f64,
+    /// Whether the change is for the worse, e.g. a lower frame rate.
+    pub worse: bool,
+}
+
+/// FNV-1a, so the file name of a pipeline's baseline stays the same across
+/// builds.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Baseline {
+    pub fn capture(pipeline: &str, store: &MetricStore) -> Self {
+        let elements = store
+            .element_stats()
+            .into_iter()
+            .map(|stats| {
+                let metrics = Metric::ALL
+                    .into_iter()
+                    .filter_map(|metric| {
+                        let summary = stats.summary(metric);
+                        let summary = BaselineSummary {
+                            count: summary.count,
+                            avg: summary.get(Aggregate::Avg)?,
+                            min: summary.get(Aggregate::Min)?,
+                            max: summary.get(Aggregate::Max)?,
+                        };
+                        Some((metric.name().to_string(), summary))
+                    })
+                    .collect();
+                (stats.element, metrics)
+            })
+            .collect();
+        Baseline {
+            pipeline: pipeline.to_string(),
+            elements,
+        }
+    }
+
+    /// The default file for the baseline of `pipeline`. Pipelines that only
+    /// differ in whitespace share it.
+    pub fn path_for(pipeline: &str) -> PathBuf {
+        let words: Vec<&str> = pipeline.split_whitespace().collect();
+        Path::new(BASELINE_DIR).join(format!("{:016x}.toml", fnv1a(&words.join(" "))))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        toml::from_str(&text)
+            .map_err(|err| format!("Invalid baseline in {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self)
+            .map_err(|err| format!("Failed to serialize the baseline: {}", err))?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| format!("Failed to create {}: {}", dir.display(), err))?;
+        }
+        std::fs::write(path, text)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+    }
+
+    /// How `avg`, an element's average of `metric` in this run, compares
+    /// with the baseline. `None` when the baseline has no such average.
+    pub fn delta(&self, element: &str, metric: Metric, avg: f64) -> Option<BaselineDelta> {
+        let before = self.elements.get(element)?.get(metric.name())?.avg;
+        let percent = if before == 0.0 {
+            0.0
+        } else {
+            (avg - before) / before.abs() * 100.0
+        };
+        let worse = if metric.higher_is_better() {
+            avg < before
+        } else {
+            avg > before
+        };
+        Some(BaselineDelta {
+            before,
+            after: avg,
+            percent,
+            worse,
+        })
+    }
+
+    /// Compare every average of the baseline that has a tolerance with the
+    /// run's. A change for the worse by more than the tolerance fails, and
+    /// so does an element that no longer reports a metric.
+    pub fn compare(&self, store: &MetricStore, tolerances: &[Tolerance]) -> Vec<RuleResult> {
+        let now_ns = store.latest_timestamp();
+        let stats = store.element_stats();
+        let mut results = Vec::new();
+        for (element, metrics) in &self.elements {
+            for metric in Metric::ALL {
+                let (Some(before), Some(tolerance)) = (
+                    metrics.get(metric.name()),
+                    Tolerance::for_metric(tolerances, metric),
+                ) else {
+                    continue;
+                };
+                let check = format!("avg {} within {}% of the baseline", metric.label(), tolerance);
+                let avg = stats
+                    .iter()
+                    .find(|stats| &stats.element == element)
+                    .and_then(|stats| stats.summary(metric).get(Aggregate::Avg));
+                let Some(avg) = avg else {
+                    results.push(RuleResult {
+                        element: element.clone(),
+                        check,
+                        failure: Some(format!(
+                            "{}: no {} samples, the baseline averaged {}",
+                            element,
+                            metric.label(),
+                            metric.format(before.avg)
+                        )),
+                        active: true,
+                        metric,
+                        threshold: before.avg,
+                        value: None,
+                        timestamp_ns: now_ns,
+                    });
+                    continue;
+                };
+                let Some(delta) = self.delta(element, metric, avg) else {
+                    continue;
+                };
+                let regressed = delta.worse && delta.percent.abs() > tolerance;
+                let failure = regressed.then(|| {
+                    format!(
+                        "{}: avg {} {} is {:.1}% worse than the baseline of {}",
+                        element,
+                        metric.label(),
+                        metric.format(delta.after),
+                        delta.percent.abs(),
+                        metric.format(delta.before)
+                    )
+                });
+                results.push(RuleResult {
+                    element: element.clone(),
+                    check,
+                    failure,
+                    active: regressed,
+                    metric,
+                    threshold: delta.before,
+                    value: Some(delta.after),
+                    timestamp_ns: now_ns,
+                });
+            }
+        }
+        results
+    }
+}
+
+/// The results of [`Baseline::compare`] as a table of every compared
+/// average with how it changed, regressions marked with `*`.
+pub fn format_baseline_diff(results: &[RuleResult]) -> String {
+    let width = results
+        .iter()
+        .map(|result| result.element.len())
+        .chain(["Element".len()])
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "{:<width$}  {:<12}  {:>16}  {:>16}  {:>8}\n",
+        "Element",
+        "Metric",
+        "Baseline",
+        "This run",
+        "Change",
+        width = width
+    );
+    for result in results {
+        let metric = result.metric;
+        let before = result.threshold;
+        let (after, change) = match result.value {
+            Some(after) if before != 0.0 => (
+                metric.format(after),
+                format!("{:+.1}%", (after - before) / before.abs() * 100.0),
+            ),
+            Some(after) => (metric.format(after), "-".to_string()),
+            None => ("-".to_string(), "missing".to_string()),
+        };
+        table.push_str(&format!(
+            "{:<width$}  {:<12}  {:>16}  {:>16}  {:>8}{}\n",
+            result.element,
+            metric.label(),
+            metric.format(before),
+            after,
+            change,
+            if result.passed() { "" } else { "  *" },
+            width = width
+        ));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, proctime, SECOND_NS};
+
+    /// A sample a second of `enc0` with each ProcTime.
+    fn store(proctimes_ns: &[u64]) -> MetricStore {
+        testing::store(
+            (0..)
+                .zip(proctimes_ns)
+                .map(|(second, &proctime_ns)| proctime("enc0", second * SECOND_NS, proctime_ns)),
+        )
+    }
+
+    #[test]
+    fn tolerances_parse_for_every_or_one_metric() {
+        let every = Tolerance::parse("10").unwrap();
+        assert_eq!(every, Tolerance { metric: None, percent: 10.0 });
+        let proctime = Tolerance::parse("proctime = 25%").unwrap();
+        assert_eq!(proctime, Tolerance { metric: Some(Metric::ProcTime), percent: 25.0 });
+        assert!(Tolerance::parse("speed=5").is_err());
+        assert!(Tolerance::parse("lots").is_err());
+
+        let tolerances = [every, proctime];
+        assert_eq!(Tolerance::for_metric(&tolerances, Metric::ProcTime), Some(25.0));
+        assert_eq!(Tolerance::for_metric(&tolerances, Metric::Framerate), Some(10.0));
+        assert_eq!(Tolerance::for_metric(&[proctime], Metric::Framerate), None);
+    }
+
+    #[test]
+    fn averages_worse_beyond_the_tolerance_fail() {
+        let baseline = Baseline::capture("videotestsrc ! x264enc name=enc0", &store(&[10_000_000]));
+        let slower = store(&[12_000_000, 14_000_000]);
+
+        let tolerance = [Tolerance::parse("proctime=20").unwrap()];
+        let results = baseline.compare(&slower, &tolerance);
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed());
+        assert_eq!(results[0].value, Some(13_000_000.0));
+
+        let results = baseline.compare(&slower, &[Tolerance::parse("proctime=50").unwrap()]);
+        assert!(results[0].passed());
+        let results = baseline.compare(&store(&[8_000_000]), &tolerance);
+        assert!(results[0].passed());
+
+        let results = baseline.compare(&MetricStore::default(), &tolerance);
+        assert!(!results[0].passed());
+        assert_eq!(results[0].value, None);
+    }
+}
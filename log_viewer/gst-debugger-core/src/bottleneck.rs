@@ -0,0 +1,301 @@
+//! Triage of a recorded log: where the time goes, where buffers wait, when
+//! elements went quiet, which elements share a thread and how the caps
+//! changed along the way.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::model::StreamingThread;
+use crate::parse::{format_clock_time, parse_duration_to_ns};
+use crate::startup::{first_buffers, FirstBuffer};
+use crate::stats::{Aggregate, Metric, Summary};
+use crate::store::MetricStore;
+
+/// How many elements and links the report ranks.
+const RANKED: usize = 10;
+/// The shortest gap between an element's samples that counts as a stall.
+const MIN_STALL_NS: u64 = 2_000_000_000;
+/// How many of its usual intervals an element must miss to stall.
+const STALL_INTERVALS: u64 = 10;
+/// Width of the longest bar in the text waterfall of first buffers.
+const WATERFALL_WIDTH: usize = 40;
+
+/// Median, tail percentiles and maximum of a timing, in nanoseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50_ns: f64,
+    pub p95_ns: f64,
+    pub p99_ns: f64,
+    pub max_ns: f64,
+}
+
+impl Percentiles {
+    fn of(summary: &Summary) -> Option<Self> {
+        Some(Percentiles {
+            count: summary.count,
+            p50_ns: summary.get(Aggregate::P50)?,
+            p95_ns: summary.get(Aggregate::P95)?,
+            p99_ns: summary.get(Aggregate::P99)?,
+            max_ns: summary.get(Aggregate::Max)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowElement {
+    pub element: String,
+    pub proctime: Percentiles,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowLink {
+    pub from: String,
+    pub to: String,
+    pub interlatency: Percentiles,
+}
+
+/// A stretch in which an element reported no samples.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stall {
+    pub element: String,
+    pub from_ns: u64,
+    pub to_ns: u64,
+    /// Whether the element never reported again.
+    pub stopped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapsChange {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub pad: String,
+    pub caps: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BottleneckReport {
+    pub duration_ns: u64,
+    pub samples: usize,
+    pub latencies: usize,
+    /// When the first buffer reached each element, from the start of the log.
+    pub startup: Vec<FirstBuffer>,
+    /// Elements by p95 processing time, slowest first.
+    pub slowest_elements: Vec<SlowElement>,
+    /// Links by p95 interlatency, slowest first.
+    pub worst_links: Vec<SlowLink>,
+    pub stalls: Vec<Stall>,
+    /// Elements by the streaming thread that ran them, busiest first.
+    pub threads: Vec<StreamingThread>,
+    pub caps_changes: Vec<CapsChange>,
+}
+
+impl BottleneckReport {
+    pub fn new(
+        store: &MetricStore,
+        threads: Vec<StreamingThread>,
+        caps_changes: Vec<CapsChange>,
+    ) -> Self {
+        let start_ns = store.earliest_timestamp();
+        let end_ns = store.latest_timestamp();
+
+        let mut slowest_elements: Vec<SlowElement> = store
+            .element_stats()
+            .into_iter()
+            .filter_map(|stats| {
+                let proctime = Percentiles::of(stats.summary(Metric::ProcTime))?;
+                Some(SlowElement {
+                    element: stats.element,
+                    proctime,
+                })
+            })
+            .collect();
+        slowest_elements.sort_by(|a, b| b.proctime.p95_ns.total_cmp(&a.proctime.p95_ns));
+        slowest_elements.truncate(RANKED);
+
+        let mut links: BTreeMap<(String, String), Summary> = BTreeMap::new();
+        for latency in store.latencies() {
+            if let Some(ns) = parse_duration_to_ns(&latency.time) {
+                let key = (latency.from.clone(), latency.to.clone());
+                links.entry(key).or_default().add(ns as f64);
+            }
+        }
+        let mut worst_links: Vec<SlowLink> = links
+            .into_iter()
+            .filter_map(|((from, to), summary)| {
+                Some(SlowLink {
+                    from,
+                    to,
+                    interlatency: Percentiles::of(&summary)?,
+                })
+            })
+            .collect();
+        worst_links.sort_by(|a, b| b.interlatency.p95_ns.total_cmp(&a.interlatency.p95_ns));
+        worst_links.truncate(RANKED);
+
+        BottleneckReport {
+            duration_ns: end_ns.saturating_sub(start_ns),
+            samples: store.samples().len(),
+            latencies: store.latencies().len(),
+            startup: first_buffers(store, 0),
+            slowest_elements,
+            worst_links,
+            stalls: find_stalls(store, end_ns),
+            threads,
+            caps_changes,
+        }
+    }
+
+    /// The report as plain text for terminals and issue comments.
+    pub fn format_text(&self) -> String {
+        let mut text = String::new();
+        let ms = |ns: f64| format!("{:.2} ms", ns / 1e6);
+
+        text.push_str("Time to first buffer\n");
+        if self.startup.is_empty() {
+            text.push_str("  no per-buffer samples (needs proctime or interlatency)\n");
+        }
+        let last_ns = self.startup.last().map_or(0, |buffer| buffer.after_ns).max(1);
+        for buffer in &self.startup {
+            let width = (buffer.after_ns as f64 / last_ns as f64 * WATERFALL_WIDTH as f64).round();
+            text.push_str(&format!(
+                "  {:<24} {:>10}  {}\n",
+                buffer.element,
+                ms(buffer.after_ns as f64),
+                "█".repeat((width as usize).max(1))
+            ));
+        }
+
+        text.push_str("\nSlowest elements (ProcTime)\n");
+        if self.slowest_elements.is_empty() {
+            text.push_str("  no proctime samples\n");
+        }
+        for slow in &self.slowest_elements {
+            let p = &slow.proctime;
+            text.push_str(&format!(
+                "  {:<24} p50 {:>10}  p95 {:>10}  p99 {:>10}  max {:>10}\n",
+                slow.element,
+                ms(p.p50_ns),
+                ms(p.p95_ns),
+                ms(p.p99_ns),
+                ms(p.max_ns)
+            ));
+        }
+
+        text.push_str("\nWorst links (interlatency)\n");
+        if self.worst_links.is_empty() {
+            text.push_str("  no interlatency samples\n");
+        }
+        for link in &self.worst_links {
+            let p = &link.interlatency;
+            text.push_str(&format!(
+                "  {:<24} p50 {:>10}  p95 {:>10}  p99 {:>10}  max {:>10}\n",
+                format!("{} -> {}", link.from, link.to),
+                ms(p.p50_ns),
+                ms(p.p95_ns),
+                ms(p.p99_ns),
+                ms(p.max_ns)
+            ));
+        }
+
+        text.push_str("\nStalls\n");
+        if self.stalls.is_empty() {
+            text.push_str("  none\n");
+        }
+        for stall in &self.stalls {
+            let seconds = (stall.to_ns - stall.from_ns) as f64 / 1e9;
+            if stall.stopped {
+                text.push_str(&format!(
+                    "  {} stopped reporting at {}, {:.1} s before the end\n",
+                    stall.element,
+                    format_clock_time(stall.from_ns),
+                    seconds
+                ));
+            } else {
+                text.push_str(&format!(
+                    "  {} reported nothing from {} to {} ({:.1} s)\n",
+                    stall.element,
+                    format_clock_time(stall.from_ns),
+                    format_clock_time(stall.to_ns),
+                    seconds
+                ));
+            }
+        }
+
+        text.push_str("\nStreaming threads\n");
+        if self.threads.is_empty() {
+            text.push_str("  unknown (needs proctime or interlatency with the thread column)\n");
+        }
+        for thread in &self.threads {
+            let heavy = thread.elements.iter().filter(|element| element.heavy).count();
+            text.push_str(&format!("  {}", thread.thread));
+            if heavy > 1 {
+                text.push_str(&format!("  {} heavy elements share this thread", heavy));
+            }
+            text.push('\n');
+            for element in &thread.elements {
+                text.push_str(&format!(
+                    "    {:<22} {:>5.1}% busy{}\n",
+                    element.element,
+                    element.busy * 100.0,
+                    if element.heavy { "  heavy" } else { "" }
+                ));
+            }
+        }
+
+        text.push_str("\nCaps changes\n");
+        if self.caps_changes.is_empty() {
+            text.push_str("  none recorded (the log needs gst-launch-1.0 -v output)\n");
+        }
+        for change in &self.caps_changes {
+            text.push_str(&format!(
+                "  {} {}:{} {}\n",
+                format_clock_time(change.timestamp_ns),
+                change.element,
+                change.pad,
+                change.caps
+            ));
+        }
+        text
+    }
+}
+
+/// Gaps in each element's samples much longer than its usual interval, and
+/// elements that fell silent well before the end of the log.
+fn find_stalls(store: &MetricStore, end_ns: u64) -> Vec<Stall> {
+    let mut timestamps: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for sample in store.samples() {
+        timestamps.entry(&sample.element).or_default().push(sample.timestamp_ns);
+    }
+    let mut stalls = Vec::new();
+    for (element, mut times) in timestamps {
+        times.sort_unstable();
+        let mut intervals: Vec<u64> = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        intervals.sort_unstable();
+        let usual_ns = intervals.get(intervals.len() / 2).copied().unwrap_or(0);
+        let limit_ns = (usual_ns * STALL_INTERVALS).max(MIN_STALL_NS);
+        for pair in times.windows(2) {
+            if pair[1] - pair[0] > limit_ns {
+                stalls.push(Stall {
+                    element: element.to_string(),
+                    from_ns: pair[0],
+                    to_ns: pair[1],
+                    stopped: false,
+                });
+            }
+        }
+        if let Some(&last_ns) = times.last()
+            && end_ns.saturating_sub(last_ns) > limit_ns
+        {
+            stalls.push(Stall {
+                element: element.to_string(),
+                from_ns: last_ns,
+                to_ns: end_ns,
+                stopped: true,
+            });
+        }
+    }
+    stalls.sort_by_key(|stall| stall.from_ns);
+    stalls
+}
@@ -0,0 +1,368 @@
+//! An editable pipeline for the GUI's builder mode: elements with their
+//! properties and the links between them, converted from and to gst-launch
+//! descriptions.
+
+use std::collections::HashMap;
+
+use crate::pipeline::split_pipeline_args;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DraftElement {
+    /// The factory, or the caps of a capsfilter written as caps.
+    pub factory: String,
+    pub name: String,
+    pub properties: Vec<(String, String)>,
+}
+
+impl DraftElement {
+    pub fn is_caps(&self) -> bool {
+        self.factory.contains('/')
+    }
+
+    /// The element as written in a description, with its name when asked
+    /// to. Caps are quoted when they hold spaces or commas.
+    fn describe(&self, with_name: bool) -> String {
+        if self.is_caps() {
+            let spaced = self.factory.contains(|c: char| c.is_whitespace() || c == ',');
+            return if spaced { quoted(&self.factory) } else { self.factory.clone() };
+        }
+        let mut description = self.factory.clone();
+        if with_name {
+            description.push_str(&format!(" name={}", quote(&self.name)));
+        }
+        for (key, value) in &self.properties {
+            description.push_str(&format!(" {}={}", key, quote(value)));
+        }
+        description
+    }
+}
+
+/// A link between two elements, optionally between named pads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DraftLink {
+    pub from: usize,
+    pub to: usize,
+    pub from_pad: Option<String>,
+    pub to_pad: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineDraft {
+    pub elements: Vec<DraftElement>,
+    pub links: Vec<DraftLink>,
+}
+
+/// The name GStreamer would give the next element made by `factory`, e.g.
+/// `queue1` when `queue0` is `taken` already.
+fn free_name<'a>(factory: &str, taken: impl Iterator<Item = &'a String> + Clone) -> String {
+    let base = if factory.contains('/') { "capsfilter" } else { factory };
+    (0..)
+        .map(|index| format!("{}{}", base, index))
+        .find(|name| taken.clone().all(|taken| taken != name))
+        .unwrap_or_default()
+}
+
+/// Quote a property value that would otherwise split into several tokens.
+fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '!' || c == '"') {
+        quoted(value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// How a chain of the written description starts: with its first element,
+/// or with a reference to the element a link comes from.
+enum ChainStart {
+    Element,
+    Link(usize),
+}
+
+/// One end of a link while parsing: an element and maybe a pad, or a
+/// `name.pad` reference to an element that may only appear later.
+#[derive(Clone)]
+enum Endpoint {
+    Element(usize),
+    Reference(String, Option<String>),
+}
+
+impl PipelineDraft {
+    /// Parse a gst-launch description. Elements get the name GStreamer
+    /// would give them when the description does not set one.
+    pub fn parse(pipeline: &str) -> PipelineDraft {
+        let mut draft = PipelineDraft::default();
+        let mut links: Vec<(Endpoint, Endpoint)> = Vec::new();
+        let mut previous: Option<Endpoint> = None;
+        let mut linking = false;
+
+        for token in split_pipeline_args(pipeline) {
+            if token == "!" {
+                linking = true;
+                continue;
+            }
+            let (key, _) = token.split_once('=').unwrap_or((&token, ""));
+            let is_property = token.contains('=') && !key.contains(['/', ',']);
+            if is_property && !linking {
+                if let Some(Endpoint::Element(index)) = previous {
+                    let (key, value) = token.split_once('=').unwrap_or_default();
+                    let element = &mut draft.elements[index];
+                    if key == "name" {
+                        element.name = value.to_string();
+                    } else {
+                        element.properties.push((key.to_string(), value.to_string()));
+                    }
+                }
+                continue;
+            }
+
+            let endpoint = if !token.contains(['/', '=']) && token.contains('.') {
+                let (name, pad) = token.split_once('.').unwrap_or_default();
+                let pad = Some(pad.to_string()).filter(|pad| !pad.is_empty());
+                Endpoint::Reference(name.to_string(), pad)
+            } else {
+                Endpoint::Element(draft.add_element(&token))
+            };
+            let from = previous.replace(endpoint.clone());
+            if linking && let Some(from) = from {
+                links.push((from, endpoint));
+            }
+            linking = false;
+        }
+
+        // References can name elements that come later in the description,
+        // so they are resolved once every element has its name.
+        let names: HashMap<String, usize> = draft
+            .elements
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (element.name.clone(), index))
+            .collect();
+        let resolve = |endpoint: Endpoint| match endpoint {
+            Endpoint::Element(index) => Some((index, None)),
+            Endpoint::Reference(name, pad) => names.get(&name).map(|&index| (index, pad)),
+        };
+        for (from, to) in links {
+            if let (Some((from, from_pad)), Some((to, to_pad))) = (resolve(from), resolve(to)) {
+                draft.links.push(DraftLink {
+                    from,
+                    to,
+                    from_pad,
+                    to_pad,
+                });
+            }
+        }
+        draft
+    }
+
+    /// Add an element made by `factory` with a name that is not taken yet,
+    /// e.g. `queue1` when there already is a `queue0`.
+    pub fn add_element(&mut self, factory: &str) -> usize {
+        let name = free_name(factory, self.elements.iter().map(|element| &element.name));
+        self.elements.push(DraftElement {
+            factory: factory.to_string(),
+            name,
+            properties: Vec::new(),
+        });
+        self.elements.len() - 1
+    }
+
+    pub fn remove_element(&mut self, index: usize) {
+        self.elements.remove(index);
+        self.links.retain(|link| link.from != index && link.to != index);
+        for link in &mut self.links {
+            if link.from > index {
+                link.from -= 1;
+            }
+            if link.to > index {
+                link.to -= 1;
+            }
+        }
+    }
+
+    /// Link the source of `from` to the sink of `to`, unless they already
+    /// are or the link would point back at `from`.
+    pub fn link(&mut self, from: usize, to: usize) {
+        if from == to || self.links.iter().any(|link| link.from == from && link.to == to) {
+            return;
+        }
+        self.links.push(DraftLink {
+            from,
+            to,
+            from_pad: None,
+            to_pad: None,
+        });
+    }
+
+    pub fn unlink(&mut self, from: usize, to: usize) {
+        self.links.retain(|link| link.from != from || link.to != to);
+    }
+
+    /// Write the draft as a gst-launch description. Linear runs become
+    /// `a ! b ! c` chains; branches, merges and links between named pads
+    /// refer to elements by name.
+    pub fn to_launch(&self) -> String {
+        let mut emitted = vec![false; self.elements.len()];
+        let mut used = vec![false; self.links.len()];
+        let mut chains = Vec::new();
+
+        let roots = (0..self.elements.len())
+            .filter(|&index| self.links.iter().all(|link| link.to != index))
+            .collect::<Vec<_>>();
+        for root in roots {
+            emitted[root] = true;
+            let elements = self.extend(vec![root], &mut emitted, &mut used);
+            chains.push((ChainStart::Element, elements, None));
+        }
+        loop {
+            // A branch into an element that is not written yet starts with
+            // a reference to the element it branches from.
+            if let Some(index) = (0..self.links.len()).find(|&index| {
+                let link = &self.links[index];
+                !used[index] && !emitted[link.to] && link.to_pad.is_none()
+            }) {
+                used[index] = true;
+                emitted[self.links[index].to] = true;
+                let elements = self.extend(vec![self.links[index].to], &mut emitted, &mut used);
+                chains.push((ChainStart::Link(index), elements, None));
+                continue;
+            }
+            // Elements on a cycle, or only reached through pad references.
+            if let Some(index) = emitted.iter().position(|emitted| !emitted) {
+                emitted[index] = true;
+                let elements = self.extend(vec![index], &mut emitted, &mut used);
+                chains.push((ChainStart::Element, elements, None));
+                continue;
+            }
+            break;
+        }
+        // What is left links two elements that are both written already. A
+        // chain that ends in the source element links on with a reference.
+        for index in (0..self.links.len()).filter(|&index| !used[index]) {
+            let link = &self.links[index];
+            let chain = chains.iter_mut().find(|(_, elements, end)| {
+                end.is_none() && elements.last() == Some(&link.from) && link.from_pad.is_none()
+            });
+            match chain {
+                Some((_, _, end)) => *end = Some(index),
+                None => chains.push((ChainStart::Link(index), Vec::new(), Some(index))),
+            }
+        }
+
+        let mut referenced = vec![false; self.elements.len()];
+        for (start, _, end) in &chains {
+            if let ChainStart::Link(index) = start {
+                referenced[self.links[*index].from] = true;
+            }
+            if let Some(index) = end {
+                referenced[self.links[*index].to] = true;
+            }
+        }
+
+        // Parsing the description again names the elements without a name
+        // in the order they are written, so every other name is written.
+        let mut names: Vec<&String> = Vec::new();
+        let mut text = Vec::new();
+        for (start, elements, end) in chains {
+            let mut parts = Vec::new();
+            if let ChainStart::Link(index) = start {
+                let link = &self.links[index];
+                parts.push(self.reference(link.from, &link.from_pad));
+            }
+            for index in elements {
+                let element = &self.elements[index];
+                let named = element.name != free_name(&element.factory, names.iter().copied());
+                parts.push(element.describe(referenced[index] || named));
+                names.push(&element.name);
+            }
+            if let Some(index) = end {
+                let link = &self.links[index];
+                parts.push(self.reference(link.to, &link.to_pad));
+            }
+            text.push(parts.join(" ! "));
+        }
+        text.join(" ")
+    }
+
+    /// Continue the chain in `elements` along pad-less links to elements
+    /// that are not written yet.
+    fn extend(
+        &self,
+        mut elements: Vec<usize>,
+        emitted: &mut [bool],
+        used: &mut [bool],
+    ) -> Vec<usize> {
+        while let Some(&last) = elements.last()
+            && let Some(index) = (0..self.links.len()).find(|&index| {
+                let link = &self.links[index];
+                !used[index]
+                    && link.from == last
+                    && !emitted[link.to]
+                    && link.from_pad.is_none()
+                    && link.to_pad.is_none()
+            })
+        {
+            used[index] = true;
+            emitted[self.links[index].to] = true;
+            elements.push(self.links[index].to);
+        }
+        elements
+    }
+
+    fn reference(&self, index: usize, pad: &Option<String>) -> String {
+        format!("{}.{}", self.elements[index].name, pad.as_deref().unwrap_or(""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(pipeline: &str) -> PipelineDraft {
+        let draft = PipelineDraft::parse(pipeline);
+        assert_eq!(PipelineDraft::parse(&draft.to_launch()), draft, "{}", draft.to_launch());
+        draft
+    }
+
+    #[test]
+    fn linear_chain_round_trips() {
+        let draft = round_trip("videotestsrc pattern=ball ! x264enc bitrate=512 ! fakesink");
+        assert_eq!(draft.elements.len(), 3);
+        assert_eq!(draft.links.len(), 2);
+        assert_eq!(draft.to_launch(), "videotestsrc pattern=ball ! x264enc bitrate=512 ! fakesink");
+    }
+
+    #[test]
+    fn branches_round_trip() {
+        let draft =
+            round_trip("videotestsrc ! tee name=t ! queue ! fakesink t. ! queue ! autovideosink");
+        assert_eq!(draft.elements.len(), 6);
+        assert_eq!(draft.links.len(), 5);
+    }
+
+    #[test]
+    fn explicit_names_are_kept() {
+        let draft = round_trip("videotestsrc name=camera ! queue name=queue3 ! fakesink");
+        let launch = draft.to_launch();
+        assert!(launch.contains("name=camera"), "{}", launch);
+        assert!(launch.contains("name=queue3"), "{}", launch);
+        assert!(!launch.contains("name=fakesink0"), "{}", launch);
+    }
+
+    #[test]
+    fn caps_with_spaces_round_trip() {
+        let draft = round_trip("videotestsrc ! \"video/x-raw, width=640, height=480\" ! fakesink");
+        assert_eq!(draft.elements[1].factory, "video/x-raw, width=640, height=480");
+        assert!(draft.to_launch().contains("\"video/x-raw, width=640, height=480\""));
+    }
+
+    #[test]
+    fn quoted_property_values_round_trip() {
+        let draft = round_trip("videotestsrc ! textoverlay text=\"hello world\" ! fakesink");
+        let overlay = &draft.elements[1];
+        assert_eq!(overlay.properties, vec![("text".to_string(), "hello world".to_string())]);
+    }
+}
@@ -0,0 +1,432 @@
+//! Comparison of the caps on both sides of a link that failed to
+//! negotiate, field by field, to point at the ones that do not intersect.
+
+use std::collections::HashMap;
+
+use crate::builder::{DraftElement, PipelineDraft};
+use crate::model::CapsMismatch;
+use crate::parse::{parse_caps_notify, parse_debug_line};
+
+/// A value of a caps field: a single value, a `[ min, max ]` range or a
+/// `{ a, b }` list.
+#[derive(Debug, Clone, PartialEq)]
+enum CapsValue {
+    Scalar(String),
+    Range(f64, f64),
+    List(Vec<CapsValue>),
+}
+
+/// Numbers, including fractions such as `30/1`, as one comparable value.
+fn number(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator: f64 = denominator.trim().parse().ok()?;
+            Some(numerator.trim().parse::<f64>().ok()? / denominator)
+        }
+        None => value.trim().parse().ok(),
+    }
+}
+
+/// Split `text` at `separator`, except inside brackets, braces and quotes.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '{' | '(' | '<' if !quoted => depth += 1,
+            ']' | '}' | ')' | '>' if !quoted => depth -= 1,
+            c if c == separator && depth == 0 && !quoted => {
+                parts.push(text[start..index].trim());
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+impl CapsValue {
+    fn parse(text: &str) -> CapsValue {
+        let mut text = text.trim();
+        // Drop the type, as in `(int)320`.
+        if text.starts_with('(')
+            && let Some(end) = text.find(')')
+        {
+            text = text[end + 1..].trim();
+        }
+        if let Some(inner) = text.strip_prefix('{').and_then(|text| text.strip_suffix('}')) {
+            let values = split_top_level(inner, ',').into_iter().map(CapsValue::parse);
+            return CapsValue::List(values.collect());
+        }
+        if let Some(inner) = text.strip_prefix('[').and_then(|text| text.strip_suffix(']')) {
+            let bounds = split_top_level(inner, ',');
+            if let (Some(min), Some(max)) = (
+                bounds.first().and_then(|bound| number(bound)),
+                bounds.get(1).and_then(|bound| number(bound)),
+            ) {
+                return CapsValue::Range(min, max);
+            }
+        }
+        CapsValue::Scalar(text.trim_matches('"').to_string())
+    }
+
+    /// Whether some value satisfies both.
+    fn intersects(&self, other: &CapsValue) -> bool {
+        match (self, other) {
+            (CapsValue::List(values), other) | (other, CapsValue::List(values)) => {
+                values.iter().any(|value| value.intersects(other))
+            }
+            (CapsValue::Range(min, max), CapsValue::Range(other_min, other_max)) => {
+                min <= other_max && other_min <= max
+            }
+            (CapsValue::Range(min, max), CapsValue::Scalar(value))
+            | (CapsValue::Scalar(value), CapsValue::Range(min, max)) => {
+                number(value).is_some_and(|value| *min <= value && value <= *max)
+            }
+            (CapsValue::Scalar(a), CapsValue::Scalar(b)) => match (number(a), number(b)) {
+                (Some(a), Some(b)) => a == b,
+                _ => a == b,
+            },
+        }
+    }
+}
+
+/// One structure of a caps string: its media type with any caps features,
+/// and its fields as written.
+#[derive(Debug, Clone)]
+struct CapsStructure {
+    name: String,
+    fields: Vec<(String, String)>,
+}
+
+impl CapsStructure {
+    fn parse(text: &str) -> Option<CapsStructure> {
+        let mut parts = split_top_level(text, ',').into_iter();
+        let name = parts.next()?.to_string();
+        let fields = parts
+            .filter_map(|field| field.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Some(CapsStructure { name, fields })
+    }
+
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsFieldDiff {
+    pub name: String,
+    /// The value on each side; a field one side leaves out accepts anything.
+    pub offered: Option<String>,
+    pub accepted: Option<String>,
+    pub compatible: bool,
+}
+
+/// The closest pair of structures of the offered and accepted caps, with
+/// every field of either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsDiff {
+    pub offered_name: String,
+    pub accepted_name: String,
+    pub fields: Vec<CapsFieldDiff>,
+}
+
+impl CapsDiff {
+    pub fn incompatible(&self) -> usize {
+        usize::from(self.offered_name != self.accepted_name)
+            + self.fields.iter().filter(|field| !field.compatible).count()
+    }
+}
+
+fn diff_structures(offered: &CapsStructure, accepted: &CapsStructure) -> CapsDiff {
+    let mut names: Vec<&str> = offered.fields.iter().map(|(key, _)| key.as_str()).collect();
+    for (key, _) in &accepted.fields {
+        if !names.contains(&key.as_str()) {
+            names.push(key);
+        }
+    }
+    let fields = names
+        .into_iter()
+        .map(|name| {
+            let (offered, accepted) = (offered.field(name), accepted.field(name));
+            let compatible = match (offered, accepted) {
+                (Some(offered), Some(accepted)) => {
+                    CapsValue::parse(offered).intersects(&CapsValue::parse(accepted))
+                }
+                _ => true,
+            };
+            CapsFieldDiff {
+                name: name.to_string(),
+                offered: offered.map(str::to_string),
+                accepted: accepted.map(str::to_string),
+                compatible,
+            }
+        })
+        .collect();
+    CapsDiff {
+        offered_name: offered.name.clone(),
+        accepted_name: accepted.name.clone(),
+        fields,
+    }
+}
+
+/// Compare two caps strings as GStreamer prints them. Of all pairs of
+/// structures, the one with the fewest incompatible fields is returned;
+/// `None` when either side is `ANY`, `EMPTY` or has no structure.
+pub fn diff_caps(offered: &str, accepted: &str) -> Option<CapsDiff> {
+    let structures = |caps: &str| -> Vec<CapsStructure> {
+        split_top_level(caps, ';')
+            .into_iter()
+            .filter(|structure| !matches!(*structure, "ANY" | "EMPTY" | "NONE"))
+            .filter_map(CapsStructure::parse)
+            .collect()
+    };
+    let (offered, accepted) = (structures(offered), structures(accepted));
+    offered
+        .iter()
+        .flat_map(|offered| accepted.iter().map(move |accepted| diff_structures(offered, accepted)))
+        .min_by_key(CapsDiff::incompatible)
+}
+
+/// The sink pad, as `element:pad`, and the caps of a refusal GStreamer warns
+/// about in the GST_CAPS category, e.g. `WARN GST_CAPS
+/// gstpad.c:5712:pre_eventfunc_check:<fakesink0:sink> caps video/x-raw,
+/// format=(string)RGB not accepted`.
+pub fn parse_refused_caps(line: &str) -> Option<(String, String)> {
+    let debug = parse_debug_line(line).filter(|debug| debug.category == "GST_CAPS")?;
+    let (_, object) = debug.location.rsplit_once(":<")?;
+    let pad = object.strip_suffix('>').filter(|pad| pad.contains(':'))?;
+    let caps = debug.message.strip_prefix("caps ")?.strip_suffix(" not accepted")?;
+    Some((pad.to_string(), caps.to_string()))
+}
+
+/// What the output of a gst-launch run tells about caps negotiation, for the
+/// not-negotiated errors of runs whose pads cannot be queried: the caps each
+/// pad negotiated, which `-v` prints, and the caps sink pads refused.
+#[derive(Debug, Default)]
+pub struct NegotiationLog {
+    /// Caps by pad, as `element:pad`.
+    negotiated: HashMap<String, String>,
+    refused: HashMap<String, String>,
+}
+
+impl NegotiationLog {
+    pub fn observe(&mut self, line: &str) {
+        if let Some((element, pad, caps)) = parse_caps_notify(line) {
+            self.negotiated.insert(format!("{}:{}", element, pad), caps);
+        } else if let Some((pad, caps)) = parse_refused_caps(line) {
+            self.refused.insert(pad, caps);
+        }
+    }
+
+    /// The first link of `pipeline` whose source cannot offer caps its peer
+    /// accepts. A sink pad that refused caps decides that for its link, so
+    /// those links come first, then the links of `element`, the one that
+    /// posted the error.
+    ///
+    /// A link offers the caps its sink pad refused or else the ones its
+    /// source pad negotiated. Otherwise, and for what a link accepts,
+    /// `template_caps` gives the pad template caps of a factory in a
+    /// direction, `src` or `sink`; caps filters offer and accept their caps.
+    pub fn mismatch(
+        &self,
+        pipeline: &str,
+        element: Option<&str>,
+        template_caps: impl Fn(&str, &str) -> Option<String>,
+    ) -> Option<CapsMismatch> {
+        let draft = PipelineDraft::parse(pipeline);
+        let caps = |element: &DraftElement, direction: &str| {
+            if element.is_caps() {
+                return Some(element.factory.clone());
+            }
+            let filter = element
+                .properties
+                .iter()
+                .find(|(key, _)| element.factory == "capsfilter" && key == "caps");
+            match filter {
+                Some((_, caps)) => Some(caps.clone()),
+                None => template_caps(&element.factory, direction),
+            }
+        };
+
+        let mut links: Vec<_> = draft
+            .links
+            .iter()
+            .map(|link| {
+                let (from, to) = (&draft.elements[link.from], &draft.elements[link.to]);
+                let from_pad = link.from_pad.as_deref().unwrap_or("src");
+                let src_pad = format!("{}:{}", from.name, from_pad);
+                let sink_pad = format!("{}:{}", to.name, link.to_pad.as_deref().unwrap_or("sink"));
+                let posted = element.is_some_and(|name| name == from.name || name == to.name);
+                let rank = if self.refused.contains_key(&sink_pad) {
+                    0
+                } else {
+                    2 - u8::from(posted)
+                };
+                (rank, from, to, src_pad, sink_pad)
+            })
+            .collect();
+        links.sort_by_key(|(rank, ..)| *rank);
+
+        links.into_iter().find_map(|(_, from, to, src_pad, sink_pad)| {
+            let refused = self.refused.get(&sink_pad);
+            let offered = match refused.or_else(|| self.negotiated.get(&src_pad)) {
+                Some(caps) => caps.clone(),
+                None => caps(from, "src")?,
+            };
+            let accepted = caps(to, "sink")?;
+            let incompatible =
+                diff_caps(&offered, &accepted).is_some_and(|diff| diff.incompatible() > 0);
+            (refused.is_some() || incompatible).then_some(CapsMismatch {
+                src_pad,
+                sink_pad,
+                offered,
+                accepted,
+            })
+        })
+    }
+}
+
+/// The mismatch as text for the terminal and reports: a row per field, the
+/// incompatible ones marked with `*`.
+pub fn format_caps_mismatch(mismatch: &CapsMismatch) -> String {
+    let mut text = format!(
+        "Caps from {} do not fit {}:\n  offered:  {}\n  accepted: {}\n",
+        mismatch.src_pad, mismatch.sink_pad, mismatch.offered, mismatch.accepted
+    );
+    let Some(diff) = diff_caps(&mismatch.offered, &mismatch.accepted) else {
+        return text;
+    };
+    let marker = |compatible: bool| if compatible { ' ' } else { '*' };
+    text.push_str(&format!(
+        "{} {:<16} {:<40} {}\n",
+        marker(diff.offered_name == diff.accepted_name),
+        "media type",
+        diff.offered_name,
+        diff.accepted_name
+    ));
+    for field in &diff.fields {
+        text.push_str(&format!(
+            "{} {:<16} {:<40} {}\n",
+            marker(field.compatible),
+            field.name,
+            field.offered.as_deref().unwrap_or("-"),
+            field.accepted.as_deref().unwrap_or("-")
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incompatible_fields(diff: &CapsDiff) -> Vec<&str> {
+        diff.fields
+            .iter()
+            .filter(|field| !field.compatible)
+            .map(|field| field.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn values_intersect_across_ranges_lists_and_fractions() {
+        let diff = diff_caps(
+            "video/x-raw, format=(string)I420, width=(int)640, framerate=(fraction)30/1",
+            "video/x-raw, format=(string){ NV12, I420 }, width=(int)[ 1, 320 ], \
+             framerate=(fraction)[ 0/1, 60/1 ]",
+        )
+        .unwrap();
+        assert_eq!(incompatible_fields(&diff), ["width"]);
+        assert_eq!(diff.incompatible(), 1);
+    }
+
+    #[test]
+    fn the_closest_structures_are_compared() {
+        let diff = diff_caps(
+            "video/x-raw, format=(string)RGB",
+            "audio/x-raw, format=(string)S16LE; video/x-raw, format=(string){ I420, NV12 }",
+        )
+        .unwrap();
+        assert_eq!(diff.accepted_name, "video/x-raw");
+        assert_eq!(incompatible_fields(&diff), ["format"]);
+
+        // A field only one side names accepts anything.
+        let diff = diff_caps("video/x-raw, width=(int)640", "video/x-raw").unwrap();
+        assert_eq!(diff.incompatible(), 0);
+        assert_eq!(diff.fields[0].accepted, None);
+    }
+
+    #[test]
+    fn any_and_empty_caps_have_nothing_to_compare() {
+        assert_eq!(diff_caps("ANY", "video/x-raw"), None);
+        assert_eq!(diff_caps("video/x-raw", "EMPTY"), None);
+    }
+
+    /// Pad template caps of a few factories, as the registry would give them.
+    fn template_caps(factory: &str, direction: &str) -> Option<String> {
+        let caps = match (factory, direction) {
+            ("videotestsrc", "src") => "video/x-raw, format=(string){ I420, NV12, RGB }",
+            ("audiotestsrc", "src") => "audio/x-raw, format=(string){ S16LE, F32LE }",
+            ("videoconvert", _) => "video/x-raw, format=(string){ I420, NV12, RGB, BGRx }",
+            ("fakesink", "sink") => "ANY",
+            ("autovideosink", "sink") => "video/x-raw",
+            _ => return None,
+        };
+        Some(caps.to_string())
+    }
+
+    #[test]
+    fn launch_runs_find_the_link_from_templates_and_caps_filters() {
+        let log = NegotiationLog::default();
+        let pipeline = "videotestsrc ! video/x-raw,format=YUY9 ! videoconvert ! fakesink";
+        let mismatch = log.mismatch(pipeline, Some("videotestsrc0"), template_caps).unwrap();
+        assert_eq!(mismatch.src_pad, "videotestsrc0:src");
+        assert_eq!(mismatch.sink_pad, "capsfilter0:sink");
+        assert_eq!(mismatch.accepted, "video/x-raw,format=YUY9");
+
+        let pipeline = "audiotestsrc ! autovideosink name=out";
+        let mismatch = log.mismatch(pipeline, None, template_caps).unwrap();
+        assert_eq!(mismatch.sink_pad, "out:sink");
+
+        let pipeline = "videotestsrc ! videoconvert ! fakesink";
+        assert_eq!(log.mismatch(pipeline, Some("videotestsrc0"), template_caps), None);
+    }
+
+    #[test]
+    fn logged_caps_are_offered_before_the_templates() {
+        let mut log = NegotiationLog::default();
+        log.observe(
+            "/GstPipeline:pipeline0/GstVideoTestSrc:videotestsrc0.GstPad:src: caps = video/x-raw, \
+             format=(string)RGB, width=(int)320",
+        );
+        log.observe(
+            "0:00:00.1 1 0x1 WARN GST_CAPS gstpad.c:5712:pre_eventfunc_check:<sink:sink> caps \
+             video/x-raw, format=(string)NV12 not accepted",
+        );
+        assert_eq!(
+            parse_refused_caps("0:00:00.1 1 0x1 WARN basesrc gstbasesrc.c:1:f:<src> caps x"),
+            None
+        );
+
+        let pipeline = "videotestsrc ! video/x-raw,width=640 ! fakesink";
+        let mismatch = log.mismatch(pipeline, None, template_caps).unwrap();
+        assert_eq!(mismatch.offered, "video/x-raw, format=(string)RGB, width=(int)320");
+
+        let pipeline = "videotestsrc ! video/x-raw,format=RGB ! videoconvert \
+                        ! video/x-raw,format=RGB ! fakesink name=sink";
+        let mismatch = log.mismatch(pipeline, Some("videotestsrc0"), template_caps).unwrap();
+        assert_eq!(mismatch.sink_pad, "sink:sink");
+        assert_eq!(mismatch.offered, "video/x-raw, format=(string)NV12");
+    }
+}
@@ -0,0 +1,133 @@
+//! Processing time by element hierarchy, as folded stacks for flamegraph
+//! tools or as a self-contained flamegraph SVG.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::report::escape_xml;
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+const FLAME_WIDTH: f64 = 1200.0;
+const FLAME_ROW: f64 = 18.0;
+const FLAME_TITLE: f64 = 30.0;
+
+/// Total processing time of every element, in microseconds, under the bins
+/// that contain it: `ancestors` maps an element to the names from the
+/// pipeline down to its parent. Elements without known ancestors sit
+/// directly under `pipeline`.
+pub fn proctime_stacks(
+    store: &MetricStore,
+    ancestors: &HashMap<String, Vec<String>>,
+    pipeline: &str,
+) -> Vec<(Vec<String>, u64)> {
+    store
+        .element_stats()
+        .into_iter()
+        .filter_map(|stats| {
+            let summary = stats.summary(Metric::ProcTime);
+            let micros = (summary.sum / 1e3).round() as u64;
+            if summary.count == 0 || micros == 0 {
+                return None;
+            }
+            let mut stack = ancestors
+                .get(&stats.element)
+                .cloned()
+                .unwrap_or_else(|| vec![pipeline.to_string()]);
+            stack.push(stats.element);
+            Some((stack, micros))
+        })
+        .collect()
+}
+
+/// One `frame;frame;frame value` line per stack, the input format of
+/// flamegraph.pl, inferno and speedscope.
+pub fn format_folded(stacks: &[(Vec<String>, u64)]) -> String {
+    stacks
+        .iter()
+        .map(|(stack, value)| format!("{} {}\n", stack.join(";"), value))
+        .collect()
+}
+
+#[derive(Default)]
+struct Frame {
+    total: u64,
+    children: BTreeMap<String, Frame>,
+}
+
+impl Frame {
+    fn add(&mut self, stack: &[String], value: u64) {
+        self.total += value;
+        if let Some((name, rest)) = stack.split_first() {
+            self.children.entry(name.clone()).or_default().add(rest, value);
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.children.values().map(|child| child.depth() + 1).max().unwrap_or(0)
+    }
+}
+
+/// A warm color that stays the same for a name.
+fn frame_color(name: &str) -> String {
+    let hash = name.bytes().fold(2166136261u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(16777619)
+    });
+    format!("rgb({},{},{})", 205 + hash % 50, 80 + (hash >> 8) % 130, 40 + (hash >> 16) % 50)
+}
+
+/// The stacks as a flamegraph with the pipeline at the bottom, each frame
+/// as wide as its share of the total processing time.
+pub fn render_flamegraph_svg(stacks: &[(Vec<String>, u64)], title: &str) -> String {
+    let mut root = Frame::default();
+    for (stack, value) in stacks {
+        root.add(stack, *value);
+    }
+    let height = FLAME_TITLE + (root.depth() + 1) as f64 * FLAME_ROW;
+    let mut shapes = String::new();
+    draw_frame(&mut shapes, "all", &root, root.total, 0.0, 0, height);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         font-family=\"sans-serif\" font-size=\"11\">\
+         <text x=\"{x}\" y=\"20\" font-size=\"15\" text-anchor=\"middle\">{title}</text>\
+         {shapes}</svg>\n",
+        w = FLAME_WIDTH,
+        h = height,
+        x = FLAME_WIDTH / 2.0,
+        title = escape_xml(title),
+        shapes = shapes
+    )
+}
+
+fn draw_frame(
+    shapes: &mut String,
+    name: &str,
+    frame: &Frame,
+    total: u64,
+    x: f64,
+    depth: usize,
+    height: f64,
+) {
+    let width = frame.total as f64 / total.max(1) as f64 * FLAME_WIDTH;
+    let y = height - (depth + 1) as f64 * FLAME_ROW;
+    // Roughly 7 pixels per character; narrower frames get no label.
+    let label: String = name.chars().take(((width - 6.0) / 7.0).max(0.0) as usize).collect();
+    shapes.push_str(&format!(
+        "<g><title>{name} ({ms:.2} ms, {share:.1}%)</title>\
+         <rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{h:.1}\" fill=\"{color}\" \
+         stroke=\"#fff\" stroke-width=\"0.5\"/>\
+         <text x=\"{tx:.1}\" y=\"{ty:.1}\">{label}</text></g>",
+        name = escape_xml(name),
+        ms = frame.total as f64 / 1e3,
+        share = frame.total as f64 * 100.0 / total.max(1) as f64,
+        h = FLAME_ROW - 1.0,
+        color = frame_color(name),
+        tx = x + 3.0,
+        ty = y + FLAME_ROW - 5.0,
+        label = escape_xml(&label),
+    ));
+    let mut child_x = x;
+    for (child_name, child) in &frame.children {
+        draw_frame(shapes, child_name, child, total, child_x, depth + 1, height);
+        child_x += child.total as f64 / total.max(1) as f64 * FLAME_WIDTH;
+    }
+}
@@ -0,0 +1,99 @@
+//! The framerate each element negotiated in its caps, checked against the
+//! rate the framerate tracer measures there.
+
+use std::collections::BTreeMap;
+
+use crate::rules::RuleResult;
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+/// How far back the measured rate is averaged.
+const RECENT_NS: u64 = 5_000_000_000;
+/// How far below the negotiated rate an element may run, in percent. The
+/// tracer counts whole frames per second, so 29.97 fps shows as 29 or 30.
+const TOLERANCE_PERCENT: f64 = 5.0;
+
+/// The rate in caps such as `video/x-raw, framerate=(fraction)30000/1001`.
+/// `None` without a framerate and for `0/1`, which means a variable rate.
+pub fn caps_framerate(caps: &str) -> Option<f64> {
+    let (_, rest) = caps.split_once("framerate=")?;
+    let rest = rest.strip_prefix("(fraction)").unwrap_or(rest);
+    let fraction: String = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '/').collect();
+    let (numerator, denominator) = fraction.split_once('/')?;
+    let numerator: f64 = numerator.parse().ok()?;
+    let denominator: f64 = denominator.parse().ok()?;
+    (numerator > 0.0 && denominator > 0.0).then(|| numerator / denominator)
+}
+
+/// The latest framerate negotiated on any pad of each element.
+#[derive(Debug, Clone, Default)]
+pub struct NegotiatedFramerates {
+    rates: BTreeMap<String, f64>,
+}
+
+impl NegotiatedFramerates {
+    /// Remember the framerate in the caps `element` negotiated, if any.
+    pub fn record(&mut self, element: &str, caps: &str) {
+        if let Some(fps) = caps_framerate(caps) {
+            self.rates.insert(element.to_string(), fps);
+        }
+    }
+
+    pub fn get(&self, element: &str) -> Option<f64> {
+        self.rates.get(element).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rates.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.rates.clear();
+    }
+
+    /// The first element along `chain`, a list of element name prefixes from
+    /// upstream to downstream, whose measured rate over the last seconds is
+    /// below the rate it negotiated. Elements further down usually only
+    /// inherit the shortfall, so they are not reported.
+    pub fn first_shortfall(&self, store: &MetricStore, chain: &[String]) -> Option<RuleResult> {
+        let now_ns = store.latest_timestamp();
+        let since_ns = now_ns.saturating_sub(RECENT_NS);
+        let elements = store.element_stats().into_iter().map(|stats| stats.element);
+        let mut measured: Vec<(usize, String)> = elements
+            .filter_map(|element| {
+                let position = chain.iter().position(|name| element.starts_with(name.as_str()));
+                Some((position?, element))
+            })
+            .collect();
+        measured.sort();
+        measured.into_iter().find_map(|(_, element)| {
+            let expected = self.get(&element)?;
+            let recent: Vec<f64> = store
+                .series(&element, Metric::Framerate)
+                .into_iter()
+                .filter(|(timestamp_ns, _)| *timestamp_ns >= since_ns)
+                .map(|(_, fps)| fps)
+                .collect();
+            if recent.is_empty() {
+                return None;
+            }
+            let actual = recent.iter().sum::<f64>() / recent.len() as f64;
+            if actual >= expected * (1.0 - TOLERANCE_PERCENT / 100.0) {
+                return None;
+            }
+            Some(RuleResult {
+                failure: Some(format!(
+                    "{}: FPS {:.1} is below the negotiated {:.2}, first in the chain",
+                    element, actual, expected
+                )),
+                check: format!("FPS >= negotiated {:.2}", expected),
+                element,
+                active: true,
+                metric: Metric::Framerate,
+                threshold: expected,
+                value: Some(actual),
+                timestamp_ns: now_ns,
+            })
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! A 0–100 health score per element, for a first look at which parts of a
+//! large pipeline need attention.
+
+use crate::stats::{Aggregate, ElementStats, Metric};
+use crate::store::MetricStore;
+
+/// Points an element can lose for each factor.
+const FPS_STABILITY_POINTS: f64 = 25.0;
+const PROCTIME_POINTS: f64 = 30.0;
+const LATENCY_POINTS: f64 = 20.0;
+const DROP_POINTS: f64 = 25.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Health {
+    /// 100 for a healthy element, down to 0.
+    pub score: f64,
+    /// What cost the element points, worst first, e.g.
+    /// `-18: ProcTime p95 takes 80% of the 33.3 ms frame budget`.
+    pub factors: Vec<String>,
+}
+
+/// Score `stats` from how steady its frame rate is, how much of the frame
+/// budget its processing takes, its interlatency against `latency_budget_ns`
+/// (not judged when 0) and the share of frames its sink dropped, if it is
+/// one. `None` when the element reported nothing to judge.
+pub fn element_health(
+    store: &MetricStore,
+    stats: &ElementStats,
+    latency_budget_ns: u64,
+    drop_percent: Option<f64>,
+) -> Option<Health> {
+    let mut penalties: Vec<(f64, String)> = Vec::new();
+    let mut judged = false;
+
+    let fps = stats.summary(Metric::Framerate).get(Aggregate::Avg).filter(|fps| *fps > 0.0);
+    if let Some(fps) = fps {
+        judged = true;
+        let values = store.series(&stats.element, Metric::Framerate);
+        let variance = values.iter().map(|(_, value)| (value - fps).powi(2)).sum::<f64>()
+            / values.len().max(1) as f64;
+        let variation = variance.sqrt() / fps;
+        // Losing every point takes a frame rate that varies by 10%.
+        let penalty = (variation * 10.0).min(1.0) * FPS_STABILITY_POINTS;
+        penalties.push((penalty, format!("FPS varies by ±{:.0}%", variation * 100.0)));
+    }
+
+    if let Some(proctime_ns) = stats.summary(Metric::ProcTime).get(Aggregate::P95) {
+        judged = true;
+        if let Some(fps) = fps {
+            let budget_ns = 1e9 / fps;
+            let share = proctime_ns / budget_ns;
+            // Taking half of the budget is fine; all of it costs every point.
+            let penalty = ((share - 0.5) * 2.0).clamp(0.0, 1.0) * PROCTIME_POINTS;
+            penalties.push((
+                penalty,
+                format!(
+                    "ProcTime p95 takes {:.0}% of the {:.1} ms frame budget",
+                    share * 100.0,
+                    budget_ns / 1e6
+                ),
+            ));
+        }
+    }
+
+    let latency_ns = stats.summary(Metric::Interlatency).get(Aggregate::P95);
+    if let Some(latency_ns) = latency_ns.filter(|_| latency_budget_ns > 0) {
+        judged = true;
+        let share = latency_ns / latency_budget_ns as f64;
+        let limit_ms = latency_budget_ns as f64 / 1e6;
+        let penalty = ((share - 0.5) * 2.0).clamp(0.0, 1.0) * LATENCY_POINTS;
+        penalties.push((
+            penalty,
+            format!("Interlatency p95 is {:.0}% of the {:.1} ms limit", share * 100.0, limit_ms),
+        ));
+    }
+
+    if let Some(drop_percent) = drop_percent {
+        judged = true;
+        // Dropping 10% of the frames costs every point.
+        let penalty = (drop_percent / 10.0).min(1.0) * DROP_POINTS;
+        penalties.push((penalty, format!("Drops {:.1}% of its frames", drop_percent)));
+    }
+
+    if !judged {
+        return None;
+    }
+    penalties.retain(|(penalty, _)| *penalty >= 0.5);
+    penalties.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let lost: f64 = penalties.iter().map(|(penalty, _)| penalty).sum();
+    Some(Health {
+        score: (100.0 - lost).max(0.0),
+        factors: penalties
+            .into_iter()
+            .map(|(penalty, reason)| format!("-{:.0}: {}", penalty, reason))
+            .collect(),
+    })
+}
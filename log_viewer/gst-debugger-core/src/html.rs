@@ -0,0 +1,203 @@
+use crate::parse::format_clock_time;
+use crate::pipeline::pipeline_elements;
+use crate::report::escape_xml;
+use crate::rules::RuleResult;
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 160.0;
+const CHART_MARGIN: f64 = 30.0;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}th{background:#f0f0f0}\
+.chart{margin:0 1em 1em 0;display:inline-block}.fail{color:#b00}.pass{color:#070}";
+
+/// A self-contained HTML page summarizing a recorded session.
+pub struct HtmlReport<'a> {
+    pub title: &'a str,
+    pub pipeline: &'a str,
+    pub store: &'a MetricStore,
+    pub results: &'a [RuleResult],
+}
+
+impl HtmlReport<'_> {
+    pub fn render(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape_xml(self.title),
+            STYLE,
+            escape_xml(self.title)
+        );
+
+        html.push_str(&format!(
+            "<p>{} metric samples, {} latencies over {}</p>\n",
+            self.store.samples().len(),
+            self.store.latencies().len(),
+            format_clock_time(
+                self.store.latest_timestamp().saturating_sub(self.store.earliest_timestamp())
+            )
+        ));
+
+        if !self.pipeline.is_empty() {
+            html.push_str("<h2>Pipeline</h2>\n");
+            html.push_str(&format!("<p><code>{}</code></p>\n", escape_xml(self.pipeline)));
+            html.push_str(&pipeline_svg(&pipeline_elements(self.pipeline)));
+        }
+
+        html.push_str(&self.stats_table());
+        html.push_str(&self.latency_charts());
+        html.push_str(&self.alerts());
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn stats_table(&self) -> String {
+        let mut table = String::from("<h2>Statistics</h2>\n<table>\n<tr><th>Element</th>");
+        for metric in Metric::ALL {
+            for &aggregate in metric.aggregates() {
+                table.push_str(&format!("<th>{} {}</th>", metric.label(), aggregate.label()));
+            }
+        }
+        table.push_str("</tr>\n");
+
+        for element in self.store.element_stats() {
+            table.push_str(&format!("<tr><td>{}</td>", escape_xml(&element.element)));
+            for metric in Metric::ALL {
+                for &aggregate in metric.aggregates() {
+                    let value = element
+                        .summary(metric)
+                        .get(aggregate)
+                        .map_or("-".to_string(), |value| metric.format(value));
+                    table.push_str(&format!("<td>{}</td>", value));
+                }
+            }
+            table.push_str("</tr>\n");
+        }
+        table.push_str("</table>\n");
+        table
+    }
+
+    fn latency_charts(&self) -> String {
+        let mut charts = String::new();
+        for element in self.store.element_stats() {
+            for metric in [Metric::Interlatency, Metric::ProcTime] {
+                let series = self.store.series(&element.element, metric);
+                if series.len() < 2 {
+                    continue;
+                }
+                charts.push_str(&format!(
+                    "<div class=\"chart\"><h3>{} {}</h3>\n{}</div>\n",
+                    escape_xml(&element.element),
+                    metric.label(),
+                    chart_svg(&series, metric)
+                ));
+            }
+        }
+
+        if charts.is_empty() {
+            return String::new();
+        }
+        format!("<h2>Latency</h2>\n{}", charts)
+    }
+
+    fn alerts(&self) -> String {
+        if self.results.is_empty() {
+            return String::new();
+        }
+
+        let violations: Vec<&RuleResult> =
+            self.results.iter().filter(|result| !result.passed()).collect();
+        let mut html = format!(
+            "<h2>Alerts</h2>\n<p class=\"{}\">{} of {} rule checks passed</p>\n",
+            if violations.is_empty() { "pass" } else { "fail" },
+            self.results.len() - violations.len(),
+            self.results.len()
+        );
+        if !violations.is_empty() {
+            html.push_str("<ul>\n");
+            for result in violations {
+                html.push_str(&format!(
+                    "<li class=\"fail\">{}</li>\n",
+                    escape_xml(&result.describe())
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        html
+    }
+}
+
+/// Draw the elements as a row of linked boxes.
+fn pipeline_svg(elements: &[String]) -> String {
+    let mut shapes = String::new();
+    let mut x = 10.0;
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            shapes.push_str(&format!(
+                "<line x1=\"{}\" y1=\"30\" x2=\"{}\" y2=\"30\" stroke=\"#555\" marker-end=\"url(#arrow)\"/>",
+                x - 40.0,
+                x - 4.0
+            ));
+        }
+        let width = 20.0 + 8.0 * element.chars().count() as f64;
+        shapes.push_str(&format!(
+            "<rect x=\"{}\" y=\"10\" width=\"{}\" height=\"40\" rx=\"6\" fill=\"#e8f0fe\" stroke=\"#4a6fa5\"/>\
+             <text x=\"{}\" y=\"35\" text-anchor=\"middle\" font-size=\"13\">{}</text>",
+            x,
+            width,
+            x + width / 2.0,
+            escape_xml(element)
+        ));
+        x += width + 40.0;
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"60\">\
+         <defs><marker id=\"arrow\" markerWidth=\"8\" markerHeight=\"8\" refX=\"8\" refY=\"4\" orient=\"auto\">\
+         <path d=\"M0,0 L8,4 L0,8 z\" fill=\"#555\"/></marker></defs>{}</svg>\n",
+        x - 30.0,
+        shapes
+    )
+}
+
+/// Plot the series as a line chart from zero to its maximum value.
+fn chart_svg(series: &[(u64, f64)], metric: Metric) -> String {
+    let first = series.iter().map(|point| point.0).min().unwrap_or(0);
+    let last = series.iter().map(|point| point.0).max().unwrap_or(0);
+    let span = (last - first).max(1) as f64;
+    let max = series.iter().map(|point| point.1).fold(0.0, f64::max).max(f64::EPSILON);
+
+    let plot_width = CHART_WIDTH - 2.0 * CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+    let points: Vec<String> = series
+        .iter()
+        .map(|(timestamp, value)| {
+            let x = CHART_MARGIN + (timestamp - first) as f64 / span * plot_width;
+            let y = CHART_MARGIN + plot_height - value / max * plot_height;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\">\
+         <rect x=\"{m}\" y=\"{m}\" width=\"{pw}\" height=\"{ph}\" fill=\"none\" stroke=\"#ccc\"/>\
+         <polyline points=\"{points}\" fill=\"none\" stroke=\"#4a6fa5\" stroke-width=\"1.5\"/>\
+         <text x=\"{m}\" y=\"{top}\" font-size=\"11\">{max}</text>\
+         <text x=\"{m}\" y=\"{bottom}\" font-size=\"11\">{start}</text>\
+         <text x=\"{right}\" y=\"{bottom}\" font-size=\"11\" text-anchor=\"end\">{end}</text></svg>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        m = CHART_MARGIN,
+        pw = plot_width,
+        ph = plot_height,
+        points = points.join(" "),
+        top = CHART_MARGIN - 6.0,
+        bottom = CHART_HEIGHT - CHART_MARGIN + 16.0,
+        right = CHART_WIDTH - CHART_MARGIN,
+        max = metric.format(max),
+        start = format_clock_time(first),
+        end = format_clock_time(last),
+    )
+}
@@ -0,0 +1,78 @@
+//! Inter-arrival jitter: how unevenly buffers leave a pad, as the standard
+//! deviation of the time between consecutive buffers.
+
+use std::collections::HashMap;
+
+use crate::model::TracingData;
+use crate::parse::parse_schedule_time;
+
+/// Intervals gathered per pad before a jitter sample is taken.
+const WINDOW_NS: u64 = 1_000_000_000;
+
+/// Standard deviation of the buffer inter-arrival times.
+pub fn inter_arrival_jitter(intervals_ns: &[u64]) -> Option<u64> {
+    if intervals_ns.is_empty() {
+        return None;
+    }
+    let count = intervals_ns.len() as f64;
+    let mean = intervals_ns.iter().sum::<u64>() as f64 / count;
+    let variance = intervals_ns
+        .iter()
+        .map(|&interval| (interval as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    Some(variance.sqrt() as u64)
+}
+
+/// The name the samples of a source pad are reported under: the element's
+/// for the `first` of its source pads to carry a buffer, `element_pad` as
+/// GstShark names pads for the others. The pads of a tee or demuxer each
+/// keep their own jitter, while the element's series and the rules naming
+/// it carry on when it adds pads.
+pub fn pad_sample_name(element: &str, pad: &str, first: bool) -> String {
+    if first {
+        element.to_string()
+    } else {
+        format!("{}_{}", element, pad)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    start_ns: u64,
+    intervals_ns: Vec<u64>,
+}
+
+/// Jitter per pad from GstShark's `scheduletime` tracer, which logs the
+/// time since the previous buffer for every buffer on every pad.
+#[derive(Debug, Default)]
+pub struct ScheduleJitter {
+    pads: HashMap<String, Window>,
+}
+
+impl ScheduleJitter {
+    /// Record a `scheduling` line. Once a pad has gathered a second of
+    /// intervals, their jitter is returned as a sample for the pad.
+    pub fn push_line(&mut self, line: &str) -> Option<TracingData> {
+        let (pad, timestamp_ns, interval_ns) = parse_schedule_time(line)?;
+        let window = self.pads.entry(pad.clone()).or_insert_with(|| Window {
+            start_ns: timestamp_ns,
+            intervals_ns: Vec::new(),
+        });
+        window.intervals_ns.push(interval_ns);
+        if timestamp_ns.saturating_sub(window.start_ns) < WINDOW_NS {
+            return None;
+        }
+        let jitter_ns = inter_arrival_jitter(&window.intervals_ns);
+        window.start_ns = timestamp_ns;
+        window.intervals_ns.clear();
+        Some(TracingData {
+            timestamp_ns,
+            element: pad,
+            bitrate: None,
+            framerate: None,
+            proctime_ns: None,
+            jitter_ns,
+        })
+    }
+}
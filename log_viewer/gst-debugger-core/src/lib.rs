@@ -0,0 +1,96 @@
+//! Parsing, storage and analysis of GStreamer tracer output, shared by the
+//! headless engine and the GUI.
+
+pub mod alerts;
+pub mod anomaly;
+pub mod baseline;
+pub mod bottleneck;
+pub mod builder;
+pub mod caps;
+pub mod flamegraph;
+pub mod framerate;
+pub mod health;
+pub mod html;
+pub mod jitter;
+pub mod linefilter;
+pub mod memory;
+pub mod model;
+pub mod parse;
+pub mod pipeline;
+pub mod queuelevel;
+pub mod report;
+pub mod rollup;
+pub mod rules;
+pub mod speedscope;
+pub mod stall;
+pub mod startup;
+pub mod stats;
+pub mod store;
+pub mod suggest;
+pub mod syntax;
+pub mod threads;
+pub mod throughput;
+pub mod trend;
+pub mod wire;
+
+#[cfg(test)]
+mod testing;
+
+pub use alerts::*;
+pub use anomaly::AnomalyDetector;
+pub use baseline::{
+    format_baseline_diff, Baseline, BaselineDelta, BaselineSummary, Tolerance, BASELINE_DIR,
+};
+pub use bottleneck::{BottleneckReport, CapsChange, Percentiles, SlowElement, SlowLink, Stall};
+pub use builder::{DraftElement, DraftLink, PipelineDraft};
+pub use caps::{
+    diff_caps, format_caps_mismatch, parse_refused_caps, CapsDiff, CapsFieldDiff, NegotiationLog,
+};
+pub use flamegraph::{format_folded, proctime_stacks, render_flamegraph_svg};
+pub use framerate::{caps_framerate, NegotiatedFramerates};
+pub use health::{element_health, Health};
+pub use html::HtmlReport;
+pub use jitter::{inter_arrival_jitter, pad_sample_name, ScheduleJitter};
+pub use linefilter::LineFilter;
+pub use memory::{parse_live_object, parse_vm_rss, steady_growth, MemoryTracker};
+pub use model::*;
+pub use parse::*;
+pub use pipeline::*;
+pub use queuelevel::{correlation, latency_against_fill, parse_queue_level};
+pub use report::{format_junit, format_markdown_summary, format_stats_table, TestCase};
+pub use rollup::{format_rollups, Rollup, ROLLUP_NS};
+pub use rules::*;
+pub use speedscope::{
+    SpeedscopeEvent, SpeedscopeFile, SpeedscopeFrame, SpeedscopeProfile, SpeedscopeShared,
+};
+pub use stats::*;
+pub use store::MetricStore;
+pub use suggest::{format_rules_toml, suggest_rules};
+pub use syntax::{completion_at, highlight, Completion, SyntaxKind, SyntaxSpan};
+pub use stall::{last_active, StallDetector};
+pub use startup::{first_buffers, FirstBuffer};
+pub use threads::{parse_line_thread, ThreadTracker};
+pub use throughput::throughput_losses;
+pub use trend::TrendDetector;
+
+pub const TRACER_PRESETS: [(&str, &str); 4] = [
+    ("Throughput", "bitrate;framerate"),
+    ("Latency", "interlatency;proctime"),
+    ("Scheduling", "scheduletime;queuelevel"),
+    (
+        "Everything",
+        "bitrate;framerate;interlatency;proctime;scheduletime;queuelevel;cpuusage",
+    ),
+];
+
+pub const GSTSHARK_TRACERS: [&str; 9] = [
+    "interlatency",
+    "proctime",
+    "framerate",
+    "scheduletime",
+    "cpuusage",
+    "graphic",
+    "bitrate",
+    "queuelevel",
+    "buffer",
+];
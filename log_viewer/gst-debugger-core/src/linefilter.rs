@@ -0,0 +1,151 @@
+//! Holding back log lines that would drown out the rest: a run of identical
+//! lines collapses into a "repeated" line, and each debug category may only
+//! log so many lines a second. Tracer lines carry the metrics and always go
+//! through.
+
+use std::collections::HashMap;
+
+use crate::parse::{parse_line_category, parse_line_timestamp, TRACER_CATEGORY};
+
+/// Lines one category logged in the current second of the log.
+#[derive(Debug, Default)]
+struct CategoryWindow {
+    second: u64,
+    count: u32,
+    dropped: u64,
+}
+
+/// Which lines of a run's log are written and shown.
+#[derive(Debug, Default)]
+pub struct LineFilter {
+    dedup: bool,
+    /// Most lines a second per category, unlimited when `None`.
+    rate_limit: Option<u32>,
+    /// The last line let through, without its timestamp.
+    last: Option<String>,
+    /// How often `last` came again since.
+    repeated: usize,
+    categories: HashMap<String, CategoryWindow>,
+}
+
+impl LineFilter {
+    pub fn new(dedup: bool, rate_limit: Option<u32>) -> Self {
+        LineFilter {
+            dedup,
+            rate_limit,
+            ..Default::default()
+        }
+    }
+
+    /// The lines to keep for `line`: nothing when it is held back, otherwise
+    /// the line after a summary of what was held back before it.
+    pub fn push(&mut self, line: &str) -> Vec<String> {
+        let category = parse_line_category(line);
+        if category == Some(TRACER_CATEGORY) {
+            return vec![line.to_string()];
+        }
+
+        let mut kept = Vec::new();
+        let body = without_timestamp(line);
+        if self.dedup {
+            if self.last.as_deref() == Some(body) {
+                self.repeated += 1;
+                return kept;
+            }
+            kept.extend(self.repeat_summary());
+            self.last = None;
+        }
+
+        if let Some(limit) = self.rate_limit
+            && let Some(category) = category
+        {
+            let second = parse_line_timestamp(line) / 1_000_000_000;
+            let window = self.categories.entry(category.to_string()).or_default();
+            if window.second != second {
+                kept.extend(drop_summary(category, window));
+                *window = CategoryWindow {
+                    second,
+                    ..Default::default()
+                };
+            }
+            if window.count >= limit {
+                window.dropped += 1;
+                return kept;
+            }
+            window.count += 1;
+        }
+
+        if self.dedup {
+            self.last = Some(body.to_string());
+        }
+        kept.push(line.to_string());
+        kept
+    }
+
+    /// The summaries of what is still held back, once the log ends.
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut kept: Vec<String> = self.repeat_summary().into_iter().collect();
+        for (category, window) in &mut self.categories {
+            kept.extend(drop_summary(category, window));
+        }
+        kept
+    }
+
+    fn repeat_summary(&mut self) -> Option<String> {
+        let repeated = std::mem::take(&mut self.repeated);
+        (repeated > 0).then(|| format!("last message repeated {} times", repeated))
+    }
+}
+
+fn drop_summary(category: &str, window: &mut CategoryWindow) -> Option<String> {
+    let dropped = std::mem::take(&mut window.dropped);
+    (dropped > 0).then(|| format!("rate limit dropped {} {} lines", dropped, category))
+}
+
+/// The line after its timestamp column, which differs between repeats.
+fn without_timestamp(line: &str) -> &str {
+    match line.split_once(char::is_whitespace) {
+        Some((first, rest)) if first.contains(':') => rest.trim_start(),
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(seconds: u32, category: &str, message: &str) -> String {
+        format!("0:00:{:02}.000000000 1 0x1 DEBUG {} file.c:1:f: {}", seconds, category, message)
+    }
+
+    #[test]
+    fn repeats_collapse_into_one_line() {
+        let mut filter = LineFilter::new(true, None);
+        assert_eq!(filter.push(&line(1, "basesrc", "pushing")).len(), 1);
+        assert!(filter.push(&line(2, "basesrc", "pushing")).is_empty());
+        assert!(filter.push(&line(3, "basesrc", "pushing")).is_empty());
+        let kept = filter.push(&line(4, "basesrc", "done"));
+        assert_eq!(kept, ["last message repeated 2 times".to_string(), line(4, "basesrc", "done")]);
+        assert!(filter.finish().is_empty());
+    }
+
+    #[test]
+    fn categories_are_rate_limited_but_tracer_lines_are_not() {
+        let mut filter = LineFilter::new(false, Some(2));
+        for message in ["a", "b", "c", "d"] {
+            filter.push(&line(1, "basesrc", message));
+        }
+        let tracer = "0:00:01.000000000 1 0x1 TRACE GST_TRACER :0:: proctime, element=enc0;";
+        for _ in 0..3 {
+            assert_eq!(filter.push(tracer).len(), 1);
+        }
+        assert_eq!(filter.push(&line(1, "queue", "e")).len(), 1);
+        let kept = filter.push(&line(2, "basesrc", "f"));
+        let dropped = "rate limit dropped 2 basesrc lines".to_string();
+        assert_eq!(kept, [dropped, line(2, "basesrc", "f")]);
+
+        filter.push(&line(2, "basesrc", "g"));
+        filter.push(&line(2, "basesrc", "h"));
+        assert_eq!(filter.finish(), ["rate limit dropped 1 basesrc lines"]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Memory of the pipeline process over a soak test: its resident set, the
+//! objects the leaks tracer finds alive, and whether either keeps growing.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+
+use crate::model::MemoryUsage;
+
+/// How far back the growth of the resident set is judged.
+const GROWTH_WINDOW_NS: u64 = 600_000_000_000;
+/// How many consecutive parts of the window must each average higher.
+const GROWTH_STEPS: usize = 5;
+/// Growth is only judged once the samples span this long.
+const MIN_GROWTH_SPAN_NS: u64 = 60_000_000_000;
+
+/// The type of an object in a leaks tracer `object-alive` line, which the
+/// tracer logs for every live object when it receives SIGUSR1.
+pub fn parse_live_object(line: &str) -> Option<String> {
+    let regex = Regex::new(r"object-alive, type-name=\(string\)([^,;\s]+)").ok()?;
+    Some(regex.captures(line)?[1].to_string())
+}
+
+/// The resident set in `/proc/PID/status`, e.g. `VmRSS:  123456 kB`.
+pub fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// The slope, in bytes per second, of a resident set that grew steadily:
+/// cut into equal steps, every step averages more than the one before.
+/// `None` while the samples span less than a minute or memory levels off.
+pub fn steady_growth(samples: &[(u64, u64)]) -> Option<f64> {
+    let (&(first_ns, _), &(last_ns, _)) = (samples.first()?, samples.last()?);
+    let span_ns = last_ns.checked_sub(first_ns).filter(|span| *span >= MIN_GROWTH_SPAN_NS)?;
+    let mut steps = [(0.0, 0usize); GROWTH_STEPS];
+    for &(timestamp_ns, bytes) in samples {
+        let step = ((timestamp_ns - first_ns) as u128 * GROWTH_STEPS as u128 / span_ns as u128)
+            as usize;
+        let step = &mut steps[step.min(GROWTH_STEPS - 1)];
+        step.0 += bytes as f64;
+        step.1 += 1;
+    }
+    if steps.iter().any(|(_, count)| *count == 0) {
+        return None;
+    }
+    let averages: Vec<f64> = steps.iter().map(|(sum, count)| sum / *count as f64).collect();
+    if !averages.windows(2).all(|pair| pair[1] > pair[0]) {
+        return None;
+    }
+    // Least-squares slope over every sample.
+    let n = samples.len() as f64;
+    let mean_t = samples.iter().map(|(ns, _)| (ns - first_ns) as f64 / 1e9).sum::<f64>() / n;
+    let mean_b = samples.iter().map(|(_, bytes)| *bytes as f64).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for &(timestamp_ns, bytes) in samples {
+        let t = (timestamp_ns - first_ns) as f64 / 1e9 - mean_t;
+        covariance += t * (bytes as f64 - mean_b);
+        variance += t * t;
+    }
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// Gathers the memory samples of one pipeline run.
+#[derive(Debug)]
+pub struct MemoryTracker {
+    limit_bytes_per_s: f64,
+    rss: Vec<(u64, u64)>,
+    /// Objects of the dump being logged, and of the last complete one.
+    counting: BTreeMap<String, u64>,
+    live_objects: BTreeMap<String, u64>,
+    first_objects: Option<BTreeMap<String, u64>>,
+    leaking: bool,
+}
+
+impl MemoryTracker {
+    /// `limit_mb_per_min` is the steady growth reported as a leak.
+    pub fn new(limit_mb_per_min: f64) -> Self {
+        MemoryTracker {
+            limit_bytes_per_s: limit_mb_per_min * 1e6 / 60.0,
+            rss: Vec::new(),
+            counting: BTreeMap::new(),
+            live_objects: BTreeMap::new(),
+            first_objects: None,
+            leaking: false,
+        }
+    }
+
+    /// Count a leaks tracer `object-alive` line. Returns whether it was one.
+    pub fn observe_line(&mut self, line: &str) -> bool {
+        match parse_live_object(line) {
+            Some(type_name) => {
+                *self.counting.entry(type_name).or_default() += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The object types whose live count grew most since the first dump,
+    /// largest growth first.
+    pub fn growing_objects(&self) -> Vec<(String, i64)> {
+        let Some(first) = &self.first_objects else {
+            return Vec::new();
+        };
+        let mut growing: Vec<(String, i64)> = self
+            .live_objects
+            .iter()
+            .map(|(name, count)| {
+                let before = first.get(name).copied().unwrap_or(0);
+                (name.clone(), *count as i64 - before as i64)
+            })
+            .filter(|(_, growth)| *growth > 0)
+            .collect();
+        growing.sort_by_key(|(_, growth)| std::cmp::Reverse(*growth));
+        growing
+    }
+
+    /// Take a sample at `timestamp_ns` with the resident set, if it could be
+    /// read, and the objects counted since the previous sample. Also returns
+    /// a notice when the growth crossed the limit either way.
+    pub fn sample(
+        &mut self,
+        timestamp_ns: u64,
+        rss_bytes: Option<u64>,
+    ) -> (MemoryUsage, Option<String>) {
+        if !self.counting.is_empty() {
+            self.live_objects = std::mem::take(&mut self.counting);
+            self.first_objects.get_or_insert_with(|| self.live_objects.clone());
+        }
+        if let Some(bytes) = rss_bytes {
+            self.rss.push((timestamp_ns, bytes));
+            let since_ns = timestamp_ns.saturating_sub(GROWTH_WINDOW_NS);
+            self.rss.retain(|(ns, _)| *ns >= since_ns);
+        }
+        let growth = steady_growth(&self.rss);
+        let leaking = growth.is_some_and(|growth| growth > self.limit_bytes_per_s);
+        let notice = match (self.leaking, leaking) {
+            (false, true) => {
+                let mut notice = format!(
+                    "Memory grows steadily by {:.2} MB/min (limit {:.2} MB/min)",
+                    growth.unwrap_or(0.0) * 60.0 / 1e6,
+                    self.limit_bytes_per_s * 60.0 / 1e6
+                );
+                let objects: Vec<String> = self
+                    .growing_objects()
+                    .into_iter()
+                    .take(3)
+                    .map(|(name, growth)| format!("{} +{}", name, growth))
+                    .collect();
+                if !objects.is_empty() {
+                    notice.push_str(&format!("; growing objects: {}", objects.join(", ")));
+                }
+                Some(notice)
+            }
+            (true, false) => Some("Memory stopped growing".to_string()),
+            _ => None,
+        };
+        self.leaking = leaking;
+        let usage = MemoryUsage {
+            timestamp_ns,
+            rss_bytes,
+            live_objects: self.live_objects.clone(),
+            growth_bytes_per_s: growth,
+            limit_bytes_per_s: self.limit_bytes_per_s,
+        };
+        (usage, notice)
+    }
+}
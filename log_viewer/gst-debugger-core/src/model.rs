@@ -0,0 +1,589 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TracingData {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub bitrate: Option<u64>,
+    pub framerate: Option<f64>,
+    pub proctime_ns: Option<u64>,
+    pub jitter_ns: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterLatencyData {
+    pub timestamp_ns: u64,
+    pub from: String,
+    pub to: String,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineCommand {
+    Play,
+    Pause,
+    Stop,
+    Restart,
+    Relaunch(String),
+    Seek(u64),
+    SendEos,
+    SetTracers(String),
+    QueryProperties(String),
+    SetProperty {
+        element: String,
+        name: String,
+        value: String,
+    },
+    /// Play the streams with these ids from the collection of `element`.
+    SelectStreams {
+        element: String,
+        stream_ids: Vec<String>,
+    },
+    /// Record a timeline marker in the log of the run.
+    Annotate(Annotation),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PipelineState {
+    Playing,
+    Paused,
+    Stopped,
+    Restarting,
+}
+
+/// Serialized as a JSON object with the event in `kind`, as the HTTP API
+/// and scripts get it, e.g. `{"kind":"eos","element":"fakesink0"}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PipelineEvent {
+    #[serde(serialize_with = "state_field")]
+    State(PipelineState),
+    // Position and property events are only reported by backends that can
+    // query the running pipeline.
+    Position { position_ns: u64, duration_ns: u64 },
+    #[serde(rename = "eos", serialize_with = "element_field")]
+    ElementEos(String),
+    Properties {
+        element: String,
+        properties: Vec<ElementProperty>,
+    },
+    PropertyChanged {
+        element: String,
+        name: String,
+        value: String,
+    },
+    #[serde(serialize_with = "message_field")]
+    Notice(String),
+    Error(PipelineError),
+    Restarting {
+        attempt: u32,
+        #[serde(rename = "delay_ms", serialize_with = "millis")]
+        delay: Duration,
+    },
+    /// A message from the pipeline bus, for the event timeline.
+    Bus(BusMessage),
+    #[serde(rename = "level")]
+    AudioLevel(AudioLevel),
+    #[serde(rename = "webrtc")]
+    WebRtcStats(WebRtcStats),
+    #[serde(rename = "jitterbuffer")]
+    JitterBuffer(JitterBufferStats),
+    #[serde(rename = "rtpsession")]
+    RtpSession(RtpSessionStats),
+    Rtsp(RtspSourceStats),
+    Streams(StreamCollection),
+    Clock(ClockStats),
+    #[serde(rename = "avsync")]
+    AvSync(AvSync),
+    SinkFrames(SinkFrames),
+    Latency(LatencyReport),
+    Segment(Segment),
+    Memory(MemoryUsage),
+    Threads(StreamingThreads),
+    QueueLevel(QueueLevel),
+    /// A timeline marker read back from a recorded log.
+    Annotation(Annotation),
+    /// The tracer log the run is written to, or the one being replayed;
+    /// once an archived run exits, its `.tar.zst`. The timestamps of the
+    /// run are the log's plus `base_ns`.
+    LogFile { path: PathBuf, base_ns: u64 },
+    /// Caps an element's pad negotiated, as `gst-launch-1.0 -v` prints them.
+    Caps {
+        element: String,
+        pad: String,
+        caps: String,
+    },
+}
+
+fn single_field<S: Serializer, T: Serialize + ?Sized>(
+    serializer: S,
+    key: &'static str,
+    value: &T,
+) -> Result<S::Ok, S::Error> {
+    let mut fields = serializer.serialize_struct("PipelineEvent", 1)?;
+    fields.serialize_field(key, value)?;
+    fields.end()
+}
+
+fn state_field<S: Serializer>(state: &PipelineState, serializer: S) -> Result<S::Ok, S::Error> {
+    single_field(serializer, "state", state)
+}
+
+fn element_field<S: Serializer>(element: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    single_field(serializer, "element", element)
+}
+
+fn message_field<S: Serializer>(message: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    single_field(serializer, "message", message)
+}
+
+fn millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Per-channel levels reported by a `level` element, in dB.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioLevel {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub rms_db: Vec<f64>,
+    pub peak_db: Vec<f64>,
+}
+
+/// Serialized as its label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BusMessageKind {
+    StateChanged,
+    NewClock,
+    ClockLost,
+    Tag,
+    Buffering,
+    Latency,
+    Warning,
+    Eos,
+}
+
+impl BusMessageKind {
+    pub const ALL: [BusMessageKind; 8] = [
+        BusMessageKind::StateChanged,
+        BusMessageKind::NewClock,
+        BusMessageKind::ClockLost,
+        BusMessageKind::Tag,
+        BusMessageKind::Buffering,
+        BusMessageKind::Latency,
+        BusMessageKind::Warning,
+        BusMessageKind::Eos,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BusMessageKind::StateChanged => "state",
+            BusMessageKind::NewClock => "new clock",
+            BusMessageKind::ClockLost => "clock lost",
+            BusMessageKind::Tag => "tag",
+            BusMessageKind::Buffering => "buffering",
+            BusMessageKind::Latency => "latency",
+            BusMessageKind::Warning => "warning",
+            BusMessageKind::Eos => "eos",
+        }
+    }
+}
+
+impl Serialize for BusMessageKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BusMessage {
+    pub timestamp_ns: u64,
+    #[serde(rename = "type")]
+    pub kind: BusMessageKind,
+    /// Name of the element or bin that posted the message.
+    pub source: String,
+    /// E.g. `Paused -> Playing` or `45%`.
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PipelineError {
+    pub message: String,
+    pub debug: String,
+    pub element: Option<String>,
+    pub recent_lines: Vec<String>,
+    /// For not-negotiated errors, the link that could not agree on caps.
+    pub caps: Option<CapsMismatch>,
+}
+
+/// The caps on both ends of a link that failed to negotiate: what the
+/// source pad can produce and what the sink pad accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapsMismatch {
+    /// Pads as `element:pad`.
+    pub src_pad: String,
+    pub sink_pad: String,
+    pub offered: String,
+    pub accepted: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ElementProperty {
+    pub name: String,
+    pub type_name: String,
+    pub value: String,
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamDirection {
+    Inbound,
+    Outbound,
+}
+
+impl StreamDirection {
+    pub fn label(self) -> &'static str {
+        match self {
+            StreamDirection::Inbound => "inbound",
+            StreamDirection::Outbound => "outbound",
+        }
+    }
+}
+
+/// One RTP stream of a `webrtcbin`. For outbound streams, loss, jitter and
+/// RTT come from the receiver reports of the remote peer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebRtcStream {
+    pub direction: StreamDirection,
+    pub ssrc: u32,
+    /// `audio`, `video`, or empty when webrtcbin does not say.
+    pub kind: String,
+    /// Bits per second since the previous stats, `None` on the first.
+    pub bitrate: Option<u64>,
+    pub packets: u64,
+    pub packets_lost: Option<i64>,
+    pub fraction_lost: Option<f64>,
+    pub jitter_ns: Option<u64>,
+    pub rtt_ns: Option<u64>,
+}
+
+/// The stats of one `webrtcbin`, condensed from its `get-stats` reply.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebRtcStats {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub streams: Vec<WebRtcStream>,
+}
+
+/// The `stats` property of an `rtpjitterbuffer`. The packet counts are
+/// totals since the jitterbuffer started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JitterBufferStats {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub pushed: u64,
+    pub lost: u64,
+    pub late: u64,
+    pub duplicates: u64,
+    pub avg_jitter_ns: u64,
+}
+
+/// One SSRC of an `rtpsession`. Our own sources report what the receivers
+/// sent back in RTCP receiver reports; remote sources report what we
+/// measured while receiving them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RtpSource {
+    pub ssrc: u32,
+    /// Whether the source is sent by this pipeline rather than received.
+    pub internal: bool,
+    pub cname: Option<String>,
+    pub bitrate: u64,
+    pub packets: u64,
+    pub packets_lost: Option<i64>,
+    pub fraction_lost: Option<f64>,
+    pub jitter_ns: Option<u64>,
+    pub rtt_ns: Option<u64>,
+}
+
+/// The sources of one `rtpsession`, read from its `stats` property.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RtpSessionStats {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub sources: Vec<RtpSource>,
+}
+
+/// One stream an `rtspsrc` receives, measured on its source pad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RtspStream {
+    pub pad: String,
+    /// Media and encoding from the pad caps, e.g. `video H264`.
+    pub media: String,
+    pub bitrate: u64,
+    pub packets: u64,
+    /// Time since the last packet arrived.
+    pub idle_ns: u64,
+}
+
+/// What an `rtspsrc` negotiated with the server and how its streams flow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RtspSourceStats {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub location: String,
+    /// The latest progress rtspsrc reported, e.g. `Opened Stream`.
+    pub state: String,
+    /// `UDP` or `TCP`, `None` until the streams are set up.
+    pub transport: Option<String>,
+    pub sdp: Option<String>,
+    pub streams: Vec<RtspStream>,
+    /// Whether the server stopped sending data while playing.
+    pub stalled: bool,
+}
+
+/// The clock the pipeline selected and how it keeps time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClockStats {
+    pub timestamp_ns: u64,
+    pub name: String,
+    /// `system`, `audio`, `PTP`, `NTP`, `network` or the type name of any
+    /// other clock.
+    #[serde(rename = "clock_kind")]
+    pub kind: String,
+    /// The element that provides the clock, `None` for the system clock.
+    pub provider: Option<String>,
+    /// The calibrated rate of the clock against its internal time.
+    pub rate: f64,
+    /// The calibrated external minus internal time.
+    pub offset_ns: i64,
+    pub running_time_ns: u64,
+    /// How far running time got ahead of the monotonic system time since
+    /// the clock was selected or the pipeline last started playing.
+    pub drift_ns: i64,
+}
+
+/// How far the video at `video_sink` is presented behind the audio at
+/// `audio_sink`; negative when the video is ahead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AvSync {
+    pub timestamp_ns: u64,
+    pub video_sink: String,
+    pub audio_sink: String,
+    pub offset_ns: i64,
+    /// The offset either way that is reported as out of sync.
+    pub limit_ns: u64,
+}
+
+/// Memory of the pipeline process: its resident set and, with the leaks
+/// tracer, how many objects of each type are alive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryUsage {
+    pub timestamp_ns: u64,
+    /// `None` where the process cannot be looked into, e.g. on a remote target.
+    pub rss_bytes: Option<u64>,
+    /// Live objects by type name, from the latest dump of the leaks tracer.
+    pub live_objects: BTreeMap<String, u64>,
+    /// How fast the resident set grew lately, when it grew steadily.
+    pub growth_bytes_per_s: Option<f64>,
+    /// The steady growth that is reported as a leak.
+    pub limit_bytes_per_s: f64,
+}
+
+/// How full a queue was, from GstShark's `queuelevel` tracer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueLevel {
+    pub timestamp_ns: u64,
+    pub queue: String,
+    pub buffers: u64,
+    pub bytes: u64,
+    pub time_ns: u64,
+    /// The fullest of the queue's limits, from 0 to 1.
+    pub fill: f64,
+}
+
+/// The elements each streaming thread ran, from the thread column of the
+/// tracer lines, busiest thread first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamingThreads {
+    pub timestamp_ns: u64,
+    pub threads: Vec<StreamingThread>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamingThread {
+    /// The thread as GStreamer logs it, e.g. `0x55d5c0a4e300`.
+    pub thread: String,
+    /// Busiest element first.
+    pub elements: Vec<ThreadElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreadElement {
+    pub element: String,
+    /// Share of the thread's time spent processing in the element, from 0
+    /// to 1.
+    pub busy: f64,
+    /// Whether the element takes enough of the thread to slow down the
+    /// others on it.
+    pub heavy: bool,
+}
+
+/// Frames a video sink rendered and dropped so far, with the share of the
+/// frames since the previous sample it dropped.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SinkFrames {
+    pub timestamp_ns: u64,
+    pub sink: String,
+    pub rendered: u64,
+    pub dropped: u64,
+    pub drop_percent: f64,
+    /// The drop rate above which the sink is reported.
+    pub limit_percent: f64,
+}
+
+/// The latency the chain of elements upstream of `sink` reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainLatency {
+    pub sink: String,
+    pub live: bool,
+    pub min_ns: u64,
+    /// `None` when the chain can buffer without bound.
+    pub max_ns: Option<u64>,
+}
+
+/// The answer to a latency query on the pipeline and on each sink's chain,
+/// with the latency the pipeline configured from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyReport {
+    pub timestamp_ns: u64,
+    pub live: bool,
+    pub min_ns: u64,
+    pub max_ns: Option<u64>,
+    pub configured_ns: Option<u64>,
+    pub chains: Vec<ChainLatency>,
+}
+
+/// A fragment `splitmuxsink` or a segment `hlssink2` finished writing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Segment {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub location: String,
+    /// The running time the segment ended at.
+    pub running_time_ns: Option<u64>,
+    pub duration_ns: Option<u64>,
+    pub size_bytes: Option<u64>,
+}
+
+/// A stream a demuxer or decodebin3 offers to play.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MediaStream {
+    pub stream_id: String,
+    /// `video`, `audio`, `text`, `container` or `unknown`.
+    pub kind: String,
+    /// The codec from the tags, e.g. `H.264`, or else the media type of the
+    /// caps.
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub selected: bool,
+}
+
+/// The streams an element posted in its latest stream collection, with
+/// the ones it currently plays marked as selected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamCollection {
+    pub timestamp_ns: u64,
+    pub element: String,
+    pub streams: Vec<MediaStream>,
+}
+
+/// A pad template of an element factory, as gst-inspect lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PadTemplateInfo {
+    pub name: String,
+    /// `src` or `sink`.
+    pub direction: String,
+    /// `always`, `sometimes` or `request`.
+    pub presence: String,
+    pub caps: String,
+}
+
+/// A property of an element factory with its default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyInfo {
+    pub name: String,
+    pub type_name: String,
+    pub default: String,
+    pub blurb: String,
+    pub writable: bool,
+}
+
+/// What the registry knows about an element factory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementInfo {
+    pub factory: String,
+    pub long_name: String,
+    pub klass: String,
+    pub description: String,
+    pub author: String,
+    pub plugin: Option<String>,
+    pub pad_templates: Vec<PadTemplateInfo>,
+    pub properties: Vec<PropertyInfo>,
+}
+
+/// An element factory in the registry, for the builder's palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactoryEntry {
+    pub name: String,
+    pub long_name: String,
+    pub klass: String,
+}
+
+/// A downscaled video frame for the preview panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed RGBA rows.
+    pub rgba: Vec<u8>,
+}
+
+/// A named marker on the timeline of every chart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub timestamp_ns: u64,
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn events_serialize_with_their_kind() {
+        let json = |event: PipelineEvent| serde_json::to_value(event).unwrap();
+        assert_eq!(
+            json(PipelineEvent::State(PipelineState::Playing)),
+            json!({ "kind": "state", "state": "Playing" })
+        );
+        assert_eq!(
+            json(PipelineEvent::ElementEos("fakesink0".to_string())),
+            json!({ "kind": "eos", "element": "fakesink0" })
+        );
+        assert_eq!(
+            json(PipelineEvent::Restarting {
+                attempt: 2,
+                delay: Duration::from_millis(1500),
+            }),
+            json!({ "kind": "restarting", "attempt": 2, "delay_ms": 1500 })
+        );
+    }
+}
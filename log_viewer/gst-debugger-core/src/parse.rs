@@ -0,0 +1,507 @@
+use std::collections::VecDeque;
+use std::process::ExitStatus;
+
+use regex::Regex;
+
+use crate::model::{Annotation, InterLatencyData, PipelineError, TracingData};
+
+pub const RECENT_LINE_COUNT: usize = 50;
+
+/// What the lines a pipeline prints to stdout start with in the tracer log
+/// and the raw log panel, to tell them from its debug output on stderr.
+pub const STDOUT_TAG: &str = "[stdout] ";
+
+/// The debug category of the tracer lines.
+pub const TRACER_CATEGORY: &str = "GST_TRACER";
+const LEVELS: [&str; 8] = ["ERROR", "WARN", "FIXME", "INFO", "DEBUG", "LOG", "TRACE", "MEMDUMP"];
+
+/// What follows the process and thread columns of a marker line.
+const ANNOTATION_TAG: &str = "INFO gst_debugger annotation: ";
+/// What a native tracer sample's record starts with in the tracer log.
+const NATIVE_RECORD: &str = ":0:: gstdebugger, ";
+
+#[derive(Debug, Default)]
+pub struct ErrorTracker {
+    recent_lines: VecDeque<String>,
+    error: Option<PipelineError>,
+    debug_lines_pending: usize,
+}
+
+impl ErrorTracker {
+    pub fn observe(&mut self, line: &str) {
+        if self.recent_lines.len() == RECENT_LINE_COUNT {
+            self.recent_lines.pop_front();
+        }
+        self.recent_lines.push_back(line.to_string());
+
+        if self.debug_lines_pending > 0 {
+            self.debug_lines_pending -= 1;
+            if let Some(error) = &mut self.error {
+                if !error.debug.is_empty() {
+                    error.debug.push('\n');
+                }
+                error.debug.push_str(line);
+            }
+        } else if line.trim() == "Additional debug info:"
+            && self.error.as_ref().is_some_and(|error| error.debug.is_empty())
+        {
+            self.debug_lines_pending = 2;
+        } else if let Some((element, message)) =
+            parse_error_line(line).filter(|_| self.error.is_none())
+        {
+            self.error = Some(PipelineError {
+                message,
+                debug: String::new(),
+                element,
+                recent_lines: Vec::new(),
+                caps: None,
+            });
+        }
+    }
+
+    pub fn finish(&mut self, exit: Option<ExitStatus>, stop_requested: bool) -> Option<PipelineError> {
+        let mut error = self.error.take().or_else(|| match exit {
+            Some(status) if !status.success() && !stop_requested => Some(PipelineError {
+                message: format!("gst-launch exited unexpectedly ({})", status),
+                debug: String::new(),
+                element: None,
+                recent_lines: Vec::new(),
+                caps: None,
+            }),
+            _ => None,
+        })?;
+        error.recent_lines = self.recent_lines.iter().cloned().collect();
+        Some(error)
+    }
+}
+
+/// A GStreamer debug line split into its columns, e.g. `0:00:01.2 4242
+/// 0x55d5c0a4e300 WARN rtpjitterbuffer gstrtpjitterbuffer.c:1234:chain:<rtpjitterbuffer0>
+/// Packet #123 too late`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugLine<'a> {
+    pub timestamp: &'a str,
+    pub level: &'a str,
+    pub category: &'a str,
+    /// `file:line:function:<object>`, where the object may be missing.
+    pub location: &'a str,
+    pub message: &'a str,
+}
+
+impl DebugLine<'_> {
+    /// GStreamer's number for the level, from 1 for ERROR to 9 for MEMDUMP.
+    pub fn severity(&self) -> u8 {
+        match self.level {
+            "ERROR" => 1,
+            "WARN" => 2,
+            "FIXME" => 3,
+            "INFO" => 4,
+            "DEBUG" => 5,
+            "LOG" => 6,
+            "TRACE" => 7,
+            _ => 9,
+        }
+    }
+}
+
+pub fn parse_debug_line(line: &str) -> Option<DebugLine<'_>> {
+    let mut rest = line;
+    let timestamp = next_column(&mut rest)?;
+    // The process and thread.
+    next_column(&mut rest)?;
+    next_column(&mut rest)?;
+    let level = next_column(&mut rest)?;
+    if !LEVELS.contains(&level) {
+        return None;
+    }
+    let category = next_column(&mut rest)?;
+    let location = next_column(&mut rest).unwrap_or_default();
+    Some(DebugLine {
+        timestamp,
+        level,
+        category,
+        location,
+        message: rest.trim_start(),
+    })
+}
+
+/// A line GStreamer logged to Android's logcat, as `adb logcat -v
+/// threadtime` prints it, rewritten to the layout of a GST_DEBUG line so the
+/// same parsers read it. `None` for lines other programs logged.
+///
+/// `05-16 10:23:45.123  1234  1250 V GStreamer+GST_TRACER: 0:00:01.000000000
+/// 0x7b2c :0::0x0 proctime, ...` becomes `0:00:01.000000000 1234 0x7b2c TRACE
+/// GST_TRACER :0:: proctime, ...`.
+pub fn parse_logcat_line(line: &str) -> Option<String> {
+    let mut rest = line;
+    // The date and time of logcat.
+    next_column(&mut rest)?;
+    next_column(&mut rest)?;
+    let process = next_column(&mut rest)?;
+    next_column(&mut rest)?;
+    // GStreamer logs LOG, TRACE and MEMDUMP as verbose.
+    let level = match next_column(&mut rest)? {
+        "E" => "ERROR",
+        "W" => "WARN",
+        "I" => "INFO",
+        "D" => "DEBUG",
+        "V" => "TRACE",
+        _ => return None,
+    };
+    let (tag, message) = rest.split_once(':')?;
+    let category = tag.trim().strip_prefix("GStreamer+")?;
+    let mut rest = message;
+    let timestamp = next_column(&mut rest)?;
+    let thread = next_column(&mut rest)?;
+    let location = next_column(&mut rest)?;
+    // Without an object, logcat gets its address, e.g. `0x0`, where
+    // GST_DEBUG leaves the object out.
+    let mut parts = location.splitn(4, ':');
+    let (file, line_number, function) = (parts.next()?, parts.next()?, parts.next()?);
+    let object = parts.next().filter(|object| object.starts_with('<')).unwrap_or_default();
+    Some(format!(
+        "{} {} {} {} {} {}:{}:{}:{} {}",
+        timestamp,
+        process,
+        thread,
+        level,
+        category,
+        file,
+        line_number,
+        function,
+        object,
+        rest.trim_start()
+    ))
+}
+
+/// The debug category of a GStreamer debug line, the column after the
+/// level.
+pub fn parse_line_category(line: &str) -> Option<&str> {
+    parse_debug_line(line).map(|debug| debug.category)
+}
+
+/// Take the next whitespace-separated column off the front of `rest`.
+fn next_column<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_start();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (column, tail) = trimmed.split_at(end);
+    *rest = tail;
+    Some(column)
+}
+
+pub fn parse_gst_tracer_output(line: &str) -> Option<TracingData> {
+    if line.contains(NATIVE_RECORD) {
+        return parse_native_sample_line(line);
+    }
+    let bitrate_re = Regex::new(r"bitrate.*pad=\(string\)(\S+), bitrate=\(guint64\)(\d+);").ok()?;
+    let framerate_re = Regex::new(r"framerate.*pad=\(string\)(\S+), fps=\(uint\)(\d+);").ok()?;
+    let proctime_re = Regex::new(r"proc_time, element=\(string\)(\S+), time=\(string\)(\S+);").ok()?;
+
+   if let Some(caps) = bitrate_re.captures(line) {
+    return Some(TracingData {
+        timestamp_ns: parse_line_timestamp(line),
+        element: extract_element_name(&caps[1]),
+        bitrate: Some(caps[2].parse().ok()?),
+        framerate: None,
+        proctime_ns: None,
+        jitter_ns: None,
+    });
+}
+
+if let Some(caps) = framerate_re.captures(line) {
+    return Some(TracingData {
+        timestamp_ns: parse_line_timestamp(line),
+        element: extract_element_name(&caps[1]),
+        bitrate: None,
+        framerate: Some(caps[2].parse().ok()?),
+        proctime_ns: None,
+        jitter_ns: None,
+    });
+}
+
+    if let Some(caps) = proctime_re.captures(line) {
+        let element = extract_element_name(&caps[1]);
+        let time_str = &caps[2];
+
+        if let Some(ns) = parse_duration_to_ns(time_str) {
+            return Some(TracingData {
+                timestamp_ns: parse_line_timestamp(line),
+                element,
+                bitrate: None,
+                framerate: None,
+                proctime_ns: Some(ns),
+                jitter_ns: None,
+            });
+        }
+    }
+
+    None
+}
+
+/// The pad, log timestamp and time since the pad's previous buffer in a
+/// line of GstShark's `scheduletime` tracer.
+pub fn parse_schedule_time(line: &str) -> Option<(String, u64, u64)> {
+    let regex = Regex::new(r"scheduling.*pad=\(string\)(\S+), time=\(string\)(\S+);").ok()?;
+    let caps = regex.captures(line)?;
+    let interval_ns = parse_duration_to_ns(&caps[2])?;
+    Some((caps[1].to_string(), parse_line_timestamp(line), interval_ns))
+}
+
+pub fn format_clock_time(ns: u64) -> String {
+    let total_seconds = ns / 1_000_000_000;
+    format!(
+        "{}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds / 60) % 60,
+        total_seconds % 60
+    )
+}
+
+pub fn parse_line_timestamp(line: &str) -> u64 {
+    line.split_whitespace()
+        .next()
+        .and_then(parse_duration_to_ns)
+        .unwrap_or(0)
+}
+
+pub fn parse_duration_to_ns(time_str: &str) -> Option<u64> {
+    let parts: Vec<&str> = time_str.split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let hours = parts[0].parse::<u64>().ok()?;
+    let minutes = parts[1].parse::<u64>().ok()?;
+    let secs_frac: Vec<&str> = parts[2].split('.').collect();
+    let seconds = secs_frac.first()?.parse::<u64>().ok()?;
+    let nanoseconds = secs_frac.get(1).unwrap_or(&"0").parse::<u64>().ok()?;
+
+    Some(hours * 3_600_000_000_000
+        + minutes * 60_000_000_000
+        + seconds * 1_000_000_000
+        + nanoseconds)
+}
+
+pub fn parse_interlatency(line: &str) -> Option<InterLatencyData> {
+    let regex = Regex::new(r"interlatency.*from_pad=\(string\)(\S+), to_pad=\(string\)(\S+), time=\(string\)(\S+);").ok()?;
+    let caps = regex.captures(line)?;
+
+    let mut from = extract_element_name(&caps[1]);
+    from.truncate(from.len() - 1);
+    let to = caps[2].split('.').next()?.split('_').next()?.to_string();
+
+    Some(InterLatencyData {
+        timestamp_ns: parse_line_timestamp(line),
+        from,
+        to,
+        time: caps[3].to_string(),
+    })
+}
+
+pub fn parse_error_line(line: &str) -> Option<(Option<String>, String)> {
+    let rest = line.trim().strip_prefix("ERROR: ")?;
+    if let Some(rest) = rest.strip_prefix("from element ") {
+        let (path, message) = rest.split_once(": ")?;
+        let element = path.rsplit('/').next()?;
+        let element = element.split_once(':').map_or(element, |(_, name)| name);
+        return Some((Some(element.to_string()), message.to_string()));
+    }
+    Some((None, rest.to_string()))
+}
+
+pub fn parse_eos_event(line: &str) -> Option<String> {
+    if !line.contains("eos event") {
+        return None;
+    }
+    let regex = Regex::new(r"<([^:>]+):[^>]*>.*\beos event: ").ok()?;
+    let caps = regex.captures(line)?;
+    Some(caps[1].to_string())
+}
+
+/// A timeline marker as a line of the tracer log, in the layout of a
+/// GStreamer debug line: `0:00:05.250000000 0 0x0 INFO gst_debugger
+/// annotation: switched to WiFi`.
+pub fn format_annotation_line(annotation: &Annotation) -> String {
+    let ns = annotation.timestamp_ns;
+    format!(
+        "{}.{:09} 0 0x0 {}{}",
+        format_clock_time(ns),
+        ns % 1_000_000_000,
+        ANNOTATION_TAG,
+        annotation.text.replace('\n', " ")
+    )
+}
+
+/// A sample of the native `gstdebugger` tracer as a tracer line of the
+/// log, so a replay or `analyze` of the log finds it: `0:00:05.250000000 0
+/// 0x0 TRACE GST_TRACER :0:: gstdebugger, element=(string)queue0,
+/// bitrate=(guint64)800000, fps=(double)30;`.
+pub fn format_native_sample_line(sample: &TracingData) -> String {
+    let ns = sample.timestamp_ns;
+    let mut line = format!(
+        "{}.{:09} 0 0x0 TRACE {} {}element=(string){}",
+        format_clock_time(ns),
+        ns % 1_000_000_000,
+        TRACER_CATEGORY,
+        NATIVE_RECORD,
+        sample.element
+    );
+    if let Some(bitrate) = sample.bitrate {
+        line.push_str(&format!(", bitrate=(guint64){}", bitrate));
+    }
+    if let Some(framerate) = sample.framerate {
+        line.push_str(&format!(", fps=(double){}", framerate));
+    }
+    if let Some(proctime_ns) = sample.proctime_ns {
+        line.push_str(&format!(", proctime=(guint64){}", proctime_ns));
+    }
+    if let Some(jitter_ns) = sample.jitter_ns {
+        line.push_str(&format!(", jitter=(guint64){}", jitter_ns));
+    }
+    line.push(';');
+    line
+}
+
+fn parse_native_sample_line(line: &str) -> Option<TracingData> {
+    let (_, record) = line.split_once(NATIVE_RECORD)?;
+    let mut sample = TracingData {
+        timestamp_ns: parse_line_timestamp(line),
+        element: String::new(),
+        bitrate: None,
+        framerate: None,
+        proctime_ns: None,
+        jitter_ns: None,
+    };
+    for field in record.trim_end().strip_suffix(';')?.split(", ") {
+        let (key, value) = field.split_once('=')?;
+        let value = value.split_once(')').map_or(value, |(_, value)| value);
+        match key {
+            "element" => sample.element = value.to_string(),
+            "bitrate" => sample.bitrate = value.parse().ok(),
+            "fps" => sample.framerate = value.parse().ok(),
+            "proctime" => sample.proctime_ns = value.parse().ok(),
+            "jitter" => sample.jitter_ns = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (!sample.element.is_empty()).then_some(sample)
+}
+
+pub fn parse_annotation_line(line: &str) -> Option<Annotation> {
+    let (_, text) = line.split_once(ANNOTATION_TAG)?;
+    Some(Annotation {
+        timestamp_ns: parse_line_timestamp(line),
+        text: text.to_string(),
+    })
+}
+
+/// The record a tracer line logs, such as `interlatency` for
+/// `0:00:01.2 4242 0x55d5c0a4e300 TRACE GST_TRACER :0:: interlatency, ...`.
+pub fn parse_tracer_record(line: &str) -> Option<&str> {
+    if parse_line_category(line)? != TRACER_CATEGORY {
+        return None;
+    }
+    let (_, record) = line.split_once(":: ")?;
+    let name = record.split(',').next()?.trim();
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some(name)
+}
+
+/// The element, pad and caps of a caps notification that
+/// `gst-launch-1.0 -v` prints, such as
+/// `/GstPipeline:pipeline0/GstCapsFilter:capsfilter0.GstPad:src: caps = video/x-raw`.
+pub fn parse_caps_notify(line: &str) -> Option<(String, String, String)> {
+    let line = line.strip_prefix(STDOUT_TAG).unwrap_or(line);
+    let (path, caps) = line.trim().split_once(": caps = ")?;
+    // A ghost pad is followed by its proxy pad, which is left out.
+    let (element_path, pads) = path.split_once(".Gst")?;
+    let element = element_path.rsplit('/').next()?;
+    let element = element.split_once(':').map_or(element, |(_, name)| name);
+    let pad = pads.split(".Gst").next()?;
+    let (_, pad) = pad.split_once(':')?;
+    Some((element.to_string(), pad.to_string(), caps.to_string()))
+}
+
+/// The names from the pipeline down to the element in an object path that
+/// `gst-launch-1.0 -v` prints, e.g. `pipeline0`, `bin0`, `x264enc0` for
+/// `/GstPipeline:pipeline0/GstBin:bin0/GstX264Enc:x264enc0.GstPad:src: ...`.
+pub fn parse_object_path(line: &str) -> Option<Vec<String>> {
+    let line = line.strip_prefix(STDOUT_TAG).unwrap_or(line);
+    let path = line.trim().strip_prefix('/')?;
+    let path = path.split([' ', '.']).next()?.trim_end_matches(':');
+    let names: Option<Vec<String>> = path
+        .split('/')
+        .map(|object| object.split_once(':').map(|(_, name)| name.to_string()))
+        .collect();
+    names.filter(|names| !names.is_empty())
+}
+
+pub fn extract_element_name(pad_name: &str) -> String {
+    pad_name.split('_').next().unwrap_or(pad_name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_samples_round_trip_through_the_log() {
+        let sample = TracingData {
+            timestamp_ns: 5_250_000_000,
+            element: "queue0".to_string(),
+            bitrate: Some(800_000),
+            framerate: Some(29.97),
+            proctime_ns: None,
+            jitter_ns: Some(1_200),
+        };
+        let line = format_native_sample_line(&sample);
+        assert_eq!(parse_tracer_record(&line), Some("gstdebugger"));
+        assert_eq!(parse_gst_tracer_output(&line), Some(sample));
+    }
+
+    #[test]
+    fn logcat_lines_read_like_debug_lines() {
+        let tracer = "05-16 10:23:45.123  1234  1250 V GStreamer+GST_TRACER: 0:00:01.000000000 \
+                      0x7b2c :0::0x0 proctime, element=(string)enc0;";
+        let line = parse_logcat_line(tracer).unwrap();
+        assert_eq!(
+            line,
+            "0:00:01.000000000 1234 0x7b2c TRACE GST_TRACER :0:: proctime, element=(string)enc0;"
+        );
+        assert_eq!(parse_tracer_record(&line), Some("proctime"));
+
+        let warning = "05-16 10:23:45.124  1234  1250 W GStreamer+basesink: 0:00:01.100000000 \
+                       0x7b2c gstbasesink.c:3143:gst_base_sink_chain:<sink> dropping";
+        let debug = parse_logcat_line(warning).unwrap();
+        let debug = parse_debug_line(&debug).unwrap();
+        assert_eq!(debug.level, "WARN");
+        assert_eq!(debug.category, "basesink");
+        assert_eq!(debug.location, "gstbasesink.c:3143:gst_base_sink_chain:<sink>");
+        assert_eq!(debug.message, "dropping");
+
+        let other = "05-16 10:23:45.125  900  900 I ActivityManager: Start proc";
+        assert_eq!(parse_logcat_line(other), None);
+    }
+
+    #[test]
+    fn debug_lines_split_into_their_columns() {
+        let line = "0:00:02.500000000 4242 0x55d0 WARN  basesrc gstbasesrc.c:3127:\
+                    gst_base_src_loop:<src> error: Internal data stream error.";
+        let debug = parse_debug_line(line).unwrap();
+        assert_eq!(debug.timestamp, "0:00:02.500000000");
+        assert_eq!(debug.level, "WARN");
+        assert_eq!(debug.severity(), 2);
+        assert_eq!(debug.category, "basesrc");
+        assert_eq!(debug.location, "gstbasesrc.c:3127:gst_base_src_loop:<src>");
+        assert_eq!(debug.message, "error: Internal data stream error.");
+        assert_eq!(parse_line_category(line), Some("basesrc"));
+        assert_eq!(parse_line_timestamp(line), 2_500_000_000);
+
+        assert!(parse_debug_line("Setting pipeline to PLAYING ...").is_none());
+        assert!(parse_debug_line("").is_none());
+    }
+}
@@ -0,0 +1,236 @@
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub path: String,
+    pub encoder: String,
+}
+
+pub fn split_pipeline_args(pipeline: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = pipeline.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Element names of a linear pipeline description, in link order: the
+/// `name=` of an element when set, otherwise its factory name.
+pub fn pipeline_elements(pipeline: &str) -> Vec<String> {
+    pipeline
+        .split('!')
+        .map(|segment| {
+            let mut tokens = segment.split_whitespace();
+            let factory = tokens.next().unwrap_or_default();
+            let named = tokens.find_map(|token| token.strip_prefix("name="));
+            named.unwrap_or(factory).to_string()
+        })
+        .collect()
+}
+
+/// The factory of the element called `name` in a pipeline description,
+/// given either by its `name=` or, without one, by the factory itself.
+pub fn element_factory(pipeline: &str, name: &str) -> Option<String> {
+    pipeline.split('!').find_map(|segment| {
+        let mut tokens = segment.split_whitespace();
+        let factory = tokens.next()?;
+        let named = tokens.find_map(|token| token.strip_prefix("name="));
+        (named.unwrap_or(factory) == name || factory == name).then(|| factory.to_string())
+    })
+}
+
+const RECORD_TEE: &str = "gst_debugger_record";
+const PREVIEW_TEE: &str = "gst_debugger_preview";
+
+/// Name of the appsink that receives the preview frames.
+pub const PREVIEW_SINK: &str = "gst_debugger_preview_sink";
+
+/// Width the preview frames are scaled to; the height keeps the aspect ratio.
+pub const PREVIEW_WIDTH: u32 = 320;
+
+/// Index of the `!` in front of the pipeline's last sink, ignoring branches
+/// added by [`inject_recording`] and [`inject_preview`].
+fn sink_link(args: &[String]) -> Option<usize> {
+    let end = args
+        .iter()
+        .position(|arg| {
+            arg.strip_suffix('.')
+                .is_some_and(|tee| tee == RECORD_TEE || tee == PREVIEW_TEE)
+        })
+        .unwrap_or(args.len());
+    args[..end].iter().rposition(|arg| arg == "!")
+}
+
+/// Insert a `tee` named `name` and a queue after the `!` at `link`.
+fn insert_tee(args: &mut Vec<String>, link: usize, name: &str) {
+    let tee = [
+        "tee".to_string(),
+        format!("name={}", name),
+        "!".to_string(),
+        "queue".to_string(),
+        "!".to_string(),
+    ];
+    args.splice(link + 1..link + 1, tee);
+}
+
+/// The encoder for a recording to `path` when none is given: VP8 for WebM,
+/// which cannot hold H.264, and H.264 for every other container.
+pub fn default_record_encoder(path: &str) -> &'static str {
+    match path.rsplit('.').next() {
+        Some("webm") => "videoconvert ! vp8enc deadline=1",
+        _ => "videoconvert ! x264enc tune=zerolatency",
+    }
+}
+
+/// Insert a `tee` in front of the last sink and add a branch that encodes
+/// and muxes the stream into the recording file.
+pub fn inject_recording(mut args: Vec<String>, recording: &Recording) -> Vec<String> {
+    let Some(link) = sink_link(&args) else {
+        return args;
+    };
+
+    insert_tee(&mut args, link, RECORD_TEE);
+
+    let muxer = match recording.path.rsplit('.').next() {
+        Some("mp4") => "mp4mux",
+        Some("ts") => "mpegtsmux",
+        Some("webm") => "webmmux",
+        _ => "matroskamux",
+    };
+    args.extend([
+        format!("{}.", RECORD_TEE),
+        "!".to_string(),
+        "queue".to_string(),
+        "!".to_string(),
+    ]);
+    args.extend(split_pipeline_args(&recording.encoder));
+    args.extend([
+        "!".to_string(),
+        muxer.to_string(),
+        "!".to_string(),
+        "filesink".to_string(),
+        format!("location={}", recording.path),
+    ]);
+
+    args
+}
+
+/// Name of the `level` element inserted by [`inject_level`].
+pub const LEVEL_ELEMENT: &str = "gst_debugger_level";
+
+/// Insert a `level` element in front of the last sink, which must take raw
+/// audio, so its levels are posted on the bus ten times a second.
+pub fn inject_level(mut args: Vec<String>) -> Vec<String> {
+    let Some(link) = sink_link(&args) else {
+        return args;
+    };
+    let level = [
+        "level".to_string(),
+        format!("name={}", LEVEL_ELEMENT),
+        "interval=100000000".to_string(),
+        "post-messages=true".to_string(),
+        "!".to_string(),
+    ];
+    args.splice(link + 1..link + 1, level);
+    args
+}
+
+/// Insert a `tee` in front of the last sink and add a branch that scales the
+/// video down to at most 10 RGBA frames a second into the [`PREVIEW_SINK`]
+/// appsink. The stream there must be raw video.
+pub fn inject_preview(mut args: Vec<String>) -> Vec<String> {
+    let Some(link) = sink_link(&args) else {
+        return args;
+    };
+    insert_tee(&mut args, link, PREVIEW_TEE);
+
+    let branch = format!(
+        "{}. ! queue leaky=downstream max-size-buffers=1 ! videorate drop-only=true max-rate=10 \
+         ! videoconvert ! videoscale \
+         ! video/x-raw,format=RGBA,width={},pixel-aspect-ratio=1/1 \
+         ! appsink name={} max-buffers=1 drop=true sync=false",
+        PREVIEW_TEE, PREVIEW_WIDTH, PREVIEW_SINK
+    );
+    args.extend(split_pipeline_args(&branch));
+    args
+}
+
+/// A camera, microphone or screen found by the device monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureDevice {
+    pub name: String,
+    /// The device class, e.g. `Video/Source` or `Audio/Source`.
+    pub class: String,
+    /// The source element with the properties that select the device, e.g.
+    /// `v4l2src device=/dev/video0`.
+    pub source: String,
+}
+
+/// A starting pipeline that shows or plays `device`, running it through
+/// `filters` (a chain of elements such as `videoflip method=clockwise`)
+/// when given.
+pub fn capture_pipeline(device: &CaptureDevice, filters: &str) -> String {
+    let (convert, sink) = if device.class.contains("Audio") {
+        ("audioconvert ! audioresample", "autoaudiosink")
+    } else {
+        ("videoconvert", "autovideosink")
+    };
+    match filters.trim() {
+        "" => format!("{} ! {} ! {}", device.source, convert, sink),
+        filters => format!(
+            "{} ! {} ! {} ! {} ! {}",
+            device.source, convert, filters, convert, sink
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements_are_listed_by_their_name_when_set() {
+        assert_eq!(
+            pipeline_elements("videotestsrc name=src is-live=true ! x264enc ! fakesink name=out"),
+            ["src", "x264enc", "out"]
+        );
+        let pipeline = "videotestsrc name=src ! fakesink";
+        assert_eq!(pipeline_elements(pipeline), ["src", "fakesink"]);
+        assert_eq!(element_factory(pipeline, "src").as_deref(), Some("videotestsrc"));
+    }
+}
@@ -0,0 +1,98 @@
+//! Queue fill levels from GstShark's `queuelevel` tracer, set against the
+//! interlatency of a link to show how latency follows a filling queue.
+
+use std::collections::VecDeque;
+
+use regex::Regex;
+
+use crate::model::{InterLatencyData, QueueLevel};
+use crate::parse::{parse_duration_to_ns, parse_line_timestamp};
+
+/// A `queuelevel` line, such as `queuelevel, queue=(string)queue0,
+/// size_bytes=(uint)4096, max_size_bytes=(uint)10485760, size_buffers=(uint)3,
+/// max_size_buffers=(uint)200, size_time=(guint64)99000000,
+/// max_size_time=(guint64)1000000000;`. A limit of 0 is no limit.
+pub fn parse_queue_level(line: &str) -> Option<QueueLevel> {
+    let queue_re = Regex::new(r"queuelevel, queue=\(string\)([^,;\s]+)").ok()?;
+    let queue = queue_re.captures(line)?[1].to_string();
+    let field = |name: &str| -> Option<u64> {
+        let regex = Regex::new(&format!(r"\b{}=\([a-z0-9]+\)(\d+)", name)).ok()?;
+        regex.captures(line)?[1].parse().ok()
+    };
+    let bytes = field("size_bytes")?;
+    let buffers = field("size_buffers")?;
+    let time_ns = field("size_time")?;
+    let fill = [
+        (bytes, field("max_size_bytes")),
+        (buffers, field("max_size_buffers")),
+        (time_ns, field("max_size_time")),
+    ]
+    .into_iter()
+    .filter_map(|(size, max)| max.filter(|max| *max > 0).map(|max| size as f64 / max as f64))
+    .fold(0.0, f64::max);
+    Some(QueueLevel {
+        timestamp_ns: parse_line_timestamp(line),
+        queue,
+        buffers,
+        bytes,
+        time_ns,
+        fill,
+    })
+}
+
+/// Every interlatency sample of the link `from` → `to` as a (fill level,
+/// latency in nanoseconds) point, with the queue's latest level at or before
+/// the sample. `levels` are one queue's, oldest first.
+pub fn latency_against_fill(
+    latencies: &[InterLatencyData],
+    from: &str,
+    to: &str,
+    levels: &VecDeque<QueueLevel>,
+) -> Vec<(f64, f64)> {
+    latencies
+        .iter()
+        .filter(|latency| latency.from == from && latency.to == to)
+        .filter_map(|latency| {
+            let before = levels.partition_point(|level| level.timestamp_ns <= latency.timestamp_ns);
+            let level = levels.get(before.checked_sub(1)?)?;
+            let latency_ns = parse_duration_to_ns(&latency.time)?;
+            Some((level.fill, latency_ns as f64))
+        })
+        .collect()
+}
+
+/// Pearson's correlation coefficient of the points, from -1 to 1. `None`
+/// for fewer than two points or when either coordinate never changes.
+pub fn correlation(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x) * (x - mean_x);
+        variance_y += (y - mean_y) * (y - mean_y);
+    }
+    (variance_x > 0.0 && variance_y > 0.0).then(|| covariance / (variance_x * variance_y).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlation_follows_the_direction_of_the_points() {
+        let rising = [(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)];
+        assert!((correlation(&rising).unwrap() - 1.0).abs() < 1e-9);
+        let falling = [(0.0, 5.0), (1.0, 3.0), (2.0, 1.0)];
+        assert!((correlation(&falling).unwrap() + 1.0).abs() < 1e-9);
+        let scattered = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (3.0, 1.0), (4.0, 0.0)];
+        assert!(correlation(&scattered).unwrap().abs() < 0.5);
+
+        assert_eq!(correlation(&[(1.0, 2.0)]), None);
+        assert_eq!(correlation(&[(1.0, 2.0), (1.0, 3.0)]), None);
+    }
+}
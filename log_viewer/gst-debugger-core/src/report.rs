@@ -0,0 +1,197 @@
+use crate::parse::format_clock_time;
+use crate::rollup::format_rollups;
+use crate::rules::RuleResult;
+use crate::stats::{Aggregate, ElementStats, Metric};
+use crate::store::MetricStore;
+
+/// Render the per-element averages as a plain-text table for terminals.
+pub fn format_stats_table(stats: &[ElementStats]) -> String {
+    let element_width = stats
+        .iter()
+        .map(|element| element.element.len())
+        .chain(["Element".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut table = format!("{:<width$}", "Element", width = element_width);
+    for metric in Metric::ALL {
+        table.push_str(&format!("  {:>16}", metric.label()));
+    }
+    table.push('\n');
+
+    for element in stats {
+        table.push_str(&format!("{:<width$}", element.element, width = element_width));
+        for metric in Metric::ALL {
+            let value = element
+                .summary(metric)
+                .get(Aggregate::Avg)
+                .map_or("-".to_string(), |value| metric.format(value));
+            table.push_str(&format!("  {:>16}", value));
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+/// One entry of a JUnit report.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub class: String,
+    pub name: String,
+    pub failure: Option<String>,
+}
+
+impl From<&RuleResult> for TestCase {
+    fn from(result: &RuleResult) -> Self {
+        TestCase {
+            class: result.element.clone(),
+            name: result.check.clone(),
+            failure: result.failure.clone(),
+        }
+    }
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Text for a Markdown table cell, where a `|` would end the cell and a
+/// line break the row.
+pub(crate) fn escape_table_cell(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Render the test cases as a JUnit XML document understood by CI test
+/// report viewers.
+pub fn format_junit(suite: &str, cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\">\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        cases.len(),
+        failures,
+        escape_xml(suite),
+        cases.len(),
+        failures
+    ));
+    for case in cases {
+        let attributes = format!(
+            "classname=\"{}\" name=\"{}\"",
+            escape_xml(&case.class),
+            escape_xml(&case.name)
+        );
+        match &case.failure {
+            Some(message) => xml.push_str(&format!(
+                "    <testcase {}>\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                attributes,
+                escape_xml(message)
+            )),
+            None => xml.push_str(&format!("    <testcase {}/>\n", attributes)),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Render a short Markdown summary of a session for pasting into an issue:
+/// the pipeline, its duration, per-element averages and maxima, the hourly
+/// rollups of a long run, and the violated rules.
+pub fn format_markdown_summary(
+    pipeline: &str,
+    store: &MetricStore,
+    results: &[RuleResult],
+) -> String {
+    let mut md = String::from("### Pipeline summary\n\n");
+    if !pipeline.is_empty() {
+        md.push_str(&format!("```\n{}\n```\n\n", pipeline));
+    }
+    md.push_str(&format!(
+        "Duration: {} ({} metric samples, {} latencies)\n\n",
+        format_clock_time(store.latest_timestamp().saturating_sub(store.earliest_timestamp())),
+        store.samples().len(),
+        store.latencies().len()
+    ));
+
+    // With old raw samples pruned, the table covers less than the run; the
+    // rollups cover the rest.
+    let raw_start_ns = store
+        .samples()
+        .iter()
+        .map(|sample| sample.timestamp_ns)
+        .chain(store.latencies().iter().map(|latency| latency.timestamp_ns))
+        .min();
+    if let Some(raw_start_ns) = raw_start_ns
+        && raw_start_ns > store.earliest_timestamp()
+    {
+        md.push_str(&format!(
+            "Averages and maxima cover the raw samples kept, the last {}; \
+             the hourly rollups below cover the whole run.\n\n",
+            format_clock_time(store.latest_timestamp().saturating_sub(raw_start_ns))
+        ));
+    }
+
+    let stats = store.element_stats();
+    let metrics: Vec<Metric> = Metric::ALL
+        .into_iter()
+        .filter(|&metric| stats.iter().any(|element| element.summary(metric).count > 0))
+        .collect();
+    if !metrics.is_empty() {
+        md.push_str("| Element |");
+        for metric in &metrics {
+            md.push_str(&format!(" {} avg | {} max |", metric.label(), metric.label()));
+        }
+        md.push_str("\n|---|");
+        md.push_str(&"---:|".repeat(metrics.len() * 2));
+        md.push('\n');
+        for element in &stats {
+            md.push_str(&format!("| {} |", escape_table_cell(&element.element)));
+            for &metric in &metrics {
+                for aggregate in [Aggregate::Avg, Aggregate::Max] {
+                    let value = element
+                        .summary(metric)
+                        .get(aggregate)
+                        .map_or("-".to_string(), |value| metric.format(value));
+                    md.push_str(&format!(" {} |", value));
+                }
+            }
+            md.push('\n');
+        }
+        md.push('\n');
+    }
+
+    if !store.rollups().is_empty() {
+        md.push_str("**Hourly rollups**\n\n");
+        md.push_str(&format_rollups(store.rollups()));
+        md.push('\n');
+    }
+
+    let violations: Vec<&RuleResult> = results.iter().filter(|result| !result.passed()).collect();
+    if !results.is_empty() {
+        md.push_str(&format!(
+            "**Rules:** {} of {} checks passed\n",
+            results.len() - violations.len(),
+            results.len()
+        ));
+        for result in violations {
+            md.push_str(&format!("- {}\n", result.describe()));
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_cells_keep_pipes_and_line_breaks() {
+        assert_eq!(escape_table_cell("queue0"), "queue0");
+        assert_eq!(escape_table_cell("a|b"), "a\\|b");
+        assert_eq!(escape_table_cell("a\\|b"), "a\\\\\\|b");
+        assert_eq!(escape_table_cell("a\nb"), "a b");
+    }
+}
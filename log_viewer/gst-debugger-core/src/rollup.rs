@@ -0,0 +1,86 @@
+//! Hourly summaries of a long run, kept for good while the raw samples
+//! they summarize may be pruned, so a pipeline can be watched around the
+//! clock without its memory growing.
+
+use crate::model::{InterLatencyData, TracingData};
+use crate::parse::format_clock_time;
+use crate::report::escape_table_cell;
+use crate::stats::{collect_element_stats, Aggregate, Metric};
+
+/// How long a stretch each rollup summarizes.
+pub const ROLLUP_NS: u64 = 3_600_000_000_000;
+
+/// One metric of one element over an hour of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rollup {
+    /// Start of the hour, on the timeline of the samples.
+    pub start_ns: u64,
+    pub element: String,
+    pub metric: Metric,
+    pub count: usize,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p99: f64,
+}
+
+/// The rollups of every element and metric with samples in the hour
+/// starting at `start_ns`.
+pub(crate) fn roll_up(
+    samples: &[TracingData],
+    latencies: &[InterLatencyData],
+    start_ns: u64,
+) -> Vec<Rollup> {
+    let in_hour = |timestamp_ns: u64| (start_ns..start_ns + ROLLUP_NS).contains(&timestamp_ns);
+    let samples: Vec<TracingData> = samples
+        .iter()
+        .filter(|sample| in_hour(sample.timestamp_ns))
+        .cloned()
+        .collect();
+    let latencies: Vec<InterLatencyData> = latencies
+        .iter()
+        .filter(|latency| in_hour(latency.timestamp_ns))
+        .cloned()
+        .collect();
+    let mut rollups = Vec::new();
+    for stats in collect_element_stats(&samples, &latencies) {
+        for metric in Metric::ALL {
+            let summary = stats.summary(metric);
+            let aggregate = |aggregate| summary.get(aggregate).unwrap_or(0.0);
+            if summary.count == 0 {
+                continue;
+            }
+            rollups.push(Rollup {
+                start_ns,
+                element: stats.element.clone(),
+                metric,
+                count: summary.count,
+                avg: aggregate(Aggregate::Avg),
+                min: aggregate(Aggregate::Min),
+                max: aggregate(Aggregate::Max),
+                p99: aggregate(Aggregate::P99),
+            });
+        }
+    }
+    rollups
+}
+
+/// The rollups as a Markdown table, one row per hour, element and metric.
+pub fn format_rollups(rollups: &[Rollup]) -> String {
+    let mut md = String::from("| Hour | Element | Metric | avg | min | max | p99 |\n");
+    md.push_str("|---|---|---|---:|---:|---:|---:|\n");
+    for rollup in rollups {
+        let metric = rollup.metric;
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            format_clock_time(rollup.start_ns),
+            escape_table_cell(&rollup.element),
+            metric.label(),
+            metric.format(rollup.avg),
+            metric.format(rollup.min),
+            metric.format(rollup.max),
+            metric.format(rollup.p99)
+        ));
+    }
+    md
+}
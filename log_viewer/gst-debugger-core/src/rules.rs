@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::alerts::AlertRule;
+use crate::anomaly::AnomalyDetector;
+use crate::stall::StallDetector;
+use crate::stats::{Aggregate, Metric};
+use crate::store::MetricStore;
+use crate::trend::TrendDetector;
+
+/// Threshold and alert rules loaded from a TOML file with one `[[rule]]`
+/// table per threshold rule, a list of alert conditions and optional
+/// anomaly, trend and stall detectors:
+///
+/// ```toml
+/// alerts = ["fps(videosink) < 25 for 5s"]
+///
+/// [anomaly]
+/// metrics = ["fps"]
+///
+/// [[rule]]
+/// element = "x264enc"
+/// aggregate = "max"
+/// max_proctime = "10ms"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+    /// Conditions in the alert language, e.g. `fps(videosink) < 25 for 5s`.
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Flags sustained deviations from each metric's own baseline.
+    #[serde(default)]
+    pub anomaly: Option<AnomalyDetector>,
+    /// Flags metrics that keep getting worse over a long horizon.
+    #[serde(default)]
+    pub trend: Option<TrendDetector>,
+    /// Flags elements that stop reporting while others keep going.
+    #[serde(default)]
+    pub stall: Option<StallDetector>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Elements the rule applies to: a name prefix such as `queue`, or a
+    /// wildcard pattern such as `*enc*`. Every element when omitted.
+    #[serde(default)]
+    pub element: Option<String>,
+    /// Match `element` as a whole name only, so a rule for `queue1` leaves
+    /// `queue10` alone.
+    #[serde(default)]
+    pub exact: bool,
+    /// Which per-element statistic is compared against the limits.
+    #[serde(default)]
+    pub aggregate: Aggregate,
+    pub min_fps: Option<f64>,
+    pub min_bitrate: Option<f64>,
+    #[serde(default, deserialize_with = "duration_ns")]
+    pub max_proctime: Option<f64>,
+    #[serde(default, deserialize_with = "duration_ns")]
+    pub max_interlatency: Option<f64>,
+    #[serde(default, deserialize_with = "duration_ns")]
+    pub max_jitter: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    pub metric: Metric,
+    pub bound: Bound,
+    pub value: f64,
+}
+
+/// One threshold limit or alert rule checked against one element.
+#[derive(Debug, Clone)]
+pub struct RuleResult {
+    pub element: String,
+    /// Short form of what was checked, e.g. `avg FPS >= 29.0`.
+    pub check: String,
+    /// Why the check failed, `None` when it passed.
+    pub failure: Option<String>,
+    /// Whether the condition still holds at the end of the samples. Only
+    /// alerts clear once the metric recovers.
+    pub active: bool,
+    pub metric: Metric,
+    pub threshold: f64,
+    /// The value that broke the rule and the pipeline time it was seen at,
+    /// for alerts the most recent firing.
+    pub value: Option<f64>,
+    pub timestamp_ns: u64,
+}
+
+impl RuleSet {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+            && self.alerts.is_empty()
+            && self.anomaly.is_none()
+            && self.trend.is_none()
+            && self.stall.is_none()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        toml::from_str(&text).map_err(|err| format!("Invalid rules in {}: {}", path.display(), err))
+    }
+
+    /// Check every threshold and alert rule against the samples. A threshold
+    /// rule that names an element fails when no matching element reported the
+    /// metric; a rule without an element only applies to elements that did.
+    pub fn evaluate(&self, store: &MetricStore) -> Vec<RuleResult> {
+        let stats = store.element_stats();
+        let now_ns = store.latest_timestamp();
+        let mut results = Vec::new();
+        for rule in &self.rules {
+            for limit in rule.limits() {
+                let before = results.len();
+                for element in &stats {
+                    if !rule.applies_to(&element.element) {
+                        continue;
+                    }
+                    if let Some(actual) = element.summary(limit.metric).get(rule.aggregate) {
+                        let result = limit.check(&element.element, rule.aggregate, Some(actual), now_ns);
+                        results.push(result);
+                    }
+                }
+                if results.len() == before
+                    && let Some(pattern) = &rule.element
+                {
+                    results.push(limit.check(pattern, rule.aggregate, None, now_ns));
+                }
+            }
+        }
+        for alert in &self.alerts {
+            results.extend(alert.evaluate(store));
+        }
+        if let Some(anomaly) = &self.anomaly {
+            results.extend(anomaly.evaluate(store));
+        }
+        if let Some(trend) = &self.trend {
+            results.extend(trend.evaluate(store));
+        }
+        if let Some(stall) = &self.stall {
+            results.extend(stall.evaluate(store));
+        }
+        results
+    }
+}
+
+impl RuleSet {
+    /// The tightest limit any rule sets on `metric` for `element`, e.g. to
+    /// color a live value.
+    pub fn limit_for(&self, element: &str, metric: Metric) -> Option<Limit> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.applies_to(element))
+            .flat_map(|rule| rule.limits())
+            .filter(|limit| limit.metric == metric)
+            .reduce(|tightest, limit| match limit.bound {
+                Bound::Min if limit.value > tightest.value => limit,
+                Bound::Max if limit.value < tightest.value => limit,
+                _ => tightest,
+            })
+    }
+}
+
+impl Rule {
+    pub fn applies_to(&self, element: &str) -> bool {
+        self.element.as_deref().is_none_or(|pattern| match self.exact {
+            true => pattern == element,
+            false => element_matches(pattern, element),
+        })
+    }
+
+    pub fn limits(&self) -> Vec<Limit> {
+        [
+            (Metric::Framerate, Bound::Min, self.min_fps),
+            (Metric::Bitrate, Bound::Min, self.min_bitrate),
+            (Metric::ProcTime, Bound::Max, self.max_proctime),
+            (Metric::Interlatency, Bound::Max, self.max_interlatency),
+            (Metric::Jitter, Bound::Max, self.max_jitter),
+        ]
+        .into_iter()
+        .filter_map(|(metric, bound, value)| Some(Limit { metric, bound, value: value? }))
+        .collect()
+    }
+}
+
+impl Limit {
+    pub fn is_violated_by(&self, value: f64) -> bool {
+        match self.bound {
+            Bound::Min => value < self.value,
+            Bound::Max => value > self.value,
+        }
+    }
+
+    /// Short form of the limit, e.g. `ProcTime <= 10000000 ns`.
+    pub fn describe(&self) -> String {
+        let operator = match self.bound {
+            Bound::Min => ">=",
+            Bound::Max => "<=",
+        };
+        format!("{} {} {}", self.metric.label(), operator, self.metric.format(self.value))
+    }
+
+    /// Compare an element's statistic, `None` when it reported no samples
+    /// for the metric, against the limit as of `timestamp_ns`.
+    fn check(
+        &self,
+        element: &str,
+        aggregate: Aggregate,
+        actual: Option<f64>,
+        timestamp_ns: u64,
+    ) -> RuleResult {
+        let metric = self.metric;
+        let failure = match actual {
+            None => Some(format!("{}: no {} samples received", element, metric.label())),
+            Some(actual) if self.is_violated_by(actual) => Some(format!(
+                "{}: {} {} {} is {} the limit of {}",
+                element,
+                aggregate.label(),
+                metric.label(),
+                metric.format(actual),
+                if self.bound == Bound::Min { "below" } else { "above" },
+                metric.format(self.value)
+            )),
+            Some(_) => None,
+        };
+        RuleResult {
+            element: element.to_string(),
+            check: format!("{} {}", aggregate.label(), self.describe()),
+            active: failure.is_some(),
+            failure,
+            metric,
+            threshold: self.value,
+            value: actual,
+            timestamp_ns,
+        }
+    }
+}
+
+impl RuleResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// One line describing the outcome, e.g.
+    /// `queue0: max ProcTime 12000000 ns is above the limit of 10000000 ns`.
+    pub fn describe(&self) -> String {
+        match &self.failure {
+            Some(failure) => failure.clone(),
+            None => format!("{}: {} passed", self.element, self.check),
+        }
+    }
+}
+
+/// Whether `element` matches a rule's element pattern: a wildcard pattern if
+/// it contains `*` or `?`, otherwise a name prefix.
+pub(crate) fn element_matches(pattern: &str, element: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        wildcard_match(pattern, element)
+    } else {
+        element.starts_with(pattern)
+    }
+}
+
+/// Match `name` against a pattern where `*` stands for any run of characters
+/// and `?` for a single one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parse a duration such as `5ms`, `250us`, `1.5s` or a bare number of
+/// nanoseconds.
+pub fn parse_threshold_ns(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let scale = match unit.trim() {
+        "" | "ns" => 1.0,
+        "us" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        "m" => 60e9,
+        "h" => 3600e9,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+/// Metric names such as `fps` or `proctime`.
+pub(crate) fn metric_names<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Metric>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| {
+            Metric::from_name(name)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown metric `{}`", name)))
+        })
+        .collect()
+}
+
+pub(crate) fn duration_ns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Nanos(f64),
+        Text(String),
+    }
+
+    match Value::deserialize(deserializer)? {
+        Value::Nanos(ns) => Ok(Some(ns)),
+        Value::Text(text) => parse_threshold_ns(&text).map(Some).ok_or_else(|| {
+            serde::de::Error::custom(format!("expected a duration like `5ms`, got `{}`", text))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_match_runs_and_single_characters() {
+        assert!(wildcard_match("queue*", "queue0"));
+        assert!(wildcard_match("*enc*", "x264enc0"));
+        assert!(wildcard_match("queue?", "queue1"));
+        assert!(!wildcard_match("queue?", "queue10"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("a*b*c", "aXbYbZ"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("?", ""));
+    }
+
+    #[test]
+    fn elements_match_by_prefix_without_wildcards() {
+        assert!(element_matches("queue", "queue0"));
+        assert!(!element_matches("queue", "myqueue"));
+        assert!(element_matches("*queue", "myqueue"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Processing and scheduling time as a speedscope profile, for exploring
+//! what every element was doing when in the speedscope viewer.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+const SPEEDSCOPE_SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedscopeFrame {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedscopeShared {
+    pub frames: Vec<SpeedscopeFrame>,
+}
+
+/// Opening (`O`) or closing (`C`) of a frame at a time in nanoseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedscopeEvent {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub frame: usize,
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedscopeProfile {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub name: String,
+    pub unit: &'static str,
+    pub start_value: u64,
+    pub end_value: u64,
+    pub events: Vec<SpeedscopeEvent>,
+}
+
+/// A file in speedscope's format with one evented profile per element for
+/// its processing time and one per pad for the time between its buffers.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedscopeFile {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub name: String,
+    pub exporter: String,
+    pub shared: SpeedscopeShared,
+    pub profiles: Vec<SpeedscopeProfile>,
+}
+
+impl SpeedscopeFile {
+    /// `proctimes` holds each element's processing times as (end timestamp,
+    /// duration) pairs and `schedule` each pad's `scheduletime` intervals the
+    /// same way; every pair becomes a span ending at its timestamp.
+    pub fn new(
+        name: &str,
+        proctimes: &BTreeMap<String, Vec<(u64, u64)>>,
+        schedule: &BTreeMap<String, Vec<(u64, u64)>>,
+    ) -> Self {
+        let spans = proctimes
+            .iter()
+            .map(|(element, spans)| (element, "proctime", spans))
+            .chain(schedule.iter().map(|(pad, spans)| (pad, "scheduletime", spans)));
+        let all_spans = || proctimes.values().chain(schedule.values()).flatten();
+        let start_ns = all_spans().map(|(end, duration)| end.saturating_sub(*duration)).min();
+        let end_ns = all_spans().map(|(end, _)| *end).max();
+
+        let mut frames = Vec::new();
+        let mut frame_indices: HashMap<&str, usize> = HashMap::new();
+        let mut profiles = Vec::new();
+        for (owner, kind, spans) in spans {
+            let frame = *frame_indices.entry(owner).or_insert_with(|| {
+                frames.push(SpeedscopeFrame {
+                    name: owner.clone(),
+                });
+                frames.len() - 1
+            });
+            let mut spans = spans.clone();
+            spans.sort_unstable();
+            let mut events = Vec::with_capacity(spans.len() * 2);
+            let mut closed_ns = 0;
+            for (end_ns, duration_ns) in spans {
+                // Spans of one element may not overlap in an evented profile.
+                let open_ns = end_ns.saturating_sub(duration_ns).max(closed_ns);
+                if open_ns >= end_ns {
+                    continue;
+                }
+                events.push(SpeedscopeEvent {
+                    kind: "O",
+                    frame,
+                    at: open_ns,
+                });
+                events.push(SpeedscopeEvent {
+                    kind: "C",
+                    frame,
+                    at: end_ns,
+                });
+                closed_ns = end_ns;
+            }
+            if events.is_empty() {
+                continue;
+            }
+            profiles.push(SpeedscopeProfile {
+                kind: "evented",
+                name: format!("{} ({})", owner, kind),
+                unit: "nanoseconds",
+                start_value: start_ns.unwrap_or(0),
+                end_value: end_ns.unwrap_or(0),
+                events,
+            });
+        }
+
+        SpeedscopeFile {
+            schema: SPEEDSCOPE_SCHEMA,
+            name: name.to_string(),
+            exporter: format!("gst-debugger {}", env!("CARGO_PKG_VERSION")),
+            shared: SpeedscopeShared { frames },
+            profiles,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Elements that fall silent while the rest of the pipeline keeps going,
+//! as a deadlocked or starved element does.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::model::TracingData;
+use crate::parse::format_clock_time;
+use crate::rules::{duration_ns, element_matches, RuleResult};
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+/// The `[stall]` table of a rules file:
+///
+/// ```toml
+/// [stall]
+/// after = "5s"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StallDetector {
+    /// Elements watched, as in threshold rules. Every element when omitted.
+    #[serde(default)]
+    pub element: Option<String>,
+    /// How long an element may go without a tracer event.
+    #[serde(default = "default_after", rename = "after", deserialize_with = "duration_ns")]
+    pub after_ns: Option<f64>,
+}
+
+fn default_after() -> Option<f64> {
+    Some(5e9)
+}
+
+/// The metric a sample reports, for naming what an element last did.
+fn sample_metric(sample: &TracingData) -> Metric {
+    if sample.proctime_ns.is_some() {
+        Metric::ProcTime
+    } else if sample.framerate.is_some() {
+        Metric::Framerate
+    } else if sample.bitrate.is_some() {
+        Metric::Bitrate
+    } else {
+        Metric::Jitter
+    }
+}
+
+/// When each element last showed up in a tracer event, with the metric of
+/// that event.
+pub fn last_active(store: &MetricStore) -> BTreeMap<String, (u64, Metric)> {
+    let mut last: BTreeMap<String, (u64, Metric)> = BTreeMap::new();
+    let samples = store
+        .samples()
+        .iter()
+        .map(|sample| (&sample.element, sample.timestamp_ns, sample_metric(sample)));
+    let latencies = store.latencies().iter().flat_map(|latency| {
+        [&latency.from, &latency.to].map(|element| {
+            (element, latency.timestamp_ns, Metric::Interlatency)
+        })
+    });
+    for (element, timestamp_ns, metric) in samples.chain(latencies) {
+        let latest = last.entry(element.clone()).or_insert((timestamp_ns, metric));
+        if timestamp_ns >= latest.0 {
+            *latest = (timestamp_ns, metric);
+        }
+    }
+    last
+}
+
+impl StallDetector {
+    /// A detector for every element with the given silence limit.
+    pub fn new(after_ns: u64) -> Self {
+        StallDetector {
+            element: None,
+            after_ns: Some(after_ns as f64),
+        }
+    }
+
+    /// What the detector watches, e.g. `* silent for 5.0 s`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} silent for {:.1} s",
+            self.element.as_deref().unwrap_or("*"),
+            self.after_ns.unwrap_or(0.0) / 1e9
+        )
+    }
+
+    /// Every watched element's silence up to the latest event of any
+    /// element. A pipeline that went quiet as a whole has no stalled
+    /// elements, as nothing else kept going.
+    pub fn evaluate(&self, store: &MetricStore) -> Vec<RuleResult> {
+        let after_ns = self.after_ns.unwrap_or(0.0) as u64;
+        let now_ns = store.latest_timestamp();
+        let last = last_active(store);
+        last.iter()
+            .filter(|(element, _)| {
+                self.element.as_deref().is_none_or(|pattern| element_matches(pattern, element))
+            })
+            .map(|(element, &(last_ns, metric))| {
+                let silent_ns = now_ns.saturating_sub(last_ns);
+                let stalled = silent_ns > after_ns;
+                let failure = stalled.then(|| {
+                    format!(
+                        "{}: silent for {:.1} s, last {} at {}",
+                        element,
+                        silent_ns as f64 / 1e9,
+                        metric.label(),
+                        format_clock_time(last_ns)
+                    )
+                });
+                RuleResult {
+                    element: element.clone(),
+                    check: format!("active within {:.1} s", after_ns as f64 / 1e9),
+                    failure,
+                    active: stalled,
+                    metric,
+                    threshold: after_ns as f64,
+                    value: Some(silent_ns as f64),
+                    timestamp_ns: last_ns,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,51 @@
+//! How long after launch the first buffer reached each element, to tell
+//! which stage of a slow start (preroll, negotiation, connecting to a
+//! server) the time goes to.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::parse::parse_duration_to_ns;
+use crate::store::MetricStore;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FirstBuffer {
+    pub element: String,
+    /// Time from the launch to the element's first buffer.
+    pub after_ns: u64,
+}
+
+/// The first buffer at every element, earliest first, timed from
+/// `launch_ns`. Only the tracers that log every buffer count: processing
+/// times for the element that processed it, interlatencies for the element
+/// the buffer reached and, a latency earlier, for the source it came from.
+/// Framerate and bitrate are only logged once a second.
+pub fn first_buffers(store: &MetricStore, launch_ns: u64) -> Vec<FirstBuffer> {
+    let processed = store
+        .samples()
+        .iter()
+        .filter(|sample| sample.proctime_ns.is_some())
+        .map(|sample| (sample.element.as_str(), sample.timestamp_ns));
+    let reached = store.latencies().iter().flat_map(|latency| {
+        let latency_ns = parse_duration_to_ns(&latency.time).unwrap_or(0);
+        [
+            (latency.to.as_str(), latency.timestamp_ns),
+            (latency.from.as_str(), latency.timestamp_ns.saturating_sub(latency_ns)),
+        ]
+    });
+    let mut first: BTreeMap<&str, u64> = BTreeMap::new();
+    for (element, timestamp_ns) in processed.chain(reached) {
+        let earliest = first.entry(element).or_insert(timestamp_ns);
+        *earliest = (*earliest).min(timestamp_ns);
+    }
+    let mut buffers: Vec<FirstBuffer> = first
+        .into_iter()
+        .map(|(element, timestamp_ns)| FirstBuffer {
+            element: element.to_string(),
+            after_ns: timestamp_ns.saturating_sub(launch_ns),
+        })
+        .collect();
+    buffers.sort_by_key(|buffer| buffer.after_ns);
+    buffers
+}
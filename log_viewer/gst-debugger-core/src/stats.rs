@@ -0,0 +1,437 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::model::{InterLatencyData, TracingData};
+use crate::parse::parse_duration_to_ns;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Bitrate,
+    Framerate,
+    ProcTime,
+    Interlatency,
+    Jitter,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 5] = [
+        Metric::Bitrate,
+        Metric::Framerate,
+        Metric::ProcTime,
+        Metric::Interlatency,
+        Metric::Jitter,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::Bitrate => "Bitrate",
+            Metric::Framerate => "FPS",
+            Metric::ProcTime => "ProcTime",
+            Metric::Interlatency => "Interlatency",
+            Metric::Jitter => "Jitter",
+        }
+    }
+
+    /// Lowercase name used in rules, scripts and the HTTP API.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Bitrate => "bitrate",
+            Metric::Framerate => "fps",
+            Metric::ProcTime => "proctime",
+            Metric::Interlatency => "interlatency",
+            Metric::Jitter => "jitter",
+        }
+    }
+
+    /// Look a metric up by the lowercase name used in rules and scripts.
+    pub fn from_name(name: &str) -> Option<Metric> {
+        match name.to_ascii_lowercase().as_str() {
+            "fps" | "framerate" => Some(Metric::Framerate),
+            "bitrate" => Some(Metric::Bitrate),
+            "proctime" => Some(Metric::ProcTime),
+            "interlatency" | "latency" => Some(Metric::Interlatency),
+            "jitter" => Some(Metric::Jitter),
+            _ => None,
+        }
+    }
+
+    /// Whether a larger value is the healthier one, as for the frame rate,
+    /// rather than a slower one, as for the timings.
+    pub fn higher_is_better(self) -> bool {
+        matches!(self, Metric::Bitrate | Metric::Framerate)
+    }
+
+    /// The aggregates the stats table and reports show for the metric:
+    /// percentiles only for the timings, where the tail matters.
+    pub fn aggregates(self) -> &'static [Aggregate] {
+        match self {
+            Metric::ProcTime | Metric::Interlatency => &Aggregate::ALL,
+            _ => &Aggregate::ALL[..3],
+        }
+    }
+
+    /// The value of the metric in a sample; interlatency comes separately.
+    pub fn value(self, sample: &TracingData) -> Option<f64> {
+        match self {
+            Metric::Bitrate => sample.bitrate.map(|bitrate| bitrate as f64),
+            Metric::Framerate => sample.framerate,
+            Metric::ProcTime => sample.proctime_ns.map(|ns| ns as f64),
+            Metric::Jitter => sample.jitter_ns.map(|ns| ns as f64),
+            Metric::Interlatency => None,
+        }
+    }
+
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Metric::Bitrate => format!("{:.0} bps", value),
+            Metric::Framerate => format!("{:.1}", value),
+            Metric::ProcTime | Metric::Interlatency | Metric::Jitter => {
+                format!("{:.0} ns", value)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    #[default]
+    Avg,
+    Min,
+    Max,
+    P50,
+    P95,
+    P99,
+}
+
+impl Aggregate {
+    pub const ALL: [Aggregate; 6] = [
+        Aggregate::Avg,
+        Aggregate::Min,
+        Aggregate::Max,
+        Aggregate::P50,
+        Aggregate::P95,
+        Aggregate::P99,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::P50 => "p50",
+            Aggregate::P95 => "p95",
+            Aggregate::P99 => "p99",
+        }
+    }
+
+    /// Look an aggregate up by its label.
+    pub fn from_name(name: &str) -> Option<Aggregate> {
+        Aggregate::ALL.into_iter().find(|aggregate| aggregate.label() == name)
+    }
+}
+
+/// A streaming estimate of one quantile with the P² algorithm of Jain and
+/// Chlamtac: five markers whose heights follow the quantile and the points
+/// halfway to the minimum and maximum, in constant space.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quantile {
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+}
+
+impl Quantile {
+    pub fn add(&mut self, p: f64, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+                self.positions = [1.0, 2.0, 3.0, 4.0, 5.0];
+            }
+            return;
+        }
+        self.count += 1;
+
+        let cell = if value < self.heights[0] {
+            self.heights[0] = value;
+            0
+        } else if value >= self.heights[4] {
+            self.heights[4] = value;
+            3
+        } else {
+            (0..4).find(|&i| value < self.heights[i + 1]).unwrap_or(3)
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+
+        let n = self.count as f64 - 1.0;
+        // Where the three middle markers should be; the outer two stay at
+        // the minimum and maximum.
+        let desired = [1.0 + n * p / 2.0, 1.0 + n * p, 1.0 + n * (1.0 + p) / 2.0];
+        for (i, desired) in (1..4).zip(desired) {
+            let offset = desired - self.positions[i];
+            let room_above = self.positions[i + 1] - self.positions[i] > 1.0;
+            let room_below = self.positions[i - 1] - self.positions[i] < -1.0;
+            if (offset >= 1.0 && room_above) || (offset <= -1.0 && room_below) {
+                let step = offset.signum();
+                let parabolic = self.parabolic(i, step);
+                let (below, above) = (self.heights[i - 1], self.heights[i + 1]);
+                self.heights[i] = if below < parabolic && parabolic < above {
+                    parabolic
+                } else {
+                    self.linear(i, step)
+                };
+                self.positions[i] += step;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, step: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + step) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - step) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, step: f64) -> f64 {
+        let j = if step > 0.0 { i + 1 } else { i - 1 };
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step * (q[j] - q[i]) / (n[j] - n[i])
+    }
+
+    /// The estimate; exact while fewer than five values were added.
+    pub fn get(&self, p: f64) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..5 => {
+                let mut values = self.heights[..self.count].to_vec();
+                values.sort_by(f64::total_cmp);
+                Some(values[((self.count - 1) as f64 * p).round() as usize])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: Quantile,
+    pub p95: Quantile,
+    pub p99: Quantile,
+}
+
+impl Summary {
+    pub fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+        self.p50.add(0.5, value);
+        self.p95.add(0.95, value);
+        self.p99.add(0.99, value);
+    }
+
+    pub fn get(&self, aggregate: Aggregate) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        match aggregate {
+            Aggregate::Avg => Some(self.sum / self.count as f64),
+            Aggregate::Min => Some(self.min),
+            Aggregate::Max => Some(self.max),
+            Aggregate::P50 => self.p50.get(0.5),
+            Aggregate::P95 => self.p95.get(0.95),
+            Aggregate::P99 => self.p99.get(0.99),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ElementStats {
+    pub element: String,
+    pub bitrate: Summary,
+    pub framerate: Summary,
+    pub proctime: Summary,
+    pub interlatency: Summary,
+    pub jitter: Summary,
+}
+
+impl ElementStats {
+    pub fn summary(&self, metric: Metric) -> &Summary {
+        match metric {
+            Metric::Bitrate => &self.bitrate,
+            Metric::Framerate => &self.framerate,
+            Metric::ProcTime => &self.proctime,
+            Metric::Interlatency => &self.interlatency,
+            Metric::Jitter => &self.jitter,
+        }
+    }
+}
+
+pub fn collect_element_stats(logs: &[TracingData], inter: &[InterLatencyData]) -> Vec<ElementStats> {
+    let mut stats: BTreeMap<String, ElementStats> = BTreeMap::new();
+
+    for entry in logs {
+        let element = stats.entry(entry.element.clone()).or_insert_with(|| ElementStats {
+            element: entry.element.clone(),
+            ..Default::default()
+        });
+        if let Some(bitrate) = entry.bitrate {
+            element.bitrate.add(bitrate as f64);
+        }
+        if let Some(framerate) = entry.framerate {
+            element.framerate.add(framerate);
+        }
+        if let Some(proctime) = entry.proctime_ns {
+            element.proctime.add(proctime as f64);
+        }
+        if let Some(jitter) = entry.jitter_ns {
+            element.jitter.add(jitter as f64);
+        }
+    }
+
+    for lat in inter {
+        if let Some(ns) = parse_duration_to_ns(&lat.time) {
+            stats
+                .entry(lat.from.clone())
+                .or_insert_with(|| ElementStats {
+                    element: lat.from.clone(),
+                    ..Default::default()
+                })
+                .interlatency
+                .add(ns as f64);
+        }
+    }
+
+    stats.into_values().collect()
+}
+
+pub fn metric_series(
+    logs: &[TracingData],
+    inter: &[InterLatencyData],
+    element: &str,
+    metric: Metric,
+) -> Vec<(u64, f64)> {
+    match metric {
+        Metric::Interlatency => inter
+            .iter()
+            .filter(|lat| lat.from == element)
+            .filter_map(|lat| Some((lat.timestamp_ns, parse_duration_to_ns(&lat.time)? as f64)))
+            .collect(),
+        _ => logs
+            .iter()
+            .filter(|e| e.element == element)
+            .filter_map(|e| Some((e.timestamp_ns, metric.value(e)?)))
+            .collect(),
+    }
+}
+
+/// How the displayed values of a metric are smoothed; the stored samples
+/// stay raw.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Smoothing {
+    #[default]
+    Off,
+    /// The mean of the latest this many samples.
+    MovingAverage(usize),
+    /// An exponentially weighted moving average giving the newest sample
+    /// this weight.
+    Ewma(f64),
+}
+
+impl Smoothing {
+    pub fn label(self) -> &'static str {
+        match self {
+            Smoothing::Off => "Off",
+            Smoothing::MovingAverage(_) => "Moving average",
+            Smoothing::Ewma(_) => "EWMA",
+        }
+    }
+
+    /// Smooth a series, keeping its timestamps.
+    pub fn apply(self, values: &[(u64, f64)]) -> Vec<(u64, f64)> {
+        match self {
+            Smoothing::Off => values.to_vec(),
+            Smoothing::MovingAverage(window) => {
+                let window = window.max(1);
+                let mut sum = 0.0;
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &(timestamp_ns, value))| {
+                        sum += value;
+                        if index >= window {
+                            sum -= values[index - window].1;
+                        }
+                        (timestamp_ns, sum / (index + 1).min(window) as f64)
+                    })
+                    .collect()
+            }
+            Smoothing::Ewma(weight) => {
+                let mut average = None;
+                values
+                    .iter()
+                    .map(|&(timestamp_ns, value)| {
+                        let smoothed = average.map_or(value, |average: f64| {
+                            average + weight * (value - average)
+                        });
+                        average = Some(smoothed);
+                        (timestamp_ns, smoothed)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The smoothed latest value of `newest_first`, from as many of the
+    /// latest values as carry weight.
+    pub fn latest(self, newest_first: impl Iterator<Item = f64>) -> Option<f64> {
+        let span = match self {
+            Smoothing::Off => 1,
+            Smoothing::MovingAverage(window) => window.max(1),
+            // Older samples weigh less than 1% together.
+            Smoothing::Ewma(weight) => (4.6 / weight.max(0.001)).ceil().min(1_000.0) as usize,
+        };
+        let mut values: Vec<(u64, f64)> = newest_first.take(span).map(|value| (0, value)).collect();
+        values.reverse();
+        self.apply(&values).last().map(|&(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_are_exact_for_few_values_and_close_for_many() {
+        let mut median = Quantile::default();
+        assert_eq!(median.get(0.5), None);
+        for value in [3.0, 1.0, 2.0] {
+            median.add(0.5, value);
+        }
+        assert_eq!(median.get(0.5), Some(2.0));
+
+        let (mut median, mut p95) = (Quantile::default(), Quantile::default());
+        // Every value from 0 to 999, in a scrambled order.
+        for i in 0..1000 {
+            let value = (i * 389 % 1000) as f64;
+            median.add(0.5, value);
+            p95.add(0.95, value);
+        }
+        assert!((median.get(0.5).unwrap() - 500.0).abs() < 25.0);
+        assert!((p95.get(0.95).unwrap() - 950.0).abs() < 25.0);
+    }
+}
@@ -0,0 +1,276 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::jitter::ScheduleJitter;
+use crate::model::{InterLatencyData, TracingData};
+use crate::parse::{parse_duration_to_ns, parse_gst_tracer_output, parse_interlatency};
+use crate::rollup::{roll_up, Rollup, ROLLUP_NS};
+use crate::stats::{collect_element_stats, metric_series, ElementStats, Metric};
+
+/// Metric samples received from the pipeline, in arrival order, and the
+/// hourly rollups of every hour that has passed.
+#[derive(Debug, Default)]
+pub struct MetricStore {
+    samples: Vec<TracingData>,
+    latencies: Vec<InterLatencyData>,
+    schedule_jitter: ScheduleJitter,
+    rollups: Vec<Rollup>,
+    /// End of the hour the latest samples fall into.
+    next_rollup_ns: Option<u64>,
+    /// How long raw samples are kept, for good when `None`.
+    raw_retention_ns: Option<u64>,
+    /// Raw samples from before this were pruned.
+    pruned_ns: u64,
+}
+
+impl MetricStore {
+    /// Parse a tracer log written by a previous run.
+    pub fn load_log(path: &Path) -> io::Result<Self> {
+        let mut store = Self::default();
+        for line in BufReader::new(File::open(path)?).lines() {
+            store.push_line(&line?);
+        }
+        Ok(store)
+    }
+
+    /// Record the metric carried by a raw tracer log line, if any.
+    pub fn push_line(&mut self, line: &str) {
+        if let Some(sample) = parse_gst_tracer_output(line) {
+            self.push_sample(sample);
+        } else if let Some(latency) = parse_interlatency(line) {
+            self.push_latency(latency);
+        } else if let Some(sample) = self.schedule_jitter.push_line(line) {
+            self.push_sample(sample);
+        }
+    }
+
+    /// Record a sample. One arriving late for an hour that is rolled up
+    /// already goes into that hour's rollups.
+    pub fn push_sample(&mut self, sample: TracingData) {
+        match self.rolled_up_hour(sample.timestamp_ns) {
+            Some(hour_ns) if hour_ns < self.pruned_ns => {
+                for metric in Metric::ALL {
+                    if let Some(value) = metric.value(&sample) {
+                        self.merge_late(hour_ns, &sample.element, metric, value);
+                    }
+                }
+            }
+            Some(hour_ns) => {
+                let at =
+                    self.samples.partition_point(|kept| kept.timestamp_ns <= sample.timestamp_ns);
+                self.samples.insert(at, sample);
+                self.roll_up_again(hour_ns);
+            }
+            None => {
+                self.samples.push(sample);
+                self.roll_up();
+            }
+        }
+    }
+
+    /// Record an interlatency sample, like [`MetricStore::push_sample`].
+    pub fn push_latency(&mut self, latency: InterLatencyData) {
+        match self.rolled_up_hour(latency.timestamp_ns) {
+            Some(hour_ns) if hour_ns < self.pruned_ns => {
+                if let Some(value) = parse_duration_to_ns(&latency.time) {
+                    self.merge_late(hour_ns, &latency.from, Metric::Interlatency, value as f64);
+                }
+            }
+            Some(hour_ns) => {
+                let at = self
+                    .latencies
+                    .partition_point(|kept| kept.timestamp_ns <= latency.timestamp_ns);
+                self.latencies.insert(at, latency);
+                self.roll_up_again(hour_ns);
+            }
+            None => {
+                self.latencies.push(latency);
+                self.roll_up();
+            }
+        }
+    }
+
+    /// The start of the hour `timestamp_ns` falls into, when that hour is
+    /// rolled up already.
+    fn rolled_up_hour(&self, timestamp_ns: u64) -> Option<u64> {
+        let next_ns = self.next_rollup_ns?;
+        (timestamp_ns < next_ns - ROLLUP_NS).then(|| timestamp_ns - timestamp_ns % ROLLUP_NS)
+    }
+
+    /// Summarize the hour starting at `hour_ns` again from its raw samples,
+    /// which are all still kept.
+    fn roll_up_again(&mut self, hour_ns: u64) {
+        self.rollups.retain(|rollup| rollup.start_ns != hour_ns);
+        let at = self.rollups.partition_point(|rollup| rollup.start_ns < hour_ns);
+        let rollups = roll_up(&self.samples, &self.latencies, hour_ns);
+        self.rollups.splice(at..at, rollups);
+    }
+
+    /// Add a value to the rollup of an hour whose raw samples are pruned.
+    /// Its p99 stays as it was, since the samples it came from are gone.
+    fn merge_late(&mut self, hour_ns: u64, element: &str, metric: Metric, value: f64) {
+        let rollup = self.rollups.iter_mut().find(|rollup| {
+            rollup.start_ns == hour_ns && rollup.element == element && rollup.metric == metric
+        });
+        match rollup {
+            Some(rollup) => {
+                rollup.avg = (rollup.avg * rollup.count as f64 + value) / (rollup.count + 1) as f64;
+                rollup.count += 1;
+                rollup.min = rollup.min.min(value);
+                rollup.max = rollup.max.max(value);
+            }
+            None => {
+                let at = self.rollups.partition_point(|rollup| rollup.start_ns <= hour_ns);
+                self.rollups.insert(
+                    at,
+                    Rollup {
+                        start_ns: hour_ns,
+                        element: element.to_string(),
+                        metric,
+                        count: 1,
+                        avg: value,
+                        min: value,
+                        max: value,
+                        p99: value,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Drop raw samples once they are older than `retention_ns` and their
+    /// hour is rolled up. Pruning happens as each hour is rolled up.
+    pub fn set_raw_retention(&mut self, retention_ns: Option<u64>) {
+        self.raw_retention_ns = retention_ns;
+    }
+
+    /// Summaries of every hour that has passed, oldest first.
+    pub fn rollups(&self) -> &[Rollup] {
+        &self.rollups
+    }
+
+    /// Summarize the hours the samples have moved past, then prune.
+    fn roll_up(&mut self) {
+        let latest_ns = self.latest_timestamp();
+        let next_ns = self
+            .next_rollup_ns
+            .get_or_insert(latest_ns - latest_ns % ROLLUP_NS + ROLLUP_NS);
+        if latest_ns < *next_ns {
+            return;
+        }
+        while latest_ns >= *next_ns {
+            let start_ns = *next_ns - ROLLUP_NS;
+            self.rollups.extend(roll_up(&self.samples, &self.latencies, start_ns));
+            *next_ns += ROLLUP_NS;
+        }
+        if let Some(retention_ns) = self.raw_retention_ns {
+            // Samples of the hour not rolled up yet stay whatever their age.
+            let since_ns = latest_ns.saturating_sub(retention_ns).min(*next_ns - ROLLUP_NS);
+            self.samples.retain(|sample| sample.timestamp_ns >= since_ns);
+            self.latencies.retain(|latency| latency.timestamp_ns >= since_ns);
+            self.pruned_ns = self.pruned_ns.max(since_ns);
+        }
+    }
+
+    pub fn samples(&self) -> &[TracingData] {
+        &self.samples
+    }
+
+    pub fn latencies(&self) -> &[InterLatencyData] {
+        &self.latencies
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.latencies.clear();
+        self.rollups.clear();
+        self.next_rollup_ns = None;
+        self.pruned_ns = 0;
+    }
+
+    pub fn latest_timestamp(&self) -> u64 {
+        let sample_ts = self.samples.last().map_or(0, |e| e.timestamp_ns);
+        let latency_ts = self.latencies.last().map_or(0, |lat| lat.timestamp_ns);
+        sample_ts.max(latency_ts)
+    }
+
+    pub fn earliest_timestamp(&self) -> u64 {
+        self.samples
+            .iter()
+            .map(|e| e.timestamp_ns)
+            .chain(self.latencies.iter().map(|lat| lat.timestamp_ns))
+            .chain(self.rollups.first().map(|rollup| rollup.start_ns))
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn element_stats(&self) -> Vec<ElementStats> {
+        collect_element_stats(&self.samples, &self.latencies)
+    }
+
+    pub fn series(&self, element: &str, metric: Metric) -> Vec<(u64, f64)> {
+        metric_series(&self.samples, &self.latencies, element, metric)
+    }
+
+    /// ProcTime samples of every element whose name starts with `prefix`.
+    pub fn proctimes(&self, prefix: &str) -> Vec<u64> {
+        self.samples
+            .iter()
+            .filter(|e| e.element.starts_with(prefix))
+            .filter_map(|e| e.proctime_ns)
+            .collect()
+    }
+
+    /// Interlatency samples, in nanoseconds, measured from elements whose name
+    /// starts with `prefix`.
+    pub fn interlatencies(&self, prefix: &str) -> Vec<u64> {
+        self.latencies
+            .iter()
+            .filter(|lat| lat.from.starts_with(prefix))
+            .filter_map(|lat| parse_duration_to_ns(&lat.time))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn proctime(timestamp_ns: u64, proctime_ns: u64) -> TracingData {
+        testing::proctime("x264enc0", timestamp_ns, proctime_ns)
+    }
+
+    fn first_rollup(store: &MetricStore) -> &Rollup {
+        &store.rollups()[0]
+    }
+
+    #[test]
+    fn late_samples_are_rolled_up_again() {
+        let mut store = MetricStore::default();
+        store.push_sample(proctime(1_000, 100));
+        store.push_sample(proctime(ROLLUP_NS + 1_000, 100));
+        assert_eq!(first_rollup(&store).count, 1);
+
+        store.push_sample(proctime(2_000, 300));
+        assert_eq!(store.rollups().len(), 1);
+        assert_eq!(first_rollup(&store).count, 2);
+        assert_eq!(first_rollup(&store).max, 300.0);
+        assert_eq!(store.latest_timestamp(), ROLLUP_NS + 1_000);
+    }
+
+    #[test]
+    fn late_samples_merge_into_pruned_hours() {
+        let mut store = MetricStore::default();
+        store.set_raw_retention(Some(1_000));
+        store.push_sample(proctime(1_000, 100));
+        store.push_sample(proctime(ROLLUP_NS + 1_000, 100));
+        assert_eq!(store.samples().len(), 1);
+
+        store.push_sample(proctime(2_000, 300));
+        let rollup = first_rollup(&store);
+        assert_eq!((rollup.count, rollup.avg, rollup.max), (2, 200.0, 300.0));
+        assert_eq!(store.samples().len(), 1);
+    }
+}
@@ -0,0 +1,107 @@
+//! Threshold rules drawn from a run known to be good: limits just outside
+//! what each element showed, so a later run that drifts from it fails.
+
+use crate::rules::Rule;
+use crate::stats::{Aggregate, Metric};
+use crate::store::MetricStore;
+
+/// How far above its p99 a processing time, interlatency or jitter may go.
+const LATENCY_HEADROOM: f64 = 1.5;
+/// How far below its average a frame rate may drop.
+const FPS_HEADROOM: f64 = 0.9;
+
+/// Rules for every element of the store: one on the average frame rate and
+/// one on the p99 of the timings, for the metrics the element reported.
+/// Each names its element exactly, so `queue1`'s limits leave `queue10`
+/// alone.
+pub fn suggest_rules(store: &MetricStore) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for stats in store.element_stats() {
+        let get = |metric, aggregate| stats.summary(metric).get(aggregate);
+        if let Some(fps) = get(Metric::Framerate, Aggregate::Avg).filter(|fps| *fps > 0.0) {
+            rules.push(Rule {
+                element: Some(stats.element.clone()),
+                exact: true,
+                aggregate: Aggregate::Avg,
+                min_fps: Some(fps * FPS_HEADROOM),
+                ..Rule::default()
+            });
+        }
+        let limit = |metric| get(metric, Aggregate::P99).map(|p99| p99 * LATENCY_HEADROOM);
+        let timings = Rule {
+            element: Some(stats.element.clone()),
+            exact: true,
+            aggregate: Aggregate::P99,
+            max_proctime: limit(Metric::ProcTime),
+            max_interlatency: limit(Metric::Interlatency),
+            max_jitter: limit(Metric::Jitter),
+            ..Rule::default()
+        };
+        if !timings.limits().is_empty() {
+            rules.push(timings);
+        }
+    }
+    rules
+}
+
+/// The rules as `[[rule]]` tables of a rules file, durations written like
+/// `12.500ms`.
+pub fn format_rules_toml(rules: &[Rule]) -> String {
+    let mut toml = String::new();
+    for rule in rules {
+        if !toml.is_empty() {
+            toml.push('\n');
+        }
+        toml.push_str("[[rule]]\n");
+        if let Some(element) = &rule.element {
+            let element = toml::Value::String(element.clone());
+            toml.push_str(&format!("element = {}\n", element));
+        }
+        if rule.exact {
+            toml.push_str("exact = true\n");
+        }
+        toml.push_str(&format!("aggregate = \"{}\"\n", rule.aggregate.label()));
+        if let Some(fps) = rule.min_fps {
+            toml.push_str(&format!("min_fps = {:.1}\n", fps));
+        }
+        if let Some(bitrate) = rule.min_bitrate {
+            toml.push_str(&format!("min_bitrate = {:.0}\n", bitrate));
+        }
+        for (key, value) in [
+            ("max_proctime", rule.max_proctime),
+            ("max_interlatency", rule.max_interlatency),
+            ("max_jitter", rule.max_jitter),
+        ] {
+            if let Some(ns) = value {
+                toml.push_str(&format!("{} = \"{:.3}ms\"\n", key, ns / 1e6));
+            }
+        }
+    }
+    toml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+    use crate::testing::{framerate, store};
+
+    #[test]
+    fn suggested_rules_parse_back_and_match_exactly() {
+        let elements = ["queue1", "queue10", "my \"enc\""];
+        let store = store((0..).zip(elements).map(|(ns, element)| framerate(element, ns, 30.0)));
+        let rules = suggest_rules(&store);
+        assert_eq!(rules.len(), 3);
+
+        let parsed: RuleSet = toml::from_str(&format_rules_toml(&rules)).unwrap();
+        let named = |name| parsed.rules.iter().any(|rule| rule.element.as_deref() == Some(name));
+        assert!(named("my \"enc\""));
+        let queue1 = parsed
+            .rules
+            .iter()
+            .find(|rule| rule.element.as_deref() == Some("queue1"))
+            .unwrap();
+        assert!(queue1.applies_to("queue1"));
+        assert!(!queue1.applies_to("queue10"));
+    }
+}
@@ -0,0 +1,142 @@
+//! The syntax of gst-launch descriptions for the GUI's pipeline edit box:
+//! what each word is, for highlighting and validation, and what the word
+//! under the cursor can be completed with.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Element,
+    Caps,
+    Property,
+    Value,
+    Link,
+    Reference,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxSpan {
+    pub kind: SyntaxKind,
+    /// Byte range in the description.
+    pub range: Range<usize>,
+    /// For properties, the factory of the element they are set on.
+    pub factory: Option<String>,
+}
+
+/// The word under the cursor and what it can be completed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// The part of the description a completion replaces.
+    pub range: Range<usize>,
+    /// What is typed of the word up to the cursor.
+    pub prefix: String,
+    /// Whether the word can be an element factory.
+    pub elements: bool,
+    /// The factory whose property names the word can be.
+    pub properties_of: Option<String>,
+}
+
+/// Byte ranges of the words of a description, keeping quoted values
+/// together as [`split_pipeline_args`](crate::split_pipeline_args) does.
+fn words(pipeline: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in pipeline.char_indices() {
+        if quote.is_none() && !escaped && c.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(start..index);
+            }
+            continue;
+        }
+        start.get_or_insert(index);
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => escaped = true,
+            (None, '"' | '\'') => quote = Some(c),
+            _ => {}
+        }
+    }
+    if let Some(start) = start {
+        words.push(start..pipeline.len());
+    }
+    words
+}
+
+/// Split a description into the spans to highlight. Properties are
+/// attributed to the element written last before them.
+pub fn highlight(pipeline: &str) -> Vec<SyntaxSpan> {
+    let mut spans = Vec::new();
+    let mut factory: Option<String> = None;
+    for range in words(pipeline) {
+        let word = &pipeline[range.clone()];
+        let (key, _) = word.split_once('=').unwrap_or((word, ""));
+        let kind = if word == "!" {
+            SyntaxKind::Link
+        } else if key.contains(['/', ',']) {
+            factory = None;
+            SyntaxKind::Caps
+        } else if word.contains('=') {
+            let split = range.start + key.len();
+            spans.push(SyntaxSpan {
+                kind: SyntaxKind::Property,
+                range: range.start..split,
+                factory: factory.clone(),
+            });
+            spans.push(SyntaxSpan {
+                kind: SyntaxKind::Value,
+                range: split + 1..range.end,
+                factory: None,
+            });
+            continue;
+        } else if word.contains('.') {
+            factory = None;
+            SyntaxKind::Reference
+        } else {
+            factory = Some(word.to_string());
+            SyntaxKind::Element
+        };
+        spans.push(SyntaxSpan {
+            kind,
+            range,
+            factory: None,
+        });
+    }
+    spans
+}
+
+/// What the word ending at or containing the byte offset `cursor` can be
+/// completed with. A bare word straight after an element can start the
+/// next chain or be a property of that element that has no `=` yet.
+pub fn completion_at(pipeline: &str, cursor: usize) -> Option<Completion> {
+    let spans = highlight(pipeline);
+    let position = spans
+        .iter()
+        .position(|span| span.range.start < cursor && cursor <= span.range.end)?;
+    let span = &spans[position];
+    let (elements, properties_of) = match span.kind {
+        SyntaxKind::Property => (false, span.factory.clone()),
+        SyntaxKind::Element => {
+            let previous = spans[..position]
+                .iter()
+                .rev()
+                .find(|span| span.kind != SyntaxKind::Property && span.kind != SyntaxKind::Value);
+            let properties_of = match previous {
+                Some(previous) if previous.kind == SyntaxKind::Element => {
+                    Some(pipeline[previous.range.clone()].to_string())
+                }
+                _ => None,
+            };
+            (true, properties_of)
+        }
+        _ => return None,
+    };
+    Some(Completion {
+        range: span.range.clone(),
+        prefix: pipeline[span.range.start..cursor].to_string(),
+        elements,
+        properties_of,
+    })
+}
@@ -0,0 +1,41 @@
+//! Samples and stores for the tests of the analyses.
+
+use crate::model::TracingData;
+use crate::store::MetricStore;
+
+pub(crate) const SECOND_NS: u64 = 1_000_000_000;
+
+/// A sample of `element` with none of its metrics set.
+pub(crate) fn sample(element: &str, timestamp_ns: u64) -> TracingData {
+    TracingData {
+        timestamp_ns,
+        element: element.to_string(),
+        bitrate: None,
+        framerate: None,
+        proctime_ns: None,
+        jitter_ns: None,
+    }
+}
+
+pub(crate) fn framerate(element: &str, timestamp_ns: u64, fps: f64) -> TracingData {
+    TracingData {
+        framerate: Some(fps),
+        ..sample(element, timestamp_ns)
+    }
+}
+
+pub(crate) fn proctime(element: &str, timestamp_ns: u64, proctime_ns: u64) -> TracingData {
+    TracingData {
+        proctime_ns: Some(proctime_ns),
+        ..sample(element, timestamp_ns)
+    }
+}
+
+/// A store holding `samples`, pushed in order.
+pub(crate) fn store(samples: impl IntoIterator<Item = TracingData>) -> MetricStore {
+    let mut store = MetricStore::default();
+    for sample in samples {
+        store.push_sample(sample);
+    }
+    store
+}
@@ -0,0 +1,154 @@
+//! Which streaming thread runs which elements, from the thread column of
+//! the tracer lines, and how busy each element keeps its thread. Heavy
+//! elements sharing a thread wait on each other, a common cause of latency
+//! that no single element's timings explain.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::model::{StreamingThread, StreamingThreads, ThreadElement};
+use crate::parse::{parse_gst_tracer_output, parse_interlatency, parse_line_timestamp};
+
+/// The share of its thread's time that makes an element heavy.
+const HEAVY_SHARE: f64 = 0.25;
+
+/// The thread of a GStreamer debug line, its third column after the
+/// timestamp and the process ID: `0:00:01.2 4242 0x55d5c0a4e300 TRACE ...`.
+pub fn parse_line_thread(line: &str) -> Option<&str> {
+    let thread = line.split_whitespace().nth(2)?;
+    thread.starts_with("0x").then_some(thread)
+}
+
+/// Gathers the threads of the tracer lines of one pipeline run.
+#[derive(Debug)]
+pub struct ThreadTracker {
+    /// How far back busy shares are measured, the whole log when `None`.
+    window_ns: Option<u64>,
+    /// The thread each element last ran on.
+    threads: BTreeMap<String, String>,
+    /// Each element's processing times as (timestamp, duration) pairs.
+    proctimes: BTreeMap<String, VecDeque<(u64, u64)>>,
+    first_ns: Option<u64>,
+    latest_ns: u64,
+    /// Threads already reported as shared by heavy elements.
+    shared: BTreeSet<String>,
+}
+
+impl ThreadTracker {
+    pub fn new(window_ns: Option<u64>) -> Self {
+        ThreadTracker {
+            window_ns,
+            threads: BTreeMap::new(),
+            proctimes: BTreeMap::new(),
+            first_ns: None,
+            latest_ns: 0,
+            shared: BTreeSet::new(),
+        }
+    }
+
+    /// Note the thread of a line that is logged from the streaming thread:
+    /// processing times for the element that processed the buffer and
+    /// interlatencies for the element the buffer reached.
+    pub fn observe_line(&mut self, line: &str) {
+        let Some(thread) = parse_line_thread(line) else {
+            return;
+        };
+        let element = if let Some(sample) = parse_gst_tracer_output(line) {
+            let Some(proctime_ns) = sample.proctime_ns else {
+                return;
+            };
+            let proctimes = self.proctimes.entry(sample.element.clone()).or_default();
+            proctimes.push_back((sample.timestamp_ns, proctime_ns));
+            if let Some(window_ns) = self.window_ns {
+                let since_ns = sample.timestamp_ns.saturating_sub(window_ns);
+                while proctimes.front().is_some_and(|(ns, _)| *ns < since_ns) {
+                    proctimes.pop_front();
+                }
+            }
+            sample.element
+        } else if let Some(latency) = parse_interlatency(line) {
+            latency.to
+        } else {
+            return;
+        };
+        let timestamp_ns = parse_line_timestamp(line);
+        self.first_ns.get_or_insert(timestamp_ns);
+        self.latest_ns = self.latest_ns.max(timestamp_ns);
+        self.threads.insert(element, thread.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    /// The elements grouped by thread at `timestamp_ns`, with a notice
+    /// naming the threads that heavy elements started sharing since the
+    /// previous snapshot.
+    pub fn snapshot(&mut self, timestamp_ns: u64) -> (StreamingThreads, Option<String>) {
+        let mut since_ns = self.first_ns.unwrap_or(0);
+        if let Some(window_ns) = self.window_ns {
+            since_ns = since_ns.max(self.latest_ns.saturating_sub(window_ns));
+        }
+        let span_ns = self.latest_ns.saturating_sub(since_ns).max(1) as f64;
+
+        let mut grouped: BTreeMap<&str, Vec<ThreadElement>> = BTreeMap::new();
+        for (element, thread) in &self.threads {
+            let processing_ns: u64 = self
+                .proctimes
+                .get(element)
+                .into_iter()
+                .flatten()
+                .filter(|(ns, _)| *ns >= since_ns)
+                .map(|(_, duration_ns)| duration_ns)
+                .sum();
+            let busy = (processing_ns as f64 / span_ns).min(1.0);
+            grouped.entry(thread).or_default().push(ThreadElement {
+                element: element.clone(),
+                busy,
+                heavy: busy >= HEAVY_SHARE,
+            });
+        }
+        let mut threads: Vec<StreamingThread> = grouped
+            .into_iter()
+            .map(|(thread, mut elements)| {
+                elements.sort_by(|a, b| b.busy.total_cmp(&a.busy));
+                StreamingThread {
+                    thread: thread.to_string(),
+                    elements,
+                }
+            })
+            .collect();
+        let busy = |thread: &StreamingThread| -> f64 {
+            thread.elements.iter().map(|element| element.busy).sum()
+        };
+        threads.sort_by(|a, b| busy(b).total_cmp(&busy(a)));
+
+        let mut newly_shared = Vec::new();
+        for thread in &threads {
+            let heavy: Vec<String> = thread
+                .elements
+                .iter()
+                .filter(|element| element.heavy)
+                .map(|element| format!("{} ({:.0}%)", element.element, element.busy * 100.0))
+                .collect();
+            if heavy.len() < 2 {
+                self.shared.remove(&thread.thread);
+            } else if self.shared.insert(thread.thread.clone()) {
+                newly_shared.push(format!("{} on thread {}", heavy.join(", "), thread.thread));
+            }
+        }
+        let notice = (!newly_shared.is_empty()).then(|| {
+            format!(
+                "Heavy elements share a streaming thread: {}; a queue between them gives \
+                 each its own thread",
+                newly_shared.join("; ")
+            )
+        });
+        (
+            StreamingThreads {
+                timestamp_ns,
+                threads,
+            },
+            notice,
+        )
+    }
+}
@@ -0,0 +1,110 @@
+//! Data that disappears inside an element: a pass-through element such as
+//! a queue should put out the bitrate and frame rate its upstream neighbour
+//! feeds it, so less coming out means silent drops, e.g. a leaky queue.
+
+use crate::pipeline::{element_factory, pipeline_elements};
+use crate::rules::RuleResult;
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+/// How far back the rates on either side are averaged.
+const RECENT_NS: u64 = 5_000_000_000;
+/// How much less may come out than goes in, in percent, as the tracers
+/// measure each pad over their own one-second windows.
+const TOLERANCE_PERCENT: f64 = 10.0;
+/// Factories that pass every buffer on unchanged.
+const PASS_THROUGH: [&str; 8] = [
+    "queue",
+    "queue2",
+    "multiqueue",
+    "identity",
+    "capsfilter",
+    "tee",
+    "valve",
+    "progressreport",
+];
+
+/// The element of the store that stands for each name of `chain`, the
+/// element names of a pipeline from upstream to downstream. A factory name
+/// stands for the element GStreamer named after it, counting occurrences:
+/// the second `queue` of the chain is `queue1`.
+fn resolve_chain(store: &MetricStore, chain: &[String]) -> Vec<Option<String>> {
+    let elements: Vec<String> =
+        store.element_stats().into_iter().map(|stats| stats.element).collect();
+    chain
+        .iter()
+        .enumerate()
+        .map(|(position, name)| {
+            if elements.contains(name) {
+                return Some(name.clone());
+            }
+            let occurrence = chain[..position].iter().filter(|earlier| *earlier == name).count();
+            let numbered = format!("{}{}", name, occurrence);
+            elements.contains(&numbered).then_some(numbered)
+        })
+        .collect()
+}
+
+/// An element's average `metric` since `since_ns`, `None` without samples.
+fn recent_average(
+    store: &MetricStore,
+    element: &str,
+    metric: Metric,
+    since_ns: u64,
+) -> Option<f64> {
+    let recent: Vec<f64> = store
+        .series(element, metric)
+        .into_iter()
+        .filter(|(timestamp_ns, _)| *timestamp_ns >= since_ns)
+        .map(|(_, value)| value)
+        .collect();
+    (!recent.is_empty()).then(|| recent.iter().sum::<f64>() / recent.len() as f64)
+}
+
+/// Every pass-through element of a linear `pipeline` that lately put out
+/// less bitrate or fewer frames than its upstream neighbour. The tracers
+/// only measure source pads, so what the neighbour puts out is what goes in.
+pub fn throughput_losses(store: &MetricStore, pipeline: &str) -> Vec<RuleResult> {
+    let now_ns = store.latest_timestamp();
+    let since_ns = now_ns.saturating_sub(RECENT_NS);
+    let chain = pipeline_elements(pipeline);
+    let resolved = resolve_chain(store, &chain);
+    let mut losses = Vec::new();
+    for (position, pair) in resolved.windows(2).enumerate() {
+        let (Some(upstream), Some(element)) = (&pair[0], &pair[1]) else {
+            continue;
+        };
+        let factory = element_factory(pipeline, &chain[position + 1]);
+        if !factory.is_some_and(|factory| PASS_THROUGH.contains(&factory.as_str())) {
+            continue;
+        }
+        for metric in [Metric::Bitrate, Metric::Framerate] {
+            let average = |element| recent_average(store, element, metric, since_ns);
+            let (Some(input), Some(output)) = (average(upstream), average(element)) else {
+                continue;
+            };
+            if output >= input * (1.0 - TOLERANCE_PERCENT / 100.0) {
+                continue;
+            }
+            losses.push(RuleResult {
+                failure: Some(format!(
+                    "{}: {} out {} is {:.0}% below the {} coming in from {}",
+                    element,
+                    metric.label(),
+                    metric.format(output),
+                    (1.0 - output / input) * 100.0,
+                    metric.format(input),
+                    upstream
+                )),
+                check: format!("{} out >= in", metric.label()),
+                element: element.clone(),
+                active: true,
+                metric,
+                threshold: input,
+                value: Some(output),
+                timestamp_ns: now_ns,
+            });
+        }
+    }
+    losses
+}
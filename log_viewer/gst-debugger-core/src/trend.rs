@@ -0,0 +1,204 @@
+//! Slow degradation that no threshold catches: a metric that keeps getting
+//! worse over a long horizon, such as an encoder whose processing time
+//! creeps up minute after minute.
+
+use serde::Deserialize;
+
+use crate::rules::{duration_ns, element_matches, metric_names, RuleResult};
+use crate::stats::Metric;
+use crate::store::MetricStore;
+
+/// How many consecutive parts of the horizon are averaged and compared.
+const TREND_STEPS: usize = 5;
+
+/// The `[trend]` table of a rules file:
+///
+/// ```toml
+/// [trend]
+/// metrics = ["proctime"]
+/// horizon = "10m"
+/// min_change = 20.0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrendDetector {
+    /// Elements watched, as in threshold rules. Every element when omitted.
+    #[serde(default)]
+    pub element: Option<String>,
+    /// Metrics watched. Every metric when omitted.
+    #[serde(default = "all_metrics", deserialize_with = "metric_names")]
+    pub metrics: Vec<Metric>,
+    /// How far back from the latest sample the trend is measured.
+    #[serde(default = "default_horizon", rename = "horizon", deserialize_with = "duration_ns")]
+    pub horizon_ns: Option<f64>,
+    /// The change over the horizon, in percent, worth a warning.
+    #[serde(default = "default_min_change")]
+    pub min_change: f64,
+}
+
+fn all_metrics() -> Vec<Metric> {
+    Metric::ALL.to_vec()
+}
+
+fn default_horizon() -> Option<f64> {
+    Some(600e9)
+}
+
+fn default_min_change() -> f64 {
+    20.0
+}
+
+/// A horizon in the unit it reads best in, e.g. `10 minutes`.
+fn format_horizon(ns: u64) -> String {
+    let seconds = ns / 1_000_000_000;
+    if seconds >= 7200 {
+        format!("{} hours", seconds / 3600)
+    } else if seconds >= 120 {
+        format!("{} minutes", seconds / 60)
+    } else {
+        format!("{} seconds", seconds)
+    }
+}
+
+impl TrendDetector {
+    /// What the detector watches, e.g. `* ProcTime worsening 20% over 10 minutes`.
+    pub fn describe(&self) -> String {
+        let metrics: Vec<&str> = self.metrics.iter().map(|metric| metric.label()).collect();
+        format!(
+            "{} {} worsening {}% over {}",
+            self.element.as_deref().unwrap_or("*"),
+            metrics.join(", "),
+            self.min_change,
+            format_horizon(self.horizon_ns.unwrap_or(0.0) as u64)
+        )
+    }
+
+    /// Check the last horizon of every watched series. Series that do not
+    /// reach back that far yet are not judged.
+    pub fn evaluate(&self, store: &MetricStore) -> Vec<RuleResult> {
+        let stats = store.element_stats();
+        let mut results = Vec::new();
+        for &metric in &self.metrics {
+            for element in &stats {
+                let matches = self
+                    .element
+                    .as_deref()
+                    .is_none_or(|pattern| element_matches(pattern, &element.element));
+                if matches && element.summary(metric).count > 0 {
+                    let values = store.series(&element.element, metric);
+                    results.extend(self.check(&element.element, metric, &values));
+                }
+            }
+        }
+        results
+    }
+
+    /// The series over the horizon is cut into equal steps; it trends when
+    /// the average of every step is worse than the one before and the last
+    /// is worse than the first by at least `min_change`.
+    fn check(&self, element: &str, metric: Metric, values: &[(u64, f64)]) -> Option<RuleResult> {
+        let horizon_ns = (self.horizon_ns.unwrap_or(0.0) as u64).max(TREND_STEPS as u64);
+        let (&(first_ns, _), &(last_ns, _)) = (values.first()?, values.last()?);
+        if last_ns.saturating_sub(first_ns) < horizon_ns {
+            return None;
+        }
+        let start_ns = last_ns - horizon_ns;
+        let step_ns = horizon_ns / TREND_STEPS as u64;
+        let mut sums = [(0.0, 0usize); TREND_STEPS];
+        for &(timestamp_ns, value) in values.iter().filter(|(ns, _)| *ns >= start_ns) {
+            let step = (((timestamp_ns - start_ns) / step_ns) as usize).min(TREND_STEPS - 1);
+            sums[step].0 += value;
+            sums[step].1 += 1;
+        }
+        let averages: Vec<f64> = sums
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(sum, count)| sum / *count as f64)
+            .collect();
+        let (&first, &last) = (averages.first()?, averages.last()?);
+
+        let worse = |before: f64, after: f64| {
+            if metric.higher_is_better() {
+                after < before
+            } else {
+                after > before
+            }
+        };
+        let change = if first == 0.0 {
+            0.0
+        } else {
+            (last - first) / first.abs() * 100.0
+        };
+        let trending = averages.len() >= 3
+            && averages.windows(2).all(|pair| worse(pair[0], pair[1]))
+            && change.abs() >= self.min_change;
+
+        let failure = trending.then(|| {
+            format!(
+                "{} {} has {} {:.0}% over the last {} ({} to {})",
+                element,
+                metric.label(),
+                if change > 0.0 { "increased" } else { "decreased" },
+                change.abs(),
+                format_horizon(horizon_ns),
+                metric.format(first),
+                metric.format(last)
+            )
+        });
+        Some(RuleResult {
+            element: element.to_string(),
+            check: format!(
+                "{} steady within {}% over {}",
+                metric.label(),
+                self.min_change,
+                format_horizon(horizon_ns)
+            ),
+            failure,
+            active: trending,
+            metric,
+            threshold: first,
+            value: trending.then_some(last),
+            timestamp_ns: if trending { start_ns } else { last_ns },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, proctime, SECOND_NS};
+
+    /// A sample a second of `enc0` for `seconds`, its ProcTime in milliseconds.
+    fn store(proctime_ms: impl Fn(u64) -> u64, seconds: u64) -> MetricStore {
+        testing::store((0..=seconds).map(|second| {
+            proctime("enc0", second * SECOND_NS, proctime_ms(second) * 1_000_000)
+        }))
+    }
+
+    fn detector() -> TrendDetector {
+        TrendDetector {
+            element: None,
+            metrics: vec![Metric::ProcTime],
+            horizon_ns: Some(10e9),
+            min_change: 20.0,
+        }
+    }
+
+    #[test]
+    fn steadily_worsening_series_trend() {
+        let results = detector().evaluate(&store(|second| 10 + second, 10));
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed());
+        assert!(results[0].active);
+
+        let results = detector().evaluate(&store(|second| 10 + second % 2, 10));
+        assert!(results[0].passed());
+        let results = detector().evaluate(&store(|second| 20 - second, 10));
+        assert!(results[0].passed());
+    }
+
+    #[test]
+    fn series_shorter_than_the_horizon_are_not_judged() {
+        assert!(detector().evaluate(&store(|second| 10 + second, 5)).is_empty());
+    }
+}
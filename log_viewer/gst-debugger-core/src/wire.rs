@@ -0,0 +1,146 @@
+//! Binary framing of the samples the native `gstdebugger` tracer sends to
+//! the debugger over a Unix socket, so nothing has to be printed and parsed
+//! back. Each frame is a little-endian `u32` payload length followed by:
+//!
+//! | field | encoding |
+//! |---|---|
+//! | version | `u8`, currently 1 |
+//! | timestamp_ns | `u64` |
+//! | fields present | `u8` bit mask: bitrate, framerate, proctime, jitter |
+//! | element | `u16` length and UTF-8 bytes |
+//! | values | `u64` bitrate, `f64` framerate, `u64` proctime, `u64` jitter |
+//!
+//! Only the values flagged in the mask are written. All numbers are
+//! little-endian.
+
+use crate::TracingData;
+
+pub const WIRE_VERSION: u8 = 1;
+/// Frames larger than this are rejected as corrupt.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+const HAS_BITRATE: u8 = 1;
+const HAS_FRAMERATE: u8 = 1 << 1;
+const HAS_PROCTIME: u8 = 1 << 2;
+const HAS_JITTER: u8 = 1 << 3;
+
+/// Append `sample` to `out` as one length-prefixed frame.
+pub fn encode_sample(sample: &TracingData, out: &mut Vec<u8>) {
+    let element = &sample.element.as_bytes()[..sample.element.len().min(u16::MAX as usize)];
+    let mut mask = 0;
+    let mut values = Vec::with_capacity(32);
+    if let Some(bitrate) = sample.bitrate {
+        mask |= HAS_BITRATE;
+        values.extend_from_slice(&bitrate.to_le_bytes());
+    }
+    if let Some(framerate) = sample.framerate {
+        mask |= HAS_FRAMERATE;
+        values.extend_from_slice(&framerate.to_le_bytes());
+    }
+    if let Some(proctime_ns) = sample.proctime_ns {
+        mask |= HAS_PROCTIME;
+        values.extend_from_slice(&proctime_ns.to_le_bytes());
+    }
+    if let Some(jitter_ns) = sample.jitter_ns {
+        mask |= HAS_JITTER;
+        values.extend_from_slice(&jitter_ns.to_le_bytes());
+    }
+
+    let len = 1 + 8 + 1 + 2 + element.len() + values.len();
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+    out.push(WIRE_VERSION);
+    out.extend_from_slice(&sample.timestamp_ns.to_le_bytes());
+    out.push(mask);
+    out.extend_from_slice(&(element.len() as u16).to_le_bytes());
+    out.extend_from_slice(element);
+    out.extend_from_slice(&values);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes)
+    }
+}
+
+/// Decode one frame's payload, without the length prefix. `None` for a
+/// truncated frame or one from a newer tracer.
+pub fn decode_sample(payload: &[u8]) -> Option<TracingData> {
+    let mut reader = Reader(payload);
+    if reader.u8()? != WIRE_VERSION {
+        return None;
+    }
+    let timestamp_ns = reader.u64()?;
+    let mask = reader.u8()?;
+    let element_len = reader.u16()? as usize;
+    let element = String::from_utf8_lossy(reader.take(element_len)?).into_owned();
+    // `None` when the value is flagged but missing, `Some(None)` when absent.
+    let mut field = |bit: u8| match mask & bit {
+        0 => Some(None),
+        _ => reader.u64().map(Some),
+    };
+    let bitrate = field(HAS_BITRATE)?;
+    let framerate = field(HAS_FRAMERATE)?.map(f64::from_bits);
+    let proctime_ns = field(HAS_PROCTIME)?;
+    let jitter_ns = field(HAS_JITTER)?;
+    Some(TracingData {
+        timestamp_ns,
+        element,
+        bitrate,
+        framerate,
+        proctime_ns,
+        jitter_ns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::proctime;
+
+    fn sample() -> TracingData {
+        TracingData {
+            bitrate: Some(4_000_000),
+            ..proctime("x264enc0", 1_000_000_007, 2_500_000)
+        }
+    }
+
+    #[test]
+    fn samples_round_trip() {
+        let mut frame = Vec::new();
+        encode_sample(&sample(), &mut frame);
+        let (len, payload) = frame.split_at(4);
+        assert_eq!(u32::from_le_bytes(len.try_into().unwrap()) as usize, payload.len());
+        assert_eq!(decode_sample(payload), Some(sample()));
+    }
+
+    #[test]
+    fn truncated_and_newer_frames_are_rejected() {
+        let mut frame = Vec::new();
+        encode_sample(&sample(), &mut frame);
+        let payload = &frame[4..];
+        assert_eq!(decode_sample(&payload[..payload.len() - 1]), None);
+
+        let mut newer = payload.to_vec();
+        newer[0] = WIRE_VERSION + 1;
+        assert_eq!(decode_sample(&newer), None);
+    }
+}
@@ -0,0 +1,354 @@
+//! Builder mode: assemble a pipeline by dropping elements from a palette of
+//! the installed factories onto a canvas and linking them, then launch it.
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use gst_debugger::inspect;
+use gst_debugger_core::{ElementInfo, FactoryEntry, PipelineDraft};
+
+const NODE_SIZE: egui::Vec2 = egui::vec2(150.0, 44.0);
+const HANDLE_RADIUS: f32 = 6.0;
+const CANVAS_HEIGHT: f32 = 360.0;
+
+pub(crate) struct Builder {
+    draft: PipelineDraft,
+    /// Position of each element relative to the canvas, in draft order.
+    positions: Vec<egui::Pos2>,
+    /// The description being edited; rewritten whenever the draft changes.
+    text: String,
+    palette: Result<Vec<FactoryEntry>, String>,
+    search: String,
+    selected: Option<usize>,
+    /// The element whose output is being dragged onto another to link them.
+    linking: Option<usize>,
+    docs: HashMap<String, Result<ElementInfo, String>>,
+    new_property: (String, String),
+}
+
+/// Lay the elements out left to right by their distance from a source.
+fn layout(draft: &PipelineDraft) -> Vec<egui::Pos2> {
+    let mut depth = vec![0usize; draft.elements.len()];
+    // Longest path from a source; bounded so cycles do not loop forever.
+    for _ in 0..draft.elements.len() {
+        for link in &draft.links {
+            depth[link.to] = depth[link.to].max(depth[link.from] + 1);
+        }
+    }
+    let mut rows: HashMap<usize, usize> = HashMap::new();
+    depth
+        .iter()
+        .map(|&column| {
+            let row = rows.entry(column).or_default();
+            *row += 1;
+            egui::pos2(20.0 + column as f32 * 180.0, 20.0 + (*row - 1) as f32 * 70.0)
+        })
+        .collect()
+}
+
+impl Builder {
+    pub(crate) fn new(pipeline: &str) -> Self {
+        let draft = PipelineDraft::parse(pipeline);
+        Builder {
+            positions: layout(&draft),
+            text: draft.to_launch(),
+            draft,
+            palette: inspect::element_factories(),
+            search: String::new(),
+            selected: None,
+            linking: None,
+            docs: HashMap::new(),
+            new_property: (String::new(), String::new()),
+        }
+    }
+
+    fn load(&mut self) {
+        self.draft = PipelineDraft::parse(&self.text);
+        self.positions = layout(&self.draft);
+        self.selected = None;
+        self.linking = None;
+    }
+
+    fn changed(&mut self) {
+        self.text = self.draft.to_launch();
+    }
+
+    fn add_element(&mut self, factory: &str, position: egui::Pos2) {
+        let index = self.draft.add_element(factory);
+        self.positions.push(position);
+        self.selected = Some(index);
+        self.changed();
+    }
+
+    fn remove_element(&mut self, index: usize) {
+        self.draft.remove_element(index);
+        self.positions.remove(index);
+        self.selected = None;
+        self.linking = None;
+        self.changed();
+    }
+
+    /// Show the builder window. Returns the description to launch when the
+    /// user asks for it, and whether the window is still open.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> (Option<String>, bool) {
+        let mut open = true;
+        let mut launch = None;
+        egui::Window::new("🧱 Pipeline builder")
+            .open(&mut open)
+            .default_width(900.0)
+            .show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        ui.set_width(200.0);
+                        self.palette(ui);
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        self.canvas(ui);
+                        self.selected_element(ui);
+                        ui.separator();
+                        ui.add(
+                            egui::TextEdit::multiline(&mut self.text)
+                                .desired_rows(2)
+                                .desired_width(f32::INFINITY)
+                                .font(egui::TextStyle::Monospace),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button("⟲ Load text").clicked() {
+                                self.load();
+                            }
+                            if ui.button("▶ Launch").clicked() {
+                                launch = Some(self.text.trim().to_string());
+                            }
+                        });
+                    });
+                });
+            });
+        (launch, open)
+    }
+
+    fn palette(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search elements"));
+        let factories = match &self.palette {
+            Ok(factories) => factories,
+            Err(message) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, message.as_str());
+                return;
+            }
+        };
+        let search = self.search.to_lowercase();
+        egui::ScrollArea::vertical()
+            .id_source("builder_palette")
+            .max_height(CANVAS_HEIGHT + 120.0)
+            .show(ui, |ui| {
+                for factory in factories.iter().filter(|factory| {
+                    factory.name.contains(&search) || factory.klass.to_lowercase().contains(&search)
+                }) {
+                    let id = egui::Id::new(("builder_palette", &factory.name));
+                    ui.dnd_drag_source(id, factory.name.clone(), |ui| {
+                        ui.label(&factory.name);
+                    })
+                    .response
+                    .on_hover_text(format!("{}\n{}", factory.long_name, factory.klass));
+                }
+            });
+    }
+
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        ui.label("Drag elements here, then drag from an element's ● onto another to link them.");
+        let frame = egui::Frame::canvas(ui.style());
+        let (zone, dropped) = ui.dnd_drop_zone::<String>(frame, |ui| {
+            let (rect, _) = ui.allocate_exact_size(
+                egui::vec2(ui.available_width(), CANVAS_HEIGHT),
+                egui::Sense::hover(),
+            );
+            self.draw_canvas(ui, rect);
+        });
+        if let Some(factory) = dropped {
+            let pointer = ui.ctx().pointer_interact_pos().unwrap_or(zone.rect.min);
+            let position = pointer - zone.rect.min.to_vec2() - NODE_SIZE / 2.0;
+            self.add_element(&factory, position);
+        }
+    }
+
+    fn draw_canvas(&mut self, ui: &mut egui::Ui, rect: egui::Rect) {
+        let painter = ui.painter_at(rect);
+        let origin = rect.min.to_vec2();
+        let output =
+            |position: egui::Pos2| position + origin + egui::vec2(NODE_SIZE.x, NODE_SIZE.y / 2.0);
+        let input = |position: egui::Pos2| position + origin + egui::vec2(0.0, NODE_SIZE.y / 2.0);
+
+        for link in &self.draft.links {
+            let from = output(self.positions[link.from]);
+            let to = input(self.positions[link.to]);
+            painter.line_segment([from, to], egui::Stroke::new(2.0, egui::Color32::WHITE));
+            for (pad, position, align) in [
+                (&link.from_pad, from, egui::Align2::LEFT_BOTTOM),
+                (&link.to_pad, to, egui::Align2::RIGHT_BOTTOM),
+            ] {
+                if let Some(pad) = pad {
+                    painter.text(
+                        position,
+                        align,
+                        pad,
+                        egui::FontId::proportional(11.0),
+                        egui::Color32::GRAY,
+                    );
+                }
+            }
+        }
+
+        let mut removed = None;
+        let mut unlinked = None;
+        for index in 0..self.draft.elements.len() {
+            let node = egui::Rect::from_min_size(self.positions[index] + origin, NODE_SIZE);
+            let id = egui::Id::new(("builder_node", index));
+            let response = ui.interact(node, id, egui::Sense::click_and_drag());
+            if response.dragged() {
+                self.positions[index] += response.drag_delta();
+            }
+            if response.clicked() {
+                self.selected = Some(index);
+                self.new_property = (String::new(), String::new());
+            }
+            response.context_menu(|ui| {
+                for link in self.draft.links.iter().filter(|link| link.from == index) {
+                    let target = &self.draft.elements[link.to].name;
+                    if ui.button(format!("Unlink from {}", target)).clicked() {
+                        unlinked = Some((link.from, link.to));
+                        ui.close_menu();
+                    }
+                }
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                    ui.close_menu();
+                }
+            });
+
+            let handle = output(self.positions[index]);
+            let handle_rect =
+                egui::Rect::from_center_size(handle, egui::Vec2::splat(HANDLE_RADIUS * 3.0));
+            let id = egui::Id::new(("builder_handle", index));
+            let handle_response = ui.interact(handle_rect, id, egui::Sense::drag());
+            if handle_response.drag_started() {
+                self.linking = Some(index);
+            }
+
+            let element = &self.draft.elements[index];
+            let fill = if element.is_caps() {
+                egui::Color32::from_rgb(60, 60, 90)
+            } else {
+                egui::Color32::DARK_BLUE
+            };
+            painter.rect_filled(node, 5.0, fill);
+            if self.selected == Some(index) {
+                painter.rect_stroke(node, 5.0, egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE));
+            }
+            painter.text(
+                node.left_center() + egui::vec2(8.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                format!("{}\n{}", element.factory, element.name),
+                egui::FontId::proportional(12.0),
+                egui::Color32::WHITE,
+            );
+            painter.circle_filled(handle, HANDLE_RADIUS, egui::Color32::LIGHT_GREEN);
+        }
+
+        if let Some(from) = self.linking {
+            let pointer = ui.ctx().pointer_interact_pos();
+            if ui.input(|input| input.pointer.any_released()) {
+                let target = pointer.and_then(|pointer| {
+                    (0..self.draft.elements.len()).find(|&index| {
+                        egui::Rect::from_min_size(self.positions[index] + origin, NODE_SIZE)
+                            .contains(pointer)
+                    })
+                });
+                if let Some(to) = target {
+                    self.draft.link(from, to);
+                    self.changed();
+                }
+                self.linking = None;
+            } else if let Some(pointer) = pointer {
+                painter.line_segment(
+                    [output(self.positions[from]), pointer],
+                    egui::Stroke::new(2.0, egui::Color32::LIGHT_GREEN),
+                );
+            }
+        }
+
+        if let Some((from, to)) = unlinked {
+            self.draft.unlink(from, to);
+            self.changed();
+        }
+        if let Some(index) = removed {
+            self.remove_element(index);
+        }
+    }
+
+    fn selected_element(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.selected else {
+            return;
+        };
+        let factory = self.draft.elements[index].factory.clone();
+        if self.draft.elements[index].is_caps() {
+            ui.label(format!("Caps: {}", factory));
+            return;
+        }
+        let info = self
+            .docs
+            .entry(factory.clone())
+            .or_insert_with(|| inspect::element_info(&factory));
+
+        let mut changed = false;
+        let element = &mut self.draft.elements[index];
+        ui.horizontal(|ui| {
+            ui.strong(&factory);
+            ui.label("name:");
+            changed |= ui.text_edit_singleline(&mut element.name).changed();
+        });
+
+        let mut removed = None;
+        egui::Grid::new("builder_properties").striped(true).show(ui, |ui| {
+            for (position, (key, value)) in element.properties.iter_mut().enumerate() {
+                ui.label(key.as_str());
+                changed |= ui.text_edit_singleline(value).changed();
+                if ui.button("✖").clicked() {
+                    removed = Some(position);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(position) = removed {
+            element.properties.remove(position);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            let (key, value) = &mut self.new_property;
+            let writable: Vec<_> = match info {
+                Ok(info) => info.properties.iter().filter(|property| property.writable).collect(),
+                Err(_) => Vec::new(),
+            };
+            egui::ComboBox::from_id_source("builder_new_property")
+                .selected_text(if key.is_empty() { "Property" } else { key.as_str() })
+                .show_ui(ui, |ui| {
+                    for property in &writable {
+                        let response = ui.selectable_label(*key == property.name, &property.name);
+                        if response.on_hover_text(&property.blurb).clicked() {
+                            *key = property.name.clone();
+                            *value = property.default.clone();
+                        }
+                    }
+                });
+            ui.text_edit_singleline(value);
+            if ui.button("Add").clicked() && !key.is_empty() {
+                element.properties.push((std::mem::take(key), std::mem::take(value)));
+                changed = true;
+            }
+        });
+
+        if changed {
+            self.changed();
+        }
+    }
+}
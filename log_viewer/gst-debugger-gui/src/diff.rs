@@ -0,0 +1,197 @@
+//! Two recorded sessions side by side: each element's averages with how they
+//! changed from the first session to the second, and the charts of both.
+
+use std::path::Path;
+
+use eframe::egui;
+use gst_debugger::recorded_pipeline;
+use gst_debugger_core::{Aggregate, Baseline, Metric, MetricStore};
+
+use crate::{draw_baseline_delta, draw_line_chart, TimeAxis};
+
+struct RecordedSession {
+    store: MetricStore,
+    pipeline: Option<String>,
+}
+
+impl RecordedSession {
+    fn load(path: &str) -> Result<Self, String> {
+        let path = Path::new(path.trim());
+        let store = MetricStore::load_log(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        Ok(RecordedSession {
+            store,
+            pipeline: recorded_pipeline(path),
+        })
+    }
+
+    /// The session's series of `metric` for `element`, timed from its start
+    /// so both sessions line up.
+    fn series(&self, element: &str, metric: Metric) -> Vec<(u64, f64)> {
+        let start_ns = self.store.earliest_timestamp();
+        self.store
+            .series(element, metric)
+            .into_iter()
+            .map(|(timestamp_ns, value)| (timestamp_ns.saturating_sub(start_ns), value))
+            .collect()
+    }
+}
+
+pub(crate) struct SessionDiff {
+    paths: [String; 2],
+    sessions: [Option<Result<RecordedSession, String>>; 2],
+    metric: Metric,
+    element: Option<String>,
+    /// Both sessions in one chart rather than one chart each.
+    overlay: bool,
+}
+
+impl SessionDiff {
+    pub(crate) fn new() -> Self {
+        SessionDiff {
+            paths: [String::new(), String::new()],
+            sessions: [None, None],
+            metric: Metric::Framerate,
+            element: None,
+            overlay: false,
+        }
+    }
+
+    fn loaded(&self) -> Option<(&RecordedSession, &RecordedSession)> {
+        match &self.sessions {
+            [Some(Ok(before)), Some(Ok(after))] => Some((before, after)),
+            _ => None,
+        }
+    }
+
+    /// Show the comparison window. Returns whether it is still open.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        egui::Window::new("🆚 Compare sessions")
+            .open(&mut open)
+            .default_width(900.0)
+            .show(ctx, |ui| {
+                for (index, label) in ["Before:", "After:"].into_iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.paths[index])
+                                .hint_text("tracer_output_….log")
+                                .desired_width(420.0),
+                        );
+                        if ui.button("📂 Open").clicked() {
+                            self.sessions[index] = Some(RecordedSession::load(&self.paths[index]));
+                        }
+                        match &self.sessions[index] {
+                            Some(Ok(session)) => {
+                                ui.weak(session.pipeline.as_deref().unwrap_or("unknown pipeline"));
+                            }
+                            Some(Err(message)) => {
+                                ui.colored_label(egui::Color32::LIGHT_RED, message.as_str());
+                            }
+                            None => {}
+                        }
+                    });
+                }
+                if self.loaded().is_none() {
+                    return;
+                }
+                ui.separator();
+                self.averages(ui);
+                ui.separator();
+                self.charts(ui);
+            });
+        open
+    }
+
+    /// Every element's averages in the second session, with arrows for how
+    /// they moved from the first.
+    fn averages(&mut self, ui: &mut egui::Ui) {
+        let Some((before, after)) = self.loaded() else {
+            return;
+        };
+        let baseline = Baseline::capture("", &before.store);
+        let mut selected = None;
+        egui::ScrollArea::both().max_height(220.0).show(ui, |ui| {
+            egui::Grid::new("session_diff").striped(true).show(ui, |ui| {
+                ui.strong("Element");
+                for metric in Metric::ALL {
+                    ui.strong(format!("{} avg", metric.label()));
+                }
+                ui.end_row();
+
+                let after_stats = after.store.element_stats();
+                let mut elements: Vec<String> = baseline.elements.keys().cloned().collect();
+                for stats in &after_stats {
+                    if !baseline.elements.contains_key(&stats.element) {
+                        elements.push(stats.element.clone());
+                    }
+                }
+                for element in &elements {
+                    let chosen = self.element.as_ref() == Some(element);
+                    if ui.selectable_label(chosen, element).clicked() {
+                        selected = Some(element.clone());
+                    }
+                    let stats = after_stats.iter().find(|stats| &stats.element == element);
+                    for metric in Metric::ALL {
+                        let avg = stats.and_then(|stats| stats.summary(metric).get(Aggregate::Avg));
+                        let Some(avg) = avg else {
+                            // Gone from the second session, or never reported.
+                            let summary = baseline
+                                .elements
+                                .get(element)
+                                .and_then(|metrics| metrics.get(metric.name()));
+                            match summary {
+                                Some(summary) => {
+                                    ui.weak(format!("was {}", metric.format(summary.avg)))
+                                }
+                                None => ui.label("-"),
+                            };
+                            continue;
+                        };
+                        ui.horizontal(|ui| {
+                            ui.label(metric.format(avg));
+                            if let Some(delta) = baseline.delta(element, metric, avg) {
+                                draw_baseline_delta(ui, metric, &delta);
+                            }
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(element) = selected {
+            self.element = Some(element);
+        }
+    }
+
+    fn charts(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Metric")
+                .selected_text(self.metric.label())
+                .show_ui(ui, |ui| {
+                    for metric in Metric::ALL {
+                        ui.selectable_value(&mut self.metric, metric, metric.label());
+                    }
+                });
+            ui.checkbox(&mut self.overlay, "Overlay");
+        });
+        let (Some((before, after)), Some(element)) = (self.loaded(), &self.element) else {
+            ui.label("Pick an element in the table to chart it");
+            return;
+        };
+        let metric = self.metric;
+        let format = |value: f64| metric.format(value);
+        let before = (format!("before: {}", element), before.series(element, metric));
+        let after = (format!("after: {}", element), after.series(element, metric));
+        let time = TimeAxis::running_time();
+        if self.overlay {
+            draw_line_chart(ui, &[before, after], &format, &[], time);
+        } else {
+            ui.columns(2, |columns| {
+                draw_line_chart(&mut columns[0], &[before], &format, &[], time);
+                draw_line_chart(&mut columns[1], &[after], &format, &[], time);
+            });
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Highlighting, validation and completion for the pipeline edit box,
+//! checked against the element factories in the registry.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use eframe::egui;
+use egui::text::{LayoutJob, TextFormat};
+use gst_debugger_core::{highlight, Completion, ElementInfo, FactoryEntry, SyntaxKind};
+
+/// Completions listed at most.
+const MAX_COMPLETIONS: usize = 8;
+
+fn is_installed(factories: &[FactoryEntry], factory: &str) -> bool {
+    factories.binary_search_by(|entry| entry.name.as_str().cmp(factory)).is_ok()
+}
+
+/// The installed elements of `pipeline` whose registry details, wanted for
+/// their property names, are not loaded yet.
+pub(crate) fn missing_docs(
+    pipeline: &str,
+    factories: &[FactoryEntry],
+    docs: &HashMap<String, Result<ElementInfo, String>>,
+) -> Vec<String> {
+    highlight(pipeline)
+        .into_iter()
+        .filter(|span| span.kind == SyntaxKind::Element)
+        .map(|span| &pipeline[span.range])
+        .filter(|factory| is_installed(factories, factory) && !docs.contains_key(*factory))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The words of `pipeline` that name no installed element, or no property
+/// of their element, with what is wrong with them. Without a registry, as
+/// when GStreamer failed to load, elements are not checked.
+pub(crate) fn issues(
+    pipeline: &str,
+    factories: &[FactoryEntry],
+    docs: &HashMap<String, Result<ElementInfo, String>>,
+) -> Vec<(Range<usize>, String)> {
+    highlight(pipeline)
+        .into_iter()
+        .filter_map(|span| {
+            let word = &pipeline[span.range.clone()];
+            let message = match span.kind {
+                SyntaxKind::Element if !factories.is_empty() && !is_installed(factories, word) => {
+                    format!("No element called {} is installed", word)
+                }
+                SyntaxKind::Property => {
+                    let Some(Ok(info)) = span.factory.as_ref().and_then(|factory| docs.get(factory))
+                    else {
+                        return None;
+                    };
+                    if info.properties.iter().any(|property| property.name == word) {
+                        return None;
+                    }
+                    format!("{} has no property called {}", info.factory, word)
+                }
+                _ => return None,
+            };
+            Some((span.range, message))
+        })
+        .collect()
+}
+
+/// Colour `pipeline` by syntax, underlining the words in `issues`.
+pub(crate) fn layout_job(
+    ui: &egui::Ui,
+    pipeline: &str,
+    issues: &[(Range<usize>, String)],
+) -> LayoutJob {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let plain = TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let mut job = LayoutJob::default();
+    let mut end = 0;
+    for span in highlight(pipeline) {
+        job.append(&pipeline[end..span.range.start], 0.0, plain.clone());
+        let color = match span.kind {
+            SyntaxKind::Element => egui::Color32::LIGHT_BLUE,
+            SyntaxKind::Caps => egui::Color32::from_rgb(230, 170, 90),
+            SyntaxKind::Property => egui::Color32::from_rgb(140, 200, 140),
+            SyntaxKind::Value => ui.visuals().text_color(),
+            SyntaxKind::Link => egui::Color32::GRAY,
+            SyntaxKind::Reference => egui::Color32::from_rgb(200, 150, 230),
+        };
+        let mut format = TextFormat::simple(font.clone(), color);
+        if issues.iter().any(|(range, _)| *range == span.range) {
+            format.underline = egui::Stroke::new(1.5, egui::Color32::LIGHT_RED);
+        }
+        job.append(&pipeline[span.range.clone()], 0.0, format);
+        end = span.range.end;
+    }
+    job.append(&pipeline[end..], 0.0, plain);
+    job
+}
+
+/// Property names of the element before the word, then element factories,
+/// that start with what is typed.
+pub(crate) fn candidates(
+    completion: &Completion,
+    factories: &[FactoryEntry],
+    docs: &HashMap<String, Result<ElementInfo, String>>,
+) -> Vec<String> {
+    let prefix = completion.prefix.as_str();
+    let mut candidates = Vec::new();
+    if let Some(Ok(info)) = completion.properties_of.as_ref().and_then(|factory| docs.get(factory))
+    {
+        candidates.extend(
+            info.properties
+                .iter()
+                .filter(|property| property.writable && property.name.starts_with(prefix))
+                .map(|property| format!("{}=", property.name)),
+        );
+    }
+    if completion.elements {
+        candidates.extend(
+            factories
+                .iter()
+                .filter(|factory| factory.name.starts_with(prefix) && factory.name != prefix)
+                .map(|factory| factory.name.clone()),
+        );
+    }
+    candidates.truncate(MAX_COMPLETIONS);
+    candidates
+}
@@ -0,0 +1,3733 @@
+mod builder;
+mod diff;
+mod editor;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use clap::Parser;
+use eframe::egui;
+use gst_debugger::cli::{self, Cli};
+use gst_debugger::bugreport::BugReport;
+use gst_debugger::environment::RunEnvironment;
+use gst_debugger::{daemon, devices, export_range, inspect, is_archive, tui, EngineOutput};
+use gst_debugger_core::{
+    capture_pipeline, completion_at, correlation, diff_caps, element_factory, element_health,
+    first_buffers, format_clock_time, format_markdown_summary, format_rules_toml,
+    latency_against_fill, parse_duration_to_ns, pipeline_elements, suggest_rules, throughput_losses,
+    Aggregate, Annotation, AudioLevel, AvSync, Baseline, BaselineDelta, BusMessage, BusMessageKind,
+    CapsMismatch, CaptureDevice, ClockStats, Completion, ElementInfo, ElementProperty, ElementStats,
+    FactoryEntry, Health, InterLatencyData, JitterBufferStats, LatencyReport, MemoryUsage, Metric,
+    MetricStore, NegotiatedFramerates, PipelineCommand, PipelineError, PipelineEvent, PipelineState,
+    QueueLevel, RtpSessionStats, RtspSourceStats, RuleResult, Segment, SinkFrames, Smoothing,
+    StallDetector, StreamCollection, StreamingThreads, RuleSet, TracingData, VideoFrame,
+    WebRtcStats, TRACER_PRESETS,
+};
+use petgraph::graph::{DiGraph, NodeIndex};
+use tokio::sync::{mpsc, watch};
+use tokio::task;
+
+use crate::builder::Builder;
+use crate::diff::SessionDiff;
+
+/// How far either side of a bookmark the charts show when going to it.
+const BOOKMARK_FOCUS_NS: u64 = 5_000_000_000;
+
+/// Bus messages kept for the timeline; the oldest are dropped first.
+const BUS_MESSAGE_LIMIT: usize = 10_000;
+
+/// Jitterbuffer stats kept per element for the charts, one per second.
+const JITTER_BUFFER_HISTORY: usize = 3_600;
+
+/// Clock samples kept for the drift chart, one a second.
+const CLOCK_HISTORY: usize = 3_600;
+
+/// Audio/video offsets kept for the sync chart, one a second.
+const AV_SYNC_HISTORY: usize = 3_600;
+
+/// Memory samples kept for the memory chart, one every 5 seconds.
+const MEMORY_HISTORY: usize = 4_320;
+
+/// Frame counts kept per video sink for the drop-rate chart, one a second.
+const SINK_FRAMES_HISTORY: usize = 3_600;
+
+/// Fill levels kept per queue for the latency scatter plot, one per buffer.
+const QUEUE_LEVEL_HISTORY: usize = 20_000;
+
+/// Segments kept in the segments panel.
+const SEGMENT_LIMIT: usize = 10_000;
+
+/// How long raw samples are kept without `--keep-raw`; older hours are kept
+/// as their rollups, so the GUI can stay open on a pipeline for days.
+const RAW_RETENTION_NS: u64 = 6 * 3_600_000_000_000;
+
+/// How often the statistics, rules and health are worked out again from the
+/// whole store while data keeps coming in.
+const ANALYSIS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatsColumn {
+    Element,
+    Value(Metric, Aggregate),
+}
+
+/// What the jitterbuffer chart plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JitterBufferField {
+    Pushed,
+    Lost,
+    Late,
+    Duplicates,
+    Jitter,
+}
+
+impl JitterBufferField {
+    const ALL: [JitterBufferField; 5] = [
+        JitterBufferField::Pushed,
+        JitterBufferField::Lost,
+        JitterBufferField::Late,
+        JitterBufferField::Duplicates,
+        JitterBufferField::Jitter,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            JitterBufferField::Pushed => "Pushed",
+            JitterBufferField::Lost => "Lost",
+            JitterBufferField::Late => "Late",
+            JitterBufferField::Duplicates => "Duplicates",
+            JitterBufferField::Jitter => "Jitter",
+        }
+    }
+
+    fn value(self, stats: &JitterBufferStats) -> f64 {
+        match self {
+            JitterBufferField::Pushed => stats.pushed as f64,
+            JitterBufferField::Lost => stats.lost as f64,
+            JitterBufferField::Late => stats.late as f64,
+            JitterBufferField::Duplicates => stats.duplicates as f64,
+            JitterBufferField::Jitter => stats.avg_jitter_ns as f64,
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            JitterBufferField::Jitter => Metric::Jitter.format(value),
+            _ => format!("{:.0}", value),
+        }
+    }
+}
+
+/// How times on the charts and bus messages are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeBase {
+    /// Since the run started, which lines up replays and runs recorded on
+    /// other machines.
+    RunningTime,
+    /// Time of day, for matching other logs of the machine.
+    WallClock,
+}
+
+impl TimeBase {
+    fn label(self) -> &'static str {
+        match self {
+            TimeBase::RunningTime => "Running time",
+            TimeBase::WallClock => "Wall clock",
+        }
+    }
+}
+
+/// Turns the timestamps of the current run into labels in a time base.
+#[derive(Clone, Copy)]
+struct TimeAxis {
+    base: TimeBase,
+    /// Where the run's timestamps start.
+    launch_ns: u64,
+    /// Wall-clock time at `launch_ns`.
+    started: DateTime<Local>,
+    /// The span the charts are zoomed to, everything when `None`.
+    window: Option<(u64, u64)>,
+    /// The span dragged out on a chart, shaded on all of them.
+    selection: Option<(u64, u64)>,
+}
+
+impl TimeAxis {
+    /// Running time for recorded logs, whose timestamps start at 0.
+    fn running_time() -> Self {
+        TimeAxis {
+            base: TimeBase::RunningTime,
+            launch_ns: 0,
+            started: Local::now(),
+            window: None,
+            selection: None,
+        }
+    }
+
+    fn format(&self, timestamp_ns: u64) -> String {
+        let running_ns = timestamp_ns.saturating_sub(self.launch_ns);
+        match self.base {
+            TimeBase::RunningTime => format_clock_time(running_ns),
+            TimeBase::WallClock => {
+                let at = self.started + chrono::Duration::nanoseconds(running_ns as i64);
+                at.format("%H:%M:%S%.3f").to_string()
+            }
+        }
+    }
+}
+
+/// What a line chart showed, where it was right-clicked and the span
+/// being dragged out on it.
+struct ChartResponse {
+    range: (u64, u64),
+    clicked_ns: Option<u64>,
+    dragged: Option<(u64, u64)>,
+}
+
+/// A link, the interlatency on it against a queue's fill, and their
+/// correlation.
+type LinkCorrelation = ((String, String), Vec<(f64, f64)>, Option<f64>);
+
+/// What the panels show that is worked out from the whole store, kept
+/// between repaints.
+#[derive(Default)]
+struct Analysis {
+    element_stats: Vec<ElementStats>,
+    /// The health of each element in the graph.
+    health: HashMap<String, Health>,
+    /// The latest sample of each element in the graph, smoothed.
+    latest: HashMap<String, TracingData>,
+    /// The links of each queue, the one following its fill most first.
+    queue_latency: HashMap<String, Vec<LinkCorrelation>>,
+    /// The charted series, smoothed, and the metric, smoothing and elements
+    /// they were drawn for.
+    chart: Vec<(String, Vec<(u64, f64)>)>,
+    chart_key: Option<(Metric, Smoothing, Vec<String>)>,
+}
+
+/// What work done off the UI thread reports back.
+enum Job {
+    /// An event to handle as if the pipeline sent it, such as a notice.
+    Event(PipelineEvent),
+    /// The capture devices for the "New pipeline" dialog.
+    Devices(Result<Vec<CaptureDevice>, String>),
+    /// The registry details of an element factory.
+    Docs(String, Result<ElementInfo, String>),
+}
+
+/// The "New pipeline" dialog: a capture device and the filters to run it
+/// through.
+struct NewPipelineDialog {
+    /// `None` while the devices are still being probed.
+    devices: Option<Result<Vec<CaptureDevice>, String>>,
+    selected: usize,
+    filters: String,
+}
+
+struct GstDebugger {
+    store: MetricStore,
+    graph: DiGraph<String, ()>,
+    node_map: HashMap<String, NodeIndex>,
+    receiver: mpsc::Receiver<TracingData>,
+    latency_receiver: mpsc::Receiver<InterLatencyData>,
+    control_sender: mpsc::Sender<PipelineCommand>,
+    event_receiver: mpsc::Receiver<PipelineEvent>,
+    /// What the work done off the UI thread, such as an export, reports.
+    job_sender: mpsc::UnboundedSender<Job>,
+    job_receiver: mpsc::UnboundedReceiver<Job>,
+    preview_receiver: watch::Receiver<Option<Arc<VideoFrame>>>,
+    preview: Option<egui::TextureHandle>,
+    show_preview: bool,
+    audio_levels: BTreeMap<String, AudioLevel>,
+    show_levels: bool,
+    webrtc_stats: BTreeMap<String, WebRtcStats>,
+    show_webrtc: bool,
+    jitter_buffers: BTreeMap<String, VecDeque<JitterBufferStats>>,
+    show_jitter_buffers: bool,
+    jitter_buffer_chart: JitterBufferField,
+    rtp_sessions: BTreeMap<String, RtpSessionStats>,
+    show_rtp_sessions: bool,
+    rtsp_sources: BTreeMap<String, RtspSourceStats>,
+    show_rtsp: bool,
+    stream_collections: BTreeMap<String, StreamCollection>,
+    show_streams: bool,
+    clock_history: VecDeque<ClockStats>,
+    show_clock: bool,
+    av_sync: VecDeque<AvSync>,
+    show_av_sync: bool,
+    memory: VecDeque<MemoryUsage>,
+    show_memory: bool,
+    streaming_threads: Option<StreamingThreads>,
+    show_threads: bool,
+    queue_levels: BTreeMap<String, VecDeque<QueueLevel>>,
+    show_queue_latency: bool,
+    /// The queue and the interlatency link the scatter plot sets against
+    /// each other; the link defaults to the one that follows the queue most.
+    queue_latency_queue: Option<String>,
+    queue_latency_link: Option<(String, String)>,
+    sink_frames: BTreeMap<String, VecDeque<SinkFrames>>,
+    show_sink_frames: bool,
+    latency_report: Option<LatencyReport>,
+    show_latency_report: bool,
+    segments: VecDeque<Segment>,
+    show_segments: bool,
+    negotiated_framerates: NegotiatedFramerates,
+    /// Where the current run's timestamps start, to time its first buffers.
+    launch_ns: u64,
+    /// Wall-clock time at `launch_ns`.
+    run_started: DateTime<Local>,
+    /// When a replayed log was recorded, which its timestamps count from.
+    recorded_start: Option<DateTime<Local>>,
+    time_base: TimeBase,
+    /// Whether the current run's first buffer has been marked on the charts.
+    first_buffer_marked: bool,
+    /// The caps each `element.pad` last negotiated, to mark renegotiations.
+    pad_caps: HashMap<String, String>,
+    show_startup: bool,
+    pipeline_state: PipelineState,
+    playback_position: Option<(u64, u64)>,
+    seek_drag: Option<f64>,
+    eos_elements: HashSet<String>,
+    element_properties: HashMap<String, Vec<ElementProperty>>,
+    property_edits: HashMap<String, String>,
+    annotations: Vec<Annotation>,
+    /// Name of the next marker dropped on the timeline.
+    annotation_text: String,
+    /// The markers and bookmarks the user placed, which the tracer log keeps.
+    bookmarks: Vec<Annotation>,
+    show_bookmarks: bool,
+    /// The span around a bookmark the charts are zoomed to.
+    chart_focus: Option<(u64, u64)>,
+    /// The span dragged out on the chart to export.
+    selection: Option<(u64, u64)>,
+    /// The tracer log of the run and the offset of its timestamps.
+    log_file: Option<(PathBuf, u64)>,
+    /// What the pipeline runs on, for bug reports.
+    environment: Option<RunEnvironment>,
+    /// Where the graph was drawn last frame, cut out of screenshots.
+    graph_rect: Option<egui::Rect>,
+    /// A bug report waits for the screenshot of the graph.
+    bug_report_requested: bool,
+    bus_messages: VecDeque<BusMessage>,
+    hidden_bus_kinds: HashSet<BusMessageKind>,
+    notice: Option<String>,
+    pipeline_error: Option<PipelineError>,
+    restart_count: u32,
+    next_restart_at: Option<Instant>,
+    quit_requested: Arc<AtomicBool>,
+    positions: HashMap<NodeIndex, egui::Pos2>,
+    bitrate_threshold: u64,
+    framerate_threshold: f64,
+    latency_threshold_ns: u64,
+    /// How long an element may stay silent, unless the rules set a `[stall]`.
+    stall_after_s: f64,
+    rules: RuleSet,
+    alerts: Vec<RuleResult>,
+    stalled: Vec<RuleResult>,
+    analysis: Analysis,
+    /// New data or settings arrived since the last analysis.
+    analysis_stale: bool,
+    analyzed_at: Option<Instant>,
+    selected_element: Option<String>,
+    histogram_bins: usize,
+    show_stats: bool,
+    stats_sort: StatsColumn,
+    stats_sort_descending: bool,
+    /// Statistics of an earlier run of the pipeline the table compares with.
+    baseline: Option<Baseline>,
+    show_chart: bool,
+    chart_metric: Metric,
+    smoothing: HashMap<Metric, Smoothing>,
+    chart_elements: Vec<String>,
+    pipeline_text: String,
+    tracing_text: String,
+    active_tracing: String,
+    new_pipeline: Option<NewPipelineDialog>,
+    builder: Option<Builder>,
+    session_diff: Option<SessionDiff>,
+    /// Registry details per element factory, loaded when first shown.
+    element_docs: HashMap<String, Result<ElementInfo, String>>,
+    /// Factories whose details are being loaded.
+    docs_loading: HashSet<String>,
+    /// Installed element factories, for completion and validation.
+    factories: Result<Vec<FactoryEntry>, String>,
+    /// The word being completed in the pipeline field and its candidates.
+    completion: Option<(Completion, Vec<String>)>,
+    completion_selected: usize,
+}
+
+type PipelineGraph = (
+    DiGraph<String, ()>,
+    HashMap<String, NodeIndex>,
+    HashMap<NodeIndex, egui::Pos2>,
+);
+
+fn build_pipeline_graph(pipeline: &str) -> PipelineGraph {
+    let mut graph = DiGraph::new();
+    let mut node_map = HashMap::new();
+    let mut positions = HashMap::new();
+
+    let elements = pipeline_elements(pipeline);
+
+    let mut prev_node = None;
+    let mut x = 50.0;
+    let y = 200.0;
+
+    for element in &elements {
+        let node = graph.add_node(element.to_string());
+        node_map.insert(element.to_string(), node);
+        positions.insert(node, egui::pos2(x, y));
+        x += 150.0;
+
+        if let Some(prev) = prev_node {
+            graph.add_edge(prev, node, ());
+        }
+        prev_node = Some(node);
+    }
+
+    (graph, node_map, positions)
+}
+
+impl GstDebugger {
+    fn new(
+        pipeline: String,
+        tracing: String,
+        output: EngineOutput,
+        control_sender: mpsc::Sender<PipelineCommand>,
+        rules: RuleSet,
+        quit_requested: Arc<AtomicBool>,
+        recorded_start: Option<DateTime<Local>>,
+    ) -> Self {
+        let (graph, node_map, positions) = build_pipeline_graph(&pipeline);
+        let baseline = Baseline::load(&Baseline::path_for(&pipeline)).ok();
+        let (job_sender, job_receiver) = mpsc::unbounded_channel();
+
+        Self {
+            store: MetricStore::default(),
+            graph,
+            node_map,
+            receiver: output.data,
+            latency_receiver: output.latency,
+            control_sender,
+            event_receiver: output.events,
+            job_sender,
+            job_receiver,
+            preview_receiver: output.preview,
+            preview: None,
+            show_preview: true,
+            audio_levels: BTreeMap::new(),
+            show_levels: true,
+            webrtc_stats: BTreeMap::new(),
+            show_webrtc: true,
+            jitter_buffers: BTreeMap::new(),
+            show_jitter_buffers: true,
+            jitter_buffer_chart: JitterBufferField::Lost,
+            rtp_sessions: BTreeMap::new(),
+            show_rtp_sessions: true,
+            rtsp_sources: BTreeMap::new(),
+            show_rtsp: true,
+            stream_collections: BTreeMap::new(),
+            show_streams: true,
+            clock_history: VecDeque::new(),
+            show_clock: true,
+            av_sync: VecDeque::new(),
+            show_av_sync: true,
+            memory: VecDeque::new(),
+            show_memory: true,
+            streaming_threads: None,
+            show_threads: true,
+            queue_levels: BTreeMap::new(),
+            show_queue_latency: true,
+            queue_latency_queue: None,
+            queue_latency_link: None,
+            sink_frames: BTreeMap::new(),
+            show_sink_frames: true,
+            latency_report: None,
+            show_latency_report: true,
+            segments: VecDeque::new(),
+            show_segments: true,
+            negotiated_framerates: NegotiatedFramerates::default(),
+            launch_ns: 0,
+            run_started: recorded_start.unwrap_or_else(Local::now),
+            recorded_start,
+            time_base: TimeBase::RunningTime,
+            first_buffer_marked: false,
+            pad_caps: HashMap::new(),
+            show_startup: true,
+            pipeline_state: PipelineState::Playing,
+            playback_position: None,
+            seek_drag: None,
+            eos_elements: HashSet::new(),
+            element_properties: HashMap::new(),
+            property_edits: HashMap::new(),
+            annotations: Vec::new(),
+            annotation_text: String::new(),
+            bookmarks: Vec::new(),
+            show_bookmarks: true,
+            chart_focus: None,
+            selection: None,
+            log_file: None,
+            environment: None,
+            graph_rect: None,
+            bug_report_requested: false,
+            bus_messages: VecDeque::new(),
+            hidden_bus_kinds: HashSet::new(),
+            notice: None,
+            pipeline_error: None,
+            restart_count: 0,
+            next_restart_at: None,
+            quit_requested,
+            positions,
+            bitrate_threshold: 0,
+            framerate_threshold: 0.0,
+            latency_threshold_ns: 0,
+            stall_after_s: 5.0,
+            rules,
+            alerts: Vec::new(),
+            stalled: Vec::new(),
+            analysis: Analysis::default(),
+            analysis_stale: true,
+            analyzed_at: None,
+            selected_element: None,
+            histogram_bins: 20,
+            show_stats: false,
+            stats_sort: StatsColumn::Element,
+            stats_sort_descending: false,
+            baseline,
+            show_chart: false,
+            chart_metric: Metric::Framerate,
+            smoothing: HashMap::new(),
+            chart_elements: Vec::new(),
+            pipeline_text: pipeline,
+            tracing_text: tracing.clone(),
+            active_tracing: tracing,
+            new_pipeline: None,
+            builder: None,
+            session_diff: None,
+            element_docs: HashMap::new(),
+            docs_loading: HashSet::new(),
+            factories: inspect::element_factories(),
+            completion: None,
+            completion_selected: 0,
+        }
+    }
+}
+
+impl GstDebugger {
+    fn stats_table(&mut self, ui: &mut egui::Ui) {
+        let mut stats = self.analysis.element_stats.clone();
+
+        let sort = self.stats_sort;
+        stats.sort_by(|a, b| {
+            let ordering = match sort {
+                StatsColumn::Element => a.element.cmp(&b.element),
+                StatsColumn::Value(metric, aggregate) => {
+                    let a = a.summary(metric).get(aggregate);
+                    let b = b.summary(metric).get(aggregate);
+                    match (a, b) {
+                        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+                        (Some(_), None) => std::cmp::Ordering::Greater,
+                        (None, Some(_)) => std::cmp::Ordering::Less,
+                        (None, None) => std::cmp::Ordering::Equal,
+                    }
+                }
+            };
+            if self.stats_sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("💾 Save as baseline").clicked() {
+                self.save_baseline();
+            }
+            if ui
+                .button("📏 Suggest rules")
+                .on_hover_text("Write threshold rules drawn from this run for --rules")
+                .clicked()
+            {
+                self.save_suggested_rules();
+            }
+            if let Some(baseline) = &self.baseline {
+                ui.label(format!(
+                    "Averages compared with the baseline of {} elements",
+                    baseline.elements.len()
+                ));
+            }
+        });
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("stats_table")
+                .striped(true)
+                .min_col_width(70.0)
+                .show(ui, |ui| {
+                    self.stats_header(ui, StatsColumn::Element, "Element".to_string());
+                    for metric in Metric::ALL {
+                        for &aggregate in metric.aggregates() {
+                            self.stats_header(
+                                ui,
+                                StatsColumn::Value(metric, aggregate),
+                                format!("{} {}", metric.label(), aggregate.label()),
+                            );
+                        }
+                    }
+                    ui.end_row();
+
+                    for element in &stats {
+                        if ui
+                            .selectable_label(
+                                self.selected_element.as_ref() == Some(&element.element),
+                                &element.element,
+                            )
+                            .clicked()
+                        {
+                            self.selected_element = Some(element.element.clone());
+                        }
+                        for metric in Metric::ALL {
+                            for &aggregate in metric.aggregates() {
+                                let Some(value) = element.summary(metric).get(aggregate) else {
+                                    ui.label("-");
+                                    continue;
+                                };
+                                let delta = self
+                                    .baseline
+                                    .as_ref()
+                                    .filter(|_| aggregate == Aggregate::Avg)
+                                    .and_then(|baseline| {
+                                        baseline.delta(&element.element, metric, value)
+                                    });
+                                ui.horizontal(|ui| {
+                                    ui.label(metric.format(value));
+                                    if let Some(delta) = delta {
+                                        draw_baseline_delta(ui, metric, &delta);
+                                    }
+                                });
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn element_docs(&mut self, ui: &mut egui::Ui, element_name: &str) {
+        let factory = element_factory(&self.pipeline_text, element_name)
+            .unwrap_or_else(|| element_name.to_string());
+        let shown = egui::CollapsingHeader::new("📖 Documentation")
+            .id_source(("element_docs", element_name))
+            .show(ui, |ui| match self.element_docs.get(&factory) {
+                Some(Ok(info)) => draw_element_info(ui, info),
+                Some(Err(message)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, message.as_str());
+                }
+                None => {
+                    ui.spinner();
+                }
+            });
+        if shown.body_returned.is_some() {
+            self.request_docs(vec![factory], ui.ctx());
+        }
+    }
+
+    fn element_panel(&mut self, ui: &mut egui::Ui, element_name: &str) {
+        ui.horizontal(|ui| {
+            ui.heading(element_name);
+            if ui.button("✖").clicked() {
+                self.selected_element = None;
+            }
+        });
+        self.element_docs(ui, element_name);
+        ui.add(egui::Slider::new(&mut self.histogram_bins, 5..=60).text("Bins"));
+
+        let proctimes = self.store.proctimes(element_name);
+        let latencies = self.store.interlatencies(element_name);
+
+        ui.separator();
+        ui.label("ProcTime distribution");
+        draw_histogram(ui, &proctimes, self.histogram_bins, None);
+
+        ui.separator();
+        ui.label("Interlatency distribution");
+        let threshold_ns = self.latency_threshold_ns(element_name);
+        draw_histogram(ui, &latencies, self.histogram_bins, Some(threshold_ns));
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.strong("Properties");
+            if ui.button("🔄").clicked() {
+                let _ = self
+                    .control_sender
+                    .try_send(PipelineCommand::QueryProperties(element_name.to_string()));
+            }
+        });
+
+        let Some(properties) = self.element_properties.get(element_name) else {
+            ui.label("Press 🔄 to read the element's properties");
+            return;
+        };
+
+        let mut changed = None;
+        egui::Grid::new("property_grid").striped(true).show(ui, |ui| {
+            for property in properties {
+                ui.label(&property.name).on_hover_text(&property.type_name);
+                if property.writable {
+                    let edit = self
+                        .property_edits
+                        .entry(property.name.clone())
+                        .or_insert_with(|| property.value.clone());
+                    ui.add(egui::TextEdit::singleline(edit).desired_width(120.0));
+                    if ui.button("Set").clicked() {
+                        changed = Some((property.name.clone(), edit.clone()));
+                    }
+                } else {
+                    ui.label(&property.value);
+                    ui.label("");
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some((name, value)) = changed {
+            let _ = self.control_sender.try_send(PipelineCommand::SetProperty {
+                element: element_name.to_string(),
+                name,
+                value,
+            });
+        }
+    }
+
+    fn status_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("State: {:?}", self.pipeline_state));
+            if let Some(restart_at) = self.next_restart_at {
+                let remaining = restart_at.saturating_duration_since(Instant::now());
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Restarting in {:.1}s", remaining.as_secs_f32()),
+                );
+            }
+            ui.separator();
+            ui.label(format!("Restarts: {}", self.restart_count));
+            ui.separator();
+            ui.label(format!(
+                "Samples: {} metrics, {} latencies",
+                self.store.samples().len(),
+                self.store.latencies().len()
+            ));
+            if !self.alerts.is_empty() {
+                ui.separator();
+                let details: Vec<String> = self.alerts.iter().map(RuleResult::describe).collect();
+                let active = self.alerts.iter().filter(|alert| alert.active).count();
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 140, 0),
+                    format!("⚠ {} alerts, {} active", self.alerts.len(), active),
+                )
+                .on_hover_text(details.join("\n"));
+            }
+        });
+    }
+
+    /// The interlatency limit for `element`: its most specific rule, or the
+    /// global slider when no rule covers it.
+    fn latency_threshold_ns(&self, element: &str) -> u64 {
+        self.rules
+            .limit_for(element, Metric::Interlatency)
+            .map_or(self.latency_threshold_ns, |limit| limit.value as u64)
+    }
+
+    fn error_window(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.pipeline_error else {
+            return;
+        };
+
+        let mut open = true;
+        let mut dismissed = false;
+        egui::Window::new("⚠ Pipeline error")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(560.0)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.colored_label(egui::Color32::LIGHT_RED, egui::RichText::new(&error.message).strong());
+                if let Some(element) = &error.element {
+                    ui.label(format!("Element: {}", element));
+                }
+                if !error.debug.is_empty() {
+                    ui.separator();
+                    ui.label("Debug info:");
+                    ui.monospace(&error.debug);
+                }
+                if let Some(caps) = &error.caps {
+                    ui.separator();
+                    draw_caps_mismatch(ui, caps);
+                }
+
+                ui.separator();
+                ui.label(format!("Last {} log lines:", error.recent_lines.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(260.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in &error.recent_lines {
+                            ui.monospace(line);
+                        }
+                    });
+
+                ui.separator();
+                dismissed = ui.button("Close").clicked();
+            });
+
+        if !open || dismissed {
+            self.pipeline_error = None;
+        }
+    }
+
+    /// Up and down pick a completion and tab accepts it, replacing the word
+    /// being typed.
+    fn complete_pipeline(&mut self, ui: &mut egui::Ui, id: egui::Id) {
+        let Some((_, candidates)) = &self.completion else {
+            return;
+        };
+        if !ui.memory(|memory| memory.has_focus(id)) {
+            return;
+        }
+        let count = candidates.len();
+        let (down, up, accept) = ui.input_mut(|input| {
+            (
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                input.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+            )
+        });
+        if down {
+            self.completion_selected = (self.completion_selected + 1) % count;
+        }
+        if up {
+            self.completion_selected = (self.completion_selected + count - 1) % count;
+        }
+        if !accept {
+            return;
+        }
+        let Some((completion, candidates)) = self.completion.take() else {
+            return;
+        };
+        let candidate = &candidates[self.completion_selected.min(count - 1)];
+        self.pipeline_text.replace_range(completion.range.clone(), candidate);
+        let end = completion.range.start + candidate.len();
+        if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), id) {
+            let cursor = egui::text::CCursor::new(self.pipeline_text[..end].chars().count());
+            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+            state.store(ui.ctx(), id);
+        }
+    }
+
+    fn pipeline_editor(&mut self, ui: &mut egui::Ui) {
+        let id = egui::Id::new("pipeline_text");
+        self.complete_pipeline(ui, id);
+        let factories = self.factories.as_deref().unwrap_or_default();
+        let missing = editor::missing_docs(&self.pipeline_text, factories, &self.element_docs);
+        self.request_docs(missing, ui.ctx());
+        let factories = self.factories.as_deref().unwrap_or_default();
+        let issues = editor::issues(&self.pipeline_text, factories, &self.element_docs);
+        let docs = &self.element_docs;
+        let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
+            let job = editor::layout_job(ui, text, &editor::issues(text, factories, docs));
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+
+        let (mut apply, mut new_pipeline) = (false, false);
+        ui.horizontal(|ui| {
+            ui.label("Pipeline:");
+            let output = egui::TextEdit::singleline(&mut self.pipeline_text)
+                .id(id)
+                .desired_width(ui.available_width() - 80.0)
+                .font(egui::TextStyle::Monospace)
+                .lock_focus(self.completion.is_some())
+                .layouter(&mut layouter)
+                .show(ui);
+            let response = output.response;
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            apply = ui.button("Apply").clicked() || submitted;
+            if !issues.is_empty() {
+                let messages: Vec<&str> =
+                    issues.iter().map(|(_, message)| message.as_str()).collect();
+                ui.colored_label(egui::Color32::LIGHT_RED, "⚠")
+                    .on_hover_text(messages.join("\n"));
+            }
+
+            let cursor = output.cursor_range.filter(|_| response.has_focus());
+            let completion = cursor.and_then(|cursor| {
+                let text = &self.pipeline_text;
+                let index = cursor.primary.ccursor.index;
+                let byte = text.char_indices().nth(index).map_or(text.len(), |(byte, _)| byte);
+                let completion = completion_at(text, byte)?;
+                let candidates = editor::candidates(&completion, factories, docs);
+                (!candidates.is_empty()).then_some((completion, candidates))
+            });
+            let previous = self.completion.as_ref().map(|(completion, _)| &completion.prefix);
+            if completion.as_ref().map(|(completion, _)| &completion.prefix) != previous {
+                self.completion_selected = 0;
+            }
+            self.completion = completion;
+            if let Some((_, candidates)) = &self.completion {
+                egui::Area::new(id.with("completions"))
+                    .order(egui::Order::Foreground)
+                    .fixed_pos(response.rect.left_bottom())
+                    .show(ui.ctx(), |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            for (index, candidate) in candidates.iter().enumerate() {
+                                let text = egui::RichText::new(candidate).monospace();
+                                let selected = index == self.completion_selected;
+                                ui.add(egui::SelectableLabel::new(selected, text));
+                            }
+                            ui.weak("↑↓ to pick, Tab to complete");
+                        });
+                    });
+            }
+            if ui.button("🆕 New").on_hover_text("Start from a capture device").clicked() {
+                new_pipeline = true;
+            }
+            if ui.button("🧱 Builder").on_hover_text("Assemble the pipeline visually").clicked() {
+                self.builder = Some(Builder::new(&self.pipeline_text));
+            }
+            if ui.button("🆚 Compare").on_hover_text("Compare two recorded sessions").clicked() {
+                self.session_diff = Some(SessionDiff::new());
+            }
+        });
+        if apply {
+            self.apply_pipeline();
+        }
+        if new_pipeline {
+            self.open_new_pipeline(ui.ctx());
+        }
+    }
+
+    fn session_diff_window(&mut self, ctx: &egui::Context) {
+        if let Some(diff) = &mut self.session_diff
+            && !diff.show(ctx)
+        {
+            self.session_diff = None;
+        }
+    }
+
+    fn builder_window(&mut self, ctx: &egui::Context) {
+        let Some(builder) = &mut self.builder else {
+            return;
+        };
+        let (launch, open) = builder.show(ctx);
+        if !open {
+            self.builder = None;
+        }
+        if let Some(pipeline) = launch {
+            self.pipeline_text = pipeline;
+            self.apply_pipeline();
+        }
+    }
+
+    fn new_pipeline_window(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.new_pipeline else {
+            return;
+        };
+
+        let mut open = true;
+        let mut launch = None;
+        egui::Window::new("New pipeline")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let devices = match &dialog.devices {
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Looking for cameras, microphones and screens…");
+                        });
+                        return;
+                    }
+                    Some(Ok(devices)) if devices.is_empty() => {
+                        ui.label("No cameras, microphones or screens found.");
+                        return;
+                    }
+                    Some(Ok(devices)) => devices,
+                    Some(Err(message)) => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, message);
+                        return;
+                    }
+                };
+
+                ui.label("Device:");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for (index, device) in devices.iter().enumerate() {
+                        let label = format!("{} ({})", device.name, device.class);
+                        ui.selectable_value(&mut dialog.selected, index, label)
+                            .on_hover_text(&device.source);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Filters:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut dialog.filters)
+                            .hint_text("e.g. videoflip method=horizontal-flip")
+                            .desired_width(f32::INFINITY)
+                            .font(egui::TextStyle::Monospace),
+                    );
+                });
+
+                let Some(device) = devices.get(dialog.selected) else {
+                    return;
+                };
+                let pipeline = capture_pipeline(device, &dialog.filters);
+                ui.separator();
+                ui.monospace(&pipeline);
+                if ui.button("▶ Launch").clicked() {
+                    launch = Some(pipeline);
+                }
+            });
+
+        if let Some(pipeline) = launch {
+            self.pipeline_text = pipeline;
+            self.apply_pipeline();
+            self.new_pipeline = None;
+        } else if !open {
+            self.new_pipeline = None;
+        }
+    }
+
+    fn apply_pipeline(&mut self) {
+        let pipeline = self.pipeline_text.trim().to_string();
+        if pipeline.is_empty() {
+            return;
+        }
+
+        let (graph, node_map, positions) = build_pipeline_graph(&pipeline);
+        self.graph = graph;
+        self.node_map = node_map;
+        self.positions = positions;
+        self.selected_element = None;
+        self.chart_elements.clear();
+        self.clear_metrics();
+        self.baseline = Baseline::load(&Baseline::path_for(&pipeline)).ok();
+        let _ = self.control_sender.try_send(PipelineCommand::Relaunch(pipeline));
+    }
+
+    fn clear_metrics(&mut self) {
+        // A relaunched run's timestamps carry on from the previous run's,
+        // while a replayed log starts over at its recorded times.
+        if self.recorded_start.is_none() {
+            self.launch_ns = self.store.latest_timestamp();
+            self.run_started = Local::now();
+        }
+        self.store.clear();
+        self.eos_elements.clear();
+        self.negotiated_framerates.clear();
+        self.first_buffer_marked = false;
+        self.pad_caps.clear();
+        self.chart_focus = None;
+        self.selection = None;
+        self.reanalyze();
+    }
+
+    /// Mark the first buffer of the run, from a sample or latency timed at
+    /// `timestamp_ns` that only a buffer passing `element` produces.
+    fn mark_first_buffer(&mut self, element: &str, timestamp_ns: u64) {
+        if self.first_buffer_marked {
+            return;
+        }
+        self.first_buffer_marked = true;
+        let after_ns = timestamp_ns.saturating_sub(self.launch_ns) as f64;
+        self.annotations.push(Annotation {
+            timestamp_ns,
+            text: format!(
+                "first buffer at {} ({} after launch)",
+                element,
+                Metric::Interlatency.format(after_ns)
+            ),
+        });
+    }
+
+    fn tracer_selector(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Tracers:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.tracing_text)
+                    .desired_width(320.0)
+                    .font(egui::TextStyle::Monospace),
+            );
+            egui::ComboBox::from_id_source("tracer_presets")
+                .selected_text("Presets")
+                .show_ui(ui, |ui| {
+                    for (name, tracers) in TRACER_PRESETS {
+                        if ui.selectable_label(self.tracing_text == tracers, name).clicked() {
+                            self.tracing_text = tracers.to_string();
+                        }
+                    }
+                });
+
+            let tracing = self.tracing_text.trim().to_string();
+            let changed = !tracing.is_empty() && tracing != self.active_tracing;
+            if ui
+                .add_enabled(changed, egui::Button::new("🔁 Relaunch with tracers"))
+                .clicked()
+            {
+                self.clear_metrics();
+                self.active_tracing = tracing.clone();
+                let _ = self.control_sender.try_send(PipelineCommand::SetTracers(tracing));
+            }
+        });
+    }
+
+    fn seek_bar(&mut self, ui: &mut egui::Ui) {
+        let Some((position_ns, duration_ns)) = self.playback_position else {
+            return;
+        };
+        if duration_ns == 0 {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let duration_s = duration_ns as f64 / 1e9;
+            let mut position_s = self.seek_drag.unwrap_or(position_ns as f64 / 1e9);
+            let response = ui.add_sized(
+                egui::vec2(ui.available_width() - 160.0, 18.0),
+                egui::Slider::new(&mut position_s, 0.0..=duration_s).show_value(false),
+            );
+
+            if response.dragged() {
+                self.seek_drag = Some(position_s);
+            } else if response.drag_released() || response.changed() {
+                self.seek_drag = None;
+                let target_ns = (position_s * 1e9) as u64;
+                let _ = self.control_sender.try_send(PipelineCommand::Seek(target_ns));
+            }
+
+            ui.label(format!(
+                "{} / {}",
+                format_clock_time((position_s * 1e9) as u64),
+                format_clock_time(duration_ns)
+            ));
+        });
+    }
+
+    fn transport_controls(&mut self, ui: &mut egui::Ui) {
+        let state = self.pipeline_state;
+        if ui
+            .add_enabled(state == PipelineState::Paused, egui::Button::new("▶ Play"))
+            .clicked()
+        {
+            let _ = self.control_sender.try_send(PipelineCommand::Play);
+        }
+        if ui
+            .add_enabled(state == PipelineState::Playing, egui::Button::new("⏸ Pause"))
+            .clicked()
+        {
+            let _ = self.control_sender.try_send(PipelineCommand::Pause);
+        }
+        if ui
+            .add_enabled(state != PipelineState::Stopped, egui::Button::new("⏹ Stop"))
+            .clicked()
+        {
+            let _ = self.control_sender.try_send(PipelineCommand::Stop);
+        }
+        if ui
+            .add_enabled(state == PipelineState::Playing, egui::Button::new("⏏ Send EOS"))
+            .on_hover_text("Send EOS and watch it drain through the elements (see --trace-eos)")
+            .clicked()
+        {
+            let _ = self.control_sender.try_send(PipelineCommand::SendEos);
+        }
+        if ui.button("🔁 Restart").clicked() {
+            self.clear_metrics();
+            let _ = self.control_sender.try_send(PipelineCommand::Restart);
+        }
+        ui.separator();
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.annotation_text)
+                .hint_text("Marker, e.g. switched to WiFi")
+                .desired_width(180.0),
+        );
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let hotkey = ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::M));
+        if ui
+            .button("📍 Mark")
+            .on_hover_text("Drop a named marker on every chart and in the log (Ctrl+M)")
+            .clicked()
+            || submitted
+            || hotkey
+        {
+            self.annotate();
+        }
+        ui.label(format!("{:?}", state));
+    }
+
+    /// Mark the latest sample on every chart, and in the tracer log so the
+    /// marker comes back when the run is replayed.
+    fn annotate(&mut self) {
+        let text = match self.annotation_text.trim() {
+            "" => format!("marker {}", self.annotations.len() + 1),
+            text => text.to_string(),
+        };
+        self.add_bookmark(Annotation {
+            timestamp_ns: self.store.latest_timestamp(),
+            text,
+        });
+    }
+
+    /// Bookmark a moment of the charts with the note in the marker field,
+    /// after `what` is there, such as the bus message bookmarked.
+    fn bookmark(&mut self, timestamp_ns: u64, what: Option<String>) {
+        let text = match (self.annotation_text.trim(), what) {
+            ("", Some(what)) => what,
+            ("", None) => format!("bookmark {}", self.bookmarks.len() + 1),
+            (note, Some(what)) => format!("{} ({})", note, what),
+            (note, None) => note.to_string(),
+        };
+        self.add_bookmark(Annotation { timestamp_ns, text });
+    }
+
+    /// Show the bookmark on the charts and list it, and have it written
+    /// into the tracer log, where a replay finds it again.
+    fn add_bookmark(&mut self, annotation: Annotation) {
+        let _ = self.control_sender.try_send(PipelineCommand::Annotate(annotation.clone()));
+        self.annotations.push(annotation.clone());
+        self.bookmarks.push(annotation);
+        self.annotation_text.clear();
+    }
+
+    fn bookmarks_panel(&mut self, ui: &mut egui::Ui) {
+        let time = self.time_axis();
+        let mut bookmarks: Vec<&Annotation> = self.bookmarks.iter().collect();
+        bookmarks.sort_by_key(|bookmark| bookmark.timestamp_ns);
+        let mut go_to = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("bookmarks_grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for bookmark in bookmarks {
+                        ui.label(time.format(bookmark.timestamp_ns));
+                        ui.label(&bookmark.text);
+                        if ui.small_button("Go to").clicked() {
+                            go_to = Some(bookmark.timestamp_ns);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(timestamp_ns) = go_to {
+            let start = timestamp_ns.saturating_sub(BOOKMARK_FOCUS_NS);
+            self.chart_focus = Some((start, timestamp_ns + BOOKMARK_FOCUS_NS));
+        }
+    }
+
+    fn chart_panel(&mut self, ui: &mut egui::Ui) {
+        let available: Vec<String> = self
+            .analysis
+            .element_stats
+            .iter()
+            .filter(|stats| stats.summary(self.chart_metric).count > 0)
+            .map(|stats| stats.element.clone())
+            .collect();
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Metric")
+                .selected_text(self.chart_metric.label())
+                .show_ui(ui, |ui| {
+                    for metric in Metric::ALL {
+                        ui.selectable_value(&mut self.chart_metric, metric, metric.label());
+                    }
+                });
+            if ui.button("Clear selection").clicked() {
+                self.chart_elements.clear();
+            }
+            ui.separator();
+            self.smoothing_controls(ui);
+            ui.separator();
+            egui::ComboBox::from_label("Time")
+                .selected_text(self.time_base.label())
+                .show_ui(ui, |ui| {
+                    for base in [TimeBase::RunningTime, TimeBase::WallClock] {
+                        ui.selectable_value(&mut self.time_base, base, base.label());
+                    }
+                });
+            if self.chart_focus.is_some() && ui.button("↔ Show all").clicked() {
+                self.chart_focus = None;
+            }
+            if let Some((start, end)) = self.selection {
+                ui.separator();
+                let time = self.time_axis();
+                ui.label(format!("Range {} - {}", time.format(start), time.format(end)));
+                let archived = self.log_file.as_ref().is_some_and(|(log, _)| is_archive(log));
+                let export = ui
+                    .add_enabled(
+                        self.log_file.is_some() && !archived,
+                        egui::Button::new("📤 Export range"),
+                    )
+                    .on_hover_text("Write the lines and metrics of the range next to the log")
+                    .on_disabled_hover_text(if archived {
+                        "The run was archived; unpack it and open the log to export from it"
+                    } else {
+                        "The run writes no tracer log"
+                    });
+                if export.clicked() {
+                    self.export_selection(ui.ctx());
+                }
+                if ui.button("🔍 Zoom").clicked() {
+                    self.chart_focus = Some((start, end));
+                }
+                if ui.button("✕").clicked() {
+                    self.selection = None;
+                }
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            for element in &available {
+                let mut checked = self.chart_elements.contains(element);
+                if ui.checkbox(&mut checked, element).changed() {
+                    if checked {
+                        self.chart_elements.push(element.clone());
+                    } else {
+                        self.chart_elements.retain(|e| e != element);
+                    }
+                }
+            }
+        });
+
+        // The series are kept until new data arrives or the chart changes.
+        let smoothing = self.smoothing.get(&self.chart_metric).copied().unwrap_or_default();
+        let key = Some((self.chart_metric, smoothing, self.chart_elements.clone()));
+        if self.analysis.chart_key != key {
+            self.analysis.chart = self
+                .chart_elements
+                .iter()
+                .map(|element| {
+                    let values = smoothing.apply(&self.store.series(element, self.chart_metric));
+                    (element.clone(), values)
+                })
+                .collect();
+            self.analysis.chart_key = key;
+        }
+
+        let metric = self.chart_metric;
+        let chart = draw_line_chart(
+            ui,
+            &self.analysis.chart,
+            &|value| metric.format(value),
+            &self.annotations,
+            self.time_axis(),
+        );
+        if let Some(timestamp_ns) = chart.as_ref().and_then(|chart| chart.clicked_ns) {
+            self.bookmark(timestamp_ns, None);
+        }
+        if let Some(dragged) = chart.as_ref().and_then(|chart| chart.dragged) {
+            self.selection = Some(dragged);
+        }
+        if !self.bus_messages.is_empty() {
+            self.bus_lane(ui, chart.map(|chart| chart.range));
+        }
+    }
+
+    /// How the charted metric is smoothed in the chart and on the nodes.
+    fn smoothing_controls(&mut self, ui: &mut egui::Ui) {
+        let before = self.smoothing.get(&self.chart_metric).copied();
+        let smoothing = self.smoothing.entry(self.chart_metric).or_default();
+        egui::ComboBox::from_label("Smoothing")
+            .selected_text(smoothing.label())
+            .show_ui(ui, |ui| {
+                for choice in [Smoothing::Off, Smoothing::MovingAverage(5), Smoothing::Ewma(0.3)] {
+                    let selected = smoothing.label() == choice.label();
+                    let label = egui::SelectableLabel::new(selected, choice.label());
+                    if ui.add(label).clicked() && !selected {
+                        *smoothing = choice;
+                    }
+                }
+            });
+        match smoothing {
+            Smoothing::Off => {}
+            Smoothing::MovingAverage(window) => {
+                ui.add(egui::DragValue::new(window).clamp_range(2..=100).suffix(" samples"));
+            }
+            Smoothing::Ewma(weight) => {
+                ui.add(egui::Slider::new(weight, 0.01..=1.0).text("weight"));
+            }
+        }
+        // The nodes show the smoothed values too.
+        if self.smoothing.get(&self.chart_metric).copied() != before {
+            self.reanalyze();
+        }
+    }
+
+    /// Bus messages under the chart, on the same time axis, with a legend
+    /// that hides or shows each kind.
+    fn bus_lane(&mut self, ui: &mut egui::Ui, range: Option<(u64, u64)>) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Bus:");
+            for kind in BusMessageKind::ALL {
+                let mut shown = !self.hidden_bus_kinds.contains(&kind);
+                let label = egui::RichText::new(kind.label()).color(bus_kind_color(kind));
+                if ui.checkbox(&mut shown, label).changed() {
+                    if shown {
+                        self.hidden_bus_kinds.remove(&kind);
+                    } else {
+                        self.hidden_bus_kinds.insert(kind);
+                    }
+                }
+            }
+        });
+
+        let messages: Vec<&BusMessage> = self
+            .bus_messages
+            .iter()
+            .filter(|message| !self.hidden_bus_kinds.contains(&message.kind))
+            .collect();
+        let clicked = draw_bus_lane(ui, &messages, range, self.time_axis());
+        if let Some((timestamp_ns, what)) = clicked {
+            self.bookmark(timestamp_ns, Some(what));
+        }
+    }
+
+    fn time_axis(&self) -> TimeAxis {
+        TimeAxis {
+            base: self.time_base,
+            launch_ns: self.launch_ns,
+            started: self.run_started,
+            window: self.chart_focus,
+            selection: self.selection,
+        }
+    }
+
+    /// Bundle what an issue about this run needs into a zip next to the
+    /// tracer log, or in the current directory when there is none. An
+    /// archived log takes a while to copy, so the zip is written on a thread
+    /// of its own, which reports back with a notice.
+    fn write_bug_report(
+        &mut self,
+        ctx: &egui::Context,
+        screenshot: &egui::ColorImage,
+        pixels_per_point: f32,
+    ) {
+        let graph_png = self.graph_rect.and_then(|rect| {
+            let size = egui::vec2(screenshot.width() as f32, screenshot.height() as f32);
+            let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, size / pixels_per_point);
+            encode_png(&screenshot.region(&rect.intersect(screen), Some(pixels_per_point)))
+        });
+        let pipeline = self.pipeline_text.trim();
+        let results = self.rules.evaluate(&self.store);
+        let report = BugReport {
+            pipeline: pipeline.to_string(),
+            tracing: self.active_tracing.clone(),
+            environment: self.environment.clone(),
+            summary: format_markdown_summary(pipeline, &self.store, &results),
+            log: self.log_file.as_ref().map(|(log, _)| log.clone()),
+            graph_png,
+        };
+        let name = format!("bug_report_{}.zip", Local::now().format("%Y-%m-%d_%H-%M-%S"));
+        let path = match &report.log {
+            Some(log) => log.with_file_name(name),
+            None => PathBuf::from(name),
+        };
+        self.notice = Some(format!("Writing the bug report to {}…", path.display()));
+        let (jobs, ctx) = (self.job_sender.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            let message = match report.write(&path) {
+                Ok(()) => {
+                    format!("Wrote the bug report to {}; attach it to the issue", path.display())
+                }
+                Err(message) => message,
+            };
+            let _ = jobs.send(Job::Event(PipelineEvent::Notice(message)));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Write the selected span of the tracer log to a log of its own next to
+    /// it, with its metrics and metadata. A long log takes a while, so it is
+    /// cut on a thread of its own, which reports back with a notice.
+    fn export_selection(&mut self, ctx: &egui::Context) {
+        let (Some((start_ns, end_ns)), Some((log, base_ns))) = (self.selection, &self.log_file)
+        else {
+            return;
+        };
+        let start_ns = start_ns.saturating_sub(*base_ns);
+        let end_ns = end_ns.saturating_sub(*base_ns);
+        let stem = log.file_stem().unwrap_or_default().to_string_lossy();
+        let out = log.with_file_name(format!(
+            "{}.{}s-{}s.log",
+            stem,
+            start_ns / 1_000_000_000,
+            end_ns.div_ceil(1_000_000_000)
+        ));
+        self.notice = Some(format!("Exporting to {}…", out.display()));
+        let (log, jobs, ctx) = (log.clone(), self.job_sender.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            let message = match export_range(&log, start_ns, end_ns, &out) {
+                Ok(lines) => format!("Exported {} lines to {}", lines, out.display()),
+                Err(message) => message,
+            };
+            let _ = jobs.send(Job::Event(PipelineEvent::Notice(message)));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Probe the capture devices for the "New pipeline" dialog. A
+    /// GstDeviceMonitor asks every provider in turn, which takes a while, so
+    /// it runs on a thread of its own.
+    fn open_new_pipeline(&mut self, ctx: &egui::Context) {
+        self.new_pipeline = Some(NewPipelineDialog {
+            devices: None,
+            selected: 0,
+            filters: String::new(),
+        });
+        let (jobs, ctx) = (self.job_sender.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            let _ = jobs.send(Job::Devices(devices::capture_devices()));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Load the registry details of the `factories` not loaded yet. Each
+    /// takes an instance of the element, so they are read on a thread of
+    /// their own.
+    fn request_docs(&mut self, factories: Vec<String>, ctx: &egui::Context) {
+        let factories: Vec<String> = factories
+            .into_iter()
+            .filter(|factory| !self.element_docs.contains_key(factory))
+            .filter(|factory| self.docs_loading.insert(factory.clone()))
+            .collect();
+        if factories.is_empty() {
+            return;
+        }
+        let (jobs, ctx) = (self.job_sender.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            for factory in factories {
+                let info = inspect::element_info(&factory);
+                let _ = jobs.send(Job::Docs(factory, info));
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// The next event that work off the UI thread reported, taking in its
+    /// other results on the way.
+    fn next_job_event(&mut self) -> Option<PipelineEvent> {
+        while let Ok(job) = self.job_receiver.try_recv() {
+            match job {
+                Job::Event(event) => return Some(event),
+                Job::Devices(devices) => {
+                    if let Some(dialog) = &mut self.new_pipeline {
+                        dialog.devices = Some(devices);
+                    }
+                }
+                Job::Docs(factory, info) => {
+                    self.docs_loading.remove(&factory);
+                    self.element_docs.insert(factory, info);
+                }
+            }
+        }
+        None
+    }
+
+    /// Upload the latest preview frame, if a new one arrived, and drop the
+    /// texture once the pipeline stops sending frames.
+    fn update_preview(&mut self, ctx: &egui::Context) {
+        if !self.preview_receiver.has_changed().unwrap_or(false) {
+            return;
+        }
+        let Some(frame) = self.preview_receiver.borrow_and_update().clone() else {
+            self.preview = None;
+            return;
+        };
+        let image = egui::ColorImage::from_rgba_unmultiplied(
+            [frame.width as usize, frame.height as usize],
+            &frame.rgba,
+        );
+        match &mut self.preview {
+            Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+            None => {
+                let texture = ctx.load_texture("preview", image, egui::TextureOptions::LINEAR);
+                self.preview = Some(texture);
+            }
+        }
+    }
+
+    fn preview_panel(&self, ui: &mut egui::Ui) {
+        let Some(texture) = &self.preview else {
+            return;
+        };
+        let size = texture.size_vec2();
+        let scale = (ui.available_width() / size.x).min(1.0);
+        ui.add(egui::Image::from_texture(egui::load::SizedTexture::new(
+            texture.id(),
+            size * scale,
+        )));
+    }
+
+    fn levels_panel(&self, ui: &mut egui::Ui) {
+        for (element, level) in &self.audio_levels {
+            ui.label(element);
+            for (channel, (rms, peak)) in level.rms_db.iter().zip(&level.peak_db).enumerate() {
+                draw_level_meter(ui, channel, *rms, *peak);
+            }
+        }
+    }
+
+    fn webrtc_panel(&self, ui: &mut egui::Ui) {
+        let optional_ms = |ns: Option<u64>| {
+            ns.map_or("-".to_string(), |ns| format!("{:.1} ms", ns as f64 / 1e6))
+        };
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (element, stats) in &self.webrtc_stats {
+                ui.label(egui::RichText::new(element).strong());
+                egui::Grid::new(("webrtc_grid", element))
+                    .striped(true)
+                    .num_columns(9)
+                    .show(ui, |ui| {
+                        for header in [
+                            "Direction", "SSRC", "Kind", "Bitrate", "Packets", "Lost", "Loss",
+                            "Jitter", "RTT",
+                        ] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for stream in &stats.streams {
+                            ui.label(stream.direction.label());
+                            ui.label(stream.ssrc.to_string());
+                            ui.label(&stream.kind);
+                            ui.label(stream.bitrate.map_or("-".to_string(), |bitrate| {
+                                Metric::Bitrate.format(bitrate as f64)
+                            }));
+                            ui.label(stream.packets.to_string());
+                            ui.label(stream.packets_lost.map_or("-".to_string(), |lost| {
+                                lost.to_string()
+                            }));
+                            ui.label(stream.fraction_lost.map_or("-".to_string(), |fraction| {
+                                format!("{:.2}%", fraction * 100.0)
+                            }));
+                            ui.label(optional_ms(stream.jitter_ns));
+                            ui.label(optional_ms(stream.rtt_ns));
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    /// The current clock, and the drift of running time from the system time
+    /// with a line per clock the pipeline used.
+    fn clock_panel(&self, ui: &mut egui::Ui) {
+        let Some(stats) = self.clock_history.back() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(&stats.name).strong());
+            ui.label(format!("({} clock)", stats.kind));
+            if let Some(provider) = &stats.provider {
+                ui.label(format!("provided by {}", provider));
+            }
+            ui.separator();
+            ui.label(format!("Rate: {:.6}", stats.rate));
+            ui.separator();
+            ui.label(format!("Offset: {}", format_signed_ms(stats.offset_ns as f64)));
+            ui.separator();
+            ui.label(format!("Running time: {}", format_clock_time(stats.running_time_ns)));
+            ui.separator();
+            ui.label(format!("Drift: {}", format_signed_ms(stats.drift_ns as f64)));
+        });
+
+        let mut series: Vec<(String, Vec<(u64, f64)>)> = Vec::new();
+        for stats in &self.clock_history {
+            let point = (stats.timestamp_ns, stats.drift_ns as f64);
+            match series.iter_mut().find(|(name, _)| *name == stats.name) {
+                Some((_, values)) => values.push(point),
+                None => series.push((stats.name.clone(), vec![point])),
+            }
+        }
+        draw_line_chart(ui, &series, &format_signed_ms, &self.annotations, self.time_axis());
+    }
+
+    /// How far the video runs behind the audio at the sinks, between the
+    /// limits either way that count as out of sync.
+    fn av_sync_panel(&self, ui: &mut egui::Ui) {
+        let Some(sync) = self.av_sync.back() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!("{} → {}", sync.video_sink, sync.audio_sink));
+            ui.separator();
+            let offset = format!("Offset: {}", format_signed_ms(sync.offset_ns as f64));
+            if sync.offset_ns.unsigned_abs() > sync.limit_ns {
+                ui.colored_label(egui::Color32::LIGHT_RED, offset);
+            } else {
+                ui.label(offset);
+            }
+            ui.separator();
+            ui.label(format!("Limit: ±{}", format_signed_ms(sync.limit_ns as f64)));
+        });
+
+        let offsets = self.av_sync.iter().map(|sync| (sync.timestamp_ns, sync.offset_ns as f64));
+        let limits = |sign: f64| -> Vec<(u64, f64)> {
+            let limit = |sync: &AvSync| (sync.timestamp_ns, sign * sync.limit_ns as f64);
+            self.av_sync.iter().map(limit).collect()
+        };
+        let series = vec![
+            ("Video behind audio".to_string(), offsets.collect()),
+            ("+limit".to_string(), limits(1.0)),
+            ("-limit".to_string(), limits(-1.0)),
+        ];
+        draw_line_chart(ui, &series, &format_signed_ms, &self.annotations, self.time_axis());
+    }
+
+    /// The resident set of the pipeline process over time with how fast it
+    /// grows, and the live objects the leaks tracer counted, those that grew
+    /// most since the first count at the top.
+    fn memory_panel(&self, ui: &mut egui::Ui) {
+        let Some(usage) = self.memory.back() else {
+            return;
+        };
+        let per_minute = |bytes_per_s: f64| {
+            format!("{}/min", format_size((bytes_per_s * 60.0) as u64))
+        };
+        ui.horizontal(|ui| {
+            if let Some(rss_bytes) = usage.rss_bytes {
+                ui.label(format!("RSS: {}", format_size(rss_bytes)));
+                ui.separator();
+            }
+            match usage.growth_bytes_per_s {
+                Some(growth) if growth > usage.limit_bytes_per_s => {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_RED,
+                        format!("Growing steadily by {}", per_minute(growth)),
+                    );
+                }
+                Some(growth) => {
+                    ui.label(format!("Growing steadily by {}", per_minute(growth)));
+                }
+                None => {
+                    ui.label("Not growing steadily");
+                }
+            }
+            ui.separator();
+            ui.label(format!("Limit: {}", per_minute(usage.limit_bytes_per_s)));
+        });
+
+        let rss: Vec<(u64, f64)> = self
+            .memory
+            .iter()
+            .filter_map(|usage| Some((usage.timestamp_ns, usage.rss_bytes? as f64)))
+            .collect();
+        if !rss.is_empty() {
+            let series = vec![("RSS".to_string(), rss)];
+            draw_line_chart(
+                ui,
+                &series,
+                &|bytes| format_size(bytes as u64),
+                &self.annotations,
+                self.time_axis(),
+            );
+        }
+
+        if usage.live_objects.is_empty() {
+            return;
+        }
+        let first = self
+            .memory
+            .iter()
+            .find(|usage| !usage.live_objects.is_empty())
+            .map(|usage| &usage.live_objects);
+        let mut objects: Vec<(&String, u64, i64)> = usage
+            .live_objects
+            .iter()
+            .map(|(name, count)| {
+                let before = first.and_then(|first| first.get(name)).copied().unwrap_or(0);
+                (name, *count, *count as i64 - before as i64)
+            })
+            .collect();
+        objects.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)));
+        egui::ScrollArea::vertical().id_source("live_objects").show(ui, |ui| {
+            egui::Grid::new("live_objects_grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for header in ["Type", "Alive", "Since first count"] {
+                        ui.label(egui::RichText::new(header).strong());
+                    }
+                    ui.end_row();
+                    for (name, count, growth) in objects {
+                        ui.label(name.as_str());
+                        ui.label(count.to_string());
+                        if growth > 0 {
+                            ui.colored_label(egui::Color32::LIGHT_RED, format!("+{}", growth));
+                        } else {
+                            ui.label(growth.to_string());
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Interlatency on a link against the fill level of a queue at the same
+    /// time, one dot per latency sample, with their correlation. A cloud
+    /// rising to the right shows latency growing as the queue fills.
+    fn queue_latency_panel(&mut self, ui: &mut egui::Ui) {
+        let queue = match &self.queue_latency_queue {
+            Some(queue) if self.queue_levels.contains_key(queue) => queue.clone(),
+            _ => match self.queue_levels.keys().next() {
+                Some(queue) => queue.clone(),
+                None => return,
+            },
+        };
+        let levels = &self.queue_levels[&queue];
+        let correlated = self.analysis.queue_latency.get(&queue).map_or(&[][..], Vec::as_slice);
+
+        let mut selected_queue = queue.clone();
+        let mut selected_link = self
+            .queue_latency_link
+            .clone()
+            .filter(|link| correlated.iter().any(|(candidate, _, _)| candidate == link))
+            .or_else(|| correlated.first().map(|(link, _, _)| link.clone()));
+        let link_label = |(from, to): &(String, String)| format!("{} → {}", from, to);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Queue")
+                .selected_text(&selected_queue)
+                .show_ui(ui, |ui| {
+                    for name in self.queue_levels.keys() {
+                        ui.selectable_value(&mut selected_queue, name.clone(), name);
+                    }
+                });
+            egui::ComboBox::from_label("Link")
+                .selected_text(selected_link.as_ref().map_or(String::new(), link_label))
+                .show_ui(ui, |ui| {
+                    for (link, _, r) in correlated {
+                        let text = match r {
+                            Some(r) => format!("{}  (r = {:.2})", link_label(link), r),
+                            None => link_label(link),
+                        };
+                        ui.selectable_value(&mut selected_link, Some(link.clone()), text);
+                    }
+                });
+            if let Some(level) = levels.back() {
+                ui.separator();
+                ui.label(format!(
+                    "Now: {:.0}% full, {} buffers, {}",
+                    level.fill * 100.0,
+                    level.buffers,
+                    format_size(level.bytes)
+                ));
+            }
+        });
+
+        let selected = selected_link
+            .as_ref()
+            .and_then(|link| correlated.iter().find(|(candidate, _, _)| candidate == link));
+        match selected {
+            Some((_, points, r)) if !points.is_empty() => {
+                if let Some(r) = r {
+                    ui.label(format!(
+                        "Correlation r = {:.2} over {} samples{}",
+                        r,
+                        points.len(),
+                        if *r > 0.5 { ": latency grows as the queue fills" } else { "" }
+                    ));
+                }
+                draw_scatter_chart(ui, points);
+            }
+            _ => {
+                ui.label("No interlatency samples while the queue level was logged");
+            }
+        }
+        if selected_queue != queue {
+            // Another queue may follow a different link most.
+            self.queue_latency_link = None;
+        } else {
+            self.queue_latency_link = selected_link;
+        }
+        self.queue_latency_queue = Some(selected_queue);
+    }
+
+    /// The elements grouped by the streaming thread that runs them, with the
+    /// share of the thread each keeps busy. Threads that several heavy
+    /// elements share are marked, as the elements delay each other there.
+    fn threads_panel(&self, ui: &mut egui::Ui) {
+        let Some(threads) = &self.streaming_threads else {
+            return;
+        };
+        egui::ScrollArea::vertical().id_source("threads").show(ui, |ui| {
+            for thread in &threads.threads {
+                let busy: f64 = thread.elements.iter().map(|element| element.busy).sum();
+                let heavy = thread.elements.iter().filter(|element| element.heavy).count();
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&thread.thread).strong().monospace());
+                    ui.label(format!("{:.0}% busy", busy * 100.0));
+                    if heavy > 1 {
+                        ui.separator();
+                        ui.colored_label(
+                            egui::Color32::LIGHT_RED,
+                            format!("⚠ {} heavy elements share this thread", heavy),
+                        );
+                    }
+                });
+                egui::Grid::new(("thread_grid", &thread.thread))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for element in &thread.elements {
+                            if element.heavy && heavy > 1 {
+                                ui.colored_label(egui::Color32::LIGHT_RED, &element.element);
+                            } else {
+                                ui.label(&element.element);
+                            }
+                            ui.add(
+                                egui::ProgressBar::new(element.busy as f32)
+                                    .desired_width(200.0)
+                                    .text(format!("{:.1}%", element.busy * 100.0)),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    /// The streams of every collection. Ticking a stream replaces the
+    /// selected stream of the same kind; unticking drops it.
+    fn streams_panel(&self, ui: &mut egui::Ui) {
+        let mut selection = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (element, collection) in &self.stream_collections {
+                ui.label(egui::RichText::new(element).strong());
+                egui::Grid::new(("streams_grid", element))
+                    .striped(true)
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        for header in ["Play", "Type", "Codec", "Language", "Stream ID"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for stream in &collection.streams {
+                            let mut selected = stream.selected;
+                            if ui.checkbox(&mut selected, "").changed() {
+                                let stream_ids: Vec<String> = collection
+                                    .streams
+                                    .iter()
+                                    .filter(|other| {
+                                        if other.stream_id == stream.stream_id {
+                                            selected
+                                        } else {
+                                            other.selected
+                                                && (!selected || other.kind != stream.kind)
+                                        }
+                                    })
+                                    .map(|other| other.stream_id.clone())
+                                    .collect();
+                                selection = Some((element.clone(), stream_ids));
+                            }
+                            ui.label(&stream.kind);
+                            ui.label(stream.codec.as_deref().unwrap_or("-"));
+                            ui.label(stream.language.as_deref().unwrap_or("-"));
+                            ui.label(egui::RichText::new(&stream.stream_id).monospace().small());
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(8.0);
+            }
+        });
+
+        if let Some((element, stream_ids)) = selection {
+            let _ = self
+                .control_sender
+                .try_send(PipelineCommand::SelectStreams { element, stream_ids });
+        }
+    }
+
+    fn rtsp_panel(&self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (element, stats) in &self.rtsp_sources {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(element).strong());
+                    ui.label(&stats.location);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("State: {}", stats.state));
+                    ui.separator();
+                    ui.label(format!("Transport: {}", stats.transport.as_deref().unwrap_or("-")));
+                    if stats.stalled {
+                        ui.separator();
+                        ui.colored_label(egui::Color32::RED, "⚠ server stopped sending data");
+                    }
+                });
+                egui::Grid::new(("rtsp_grid", element))
+                    .striped(true)
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        for header in ["Stream", "Media", "Bitrate", "Packets", "Idle"] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for stream in &stats.streams {
+                            ui.label(&stream.pad);
+                            ui.label(&stream.media);
+                            ui.label(Metric::Bitrate.format(stream.bitrate as f64));
+                            ui.label(stream.packets.to_string());
+                            ui.label(format!("{:.1} s", stream.idle_ns as f64 / 1e9));
+                            ui.end_row();
+                        }
+                    });
+                if let Some(sdp) = &stats.sdp {
+                    egui::CollapsingHeader::new("SDP")
+                        .id_source(("rtsp_sdp", element))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(sdp).monospace());
+                        });
+                }
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    fn rtp_session_panel(&self, ui: &mut egui::Ui) {
+        let optional_ms = |ns: Option<u64>| {
+            ns.map_or("-".to_string(), |ns| format!("{:.1} ms", ns as f64 / 1e6))
+        };
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (element, stats) in &self.rtp_sessions {
+                ui.label(egui::RichText::new(element).strong());
+                egui::Grid::new(("rtp_session_grid", element))
+                    .striped(true)
+                    .num_columns(9)
+                    .show(ui, |ui| {
+                        for header in [
+                            "SSRC", "Source", "CNAME", "Bitrate", "Packets", "Lost", "Loss",
+                            "Jitter", "RTT",
+                        ] {
+                            ui.label(egui::RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for source in &stats.sources {
+                            ui.label(format!("{:#010x}", source.ssrc));
+                            ui.label(if source.internal { "sent" } else { "received" });
+                            ui.label(source.cname.as_deref().unwrap_or("-"));
+                            ui.label(Metric::Bitrate.format(source.bitrate as f64));
+                            ui.label(source.packets.to_string());
+                            ui.label(source.packets_lost.map_or("-".to_string(), |lost| {
+                                lost.to_string()
+                            }));
+                            ui.label(source.fraction_lost.map_or("-".to_string(), |fraction| {
+                                format!("{:.2}%", fraction * 100.0)
+                            }));
+                            ui.label(optional_ms(source.jitter_ns));
+                            ui.label(optional_ms(source.rtt_ns));
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    fn jitter_buffer_panel(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("jitter_buffer_grid")
+            .striped(true)
+            .num_columns(6)
+            .show(ui, |ui| {
+                for header in ["Element", "Pushed", "Lost", "Late", "Duplicates", "Jitter"] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+                for (element, history) in &self.jitter_buffers {
+                    let Some(stats) = history.back() else { continue };
+                    ui.label(element);
+                    ui.label(stats.pushed.to_string());
+                    ui.label(stats.lost.to_string());
+                    ui.label(stats.late.to_string());
+                    ui.label(stats.duplicates.to_string());
+                    ui.label(Metric::Jitter.format(stats.avg_jitter_ns as f64));
+                    ui.end_row();
+                }
+            });
+
+        egui::ComboBox::from_label("Plot")
+            .selected_text(self.jitter_buffer_chart.label())
+            .show_ui(ui, |ui| {
+                for field in JitterBufferField::ALL {
+                    ui.selectable_value(&mut self.jitter_buffer_chart, field, field.label());
+                }
+            });
+        let field = self.jitter_buffer_chart;
+        let series: Vec<(String, Vec<(u64, f64)>)> = self
+            .jitter_buffers
+            .iter()
+            .map(|(element, history)| {
+                let values = history
+                    .iter()
+                    .map(|stats| (stats.timestamp_ns, field.value(stats)))
+                    .collect();
+                (element.clone(), values)
+            })
+            .collect();
+        draw_line_chart(
+            ui,
+            &series,
+            &|value| field.format(value),
+            &self.annotations,
+            self.time_axis(),
+        );
+    }
+
+    /// Frames each video sink rendered and dropped, and the share of recent
+    /// frames it dropped, in red past the limit.
+    fn sink_frames_panel(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("sink_frames_grid")
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                for header in ["Sink", "Rendered", "Dropped", "Drop rate"] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+                for (sink, history) in &self.sink_frames {
+                    let Some(frames) = history.back() else { continue };
+                    ui.label(sink);
+                    ui.label(frames.rendered.to_string());
+                    ui.label(frames.dropped.to_string());
+                    let rate = format!("{:.1}%", frames.drop_percent);
+                    if frames.drop_percent > frames.limit_percent {
+                        ui.colored_label(egui::Color32::LIGHT_RED, rate);
+                    } else {
+                        ui.label(rate);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        let series: Vec<(String, Vec<(u64, f64)>)> = self
+            .sink_frames
+            .iter()
+            .map(|(sink, history)| {
+                let values = history
+                    .iter()
+                    .map(|frames| (frames.timestamp_ns, frames.drop_percent))
+                    .collect();
+                (sink.clone(), values)
+            })
+            .collect();
+        draw_line_chart(
+            ui,
+            &series,
+            &|value| format!("{:.1}%", value),
+            &self.annotations,
+            self.time_axis(),
+        );
+    }
+
+    /// When the first buffer reached each element after the launch, as a
+    /// waterfall with the earliest at the top.
+    fn startup_panel(&self, ui: &mut egui::Ui) {
+        let buffers = first_buffers(&self.store, self.launch_ns);
+        if buffers.is_empty() {
+            ui.label("No first buffers yet; they need the proctime or interlatency tracer");
+            return;
+        }
+        let last_ns = buffers.last().map_or(1, |buffer| buffer.after_ns.max(1));
+        let format = |ns: u64| Metric::Interlatency.format(ns as f64);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("startup_grid").num_columns(3).show(ui, |ui| {
+                for buffer in &buffers {
+                    ui.label(&buffer.element);
+                    let (rect, response) =
+                        ui.allocate_exact_size(egui::vec2(360.0, 14.0), egui::Sense::hover());
+                    let painter = ui.painter();
+                    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(45));
+                    let share = buffer.after_ns as f32 / last_ns as f32;
+                    let bar = egui::Rect::from_min_size(
+                        rect.min,
+                        egui::vec2((rect.width() * share).max(2.0), rect.height()),
+                    );
+                    painter.rect_filled(bar, 2.0, egui::Color32::LIGHT_BLUE);
+                    response.on_hover_text(format!(
+                        "First buffer at {} {} after the launch",
+                        buffer.element,
+                        format(buffer.after_ns)
+                    ));
+                    ui.label(format(buffer.after_ns));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// The latency each sink's chain declares next to the latest
+    /// interlatency measured up to that sink, in red when buffers take
+    /// longer than declared.
+    fn latency_report_panel(&self, ui: &mut egui::Ui) {
+        let Some(report) = &self.latency_report else {
+            return;
+        };
+        let format = |ns: u64| Metric::Interlatency.format(ns as f64);
+        let format_max = |ns: Option<u64>| ns.map_or("unbounded".to_string(), format);
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Pipeline").strong());
+            ui.label(if report.live { "live" } else { "not live" });
+            ui.separator();
+            ui.label(format!("Min: {}", format(report.min_ns)));
+            ui.separator();
+            ui.label(format!("Max: {}", format_max(report.max_ns)));
+            ui.separator();
+            let configured = report.configured_ns.map_or("-".to_string(), format);
+            ui.label(format!("Configured: {}", configured));
+        });
+
+        egui::Grid::new("latency_report_grid")
+            .striped(true)
+            .num_columns(5)
+            .show(ui, |ui| {
+                for header in ["Sink", "Live", "Min", "Max", "Measured"] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+                for chain in &report.chains {
+                    ui.label(&chain.sink);
+                    ui.label(if chain.live { "yes" } else { "no" });
+                    ui.label(format(chain.min_ns));
+                    ui.label(format_max(chain.max_ns));
+                    // The interlatency tracer names the element before the
+                    // first `_` of the pad it reached.
+                    let element = chain.sink.split('_').next().unwrap_or_default();
+                    let measured = self
+                        .store
+                        .latencies()
+                        .iter()
+                        .rev()
+                        .find(|latency| latency.to == element)
+                        .and_then(|latency| parse_duration_to_ns(&latency.time));
+                    match measured {
+                        Some(measured) if measured > chain.min_ns => {
+                            ui.colored_label(egui::Color32::LIGHT_RED, format(measured))
+                                .on_hover_text("Buffers take longer to arrive than declared");
+                        }
+                        Some(measured) => {
+                            ui.label(format(measured));
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// The segments written so far, the latest first.
+    fn segments_panel(&self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("segments_grid")
+                .striped(true)
+                .num_columns(5)
+                .show(ui, |ui| {
+                    for header in ["Time", "Element", "Location", "Duration", "Size"] {
+                        ui.label(egui::RichText::new(header).strong());
+                    }
+                    ui.end_row();
+                    for segment in self.segments.iter().rev() {
+                        ui.label(format_clock_time(segment.timestamp_ns));
+                        ui.label(&segment.element);
+                        ui.label(&segment.location);
+                        ui.label(segment.duration_ns.map_or("-".to_string(), format_clock_time));
+                        ui.label(segment.size_bytes.map_or("-".to_string(), format_size));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Work out the statistics, alerts and health from the whole store again,
+    /// once new data or settings arrived and at most once per
+    /// [`ANALYSIS_INTERVAL`], so a long run does not redo it every repaint.
+    fn analyze(&mut self) {
+        if !self.analysis_stale
+            || self.analyzed_at.is_some_and(|at| at.elapsed() < ANALYSIS_INTERVAL)
+        {
+            return;
+        }
+        self.analysis_stale = false;
+        self.analyzed_at = Some(Instant::now());
+
+        self.alerts = self
+            .rules
+            .evaluate(&self.store)
+            .into_iter()
+            .filter(|result| !result.passed())
+            .collect();
+        let chain: Vec<String> =
+            self.graph.node_indices().map(|node| self.graph[node].clone()).collect();
+        self.alerts.extend(self.negotiated_framerates.first_shortfall(&self.store, &chain));
+        self.alerts.extend(throughput_losses(&self.store, &self.pipeline_text));
+        let stall = match &self.rules.stall {
+            Some(stall) => stall.clone(),
+            None => StallDetector::new((self.stall_after_s * 1e9) as u64),
+        };
+        self.stalled = stall
+            .evaluate(&self.store)
+            .into_iter()
+            .filter(|result| !result.passed())
+            .collect();
+        if self.rules.stall.is_none() {
+            self.alerts.extend(self.stalled.iter().cloned());
+        }
+
+        let element_stats = self.store.element_stats();
+        let logs = self.store.samples();
+        let mut health = HashMap::new();
+        let mut latest = HashMap::new();
+        for element in chain {
+            if let Some(element_health) = self.element_health(&element_stats, &element) {
+                health.insert(element.clone(), element_health);
+            }
+            let sample = logs.iter().rev().find(|sample| sample.element.starts_with(&element));
+            if let Some(sample) = sample {
+                let smoothed = smoothed_sample(&self.smoothing, logs, &element, sample);
+                latest.insert(element, smoothed);
+            }
+        }
+
+        let mut links: Vec<(String, String)> = self
+            .store
+            .latencies()
+            .iter()
+            .map(|latency| (latency.from.clone(), latency.to.clone()))
+            .collect();
+        links.sort();
+        links.dedup();
+        let mut queue_latency = HashMap::new();
+        for (queue, levels) in &self.queue_levels {
+            let mut correlated: Vec<LinkCorrelation> = links
+                .iter()
+                .map(|(from, to)| {
+                    let points = latency_against_fill(self.store.latencies(), from, to, levels);
+                    let r = correlation(&points);
+                    ((from.clone(), to.clone()), points, r)
+                })
+                .collect();
+            correlated.sort_by(|a, b| {
+                let strength = |r: Option<f64>| r.map_or(-1.0, f64::abs);
+                strength(b.2).total_cmp(&strength(a.2))
+            });
+            queue_latency.insert(queue.clone(), correlated);
+        }
+
+        self.analysis = Analysis {
+            element_stats,
+            health,
+            latest,
+            queue_latency,
+            chart: Vec::new(),
+            chart_key: None,
+        };
+    }
+
+    /// Have the next repaint analyze the store again, for settings that
+    /// change what it shows.
+    fn reanalyze(&mut self) {
+        self.analysis_stale = true;
+        self.analyzed_at = None;
+    }
+
+    /// The health of the first element whose name starts with `element`,
+    /// judged against its interlatency limit and its sink's drop rate.
+    fn element_health(&self, stats: &[ElementStats], element: &str) -> Option<Health> {
+        let stats = stats.iter().find(|stats| stats.element.starts_with(element))?;
+        let drop_percent = self
+            .sink_frames
+            .iter()
+            .find(|(sink, _)| sink.starts_with(element))
+            .and_then(|(_, history)| history.back())
+            .map(|frames| frames.drop_percent);
+        let latency_budget_ns = self.latency_threshold_ns(&stats.element);
+        element_health(&self.store, stats, latency_budget_ns, drop_percent)
+    }
+
+    /// Save the statistics so far as the baseline of the current pipeline.
+    fn save_baseline(&mut self) {
+        let pipeline = self.pipeline_text.trim();
+        let baseline = Baseline::capture(pipeline, &self.store);
+        let path = Baseline::path_for(pipeline);
+        self.notice = Some(match baseline.save(&path) {
+            Ok(()) => format!("Saved the baseline to {}", path.display()),
+            Err(message) => message,
+        });
+        self.baseline = Some(baseline);
+    }
+
+    /// Write threshold rules drawn from the run so far, taken as a good one,
+    /// next to the baseline of the current pipeline.
+    fn save_suggested_rules(&mut self) {
+        let path = Baseline::path_for(self.pipeline_text.trim()).with_extension("rules.toml");
+        let rules = suggest_rules(&self.store);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, format_rules_toml(&rules)));
+        self.notice = Some(match written {
+            Ok(()) => format!(
+                "Wrote {} suggested rule(s) to {}; load them with --rules",
+                rules.len(),
+                path.display()
+            ),
+            Err(err) => format!("Failed to write {}: {}", path.display(), err),
+        });
+    }
+
+    fn stats_header(&mut self, ui: &mut egui::Ui, column: StatsColumn, label: String) {
+        let active = self.stats_sort == column;
+        let text = match (active, self.stats_sort_descending) {
+            (true, true) => format!("{} ▼", label),
+            (true, false) => format!("{} ▲", label),
+            (false, _) => label,
+        };
+        if ui.selectable_label(active, egui::RichText::new(text).strong()).clicked() {
+            if active {
+                self.stats_sort_descending = !self.stats_sort_descending;
+            } else {
+                self.stats_sort = column;
+                self.stats_sort_descending = column != StatsColumn::Element;
+            }
+        }
+    }
+}
+
+impl eframe::App for GstDebugger {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        while let Ok(data) = self.receiver.try_recv() {
+            // Framerate and bitrate are logged once a second, buffer or not.
+            if data.proctime_ns.is_some() {
+                self.mark_first_buffer(&data.element, data.timestamp_ns);
+            }
+            self.store.push_sample(data);
+            self.analysis_stale = true;
+        }
+
+        while let Ok(lat) = self.latency_receiver.try_recv() {
+            self.mark_first_buffer(&lat.to, lat.timestamp_ns);
+            self.store.push_latency(lat);
+            self.analysis_stale = true;
+        }
+
+        let screenshot = ctx.input(|input| {
+            input.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        if let Some(screenshot) = screenshot
+            && self.bug_report_requested
+        {
+            self.bug_report_requested = false;
+            self.write_bug_report(ctx, &screenshot, ctx.pixels_per_point());
+        }
+
+        while let Some(event) =
+            self.event_receiver.try_recv().ok().or_else(|| self.next_job_event())
+        {
+            self.analysis_stale = true;
+            match event {
+                PipelineEvent::State(state) => {
+                    if state != self.pipeline_state {
+                        self.annotations.push(Annotation {
+                            timestamp_ns: self.store.latest_timestamp(),
+                            text: format!("{:?}", state).to_lowercase(),
+                        });
+                    }
+                    self.pipeline_state = state;
+                    self.next_restart_at = None;
+                    if state == PipelineState::Stopped {
+                        self.playback_position = None;
+                    }
+                }
+                PipelineEvent::Position { position_ns, duration_ns } => {
+                    self.playback_position = Some((position_ns, duration_ns));
+                }
+                PipelineEvent::ElementEos(element) => {
+                    self.eos_elements.insert(element);
+                }
+                PipelineEvent::Properties { element, properties } => {
+                    if self.selected_element.as_ref() == Some(&element) {
+                        self.property_edits.clear();
+                    }
+                    self.element_properties.insert(element, properties);
+                }
+                PipelineEvent::PropertyChanged { element, name, value } => {
+                    self.annotations.push(Annotation {
+                        timestamp_ns: self.store.latest_timestamp(),
+                        text: format!("{}.{} = {}", element, name, value),
+                    });
+                    if let Some(property) = self
+                        .element_properties
+                        .get_mut(&element)
+                        .and_then(|properties| properties.iter_mut().find(|p| p.name == name))
+                    {
+                        property.value = value;
+                    }
+                }
+                PipelineEvent::Notice(message) => {
+                    self.notice = Some(message);
+                }
+                PipelineEvent::Error(error) => {
+                    self.annotations.push(Annotation {
+                        timestamp_ns: self.store.latest_timestamp(),
+                        text: format!("error: {}", error.message),
+                    });
+                    self.pipeline_error = Some(error);
+                }
+                PipelineEvent::Restarting { attempt, delay } => {
+                    self.restart_count += 1;
+                    self.pipeline_state = PipelineState::Restarting;
+                    self.next_restart_at = Some(Instant::now() + delay);
+                    self.annotations.push(Annotation {
+                        timestamp_ns: self.store.latest_timestamp(),
+                        text: format!("restart #{} (attempt {})", self.restart_count, attempt),
+                    });
+                }
+                PipelineEvent::Bus(message) => {
+                    if self.bus_messages.len() == BUS_MESSAGE_LIMIT {
+                        self.bus_messages.pop_front();
+                    }
+                    self.bus_messages.push_back(message);
+                }
+                PipelineEvent::AudioLevel(level) => {
+                    self.audio_levels.insert(level.element.clone(), level);
+                }
+                PipelineEvent::WebRtcStats(stats) => {
+                    self.webrtc_stats.insert(stats.element.clone(), stats);
+                }
+                PipelineEvent::Rtsp(stats) => {
+                    self.rtsp_sources.insert(stats.element.clone(), stats);
+                }
+                PipelineEvent::Streams(collection) => {
+                    self.stream_collections.insert(collection.element.clone(), collection);
+                }
+                PipelineEvent::Clock(stats) => {
+                    if self.clock_history.len() == CLOCK_HISTORY {
+                        self.clock_history.pop_front();
+                    }
+                    self.clock_history.push_back(stats);
+                }
+                PipelineEvent::Memory(usage) => {
+                    if self.memory.len() == MEMORY_HISTORY {
+                        self.memory.pop_front();
+                    }
+                    self.memory.push_back(usage);
+                }
+                PipelineEvent::Threads(threads) => {
+                    self.streaming_threads = Some(threads);
+                }
+                PipelineEvent::Annotation(annotation) => {
+                    self.annotations.push(annotation.clone());
+                    self.bookmarks.push(annotation);
+                }
+                PipelineEvent::LogFile { path, base_ns } => {
+                    self.log_file = Some((path, base_ns));
+                }
+                PipelineEvent::QueueLevel(level) => {
+                    let levels = self.queue_levels.entry(level.queue.clone()).or_default();
+                    if levels.len() == QUEUE_LEVEL_HISTORY {
+                        levels.pop_front();
+                    }
+                    levels.push_back(level);
+                }
+                PipelineEvent::AvSync(sync) => {
+                    if self.av_sync.len() == AV_SYNC_HISTORY {
+                        self.av_sync.pop_front();
+                    }
+                    self.av_sync.push_back(sync);
+                }
+                PipelineEvent::Segment(segment) => {
+                    let mut text = "segment".to_string();
+                    if let Some(duration_ns) = segment.duration_ns {
+                        text.push_str(&format!(" {:.2} s", duration_ns as f64 / 1e9));
+                    }
+                    if let Some(size_bytes) = segment.size_bytes {
+                        text.push_str(&format!(" {}", format_size(size_bytes)));
+                    }
+                    self.annotations.push(Annotation {
+                        timestamp_ns: segment.timestamp_ns,
+                        text,
+                    });
+                    if self.segments.len() == SEGMENT_LIMIT {
+                        self.segments.pop_front();
+                    }
+                    self.segments.push_back(segment);
+                }
+                PipelineEvent::Caps { element, pad, caps } => {
+                    self.negotiated_framerates.record(&element, &caps);
+                    let key = format!("{}.{}", element, pad);
+                    if let Some(previous) = self.pad_caps.insert(key.clone(), caps.clone())
+                        && previous != caps
+                    {
+                        let changes: Vec<String> = diff_caps(&previous, &caps)
+                            .into_iter()
+                            .flat_map(|diff| diff.fields)
+                            .filter(|field| field.offered != field.accepted)
+                            .map(|field| {
+                                let value = |value: Option<String>| value.unwrap_or("-".into());
+                                format!(
+                                    "{} {} → {}",
+                                    field.name,
+                                    value(field.offered),
+                                    value(field.accepted)
+                                )
+                            })
+                            .collect();
+                        self.annotations.push(Annotation {
+                            timestamp_ns: self.store.latest_timestamp(),
+                            text: format!("{} renegotiated: {}", key, changes.join(", ")),
+                        });
+                    }
+                }
+                PipelineEvent::Latency(report) => {
+                    self.latency_report = Some(report);
+                }
+                PipelineEvent::SinkFrames(frames) => {
+                    let history = self.sink_frames.entry(frames.sink.clone()).or_default();
+                    if history.len() == SINK_FRAMES_HISTORY {
+                        history.pop_front();
+                    }
+                    history.push_back(frames);
+                }
+                PipelineEvent::RtpSession(stats) => {
+                    self.rtp_sessions.insert(stats.element.clone(), stats);
+                }
+                PipelineEvent::JitterBuffer(stats) => {
+                    let history = self.jitter_buffers.entry(stats.element.clone()).or_default();
+                    if history.len() == JITTER_BUFFER_HISTORY {
+                        history.pop_front();
+                    }
+                    history.push_back(stats);
+                }
+            }
+        }
+
+        self.update_preview(ctx);
+        self.analyze();
+
+        if self.quit_requested.load(Ordering::SeqCst) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        self.error_window(ctx);
+        self.new_pipeline_window(ctx);
+        self.builder_window(ctx);
+        self.session_diff_window(ctx);
+
+        if let Some(element_name) = self.selected_element.clone() {
+            egui::SidePanel::right("histogram_panel")
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.element_panel(ui, &element_name);
+                    });
+                });
+        }
+
+        let show_preview = self.show_preview && self.preview.is_some();
+        let show_levels = self.show_levels && !self.audio_levels.is_empty();
+        if show_preview || show_levels {
+            egui::SidePanel::left("media_panel")
+                .resizable(true)
+                .default_width(340.0)
+                .show(ctx, |ui| {
+                    if show_preview {
+                        self.preview_panel(ui);
+                    }
+                    if show_levels {
+                        ui.separator();
+                        self.levels_panel(ui);
+                    }
+                });
+        }
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            self.status_bar(ui);
+        });
+
+        if self.show_chart {
+            egui::TopBottomPanel::bottom("chart_panel")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| {
+                    self.chart_panel(ui);
+                });
+        }
+
+        if self.show_webrtc && !self.webrtc_stats.is_empty() {
+            egui::TopBottomPanel::bottom("webrtc_panel")
+                .resizable(true)
+                .default_height(160.0)
+                .show(ctx, |ui| {
+                    self.webrtc_panel(ui);
+                });
+        }
+
+        if self.show_clock && !self.clock_history.is_empty() {
+            egui::TopBottomPanel::bottom("clock_panel")
+                .resizable(true)
+                .default_height(180.0)
+                .show(ctx, |ui| {
+                    self.clock_panel(ui);
+                });
+        }
+
+        if self.show_av_sync && !self.av_sync.is_empty() {
+            egui::TopBottomPanel::bottom("av_sync_panel")
+                .resizable(true)
+                .default_height(180.0)
+                .show(ctx, |ui| {
+                    self.av_sync_panel(ui);
+                });
+        }
+
+        if self.show_memory && !self.memory.is_empty() {
+            egui::TopBottomPanel::bottom("memory_panel")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| {
+                    self.memory_panel(ui);
+                });
+        }
+
+        if self.show_queue_latency && !self.queue_levels.is_empty() {
+            egui::TopBottomPanel::bottom("queue_latency_panel")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| {
+                    self.queue_latency_panel(ui);
+                });
+        }
+
+        if self.show_threads && self.streaming_threads.is_some() {
+            egui::TopBottomPanel::bottom("threads_panel")
+                .resizable(true)
+                .default_height(200.0)
+                .show(ctx, |ui| {
+                    self.threads_panel(ui);
+                });
+        }
+
+        if self.show_sink_frames && !self.sink_frames.is_empty() {
+            egui::TopBottomPanel::bottom("sink_frames_panel")
+                .resizable(true)
+                .default_height(200.0)
+                .show(ctx, |ui| {
+                    self.sink_frames_panel(ui);
+                });
+        }
+
+        if self.show_latency_report && self.latency_report.is_some() {
+            egui::TopBottomPanel::bottom("latency_report_panel")
+                .resizable(true)
+                .default_height(160.0)
+                .show(ctx, |ui| {
+                    self.latency_report_panel(ui);
+                });
+        }
+
+        let has_samples = !self.store.samples().is_empty() || !self.store.latencies().is_empty();
+        if self.show_startup && has_samples {
+            egui::TopBottomPanel::bottom("startup_panel")
+                .resizable(true)
+                .default_height(160.0)
+                .show(ctx, |ui| {
+                    self.startup_panel(ui);
+                });
+        }
+
+        if self.show_bookmarks && !self.bookmarks.is_empty() {
+            egui::TopBottomPanel::bottom("bookmarks_panel")
+                .resizable(true)
+                .default_height(120.0)
+                .show(ctx, |ui| {
+                    self.bookmarks_panel(ui);
+                });
+        }
+
+        if self.show_segments && !self.segments.is_empty() {
+            egui::TopBottomPanel::bottom("segments_panel")
+                .resizable(true)
+                .default_height(160.0)
+                .show(ctx, |ui| {
+                    self.segments_panel(ui);
+                });
+        }
+
+        if self.show_streams && !self.stream_collections.is_empty() {
+            egui::TopBottomPanel::bottom("streams_panel")
+                .resizable(true)
+                .default_height(140.0)
+                .show(ctx, |ui| {
+                    self.streams_panel(ui);
+                });
+        }
+
+        if self.show_rtsp && !self.rtsp_sources.is_empty() {
+            egui::TopBottomPanel::bottom("rtsp_panel")
+                .resizable(true)
+                .default_height(180.0)
+                .show(ctx, |ui| {
+                    self.rtsp_panel(ui);
+                });
+        }
+
+        if self.show_rtp_sessions && !self.rtp_sessions.is_empty() {
+            egui::TopBottomPanel::bottom("rtp_session_panel")
+                .resizable(true)
+                .default_height(160.0)
+                .show(ctx, |ui| {
+                    self.rtp_session_panel(ui);
+                });
+        }
+
+        if self.show_jitter_buffers && !self.jitter_buffers.is_empty() {
+            egui::TopBottomPanel::bottom("jitter_buffer_panel")
+                .resizable(true)
+                .default_height(240.0)
+                .show(ctx, |ui| {
+                    self.jitter_buffer_panel(ui);
+                });
+        }
+
+        if self.show_stats {
+            egui::TopBottomPanel::bottom("stats_panel")
+                .resizable(true)
+                .default_height(220.0)
+                .show(ctx, |ui| {
+                    self.stats_table(ui);
+                });
+        }
+
+        let central = egui::CentralPanel::default()
+            .frame(egui::Frame::default().fill(egui::Color32::from_gray(30)))
+            .show(ctx, |ui| {
+                ui.heading("GStreamer Visual Debugger");
+
+                ui.horizontal(|ui| {
+                    if ui.button("🔄 Refresh").clicked() {
+                        self.store.clear();
+                    }
+                    ui.checkbox(&mut self.show_stats, "📋 Statistics");
+                    ui.checkbox(&mut self.show_chart, "📈 Chart");
+                    if self.preview.is_some() {
+                        ui.checkbox(&mut self.show_preview, "🎞 Preview");
+                    }
+                    if !self.audio_levels.is_empty() {
+                        ui.checkbox(&mut self.show_levels, "🔊 Levels");
+                    }
+                    if !self.webrtc_stats.is_empty() {
+                        ui.checkbox(&mut self.show_webrtc, "📡 WebRTC");
+                    }
+                    if !self.rtsp_sources.is_empty() {
+                        ui.checkbox(&mut self.show_rtsp, "📺 RTSP");
+                    }
+                    if !self.clock_history.is_empty() {
+                        ui.checkbox(&mut self.show_clock, "🕒 Clock");
+                    }
+                    if !self.av_sync.is_empty() {
+                        ui.checkbox(&mut self.show_av_sync, "🎭 A/V sync");
+                    }
+                    if !self.memory.is_empty() {
+                        ui.checkbox(&mut self.show_memory, "🧠 Memory");
+                    }
+                    if self.streaming_threads.is_some() {
+                        ui.checkbox(&mut self.show_threads, "🧵 Threads");
+                    }
+                    if !self.queue_levels.is_empty() {
+                        ui.checkbox(&mut self.show_queue_latency, "🪣 Queue vs latency");
+                    }
+                    if !self.sink_frames.is_empty() {
+                        ui.checkbox(&mut self.show_sink_frames, "🎞 Sink frames");
+                    }
+                    if self.latency_report.is_some() {
+                        ui.checkbox(&mut self.show_latency_report, "⏱ Latency");
+                    }
+                    if !self.segments.is_empty() {
+                        ui.checkbox(&mut self.show_segments, "✂ Segments");
+                    }
+                    if !self.bookmarks.is_empty() {
+                        ui.checkbox(&mut self.show_bookmarks, "🔖 Bookmarks");
+                    }
+                    if !self.store.samples().is_empty() || !self.store.latencies().is_empty() {
+                        ui.checkbox(&mut self.show_startup, "🚀 Startup");
+                    }
+                    if !self.stream_collections.is_empty() {
+                        ui.checkbox(&mut self.show_streams, "🎬 Streams");
+                    }
+                    if !self.rtp_sessions.is_empty() {
+                        ui.checkbox(&mut self.show_rtp_sessions, "🛰 RTP sessions");
+                    }
+                    if !self.jitter_buffers.is_empty() {
+                        ui.checkbox(&mut self.show_jitter_buffers, "📦 Jitterbuffers");
+                    }
+                    if ui
+                        .button("🐞 Bug report")
+                        .on_hover_text("Zip the pipeline, versions, statistics and log excerpt")
+                        .clicked()
+                    {
+                        self.bug_report_requested = true;
+                        ui.ctx().send_viewport_cmd(egui::ViewportCommand::Screenshot);
+                    }
+                    ui.separator();
+                    self.transport_controls(ui);
+                });
+
+                self.pipeline_editor(ui);
+                self.tracer_selector(ui);
+                self.seek_bar(ui);
+
+                if let Some(notice) = &self.notice {
+                    let mut dismissed = false;
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::YELLOW, notice);
+                        dismissed = ui.small_button("✖").clicked();
+                    });
+                    if dismissed {
+                        self.notice = None;
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Min Bitrate:");
+                    ui.add(egui::Slider::new(&mut self.bitrate_threshold, 0..=10_000_000));
+                    ui.label("Min Framerate:");
+                    ui.add(egui::Slider::new(&mut self.framerate_threshold, 0.0..=120.0));
+                    ui.label("Max Latency (ns):");
+                    let latency = egui::Slider::new(&mut self.latency_threshold_ns, 0..=1_000_000);
+                    let mut changed = ui.add(latency).changed();
+                    ui.label("Stall after (s):");
+                    changed |= ui
+                        .add_enabled(
+                            self.rules.stall.is_none(),
+                            egui::Slider::new(&mut self.stall_after_s, 1.0..=60.0),
+                        )
+                        .on_disabled_hover_text("Set by the [stall] table of the rules file")
+                        .changed();
+                    if changed {
+                        self.reanalyze();
+                    }
+                });
+
+                let inter = self.store.latencies();
+                let element_stats = &self.analysis.element_stats;
+
+                let node_size = 120.0;
+                let node_height = 70.0;
+
+                for edge in self.graph.edge_indices() {
+                    let (start, end) = self.graph.edge_endpoints(edge).unwrap();
+                    let start_pos = self.positions[&start];
+                    let end_pos = self.positions[&end];
+
+                    ui.painter().line_segment(
+                        [
+                            egui::pos2(start_pos.x + node_size, start_pos.y + node_height / 2.0),
+                            egui::pos2(end_pos.x, end_pos.y + node_height / 2.0),
+                        ],
+                        egui::Stroke::new(2.0, egui::Color32::WHITE),
+                    );
+
+                    let from_name = &self.graph[start];
+                    let to_name = &self.graph[end];
+                    
+
+                    if let Some(latency) = inter.iter().rev().find(|lat| {
+                        lat.from.starts_with(to_name)
+                    }) {
+                        let latency_val = parse_duration_to_ns(&latency.time).unwrap_or(0);
+                        let threshold_ns = self.latency_threshold_ns(&latency.from);
+                        let color = if threshold_ns > 0 && latency_val > threshold_ns {
+                            egui::Color32::RED
+                        } else {
+                            egui::Color32::YELLOW
+                        };
+                        let label_pos = egui::pos2((start_pos.x + end_pos.x) / 2.0, start_pos.y - 10.0);
+                        ui.painter().text(
+                            label_pos,
+                            egui::Align2::CENTER_CENTER,
+                            format!("{} ns", latency.time),
+                            egui::FontId::proportional(12.0),
+                            color,
+                        );
+                    }
+                }
+
+                for node in self.graph.node_indices() {
+                    let health = self.analysis.health.get(&self.graph[node]).cloned();
+                    let pos = self.positions.entry(node).or_insert(egui::pos2(50.0, 200.0));
+                    let response = ui.allocate_rect(
+                        egui::Rect::from_min_size(*pos, egui::vec2(node_size, node_height)),
+                        egui::Sense::click_and_drag(),
+                    );
+
+                    if response.dragged() {
+                        pos.x += response.drag_delta().x;
+                        pos.y += response.drag_delta().y;
+                    }
+
+
+                    let element_name = self.graph[node].clone();
+                    if response.clicked() && self.selected_element.as_ref() != Some(&element_name) {
+                        self.selected_element = Some(element_name.clone());
+                        self.property_edits.clear();
+                    }
+                    let mut details: Vec<String> = health
+                        .iter()
+                        .flat_map(|health| {
+                            let score = format!("Health {:.0}/100", health.score);
+                            std::iter::once(score).chain(health.factors.iter().cloned())
+                        })
+                        .collect();
+                    details.extend(
+                        element_stats
+                            .iter()
+                            .filter(|stats| stats.element.starts_with(&element_name))
+                            .flat_map(percentile_lines),
+                    );
+                    if !details.is_empty() {
+                        response.on_hover_text(details.join("\n"));
+                    }
+                    let tracing_data = self.analysis.latest.get(&element_name).cloned();
+                    let interlatency_data = inter.iter().rev().find(|lat| lat.from.starts_with(&element_name));
+
+
+                 let mut display_text = match tracing_data {
+    Some(data) if data.bitrate.unwrap_or(0) >= self.bitrate_threshold
+        && data.framerate.unwrap_or(0.0) >= self.framerate_threshold =>
+    {
+        let mut text = format!(
+            "{}\nBitrate: {} bps\nFramerate: {} fps",
+            element_name,
+            data.bitrate.unwrap_or(0),
+            data.framerate.unwrap_or(0.0)
+        );
+        if let Some(expected) = self.negotiated_framerates.get(&data.element) {
+            text.push_str(&format!(" of {:.2}", expected));
+        }
+        if let Some(proctime) = data.proctime_ns {
+            text.push_str(&format!("\nProcTime: {} ns", proctime));
+        }
+        if let Some(jitter) = data.jitter_ns {
+            text.push_str(&format!("\nJitter: {} ns", jitter));
+        }
+        text
+    }
+    Some(data) => {
+        let mut text = element_name.clone();
+        if let Some(proctime) = data.proctime_ns {
+            text.push_str(&format!("\nProcTime: {} ns", proctime));
+        }
+        if let Some(jitter) = data.jitter_ns {
+            text.push_str(&format!("\nJitter: {} ns", jitter));
+        }
+        text
+    }
+    None => element_name.clone(),
+};
+                    if let Some(stall) = self
+                        .stalled
+                        .iter()
+                        .find(|stall| stall.element.starts_with(&element_name))
+                    {
+                        let silent_s = stall.value.unwrap_or(0.0) / 1e9;
+                        display_text.push_str(&format!("\n⏸ Stalled {:.0} s", silent_s));
+                    }
+                    let reached_eos = self
+                        .eos_elements
+                        .iter()
+                        .any(|element| element.starts_with(&element_name));
+                    ui.painter().rect_filled(
+                        egui::Rect::from_min_size(*pos, egui::vec2(node_size, node_height)),
+                        5.0,
+                        if reached_eos {
+                            egui::Color32::from_gray(80)
+                        } else {
+                            egui::Color32::DARK_BLUE
+                        },
+                    );
+
+                    let failed = self
+                        .pipeline_error
+                        .as_ref()
+                        .and_then(|error| error.element.as_ref())
+                        .is_some_and(|element| element.starts_with(&element_name));
+                    if failed {
+                        ui.painter().rect_stroke(
+                            egui::Rect::from_min_size(*pos, egui::vec2(node_size, node_height)),
+                            5.0,
+                            egui::Stroke::new(3.0, egui::Color32::RED),
+                        );
+                    } else if self
+                        .alerts
+                        .iter()
+                        .any(|alert| alert.element.starts_with(&element_name))
+                    {
+                        ui.painter().rect_stroke(
+                            egui::Rect::from_min_size(*pos, egui::vec2(node_size, node_height)),
+                            5.0,
+                            egui::Stroke::new(3.0, egui::Color32::from_rgb(255, 140, 0)),
+                        );
+                    } else if self.selected_element.as_ref() == Some(&element_name) {
+                        ui.painter().rect_stroke(
+                            egui::Rect::from_min_size(*pos, egui::vec2(node_size, node_height)),
+                            5.0,
+                            egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE),
+                        );
+                    }
+
+                    ui.painter().text(
+                        egui::pos2(pos.x + 10.0, pos.y + 20.0),
+                        egui::Align2::LEFT_CENTER,
+                        display_text,
+                        egui::FontId::proportional(13.0),
+                        egui::Color32::WHITE,
+                    );
+                    if let Some(health) = &health {
+                        let center = egui::pos2(pos.x + node_size - 14.0, pos.y + 14.0);
+                        draw_health_ring(ui.painter(), center, health);
+                    }
+                }
+            });
+        self.graph_rect = Some(central.response.rect);
+
+        ctx.request_repaint();
+    }
+}
+
+const SERIES_COLORS: [egui::Color32; 8] = [
+    egui::Color32::LIGHT_BLUE,
+    egui::Color32::YELLOW,
+    egui::Color32::LIGHT_GREEN,
+    egui::Color32::LIGHT_RED,
+    egui::Color32::GOLD,
+    egui::Color32::WHITE,
+    egui::Color32::from_rgb(200, 120, 255),
+    egui::Color32::from_rgb(255, 160, 60),
+];
+
+fn draw_line_chart(
+    ui: &mut egui::Ui,
+    series: &[(String, Vec<(u64, f64)>)],
+    format: &dyn Fn(f64) -> String,
+    annotations: &[Annotation],
+    time: TimeAxis,
+) -> Option<ChartResponse> {
+    let points = series
+        .iter()
+        .flat_map(|(_, values)| values.iter())
+        .filter(|(t, _)| time.window.is_none_or(|(start, end)| (start..=end).contains(t)));
+    let (mut t_min, mut t_max, mut v_min, mut v_max) = (u64::MAX, 0u64, 0.0f64, 0.0f64);
+    for (t, v) in points {
+        t_min = t_min.min(*t);
+        t_max = t_max.max(*t);
+        v_min = v_min.min(*v);
+        v_max = v_max.max(*v);
+    }
+    if t_min > t_max && time.window.is_none() {
+        ui.label("Select one or more elements to plot");
+        return None;
+    }
+    if let Some((start, end)) = time.window {
+        (t_min, t_max) = (start, end);
+    }
+    let t_span = (t_max - t_min).max(1) as f32;
+    // The axis starts at zero unless a series goes below it.
+    let v_span = if v_max > v_min { v_max - v_min } else { 1.0 };
+
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), ui.available_height().max(120.0) - 24.0),
+        egui::Sense::click_and_drag(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(20));
+    let x_of = |timestamp_ns: u64| {
+        rect.left() + rect.width() * timestamp_ns.saturating_sub(t_min) as f32 / t_span
+    };
+    let ns_at = |x: f32| {
+        t_min + ((x - rect.left()).clamp(0.0, rect.width()) / rect.width() * t_span) as u64
+    };
+
+    if let Some((start, end)) = time.selection
+        && start <= t_max
+        && end >= t_min
+    {
+        let shaded =
+            egui::Rect::from_x_y_ranges(x_of(start)..=x_of(end.min(t_max)), rect.y_range());
+        painter.rect_filled(shaded, 0.0, egui::Color32::from_rgba_unmultiplied(90, 140, 255, 40));
+    }
+
+    for i in 1..4 {
+        let y = rect.bottom() - rect.height() * i as f32 / 4.0;
+        painter.line_segment(
+            [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
+            egui::Stroke::new(1.0, egui::Color32::from_gray(50)),
+        );
+        painter.text(
+            egui::pos2(rect.left() + 4.0, y),
+            egui::Align2::LEFT_BOTTOM,
+            format(v_min + v_span * i as f64 / 4.0),
+            egui::FontId::proportional(11.0),
+            egui::Color32::GRAY,
+        );
+    }
+
+    for annotation in annotations {
+        if annotation.timestamp_ns < t_min || annotation.timestamp_ns > t_max {
+            continue;
+        }
+        let x = x_of(annotation.timestamp_ns);
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 120, 200)),
+        );
+        painter.text(
+            egui::pos2(x + 3.0, rect.top() + 2.0),
+            egui::Align2::LEFT_TOP,
+            &annotation.text,
+            egui::FontId::proportional(11.0),
+            egui::Color32::from_rgb(255, 120, 200),
+        );
+    }
+
+    for (index, (_, values)) in series.iter().enumerate() {
+        let color = SERIES_COLORS[index % SERIES_COLORS.len()];
+        let line: Vec<egui::Pos2> = values
+            .iter()
+            .filter(|(t, _)| (t_min..=t_max).contains(t))
+            .map(|(t, v)| {
+                egui::pos2(
+                    x_of(*t),
+                    rect.bottom() - rect.height() * ((*v - v_min) / v_span) as f32,
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(format!("{} - {}", time.format(t_min), time.format(t_max)));
+        for (index, (element, _)) in series.iter().enumerate() {
+            ui.colored_label(SERIES_COLORS[index % SERIES_COLORS.len()], format!("■ {}", element));
+        }
+    });
+    let clicked_ns = response
+        .secondary_clicked()
+        .then(|| response.interact_pointer_pos())
+        .flatten()
+        .map(|pos| ns_at(pos.x));
+    let origin = ui.input(|input| input.pointer.press_origin());
+    let dragged = match (response.dragged_by(egui::PointerButton::Primary), origin) {
+        (true, Some(origin)) => response.interact_pointer_pos().map(|pos| {
+            let (a, b) = (ns_at(origin.x), ns_at(pos.x));
+            (a.min(b), a.max(b))
+        }),
+        _ => None,
+    };
+    Some(ChartResponse {
+        range: (t_min, t_max),
+        clicked_ns,
+        dragged,
+    })
+}
+
+fn encode_png(image: &egui::ColorImage) -> Option<Vec<u8>> {
+    let rgba: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.to_array()).collect();
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(&rgba).ok()?;
+    writer.finish().ok()?;
+    Some(png)
+}
+
+/// Dots of (fill level, latency in nanoseconds) points, fill on the x axis
+/// from empty to full.
+fn draw_scatter_chart(ui: &mut egui::Ui, points: &[(f64, f64)]) {
+    let latency_max = points.iter().map(|(_, latency)| *latency).fold(0.0, f64::max).max(1.0);
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), ui.available_height().max(120.0) - 8.0),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(20));
+    for i in 1..4 {
+        let y = rect.bottom() - rect.height() * i as f32 / 4.0;
+        let x = rect.left() + rect.width() * i as f32 / 4.0;
+        let grid = egui::Stroke::new(1.0, egui::Color32::from_gray(50));
+        painter.line_segment([egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)], grid);
+        painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], grid);
+        painter.text(
+            egui::pos2(rect.left() + 4.0, y),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.2} ms", latency_max * i as f64 / 4.0 / 1e6),
+            egui::FontId::proportional(11.0),
+            egui::Color32::GRAY,
+        );
+        painter.text(
+            egui::pos2(x, rect.bottom() - 2.0),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{}% full", i * 25),
+            egui::FontId::proportional(11.0),
+            egui::Color32::GRAY,
+        );
+    }
+    for (fill, latency) in points {
+        let position = egui::pos2(
+            rect.left() + rect.width() * fill.clamp(0.0, 1.0) as f32,
+            rect.bottom() - rect.height() * (latency / latency_max) as f32,
+        );
+        painter.circle_filled(position, 2.0, SERIES_COLORS[0].gamma_multiply(0.6));
+    }
+}
+
+/// `latest` with the metrics that are smoothed replaced by their smoothed
+/// value over the samples of the elements starting with `element`.
+fn smoothed_sample(
+    smoothing: &HashMap<Metric, Smoothing>,
+    logs: &[TracingData],
+    element: &str,
+    latest: &TracingData,
+) -> TracingData {
+    let mut sample = latest.clone();
+    let smoothed = |metric: Metric| {
+        let smoothing = smoothing.get(&metric).copied().unwrap_or_default();
+        if smoothing == Smoothing::Off {
+            return metric.value(latest);
+        }
+        let values = logs
+            .iter()
+            .rev()
+            .filter(|e| e.element.starts_with(element))
+            .filter_map(|e| metric.value(e));
+        metric.value(latest).and(smoothing.latest(values))
+    };
+    sample.bitrate = smoothed(Metric::Bitrate).map(|bitrate| bitrate as u64);
+    sample.framerate = smoothed(Metric::Framerate);
+    sample.proctime_ns = smoothed(Metric::ProcTime).map(|ns| ns as u64);
+    sample.jitter_ns = smoothed(Metric::Jitter).map(|ns| ns as u64);
+    sample
+}
+
+/// The p50, p95 and p99 of the timings of `stats`, a line per metric.
+fn percentile_lines(stats: &ElementStats) -> Vec<String> {
+    [Metric::ProcTime, Metric::Interlatency]
+        .into_iter()
+        .filter_map(|metric| {
+            let summary = stats.summary(metric);
+            let values: Vec<String> = [Aggregate::P50, Aggregate::P95, Aggregate::P99]
+                .into_iter()
+                .map(|aggregate| {
+                    let value = summary.get(aggregate)?;
+                    Some(format!("{} {}", aggregate.label(), metric.format(value)))
+                })
+                .collect::<Option<_>>()?;
+            Some(format!("{}: {}", metric.label(), values.join(", ")))
+        })
+        .collect()
+}
+
+fn format_signed_ms(ns: f64) -> String {
+    format!("{:+.2} ms", ns / 1e6)
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{} B", bytes),
+        1_000..1_000_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.1} MB", bytes as f64 / 1e6),
+        _ => format!("{:.2} GB", bytes as f64 / 1e9),
+    }
+}
+
+/// A ring filled clockwise in proportion to a health score, green, yellow or
+/// red by how healthy.
+fn draw_health_ring(painter: &egui::Painter, center: egui::Pos2, health: &Health) {
+    let radius = 9.0;
+    let color = if health.score >= 80.0 {
+        egui::Color32::LIGHT_GREEN
+    } else if health.score >= 50.0 {
+        egui::Color32::YELLOW
+    } else {
+        egui::Color32::LIGHT_RED
+    };
+    painter.circle_stroke(center, radius, egui::Stroke::new(3.0, egui::Color32::from_gray(60)));
+    let sweep = std::f32::consts::TAU * (health.score / 100.0) as f32;
+    let steps = 32;
+    let points: Vec<egui::Pos2> = (0..=steps)
+        .map(|step| {
+            let angle = -std::f32::consts::FRAC_PI_2 + sweep * step as f32 / steps as f32;
+            center + radius * egui::vec2(angle.cos(), angle.sin())
+        })
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(3.0, color)));
+    painter.text(
+        center,
+        egui::Align2::CENTER_CENTER,
+        format!("{:.0}", health.score),
+        egui::FontId::proportional(8.0),
+        egui::Color32::WHITE,
+    );
+}
+
+/// An arrow for how far an average moved from the baseline: red when for
+/// the worse, green when for the better.
+fn draw_baseline_delta(ui: &mut egui::Ui, metric: Metric, delta: &BaselineDelta) {
+    if delta.percent.abs() < 0.5 {
+        return;
+    }
+    let arrow = if delta.percent > 0.0 { "⬆" } else { "⬇" };
+    let color = if delta.worse {
+        egui::Color32::LIGHT_RED
+    } else {
+        egui::Color32::LIGHT_GREEN
+    };
+    ui.colored_label(color, format!("{}{:.0}%", arrow, delta.percent.abs()))
+        .on_hover_text(format!("Baseline: {}", metric.format(delta.before)));
+}
+
+/// The caps on both ends of a link that failed to negotiate, side by side,
+/// with the fields that do not intersect in red.
+fn draw_caps_mismatch(ui: &mut egui::Ui, mismatch: &CapsMismatch) {
+    ui.strong(format!("{} ➜ {} could not agree on caps", mismatch.src_pad, mismatch.sink_pad));
+    if let Some(diff) = diff_caps(&mismatch.offered, &mismatch.accepted) {
+        egui::Grid::new("caps_mismatch").striped(true).show(ui, |ui| {
+            ui.strong("Field");
+            ui.strong(format!("Offered by {}", mismatch.src_pad));
+            ui.strong(format!("Accepted by {}", mismatch.sink_pad));
+            ui.end_row();
+            let mut row = |name: &str, offered: &str, accepted: &str, compatible: bool| {
+                let color = if compatible {
+                    ui.visuals().text_color()
+                } else {
+                    egui::Color32::LIGHT_RED
+                };
+                ui.colored_label(color, name);
+                ui.colored_label(color, egui::RichText::new(offered).monospace());
+                ui.colored_label(color, egui::RichText::new(accepted).monospace());
+                ui.end_row();
+            };
+            let same_media = diff.offered_name == diff.accepted_name;
+            row("media type", &diff.offered_name, &diff.accepted_name, same_media);
+            for field in &diff.fields {
+                row(
+                    &field.name,
+                    field.offered.as_deref().unwrap_or("—"),
+                    field.accepted.as_deref().unwrap_or("—"),
+                    field.compatible,
+                );
+            }
+        });
+    }
+    egui::CollapsingHeader::new("Full caps").show(ui, |ui| {
+        ui.label("Offered:");
+        ui.monospace(&mismatch.offered);
+        ui.label("Accepted:");
+        ui.monospace(&mismatch.accepted);
+    });
+}
+
+/// The factory details of an element, laid out like gst-inspect.
+fn draw_element_info(ui: &mut egui::Ui, info: &ElementInfo) {
+    ui.strong(&info.long_name);
+    ui.label(&info.description);
+    egui::Grid::new("element_info_grid").show(ui, |ui| {
+        ui.label("Factory");
+        ui.monospace(&info.factory);
+        ui.end_row();
+        ui.label("Class");
+        ui.label(&info.klass);
+        ui.end_row();
+        ui.label("Plugin");
+        ui.label(info.plugin.as_deref().unwrap_or("-"));
+        ui.end_row();
+        ui.label("Author");
+        ui.label(&info.author);
+        ui.end_row();
+    });
+
+    ui.separator();
+    ui.strong("Pad templates");
+    for template in &info.pad_templates {
+        ui.label(format!("{} ({}, {})", template.name, template.direction, template.presence));
+        let caps = egui::RichText::new(&template.caps).monospace().small();
+        ui.add(egui::Label::new(caps).wrap(true));
+    }
+
+    ui.separator();
+    ui.strong("Properties");
+    egui::Grid::new("element_info_properties").striped(true).show(ui, |ui| {
+        for property in &info.properties {
+            ui.label(&property.name).on_hover_text(&property.blurb);
+            ui.label(&property.type_name);
+            ui.monospace(&property.default);
+            ui.label(if property.writable { "" } else { "read-only" });
+            ui.end_row();
+        }
+    });
+}
+
+/// Lowest level a meter shows; quieter signals leave it empty.
+const METER_FLOOR_DB: f64 = -60.0;
+
+/// A horizontal meter for one channel: the bar is the RMS level and the tick
+/// the peak, both from -60 dB to 0 dB.
+fn draw_level_meter(ui: &mut egui::Ui, channel: usize, rms_db: f64, peak_db: f64) {
+    let fraction = |db: f64| ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0) as f32;
+    ui.horizontal(|ui| {
+        ui.label(format!("{}", channel + 1));
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 12.0),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, egui::Color32::from_gray(20));
+
+        let color = if peak_db > -1.0 {
+            egui::Color32::RED
+        } else if rms_db > -12.0 {
+            egui::Color32::YELLOW
+        } else {
+            egui::Color32::LIGHT_GREEN
+        };
+        let bar = egui::Rect::from_min_size(
+            rect.min,
+            egui::vec2(rect.width() * fraction(rms_db), rect.height()),
+        );
+        painter.rect_filled(bar, 2.0, color);
+
+        let x = rect.left() + rect.width() * fraction(peak_db);
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(2.0, egui::Color32::WHITE),
+        );
+        response.on_hover_text(format!("RMS {:.1} dB, peak {:.1} dB", rms_db, peak_db));
+    });
+}
+
+fn bus_kind_color(kind: BusMessageKind) -> egui::Color32 {
+    match kind {
+        BusMessageKind::StateChanged => egui::Color32::LIGHT_BLUE,
+        BusMessageKind::NewClock => egui::Color32::LIGHT_GREEN,
+        BusMessageKind::ClockLost => egui::Color32::RED,
+        BusMessageKind::Tag => egui::Color32::GRAY,
+        BusMessageKind::Buffering => egui::Color32::GOLD,
+        BusMessageKind::Latency => egui::Color32::from_rgb(200, 120, 255),
+        BusMessageKind::Warning => egui::Color32::from_rgb(255, 140, 0),
+        BusMessageKind::Eos => egui::Color32::WHITE,
+    }
+}
+
+/// One tick per bus message between `range`, the chart's time span, or the
+/// messages' own span when nothing is plotted. Hovering lists the messages
+/// under the pointer.
+fn draw_bus_lane(
+    ui: &mut egui::Ui,
+    messages: &[&BusMessage],
+    range: Option<(u64, u64)>,
+    time: TimeAxis,
+) -> Option<(u64, String)> {
+    let (t_min, t_max) = range.or_else(|| {
+        let first = messages.first()?.timestamp_ns;
+        let last = messages.last()?.timestamp_ns;
+        Some((first, last))
+    })?;
+    let t_span = (t_max - t_min).max(1) as f32;
+
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), 24.0),
+        egui::Sense::click(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(20));
+
+    let x_of = |timestamp_ns: u64| {
+        rect.left() + rect.width() * timestamp_ns.saturating_sub(t_min) as f32 / t_span
+    };
+    let visible = messages
+        .iter()
+        .filter(|message| (t_min..=t_max).contains(&message.timestamp_ns));
+    for message in visible.clone() {
+        let x = x_of(message.timestamp_ns);
+        painter.line_segment(
+            [egui::pos2(x, rect.top() + 2.0), egui::pos2(x, rect.bottom() - 2.0)],
+            egui::Stroke::new(2.0, bus_kind_color(message.kind)),
+        );
+    }
+
+    let near = |pos: egui::Pos2| {
+        visible
+            .clone()
+            .find(|message| (x_of(message.timestamp_ns) - pos.x).abs() <= 4.0)
+    };
+    let clicked = response
+        .secondary_clicked()
+        .then(|| response.interact_pointer_pos().and_then(near))
+        .flatten()
+        .map(|message| {
+            let what = format!("{} {}: {}", message.kind.label(), message.source, message.detail);
+            (message.timestamp_ns, what)
+        });
+
+    if let Some(hover) = response.hover_pos() {
+        let lines: Vec<String> = visible
+            .filter(|message| (x_of(message.timestamp_ns) - hover.x).abs() <= 4.0)
+            .take(20)
+            .map(|message| {
+                format!(
+                    "{} {} {} {}",
+                    time.format(message.timestamp_ns),
+                    message.kind.label(),
+                    message.source,
+                    message.detail
+                )
+            })
+            .collect();
+        if !lines.is_empty() {
+            response.on_hover_text(lines.join("\n"));
+        }
+    }
+    clicked
+}
+
+fn draw_histogram(ui: &mut egui::Ui, values: &[u64], bins: usize, threshold_ns: Option<u64>) {
+    if values.is_empty() {
+        ui.label("No samples yet");
+        return;
+    }
+
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+    let bin_width = ((max - min) / bins as u64).max(1);
+
+    let mut counts = vec![0usize; bins];
+    for value in values {
+        let bin = ((value - min) / bin_width) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+    let peak = *counts.iter().max().unwrap_or(&1) as f32;
+
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), 120.0),
+        egui::Sense::hover(),
+    );
+    ui.painter().rect_filled(rect, 2.0, egui::Color32::from_gray(20));
+
+    let bar_width = rect.width() / bins as f32;
+    for (i, count) in counts.iter().enumerate() {
+        let bin_start = min + i as u64 * bin_width;
+        let height = rect.height() * (*count as f32 / peak);
+        let color = match threshold_ns {
+            Some(threshold) if threshold > 0 && bin_start > threshold => egui::Color32::RED,
+            _ => egui::Color32::LIGHT_BLUE,
+        };
+        ui.painter().rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(rect.left() + i as f32 * bar_width + 1.0, rect.bottom() - height),
+                egui::pos2(rect.left() + (i + 1) as f32 * bar_width - 1.0, rect.bottom()),
+            ),
+            0.0,
+            color,
+        );
+    }
+
+    if let Some(hover) = response.hover_pos() {
+        let bin = (((hover.x - rect.left()) / bar_width) as usize).min(bins - 1);
+        let bin_start = min + bin as u64 * bin_width;
+        response.on_hover_text(format!(
+            "{} - {} ns: {} samples",
+            bin_start,
+            bin_start + bin_width,
+            counts[bin]
+        ));
+    }
+
+    ui.label(format!(
+        "{} samples, min {} ns, max {} ns",
+        values.len(),
+        min,
+        max
+    ));
+}
+
+
+#[tokio::main]
+async fn main() {
+    let args = Cli::parse();
+    let session = match cli::start(args.command).await {
+        Ok(Some(session)) if session.view.headless => {
+            cli::print_summaries(session).await;
+            return;
+        }
+        Ok(Some(session)) if session.view.daemon => {
+            let socket = session.view.socket.clone();
+            if let Err(message) = daemon::serve(session, &socket).await {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(Some(session)) if session.view.tui => {
+            if let Err(err) = tui::run(session).await {
+                eprintln!("Terminal UI failed: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(Some(session)) => session,
+        Ok(None) => return,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    };
+
+    let engine = session.engine;
+    let output = session.output;
+    let started = session.started;
+    let quit_requested = Arc::new(AtomicBool::new(false));
+
+    let ctrl_c_flag = quit_requested.clone();
+    task::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            ctrl_c_flag.store(true, Ordering::SeqCst);
+        }
+    });
+
+    let gui_control_tx = engine.control();
+    let raw_retention_ns = session.view.raw_retention_ns().unwrap_or(RAW_RETENTION_NS);
+    let options = eframe::NativeOptions::default();
+    let result = eframe::run_native(
+        "GStreamer Debugger",
+        options,
+        Box::new(move |_cc| {
+            let mut app = GstDebugger::new(
+                session.pipeline,
+                session.tracing,
+                output,
+                gui_control_tx,
+                session.rules,
+                quit_requested,
+                started,
+            );
+            app.store.set_raw_retention(Some(raw_retention_ns));
+            app.environment = session.environment;
+            Box::new(app)
+        }),
+    );
+
+    engine.shutdown().await;
+
+    result.expect("Failed to start GUI");
+}
@@ -0,0 +1,45 @@
+//! `gstdebugger` tracer plugin. It measures buffer flow inside the pipeline
+//! process and streams the samples to gst-debugger over a Unix socket in the
+//! binary format of [`gst_debugger_core::wire`], instead of printing tracer
+//! lines for the debugger to parse back.
+//!
+//! The debugger loads it with
+//! `GST_TRACERS="gstdebugger(socket=/path/to.sock)"` when run with
+//! `--native-tracer`.
+
+use gstreamer as gst;
+use gstreamer::glib;
+#[cfg(unix)]
+use gstreamer::prelude::*;
+
+// The tracer reaches the debugger over a Unix socket, so elsewhere the
+// plugin loads without it.
+#[cfg(unix)]
+mod tracer;
+
+#[cfg(unix)]
+fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Tracer::register(
+        Some(plugin),
+        "gstdebugger",
+        tracer::DebuggerTracer::static_type(),
+    )
+}
+
+#[cfg(not(unix))]
+fn plugin_init(_plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    Ok(())
+}
+
+// The library is `libgstdebugger`, so GStreamer looks for the `debugger`
+// plugin in it.
+gst::plugin_define!(
+    debugger,
+    env!("CARGO_PKG_DESCRIPTION"),
+    plugin_init,
+    env!("CARGO_PKG_VERSION"),
+    "unknown",
+    env!("CARGO_PKG_NAME"),
+    env!("CARGO_PKG_NAME"),
+    env!("CARGO_PKG_NAME")
+);
@@ -0,0 +1,9 @@
+use gstreamer as gst;
+use gstreamer::glib;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DebuggerTracer(ObjectSubclass<imp::DebuggerTracer>)
+        @extends gst::Tracer, gst::Object;
+}
@@ -0,0 +1,309 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, Weak};
+use std::thread;
+use std::time::Duration;
+
+use gst_debugger_core::wire::encode_sample;
+use gst_debugger_core::{inter_arrival_jitter, pad_sample_name, TracingData};
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use gstreamer::subclass::prelude::*;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "gstdebugger",
+        gst::DebugColorFlags::empty(),
+        Some("gst-debugger native tracer"),
+    )
+});
+
+#[derive(Debug, Default)]
+struct PadCounters {
+    /// Whether this was the first source pad of its element to carry a
+    /// buffer.
+    first: bool,
+    buffers: u64,
+    bytes: u64,
+    last_push_ns: Option<u64>,
+    intervals_ns: Vec<u64>,
+}
+
+impl PadCounters {
+    fn record(&mut self, ts: u64, buffers: u64, bytes: u64) {
+        if let Some(last) = self.last_push_ns {
+            self.intervals_ns.push(ts.saturating_sub(last));
+        }
+        self.last_push_ns = Some(ts);
+        self.buffers += buffers;
+        self.bytes += bytes;
+    }
+}
+
+/// Exclusive time spent in an element's chain function.
+#[derive(Debug, Default)]
+struct ChainTime {
+    total_ns: u64,
+    calls: u64,
+}
+
+/// Everything measured since the last flush.
+#[derive(Debug, Default)]
+struct Stats {
+    pads: HashMap<(String, String), PadCounters>,
+    chains: HashMap<String, ChainTime>,
+    last_ts: u64,
+    last_flush_ts: u64,
+}
+
+impl Stats {
+    /// Turn the counts gathered since the previous flush into samples and
+    /// reset them for the next interval.
+    fn take_samples(&mut self) -> Vec<TracingData> {
+        let timestamp_ns = self.last_ts;
+        let seconds =
+            (timestamp_ns.saturating_sub(self.last_flush_ts) as f64 / 1e9).max(f64::EPSILON);
+        self.last_flush_ts = timestamp_ns;
+
+        let mut samples = Vec::new();
+        for ((element, pad), counters) in &mut self.pads {
+            if counters.buffers == 0 {
+                continue;
+            }
+            samples.push(TracingData {
+                timestamp_ns,
+                element: pad_sample_name(element, pad, counters.first),
+                bitrate: Some((counters.bytes as f64 * 8.0 / seconds) as u64),
+                framerate: Some(counters.buffers as f64 / seconds),
+                proctime_ns: None,
+                jitter_ns: inter_arrival_jitter(&counters.intervals_ns),
+            });
+            counters.buffers = 0;
+            counters.bytes = 0;
+            counters.intervals_ns.clear();
+        }
+        for (element, chain) in self.chains.drain() {
+            samples.push(TracingData {
+                timestamp_ns,
+                element,
+                bitrate: None,
+                framerate: None,
+                proctime_ns: Some(chain.total_ns / chain.calls.max(1)),
+                jitter_ns: None,
+            });
+        }
+        samples
+    }
+}
+
+/// A push that has not returned yet on this thread.
+struct Push {
+    /// The element receiving the buffer, `None` for bins and ghost pads.
+    element: Option<String>,
+    start_ns: u64,
+    /// Time spent in pushes the receiving element made further downstream.
+    nested_ns: u64,
+}
+
+thread_local! {
+    static PUSHES: RefCell<Vec<Push>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The element that owns `pad`, skipping bins, whose ghost pads only
+/// forward buffers to the elements inside.
+fn pad_element(pad: &gst::Pad) -> Option<String> {
+    pad.parent_element()
+        .filter(|element| !element.is::<gst::Bin>())
+        .map(|element| element.name().to_string())
+}
+
+/// The value of `key` in the tracer's `params`, e.g. `socket=/tmp/x.sock`.
+fn param(params: &str, key: &str) -> Option<String> {
+    params.split(',').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+/// Send the samples measured since the last flush. The samples are taken
+/// under the socket's lock, so a flush on another thread can neither
+/// interleave its frames with these nor send older samples after them.
+fn flush(stats: &Mutex<Stats>, socket: &Mutex<UnixStream>) -> std::io::Result<()> {
+    let mut socket = socket.lock().unwrap();
+    let samples = stats.lock().unwrap().take_samples();
+    let mut frames = Vec::new();
+    for sample in &samples {
+        encode_sample(sample, &mut frames);
+    }
+    socket.write_all(&frames)
+}
+
+#[derive(Default)]
+pub struct DebuggerTracer {
+    stats: Arc<Mutex<Stats>>,
+    connected: Arc<AtomicBool>,
+    socket: OnceLock<Arc<Mutex<UnixStream>>>,
+}
+
+impl DebuggerTracer {
+    fn connect(&self) -> Result<(), String> {
+        let params = self.obj().property::<Option<String>>("params").unwrap_or_default();
+        let path = param(&params, "socket").ok_or("no socket=PATH in the tracer params")?;
+        let socket = UnixStream::connect(&path)
+            .map_err(|err| format!("failed to connect to {}: {}", path, err))?;
+        let socket = self.socket.get_or_init(|| Arc::new(Mutex::new(socket)));
+        let flusher = socket.clone();
+        self.connected.store(true, Ordering::Relaxed);
+
+        let stats = Arc::downgrade(&self.stats);
+        let connected = self.connected.clone();
+        thread::Builder::new()
+            .name("gstdebugger-flush".to_string())
+            .spawn(move || flush_periodically(stats, connected, flusher))
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    fn record_push(&self, ts: u64, pad: &gst::Pad, buffers: u64, bytes: u64) {
+        // The stack is kept even when not connected, so every post hook
+        // finds the push its pre hook started.
+        let receiver = pad.peer().as_ref().and_then(pad_element);
+        if self.connected.load(Ordering::Relaxed)
+            && let Some(element) = pad_element(pad)
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.last_ts = stats.last_ts.max(ts);
+            let key = (element, pad.name().to_string());
+            let first = !stats.pads.contains_key(&key)
+                && stats.pads.keys().all(|(element, _)| *element != key.0);
+            stats
+                .pads
+                .entry(key)
+                .or_insert_with(|| PadCounters {
+                    first,
+                    ..PadCounters::default()
+                })
+                .record(ts, buffers, bytes);
+        }
+        PUSHES.with_borrow_mut(|pushes| {
+            pushes.push(Push {
+                element: receiver,
+                start_ns: ts,
+                nested_ns: 0,
+            })
+        });
+    }
+
+    fn finish_push(&self, ts: u64) {
+        let Some(push) = PUSHES.with_borrow_mut(|pushes| {
+            let push = pushes.pop()?;
+            let elapsed = ts.saturating_sub(push.start_ns);
+            if let Some(outer) = pushes.last_mut() {
+                outer.nested_ns += elapsed;
+            }
+            Some(push)
+        }) else {
+            return;
+        };
+        let Some(element) = push.element else { return };
+        if !self.connected.load(Ordering::Relaxed) {
+            return;
+        }
+        let exclusive = ts.saturating_sub(push.start_ns).saturating_sub(push.nested_ns);
+        let mut stats = self.stats.lock().unwrap();
+        stats.last_ts = stats.last_ts.max(ts);
+        let chain = stats.chains.entry(element).or_default();
+        chain.total_ns += exclusive;
+        chain.calls += 1;
+    }
+}
+
+/// Send the samples once per interval until the tracer goes away or the
+/// debugger stops reading.
+fn flush_periodically(
+    stats: Weak<Mutex<Stats>>,
+    connected: Arc<AtomicBool>,
+    socket: Arc<Mutex<UnixStream>>,
+) {
+    loop {
+        thread::sleep(FLUSH_INTERVAL);
+        let Some(stats) = stats.upgrade() else {
+            return;
+        };
+        if !connected.load(Ordering::Relaxed) {
+            return;
+        }
+        if flush(&stats, &socket).is_err() {
+            connected.store(false, Ordering::Relaxed);
+            return;
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DebuggerTracer {
+    const NAME: &'static str = "GstDebuggerTracer";
+    type Type = super::DebuggerTracer;
+    type ParentType = gst::Tracer;
+}
+
+impl ObjectImpl for DebuggerTracer {
+    fn constructed(&self) {
+        self.parent_constructed();
+        if let Err(err) = self.connect() {
+            gst::warning!(CAT, imp: self, "Not tracing: {}", err);
+            return;
+        }
+        self.register_hook(TracerHook::PadPushPre);
+        self.register_hook(TracerHook::PadPushPost);
+        self.register_hook(TracerHook::PadPushListPre);
+        self.register_hook(TracerHook::PadPushListPost);
+    }
+
+    /// Send what was measured since the last flush, e.g. when gst-launch
+    /// deinitializes GStreamer after EOS.
+    fn dispose(&self) {
+        if !self.connected.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        if let Some(socket) = self.socket.get() {
+            let _ = flush(&self.stats, socket);
+        }
+    }
+}
+
+impl GstObjectImpl for DebuggerTracer {}
+
+impl TracerImpl for DebuggerTracer {
+    fn pad_push_pre(&self, ts: u64, pad: &gst::Pad, buffer: &gst::Buffer) {
+        self.record_push(ts, pad, 1, buffer.size() as u64);
+    }
+
+    fn pad_push_post(
+        &self,
+        ts: u64,
+        _pad: &gst::Pad,
+        _result: Result<gst::FlowSuccess, gst::FlowError>,
+    ) {
+        self.finish_push(ts);
+    }
+
+    fn pad_push_list_pre(&self, ts: u64, pad: &gst::Pad, list: &gst::BufferList) {
+        self.record_push(ts, pad, list.len() as u64, list.calculate_size() as u64);
+    }
+
+    fn pad_push_list_post(
+        &self,
+        ts: u64,
+        _pad: &gst::Pad,
+        _result: Result<gst::FlowSuccess, gst::FlowError>,
+    ) {
+        self.finish_push(ts);
+    }
+}
@@ -0,0 +1,7 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the vendored protoc so building does not require one on the system.
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure().compile_protos_with_config(config, &["proto/agent.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,308 @@
+//! HTTP API for polling a running debugger:
+//!
+//! - `GET /elements`: per-element statistics
+//! - `GET /elements/{name}/metrics?since=NS`: statistics and samples of one element
+//! - `GET /alerts`: failing rules
+//! - `GET /rollups`: hourly summaries of every element's metrics
+//! - `GET /topology`: the pipeline's elements and links
+//! - `GET /stream`: WebSocket pushing every sample, latency and pipeline
+//!   event as a JSON text message
+//!
+//! With a token set, every request needs `Authorization: Bearer TOKEN`.
+//! `/stream` also takes `?token=TOKEN`, for browsers, which cannot set
+//! headers on a WebSocket.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use gst_debugger_core::{
+    Aggregate, ElementStats, InterLatencyData, Metric, PipelineDraft, PipelineEvent, RuleSet,
+    TracingData,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::offline::Record;
+use crate::tap::{Outbox, SharedStore, Tap};
+use crate::tls::{self, Security};
+
+/// Messages a slow WebSocket client may fall behind by before it misses some.
+const STREAM_BACKLOG: usize = 1024;
+
+struct ApiState {
+    pipeline: String,
+    rules: RuleSet,
+    store: SharedStore,
+    live: broadcast::Sender<String>,
+}
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    /// Only return samples at or after this pipeline time, in nanoseconds.
+    since: Option<u64>,
+}
+
+fn summaries(stats: &ElementStats) -> Map<String, Value> {
+    Metric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let summary = stats.summary(metric);
+            let avg = summary.get(Aggregate::Avg)?;
+            let value = json!({
+                "count": summary.count,
+                "min": summary.min,
+                "avg": avg,
+                "max": summary.max,
+                "p50": summary.get(Aggregate::P50),
+                "p95": summary.get(Aggregate::P95),
+                "p99": summary.get(Aggregate::P99),
+            });
+            Some((metric.name().to_string(), value))
+        })
+        .collect()
+}
+
+async fn elements(State(state): State<Arc<ApiState>>) -> Json<Value> {
+    let store = state.store.read().unwrap();
+    let elements: Vec<Value> = store
+        .element_stats()
+        .iter()
+        .map(|stats| json!({ "element": stats.element, "metrics": summaries(stats) }))
+        .collect();
+    Json(Value::Array(elements))
+}
+
+async fn element_metrics(
+    State(state): State<Arc<ApiState>>,
+    Path(name): Path<String>,
+    Query(query): Query<SeriesQuery>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let store = state.store.read().unwrap();
+    let stats = store
+        .element_stats()
+        .into_iter()
+        .find(|stats| stats.element == name)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No samples from element `{}`", name)))?;
+
+    let since = query.since.unwrap_or(0);
+    let mut metrics = summaries(&stats);
+    for (metric_name, summary) in metrics.iter_mut() {
+        let Some(metric) = Metric::from_name(metric_name) else {
+            continue;
+        };
+        let series: Vec<Value> = store
+            .series(&name, metric)
+            .into_iter()
+            .filter(|&(timestamp_ns, _)| timestamp_ns >= since)
+            .map(|(timestamp_ns, value)| json!([timestamp_ns, value]))
+            .collect();
+        summary["series"] = Value::Array(series);
+    }
+    Ok(Json(json!({ "element": name, "metrics": metrics })))
+}
+
+async fn alerts(State(state): State<Arc<ApiState>>) -> Json<Value> {
+    let store = state.store.read().unwrap();
+    let alerts: Vec<Value> = state
+        .rules
+        .evaluate(&store)
+        .into_iter()
+        .filter(|result| !result.passed())
+        .map(|result| {
+            json!({
+                "element": result.element,
+                "rule": result.check,
+                "message": result.describe(),
+                "active": result.active,
+                "metric": result.metric.name(),
+                "value": result.value,
+                "threshold": result.threshold,
+                "timestamp_ns": result.timestamp_ns,
+            })
+        })
+        .collect();
+    Json(Value::Array(alerts))
+}
+
+async fn rollups(State(state): State<Arc<ApiState>>) -> Json<Value> {
+    let store = state.store.read().unwrap();
+    let rollups: Vec<Value> = store
+        .rollups()
+        .iter()
+        .map(|rollup| {
+            json!({
+                "start_ns": rollup.start_ns,
+                "element": rollup.element,
+                "metric": rollup.metric.name(),
+                "count": rollup.count,
+                "avg": rollup.avg,
+                "min": rollup.min,
+                "max": rollup.max,
+                "p99": rollup.p99,
+            })
+        })
+        .collect();
+    Json(Value::Array(rollups))
+}
+
+/// The elements and links of the launch description as GStreamer builds
+/// them, so branches after a `tee` and `name.` references keep their links.
+async fn topology(State(state): State<Arc<ApiState>>) -> Json<Value> {
+    let draft = PipelineDraft::parse(&state.pipeline);
+    let elements: Vec<&str> = draft.elements.iter().map(|element| element.name.as_str()).collect();
+    let links: Vec<Value> = draft
+        .links
+        .iter()
+        .map(|link| {
+            json!({
+                "from": elements[link.from],
+                "to": elements[link.to],
+                "from_pad": link.from_pad,
+                "to_pad": link.to_pad,
+            })
+        })
+        .collect();
+    Json(json!({
+        "pipeline": state.pipeline,
+        "elements": elements,
+        "links": links,
+    }))
+}
+
+fn event_json(event: &PipelineEvent) -> Option<Value> {
+    match event {
+        PipelineEvent::Position { .. }
+        | PipelineEvent::Properties { .. }
+        | PipelineEvent::PropertyChanged { .. } => None,
+        event => serde_json::to_value(event).ok(),
+    }
+}
+
+async fn stream(State(state): State<Arc<ApiState>>, upgrade: WebSocketUpgrade) -> Response {
+    let live = state.live.subscribe();
+    upgrade.on_upgrade(|socket| push_live(socket, live))
+}
+
+async fn push_live(mut socket: WebSocket, mut live: broadcast::Receiver<String>) {
+    loop {
+        let text = match live.recv().await {
+            Ok(text) => text,
+            Err(RecvError::Lagged(missed)) => json!({ "kind": "lagged", "missed": missed }).to_string(),
+            Err(RecvError::Closed) => break,
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+async fn require_token(
+    State(token): State<Arc<String>>,
+    Query(query): Query<TokenQuery>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    // Only the WebSocket takes the token in the query, where it is more
+    // likely to end up in logs.
+    let from_query = query
+        .token
+        .filter(|_| request.uri().path() == "/stream")
+        .map(|given| format!("Bearer {}", given));
+    if tls::bearer_matches(header, &token) || tls::bearer_matches(from_query.as_deref(), &token) {
+        next.run(request).await
+    } else {
+        (StatusCode::UNAUTHORIZED, "Missing or wrong token").into_response()
+    }
+}
+
+/// Send `message` to the WebSocket clients, if any are connected.
+fn publish(live: &broadcast::Sender<String>, message: impl FnOnce() -> Option<String>) {
+    if live.receiver_count() > 0
+        && let Some(text) = message()
+    {
+        let _ = live.send(text);
+    }
+}
+
+pub(crate) async fn bind(addr: SocketAddr) -> Result<TcpListener, String> {
+    TcpListener::bind(addr)
+        .await
+        .map_err(|err| format!("Failed to listen on {}: {}", addr, err))
+}
+
+/// Publishes what passes through to the WebSocket clients.
+struct Publishing {
+    live: broadcast::Sender<String>,
+}
+
+impl Tap for Publishing {
+    fn sample(&mut self, sample: &TracingData) {
+        publish(&self.live, || serde_json::to_string(&Record::Sample(sample)).ok());
+    }
+
+    fn latency(&mut self, latency: &InterLatencyData) {
+        publish(&self.live, || serde_json::to_string(&Record::Latency(latency)).ok());
+    }
+
+    fn event(&mut self, event: &PipelineEvent, _out: &mut Outbox) {
+        publish(&self.live, || event_json(event).map(|value| value.to_string()));
+    }
+}
+
+/// Serve the API on `listener` with the metrics in `store`.
+pub(crate) fn tap(
+    listener: TcpListener,
+    security: Security,
+    pipeline: String,
+    rules: RuleSet,
+    store: SharedStore,
+) -> Box<dyn Tap> {
+    let (live, _) = broadcast::channel(STREAM_BACKLOG);
+    let state = Arc::new(ApiState {
+        pipeline,
+        rules,
+        store,
+        live: live.clone(),
+    });
+    let mut app = Router::new()
+        .route("/elements", get(elements))
+        .route("/elements/{name}/metrics", get(element_metrics))
+        .route("/alerts", get(alerts))
+        .route("/rollups", get(rollups))
+        .route("/topology", get(topology))
+        .route("/stream", get(stream))
+        .with_state(state);
+    if let Some(token) = security.token {
+        app = app.layer(middleware::from_fn_with_state(Arc::new(token), require_token));
+    }
+    tokio::spawn(async move {
+        match security.tls {
+            Some(acceptor) => tls::serve(listener, acceptor, app, std::future::pending()).await,
+            None => {
+                if let Err(err) = axum::serve(listener, app).await {
+                    eprintln!("HTTP API stopped: {}", err);
+                }
+            }
+        }
+    });
+    Box::new(Publishing { live })
+}
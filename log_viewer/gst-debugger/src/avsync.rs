@@ -0,0 +1,135 @@
+//! Audio/video sync for `--mode in-process`: how late each sink gets its
+//! buffers compared to their timestamps, and the offset that leaves between
+//! what is seen and what is heard.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gst_debugger_core::AvSync;
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+#[derive(Debug, Default)]
+struct SinkDelays {
+    /// `audio` or `video`, from the caps of the latest buffer.
+    kind: Option<&'static str>,
+    /// Arrival running time minus buffer running time, since the last sample.
+    delays_ns: Vec<i64>,
+}
+
+type Sinks = Arc<Mutex<BTreeMap<String, SinkDelays>>>;
+
+/// Follows the buffers arriving at the audio and video sinks of a pipeline.
+pub(crate) struct AvSyncMonitor {
+    sinks: Sinks,
+    limit: Duration,
+    out_of_sync: bool,
+}
+
+impl AvSyncMonitor {
+    pub(crate) fn new(pipeline: &gst::Pipeline, limit: Duration) -> Self {
+        let sinks: Sinks = Arc::default();
+        for element in pipeline.iterate_recurse().into_iter().flatten() {
+            watch_sink(&element, &sinks);
+        }
+        // Bins such as autovideosink only add their sink once the pipeline
+        // leaves NULL, so the sinks added later are followed too.
+        let added = sinks.clone();
+        pipeline.connect_deep_element_added(move |_, _, element| watch_sink(element, &added));
+        AvSyncMonitor {
+            sinks,
+            limit,
+            out_of_sync: false,
+        }
+    }
+
+    /// The offset over the interval since the previous call, when both an
+    /// audio and a video sink received buffers, with a notice when it
+    /// crossed the limit either way.
+    pub(crate) fn sample(
+        &mut self,
+        pipeline: &gst::Pipeline,
+        timestamp_ns: u64,
+    ) -> Option<(AvSync, Option<String>)> {
+        // Synchronised sinks hold buffers that arrive early until their
+        // time, so only lateness beyond the pipeline latency shifts a stream.
+        let latency_ns = pipeline.latency().map_or(0, |latency| latency.nseconds() as i64);
+        let mut sinks = self.sinks.lock().unwrap();
+        let mut presented = |kind: &str| {
+            let (name, delays) = sinks
+                .iter_mut()
+                .find(|(_, delays)| delays.kind == Some(kind) && !delays.delays_ns.is_empty())?;
+            let total: i64 = delays.delays_ns.iter().map(|delay| (*delay).max(latency_ns)).sum();
+            let average = total / delays.delays_ns.len() as i64;
+            delays.delays_ns.clear();
+            Some((name.clone(), average))
+        };
+        let video = presented("video");
+        let audio = presented("audio");
+        for delays in sinks.values_mut() {
+            delays.delays_ns.clear();
+        }
+        let ((video_sink, video_ns), (audio_sink, audio_ns)) = (video?, audio?);
+
+        let offset_ns = video_ns - audio_ns;
+        let limit_ns = self.limit.as_nanos() as i64;
+        let out_of_sync = offset_ns.abs() > limit_ns;
+        let notice = match (self.out_of_sync, out_of_sync) {
+            (false, true) => Some(format!(
+                "Audio and video are {:.0} ms apart at {} and {} (limit {} ms)",
+                offset_ns as f64 / 1e6,
+                video_sink,
+                audio_sink,
+                self.limit.as_millis()
+            )),
+            (true, false) => Some("Audio and video are back in sync".to_string()),
+            _ => None,
+        };
+        self.out_of_sync = out_of_sync;
+        let sync = AvSync {
+            timestamp_ns,
+            video_sink,
+            audio_sink,
+            offset_ns,
+            limit_ns: limit_ns as u64,
+        };
+        Some((sync, notice))
+    }
+}
+
+/// Follow the buffers arriving at `element` when it is a leaf sink, not a
+/// bin such as autovideosink around one.
+fn watch_sink(element: &gst::Element, sinks: &Sinks) {
+    if !element.element_flags().contains(gst::ElementFlags::SINK) || element.is::<gst::Bin>() {
+        return;
+    }
+    let name = element.name().to_string();
+    for pad in element.sink_pads() {
+        let name = name.clone();
+        let sinks = sinks.clone();
+        pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            if let Some(delay_ns) = buffer_delay(pad, info) {
+                let kind = pad.current_caps().and_then(|caps| {
+                    let media = caps.structure(0)?.name();
+                    ["audio", "video"].into_iter().find(|kind| media.starts_with(kind))
+                });
+                let mut sinks = sinks.lock().unwrap();
+                let delays = sinks.entry(name.clone()).or_default();
+                delays.kind = kind.or(delays.kind);
+                delays.delays_ns.push(delay_ns);
+            }
+            gst::PadProbeReturn::Ok
+        });
+    }
+}
+
+/// How much later than its running time a buffer reached the sink pad.
+fn buffer_delay(pad: &gst::Pad, info: &gst::PadProbeInfo) -> Option<i64> {
+    let pts = info.buffer()?.pts()?;
+    let segment = pad.sticky_event::<gst::event::Segment>(0)?;
+    let segment = segment.segment().downcast_ref::<gst::ClockTime>()?;
+    let buffer_time = segment.to_running_time(pts)?;
+    let now = pad.parent_element()?.current_running_time()?;
+    Some(now.nseconds() as i64 - buffer_time.nseconds() as i64)
+}
@@ -0,0 +1,103 @@
+//! A bug report bundle: one zip with what an issue about a run needs, so
+//! every report comes with the same files: the pipeline, the GStreamer
+//! version and tracers of the target it ran on, the run's statistics and
+//! metadata, the end of its tracer log and a picture of the graph.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::environment::RunEnvironment;
+use crate::logfile::is_archive;
+
+/// How much of the end of the tracer log goes into the bundle.
+const LOG_EXCERPT_BYTES: u64 = 512 * 1024;
+
+pub struct BugReport {
+    pub pipeline: String,
+    pub tracing: String,
+    /// What the target runs, from the launch or the log's metadata.
+    pub environment: Option<RunEnvironment>,
+    /// The statistics of the run so far, as Markdown.
+    pub summary: String,
+    /// The tracer log of the run; its metadata file and metrics log go
+    /// along when they exist. An archived run's archive goes whole.
+    pub log: Option<PathBuf>,
+    /// The graph as PNG.
+    pub graph_png: Option<Vec<u8>>,
+}
+
+impl BugReport {
+    /// Write the bundle to the zip file at `path`.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        self.write_zip(path)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+    }
+
+    fn write_zip(&self, path: &Path) -> zip::result::ZipResult<()> {
+        let pipeline = format!("{}\n\nTracers: {}\n", self.pipeline, self.tracing);
+        let mut entries = vec![
+            ("pipeline.txt".to_string(), pipeline),
+            ("environment.txt".to_string(), environment(self.environment.as_ref())),
+            ("summary.md".to_string(), self.summary.clone()),
+        ];
+        let archive = self.log.as_ref().filter(|log| is_archive(log));
+        if let Some(log) = self.log.as_ref().filter(|log| !is_archive(log) && log.exists()) {
+            entries.push(("log_excerpt.log".to_string(), log_excerpt(log)?));
+            for extension in ["json", "metrics.jsonl"] {
+                if let Ok(text) = std::fs::read_to_string(log.with_extension(extension)) {
+                    entries.push((format!("session.{}", extension), text));
+                }
+            }
+        }
+
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, text) in entries {
+            zip.start_file(name, options)?;
+            zip.write_all(text.as_bytes())?;
+        }
+        // PNG and zstd are compressed already.
+        let stored = options.compression_method(CompressionMethod::Stored);
+        if let Some(png) = &self.graph_png {
+            zip.start_file("graph.png", stored)?;
+            zip.write_all(png)?;
+        }
+        if let Some(archive) = archive {
+            zip.start_file("session.tar.zst", stored)?;
+            io::copy(&mut File::open(archive)?, &mut zip)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// The last lines of the tracer log, from the start of the first whole line
+/// in its last [`LOG_EXCERPT_BYTES`].
+fn log_excerpt(log: &Path) -> io::Result<String> {
+    let mut file = File::open(log)?;
+    let start = file.metadata()?.len().saturating_sub(LOG_EXCERPT_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(match text.split_once('\n') {
+        Some((_, rest)) if start > 0 => rest.to_string(),
+        _ => text.into_owned(),
+    })
+}
+
+/// What the pipeline ran on, as the run probed or recorded it.
+fn environment(environment: Option<&RunEnvironment>) -> String {
+    let Some(environment) = environment else {
+        return "Not recorded for this run\n".to_string();
+    };
+    let mut text = format!("{}\n", environment.describe());
+    if !environment.tracers.is_empty() {
+        text.push_str(&format!("Tracers installed: {}\n", environment.tracers.join(", ")));
+    }
+    text
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use gst_debugger_core::{
+    format_baseline_diff, format_junit, format_stats_table, Baseline, MetricStore, PipelineEvent,
+    PipelineState, RuleResult, RuleSet, TestCase,
+};
+use tokio::time;
+
+use crate::cli::CheckArgs;
+use crate::{prepare, recorded_pipeline, spawn};
+
+/// Run the pipeline for the requested duration and fail if it reports an
+/// error, never produces a metric, violates one of the threshold rules or,
+/// with a tolerance, regresses against its baseline.
+pub(crate) async fn check(args: &CheckArgs) -> Result<(), String> {
+    let rules = match &args.rules {
+        Some(path) => RuleSet::load(path)?,
+        None => RuleSet::default(),
+    };
+    let baseline_path =
+        args.baseline.clone().unwrap_or_else(|| Baseline::path_for(&args.launch.pipeline));
+    if args.save_baseline && is_tracer_log(&baseline_path) {
+        return Err(format!(
+            "--save-baseline writes a baseline file, not the tracer log {}",
+            baseline_path.display()
+        ));
+    }
+    let baseline = (!args.baseline_tolerance.is_empty())
+        .then(|| load_baseline(&baseline_path))
+        .transpose()?;
+    let config = prepare(&args.launch).await?;
+    let (engine, mut output) = spawn(args.launch.pipeline.clone(), config);
+
+    let mut store = MetricStore::default();
+    let mut errors = Vec::new();
+    let deadline = time::sleep(args.duration);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            Some(sample) = output.data.recv() => store.push_sample(sample),
+            Some(latency) = output.latency.recv() => store.push_latency(latency),
+            event = output.events.recv() => match event {
+                Some(PipelineEvent::Error(error)) => errors.push(error.message),
+                Some(PipelineEvent::State(PipelineState::Stopped)) | None => break,
+                Some(_) => {}
+            },
+        }
+    }
+    engine.shutdown().await;
+    while let Ok(sample) = output.data.try_recv() {
+        store.push_sample(sample);
+    }
+    while let Ok(latency) = output.latency.try_recv() {
+        store.push_latency(latency);
+    }
+
+    let stats = store.element_stats();
+    print!("{}", format_stats_table(&stats));
+
+    let received = !store.samples().is_empty() || !store.latencies().is_empty();
+    let mut cases = vec![
+        TestCase {
+            class: "pipeline".to_string(),
+            name: "runs without errors".to_string(),
+            failure: (!errors.is_empty()).then(|| errors.join("\n")),
+        },
+        TestCase {
+            class: "pipeline".to_string(),
+            name: "produces tracer metrics".to_string(),
+            failure: (!received).then(|| "no tracer metrics were received".to_string()),
+        },
+    ];
+    let mut results = rules.evaluate(&store);
+    if let Some(baseline) = &baseline {
+        let compared = baseline.compare(&store, &args.baseline_tolerance);
+        println!();
+        println!("Compared with the baseline in {}:", baseline_path.display());
+        print!("{}", format_baseline_diff(&compared));
+        results.extend(compared);
+    }
+    cases.extend(results.iter().map(TestCase::from));
+    if let Some(path) = &args.junit {
+        write_junit(path, "gst_debugger check", &cases)?;
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("Check failed, the pipeline reported errors:\n{}", errors.join("\n")));
+    }
+    if !received {
+        return Err("Check failed, no tracer metrics were received".to_string());
+    }
+    report_rule_results(&results).map_err(|violations| format!("Check failed, {}", violations))?;
+    // Only a passing run becomes the baseline.
+    if args.save_baseline {
+        Baseline::capture(&args.launch.pipeline, &store).save(&baseline_path)?;
+        println!("Saved the baseline to {}", baseline_path.display());
+    }
+    println!("Check passed");
+    Ok(())
+}
+
+fn is_tracer_log(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "log")
+}
+
+/// A saved baseline, or one taken from a recorded tracer log of a good run.
+fn load_baseline(path: &Path) -> Result<Baseline, String> {
+    if !is_tracer_log(path) {
+        return Baseline::load(path);
+    }
+    let store = MetricStore::load_log(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    Ok(Baseline::capture(&recorded_pipeline(path).unwrap_or_default(), &store))
+}
+
+/// Print how many rule checks passed. Returns the violations as the error.
+pub(crate) fn report_rule_results(results: &[RuleResult]) -> Result<(), String> {
+    let violations: Vec<String> = results
+        .iter()
+        .filter(|result| !result.passed())
+        .map(|result| result.describe())
+        .collect();
+    if !results.is_empty() {
+        println!();
+        println!("{} of {} rule checks passed", results.len() - violations.len(), results.len());
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} rule check(s) violated:\n{}",
+        violations.len(),
+        violations.join("\n")
+    ))
+}
+
+pub(crate) fn write_junit(path: &Path, suite: &str, cases: &[TestCase]) -> Result<(), String> {
+    std::fs::write(path, format_junit(suite, cases))
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+}
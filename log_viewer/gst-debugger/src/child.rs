@@ -0,0 +1,183 @@
+use gst_debugger_core::{parse_vm_rss, PipelineEvent};
+use tokio::process::{Child, Command};
+use tokio::time;
+
+use crate::target::{Target, PID_PREFIX};
+use crate::{LaunchConfig, SHUTDOWN_TIMEOUT};
+
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Stop,
+    Cont,
+    Int,
+    Term,
+    Usr1,
+}
+
+impl Signal {
+    fn name(self) -> &'static str {
+        match self {
+            Signal::Stop => "STOP",
+            Signal::Cont => "CONT",
+            Signal::Int => "INT",
+            Signal::Term => "TERM",
+            Signal::Usr1 => "USR1",
+        }
+    }
+}
+
+/// The gst-launch process. On a remote target `child` is only the local
+/// client, e.g. `docker exec`, so signals go to the PID the wrapper reports.
+pub(crate) struct PipelineChild {
+    pub child: Child,
+    target: Target,
+    remote_pid: Option<u32>,
+}
+
+pub(crate) fn unsupported_on_platform(action: &str) -> PipelineEvent {
+    PipelineEvent::Notice(format!(
+        "{} a gst-launch child is not supported on this platform",
+        action
+    ))
+}
+
+impl PipelineChild {
+    pub(crate) fn new(child: Child, target: Target) -> Self {
+        PipelineChild {
+            child,
+            target,
+            remote_pid: None,
+        }
+    }
+
+    /// Pick up the PID a remote target reports. Returns true for that line,
+    /// which is not pipeline output.
+    pub(crate) fn observe(&mut self, line: &str) -> bool {
+        if self.target.is_local() || self.remote_pid.is_some() {
+            return false;
+        }
+        match line.trim().strip_prefix(PID_PREFIX).map(str::parse) {
+            Some(Ok(pid)) => {
+                self.remote_pid = Some(pid);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn signal(&self, signal: Signal) -> bool {
+        if self.target.is_local() {
+            return signal_child(&self.child, signal);
+        }
+        match self.remote_pid {
+            Some(pid) => self.target.signal(pid, signal.name()).await,
+            None => false,
+        }
+    }
+
+    pub(crate) async fn pause(&self) -> bool {
+        self.signal(Signal::Stop).await
+    }
+
+    pub(crate) async fn resume(&self) -> bool {
+        self.signal(Signal::Cont).await
+    }
+
+    pub(crate) async fn interrupt(&self) -> bool {
+        self.resume().await;
+        self.signal(Signal::Int).await
+    }
+
+    /// Have the leaks tracer log every live object. Only safe once the
+    /// tracer installed its handler, as SIGUSR1 ends the process otherwise:
+    /// the caller waits for the first tracer record to be logged.
+    pub(crate) async fn dump_live_objects(&self) -> bool {
+        self.signal(Signal::Usr1).await
+    }
+
+    /// The resident set of a local child, where `/proc` has it.
+    pub(crate) fn rss_bytes(&self) -> Option<u64> {
+        if !self.target.is_local() {
+            return None;
+        }
+        let status = std::fs::read_to_string(format!("/proc/{}/status", self.child.id()?)).ok()?;
+        parse_vm_rss(&status)
+    }
+
+    pub(crate) async fn shutdown(&mut self) {
+        if self.interrupt().await
+            && time::timeout(SHUTDOWN_TIMEOUT, self.child.wait()).await.is_ok()
+        {
+            return;
+        }
+
+        if self.signal(Signal::Term).await
+            && time::timeout(SHUTDOWN_TIMEOUT, self.child.wait()).await.is_ok()
+        {
+            return;
+        }
+
+        let _ = self.child.kill().await;
+    }
+}
+
+/// Apply the requested nice level and CPU affinity in the child between fork
+/// and exec, so every thread gst-launch creates inherits them.
+#[cfg(unix)]
+pub(crate) fn set_scheduling(command: &mut Command, config: &LaunchConfig) {
+    if config.nice.is_none() && config.cpus.is_empty() {
+        return;
+    }
+
+    let nice = config.nice;
+    #[cfg(target_os = "linux")]
+    let cpus = (!config.cpus.is_empty()).then(|| {
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // `CPU_SET` panics past the end of the set.
+        for &cpu in config.cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        set
+    });
+
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(nice) = nice
+                && libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(set) = &cpus
+                && libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(unix)]
+fn signal_child(child: &Child, signal: Signal) -> bool {
+    let signal = match signal {
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Cont => libc::SIGCONT,
+        Signal::Int => libc::SIGINT,
+        Signal::Term => libc::SIGTERM,
+        Signal::Usr1 => libc::SIGUSR1,
+    };
+    match child.id() {
+        Some(pid) => unsafe { libc::kill(pid as libc::pid_t, signal) == 0 },
+        None => false,
+    }
+}
+
+// Without POSIX signals a local child can only be killed outright.
+#[cfg(not(unix))]
+pub(crate) fn set_scheduling(_command: &mut Command, _config: &LaunchConfig) {}
+
+#[cfg(not(unix))]
+fn signal_child(_child: &Child, _signal: Signal) -> bool {
+    false
+}
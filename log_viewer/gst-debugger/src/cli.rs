@@ -0,0 +1,706 @@
+use std::io::{self, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use clap::{Parser, Subcommand, ValueEnum};
+use gst_debugger_core::{
+    format_caps_mismatch, format_clock_time, format_stats_table, MetricStore, PipelineEvent,
+    PipelineState, RuleSet, Tolerance, TracingData,
+};
+use tokio::net::TcpListener;
+use tokio::time;
+
+use crate::email::EmailConfig;
+use crate::environment::RunEnvironment;
+use crate::notify::{self, Notifier, SummaryReport};
+use crate::script::Script;
+use crate::tap::{self, SharedStore, Tap};
+use crate::tls::{self, Security, SecurityArgs};
+use crate::{
+    api, check, daemon, intake, offline, prepare, remote, replay, spawn, EngineHandle,
+    EngineOutput, LaunchArgs, ListenAddr,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "gst_debugger")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: CliCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Launch a pipeline and watch its metrics live
+    Run(Box<RunArgs>),
+    /// Play back a recorded tracer log as if the pipeline were running
+    Replay(ReplayArgs),
+    /// Watch a pipeline that gst-debugger-agent runs on another device
+    Connect(ConnectArgs),
+    /// Watch GST_DEBUG output that a device forwards over UDP or TCP
+    Receive(ReceiveArgs),
+    /// Print per-element statistics for a recorded tracer log
+    Analyze(AnalyzeArgs),
+    /// Convert a recorded tracer log to another format
+    Export(ExportArgs),
+    /// Run a pipeline for a fixed time and fail if it does not stay healthy
+    Check(Box<CheckArgs>),
+    /// Send a command to a debugger running with --daemon
+    Ctl(CtlArgs),
+}
+
+/// How `run` and `replay` display the metrics.
+#[derive(clap::Args, Debug, Clone)]
+pub struct ViewArgs {
+    /// Print a refreshed statistics table to the terminal instead of opening
+    /// a window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Show the graph, statistics and raw log in an interactive terminal UI
+    /// instead of opening a window
+    #[arg(long, conflicts_with = "headless")]
+    pub tui: bool,
+
+    /// Run in the background without a viewer and take commands from
+    /// `gst_debugger ctl` over a Unix domain socket
+    #[arg(long, conflicts_with_all = ["headless", "tui"])]
+    pub daemon: bool,
+
+    /// Control socket of --daemon [default: $XDG_RUNTIME_DIR/gst_debugger.sock]
+    #[arg(long, default_value_os_t = daemon::default_socket(), hide_default_value = true)]
+    pub socket: PathBuf,
+
+    /// Serve the live metrics, alerts and topology as JSON over HTTP on this
+    /// address, e.g. `127.0.0.1:8080`
+    #[arg(long, value_name = "ADDR")]
+    pub api: Option<SocketAddr>,
+
+    #[command(flatten)]
+    pub api_security: SecurityArgs,
+
+    /// TOML file of per-element threshold rules used to highlight values
+    /// and raise alerts
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+
+    /// POST a JSON payload to this URL whenever a rule starts failing
+    #[arg(long, value_name = "URL", requires = "rules")]
+    pub webhook: Option<String>,
+
+    /// Show a desktop notification when a rule starts failing or the
+    /// pipeline reports an error
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Write a Markdown summary of the run to this file periodically and
+    /// whenever the pipeline reports an error
+    #[arg(long, value_name = "FILE")]
+    pub summary_file: Option<PathBuf>,
+
+    /// TOML file of SMTP settings for mailing the periodic summary and
+    /// pipeline errors
+    #[arg(long, value_name = "FILE")]
+    pub email: Option<PathBuf>,
+
+    /// How often to write or mail the summary
+    #[arg(long, default_value = "1h", value_parser = parse_duration_arg)]
+    pub summary_every: Duration,
+
+    /// Rhai script with `on_tick(metrics)` and `on_event(metrics, event)`
+    /// callbacks for custom analysis
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+
+    /// Seconds between headless table refreshes
+    #[arg(long, default_value = "2s", value_parser = parse_duration_arg)]
+    pub interval: Duration,
+
+    /// Keep raw samples for this long and older ones only as their hourly
+    /// rollups, for runs that go on for days
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub keep_raw: Option<Duration>,
+}
+
+impl ViewArgs {
+    /// `--keep-raw` in nanoseconds, as the metric store takes it.
+    pub fn raw_retention_ns(&self) -> Option<u64> {
+        self.keep_raw.map(|keep| keep.as_nanos() as u64)
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub launch: LaunchArgs,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReplayArgs {
+    /// Tracer log written by a previous run
+    pub log: PathBuf,
+
+    /// Pipeline description used to draw the graph; read from the log's
+    /// metadata file when omitted
+    #[arg(short, long)]
+    pub pipeline: Option<String>,
+
+    /// Playback speed relative to the original timing, 0 for as fast as possible
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConnectArgs {
+    /// Address of the agent, e.g. `board.local:50051`, or
+    /// `https://board.local:50051` when it serves over TLS
+    #[arg(value_name = "HOST:PORT")]
+    pub agent: String,
+
+    /// PEM certificate to trust for an `https://` agent, e.g. a lab's
+    /// self-signed one
+    #[arg(long, value_name = "FILE")]
+    pub tls_ca: Option<PathBuf>,
+
+    /// File holding the token the agent was started with. Without it, the
+    /// token comes from GST_DEBUGGER_TOKEN
+    #[arg(long, value_name = "FILE")]
+    pub agent_token_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReceiveArgs {
+    /// Address to take the forwarded output on, e.g. `udp://0.0.0.0:5555`
+    /// or `tcp://0.0.0.0:5555`
+    #[arg(long, value_name = "URL")]
+    pub listen: ListenAddr,
+
+    /// Pipeline description used to draw the graph
+    #[arg(short, long, default_value = "")]
+    pub pipeline: String,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AnalyzeArgs {
+    /// Tracer log written by a previous run
+    pub log: PathBuf,
+
+    /// TOML file of threshold rules to evaluate against the log
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+
+    /// Write the rule results as JUnit XML to this file
+    #[arg(long, requires = "rules")]
+    pub junit: Option<PathBuf>,
+
+    /// Write a self-contained HTML report with the graph, statistics,
+    /// latency charts and rule violations to this file
+    #[arg(long)]
+    pub html: Option<PathBuf>,
+
+    /// Write threshold rules drawn from this log, taken as a good run, to
+    /// this TOML file for --rules of later runs
+    #[arg(long, value_name = "FILE")]
+    pub suggest_rules: Option<PathBuf>,
+
+    /// Print the bottleneck report as text or as JSON for scripts
+    #[arg(short, long, value_enum, default_value_t = AnalyzeFormat::Text)]
+    pub format: AnalyzeFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AnalyzeFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    /// Summary for pasting into an issue report
+    Markdown,
+    /// Processing time per element as folded stacks for flamegraph tools
+    Folded,
+    /// Processing time per element as a flamegraph SVG
+    Flamegraph,
+    /// Processing and scheduling time over time as a speedscope profile
+    Speedscope,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ExportArgs {
+    /// Tracer log written by a previous run
+    pub log: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+    pub format: ExportFormat,
+
+    /// Output file, stdout when omitted
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// TOML file of threshold rules whose violations the Markdown summary
+    /// lists
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    pub launch: LaunchArgs,
+
+    /// How long to run the pipeline, e.g. `60s` or `5m`
+    #[arg(long, default_value = "30s", value_parser = parse_duration_arg)]
+    pub duration: Duration,
+
+    /// TOML file of threshold rules the metrics must satisfy
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+
+    /// Write the results as JUnit XML to this file
+    #[arg(long)]
+    pub junit: Option<PathBuf>,
+
+    /// Fail when an element's average gets worse than in the pipeline's
+    /// saved baseline by more than this many percent: `10` for every metric
+    /// or `proctime=25` for one. Repeat it to only gate on some metrics or
+    /// to give them their own tolerance
+    #[arg(long, value_name = "[METRIC=]PERCENT", value_parser = Tolerance::parse)]
+    pub baseline_tolerance: Vec<Tolerance>,
+
+    /// Save this run's statistics as the pipeline's baseline
+    #[arg(long)]
+    pub save_baseline: bool,
+
+    /// Baseline file to compare against or save to, instead of the
+    /// pipeline's default one. A recorded tracer log (`.log`) of a good run
+    /// works as a baseline too
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CtlArgs {
+    /// Control socket of the daemon [default: $XDG_RUNTIME_DIR/gst_debugger.sock]
+    #[arg(long, default_value_os_t = daemon::default_socket(), hide_default_value = true)]
+    pub socket: PathBuf,
+
+    /// `status`, `stats`, `alerts`, `rules [load FILE | add CONDITION | clear]`,
+    /// `annotate TEXT`, `play`, `pause`, `restart`, `eos`, `stop` or `help`
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+pub fn parse_duration_arg(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a duration like `60s`, got `{}`", value))?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(number),
+        "" | "s" => Duration::from_secs(number),
+        "m" => Duration::from_secs(number * 60),
+        "h" => Duration::from_secs(number * 3600),
+        _ => return Err(format!("unknown duration unit `{}` in `{}`", unit, value)),
+    };
+    // Every duration option is a period or limit, which zero would turn
+    // into a busy loop or a panic.
+    if duration.is_zero() {
+        return Err(format!("expected a duration longer than zero, got `{}`", value));
+    }
+    Ok(duration)
+}
+
+/// Parse a size such as `500MB`, `2GB`, `64KB` or a bare number of bytes.
+pub fn parse_size_arg(value: &str) -> Result<u64, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a size like `500MB`, got `{}`", value))?;
+    match unit {
+        "" | "B" => Ok(number),
+        "KB" => Ok(number << 10),
+        "MB" => Ok(number << 20),
+        "GB" => Ok(number << 30),
+        _ => Err(format!("unknown size unit `{}` in `{}`", unit, value)),
+    }
+}
+
+/// A live pipeline or a replayed log whose metrics a viewer displays.
+pub struct Session {
+    pub pipeline: String,
+    pub tracing: String,
+    pub restart_on_failure: bool,
+    pub view: ViewArgs,
+    pub rules: RuleSet,
+    pub engine: EngineHandle,
+    pub output: EngineOutput,
+    /// Wall-clock time at which the timestamps of a replayed log start,
+    /// from its metadata file.
+    pub started: Option<DateTime<Local>>,
+    /// What the pipeline runs on, as probed at launch or recorded in the
+    /// replayed log's metadata.
+    pub environment: Option<RunEnvironment>,
+}
+
+/// Everything the view options load before the engine starts, so a bad
+/// rules file or busy port fails before a pipeline is launched.
+struct ViewSetup {
+    /// The store the script, notifier and API share.
+    store: SharedStore,
+    rules: RuleSet,
+    script: Option<Script>,
+    summary: Option<SummaryReport>,
+    api: Option<(TcpListener, Security)>,
+}
+
+impl ViewSetup {
+    async fn load(view: &ViewArgs) -> Result<Self, String> {
+        let mut store = MetricStore::default();
+        store.set_raw_retention(view.raw_retention_ns());
+        let store = Arc::new(RwLock::new(store));
+        Ok(ViewSetup {
+            rules: load_rules(view)?,
+            script: load_script(view, &store)?,
+            store,
+            summary: load_summary(view)?,
+            api: bind_api(view).await?,
+        })
+    }
+
+    /// Attach the script, notifier and API to the engine's output.
+    fn into_session(
+        self,
+        view: ViewArgs,
+        pipeline: String,
+        tracing: String,
+        restart_on_failure: bool,
+        engine: EngineHandle,
+        output: EngineOutput,
+    ) -> Session {
+        let mut taps: Vec<Box<dyn Tap>> = Vec::new();
+        if let Some(script) = self.script {
+            taps.push(Box::new(script));
+        }
+        if let Some(notifier) = notifier(&view, &pipeline, &self.rules, self.summary) {
+            taps.push(notify::tap(notifier, self.store.clone()));
+        }
+        if let Some((listener, security)) = self.api {
+            let (pipeline, rules) = (pipeline.clone(), self.rules.clone());
+            taps.push(api::tap(listener, security, pipeline, rules, self.store.clone()));
+        }
+        let output = tap::attach(taps, self.store, output);
+        Session {
+            pipeline,
+            tracing,
+            restart_on_failure,
+            view,
+            rules: self.rules,
+            engine,
+            output,
+            started: None,
+            environment: None,
+        }
+    }
+}
+
+/// Execute `command`. Subcommands that need no viewer complete here and
+/// return `None`; `run`, `replay`, `connect` and `receive` return the
+/// session to display.
+pub async fn start(command: CliCommand) -> Result<Option<Session>, String> {
+    match command {
+        CliCommand::Run(args) => {
+            let RunArgs { launch, view } = *args;
+            let setup = ViewSetup::load(&view).await?;
+            let config = prepare(&launch).await?;
+            let restart_on_failure = config.restart_on_failure;
+            let environment = config.environment.clone();
+            let (engine, output) = spawn(launch.pipeline.clone(), config);
+            let mut session = setup.into_session(
+                view,
+                launch.pipeline,
+                launch.tracing,
+                restart_on_failure,
+                engine,
+                output,
+            );
+            session.environment = environment;
+            Ok(Some(session))
+        }
+        CliCommand::Replay(args) => {
+            let setup = ViewSetup::load(&args.view).await?;
+            let pipeline = replay::replay_pipeline(&args);
+            let (engine, output) = replay::spawn_replay(&args);
+            let started = offline::recorded_start(&args.log);
+            let mut session = setup.into_session(
+                args.view,
+                pipeline,
+                String::new(),
+                false,
+                engine,
+                output,
+            );
+            session.started = started;
+            session.environment = offline::recorded_environment(&args.log);
+            Ok(Some(session))
+        }
+        CliCommand::Connect(args) => {
+            let ConnectArgs {
+                agent,
+                tls_ca,
+                agent_token_file,
+                view,
+            } = args;
+            let setup = ViewSetup::load(&view).await?;
+            let agent_token = tls::load_token(agent_token_file.as_deref())?;
+            let (info, engine, output) =
+                remote::connect(&agent, tls_ca.as_deref(), agent_token).await?;
+            Ok(Some(setup.into_session(
+                view,
+                info.pipeline,
+                info.tracing,
+                info.restart_on_failure,
+                engine,
+                output,
+            )))
+        }
+        CliCommand::Receive(ReceiveArgs { listen, pipeline, view }) => {
+            let setup = ViewSetup::load(&view).await?;
+            let (engine, output) = intake::spawn_listener(&listen).await?;
+            Ok(Some(setup.into_session(
+                view,
+                pipeline,
+                String::new(),
+                false,
+                engine,
+                output,
+            )))
+        }
+        CliCommand::Analyze(args) => offline::analyze(&args).map(|_| None),
+        CliCommand::Export(args) => offline::export(&args).map(|_| None),
+        CliCommand::Check(args) => check::check(&args).await.map(|_| None),
+        CliCommand::Ctl(args) => ctl(&args).await.map(|_| None),
+    }
+}
+
+async fn ctl(args: &CtlArgs) -> Result<(), String> {
+    let reply = daemon::send_command(&args.socket, &args.command.join(" ")).await?;
+    match reply.strip_prefix("error: ") {
+        Some(error) => Err(error.trim_end().to_string()),
+        None => {
+            print!("{}", reply);
+            Ok(())
+        }
+    }
+}
+
+fn load_rules(view: &ViewArgs) -> Result<RuleSet, String> {
+    match &view.rules {
+        Some(path) => RuleSet::load(path),
+        None => Ok(RuleSet::default()),
+    }
+}
+
+fn load_script(view: &ViewArgs, store: &SharedStore) -> Result<Option<Script>, String> {
+    let load = |path: &Path| Script::load(path, store.clone());
+    view.script.as_deref().map(load).transpose()
+}
+
+fn load_summary(view: &ViewArgs) -> Result<Option<SummaryReport>, String> {
+    if view.summary_file.is_none() && view.email.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(SummaryReport {
+        every: view.summary_every,
+        file: view.summary_file.clone(),
+        email: view.email.as_deref().map(EmailConfig::load).transpose()?,
+    }))
+}
+
+fn notifier(
+    view: &ViewArgs,
+    pipeline: &str,
+    rules: &RuleSet,
+    summary: Option<SummaryReport>,
+) -> Option<Notifier> {
+    if view.webhook.is_none() && !view.notify && summary.is_none() {
+        return None;
+    }
+    Some(Notifier {
+        pipeline: pipeline.to_string(),
+        rules: rules.clone(),
+        webhook: view.webhook.clone(),
+        desktop: view.notify,
+        summary,
+    })
+}
+
+async fn bind_api(view: &ViewArgs) -> Result<Option<(TcpListener, Security)>, String> {
+    let Some(addr) = view.api else {
+        return Ok(None);
+    };
+    let security = Security::load(&view.api_security)?;
+    Ok(Some((api::bind(addr).await?, security)))
+}
+
+fn format_sample(sample: &TracingData) -> String {
+    let mut line = format!("{} {}", format_clock_time(sample.timestamp_ns), sample.element);
+    if let Some(bitrate) = sample.bitrate {
+        line.push_str(&format!(" bitrate={}", bitrate));
+    }
+    if let Some(framerate) = sample.framerate {
+        line.push_str(&format!(" fps={:.1}", framerate));
+    }
+    if let Some(proctime) = sample.proctime_ns {
+        line.push_str(&format!(" proctime={}ns", proctime));
+    }
+    if let Some(jitter) = sample.jitter_ns {
+        line.push_str(&format!(" jitter={}ns", jitter));
+    }
+    line
+}
+
+/// Print every sample to stdout and pipeline events to stderr until the
+/// pipeline stops or Ctrl+C is pressed.
+pub async fn print_stream(session: Session) {
+    let Session {
+        engine,
+        mut output,
+        restart_on_failure,
+        ..
+    } = session;
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            Some(sample) = output.data.recv() => println!("{}", format_sample(&sample)),
+            Some(latency) = output.latency.recv() => println!(
+                "{} {} -> {} interlatency={}",
+                format_clock_time(latency.timestamp_ns),
+                latency.from,
+                latency.to,
+                latency.time
+            ),
+            event = output.events.recv() => match event {
+                Some(PipelineEvent::State(PipelineState::Stopped)) if !restart_on_failure => break,
+                Some(PipelineEvent::Error(error)) => {
+                    eprintln!("Error: {}", error.message);
+                    if !error.debug.is_empty() {
+                        eprintln!("{}", error.debug);
+                    }
+                    if let Some(caps) = &error.caps {
+                        eprint!("{}", format_caps_mismatch(caps));
+                    }
+                }
+                Some(PipelineEvent::Notice(message)) => eprintln!("{}", message),
+                Some(PipelineEvent::Restarting { attempt, delay }) => {
+                    eprintln!("Restarting in {:.1}s (attempt {})", delay.as_secs_f32(), attempt);
+                }
+                Some(_) => {}
+                None => break,
+            },
+        }
+    }
+
+    engine.shutdown().await;
+}
+
+/// Print the statistics of the samples received in each `interval` as a
+/// table, redrawn in place on a terminal, until the pipeline stops or Ctrl+C
+/// is pressed.
+pub async fn print_summaries(session: Session) {
+    let Session {
+        engine,
+        mut output,
+        restart_on_failure,
+        view,
+        rules,
+        ..
+    } = session;
+
+    let redraw = io::stdout().is_terminal();
+    let started = Instant::now();
+    let mut state = PipelineState::Playing;
+    let mut window = MetricStore::default();
+    let mut ticker = time::interval(view.interval);
+    ticker.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = ticker.tick() => {
+                print_summary(&window, &rules, state, started, view.interval, redraw);
+                window.clear();
+            }
+            Some(sample) = output.data.recv() => window.push_sample(sample),
+            Some(latency) = output.latency.recv() => window.push_latency(latency),
+            event = output.events.recv() => match event {
+                Some(PipelineEvent::State(PipelineState::Stopped)) if !restart_on_failure => break,
+                Some(PipelineEvent::State(new_state)) => state = new_state,
+                Some(PipelineEvent::Error(error)) => eprintln!("Error: {}", error.message),
+                Some(PipelineEvent::Notice(message)) => eprintln!("{}", message),
+                Some(PipelineEvent::Restarting { attempt, delay }) => {
+                    state = PipelineState::Restarting;
+                    eprintln!("Restarting in {:.1}s (attempt {})", delay.as_secs_f32(), attempt);
+                }
+                Some(_) => {}
+                None => break,
+            },
+        }
+    }
+
+    if !window.samples().is_empty() || !window.latencies().is_empty() {
+        print_summary(&window, &rules, state, started, view.interval, redraw);
+    }
+    engine.shutdown().await;
+}
+
+fn print_summary(
+    window: &MetricStore,
+    rules: &RuleSet,
+    state: PipelineState,
+    started: Instant,
+    interval: Duration,
+    redraw: bool,
+) {
+    let stats = window.element_stats();
+    let mut out = io::stdout().lock();
+    if redraw {
+        let _ = write!(out, "\x1b[2J\x1b[H");
+    }
+    let _ = writeln!(
+        out,
+        "{} | {:?} | last {:.0}s",
+        format_clock_time(started.elapsed().as_nanos() as u64),
+        state,
+        interval.as_secs_f32()
+    );
+    if stats.is_empty() {
+        let _ = writeln!(out, "No samples received");
+    } else {
+        let _ = write!(out, "{}", format_stats_table(&stats));
+    }
+    for alert in rules.evaluate(window).iter().filter(|result| !result.passed()) {
+        let _ = writeln!(out, "ALERT {}", alert.describe());
+    }
+    let _ = writeln!(out);
+    let _ = out.flush();
+}
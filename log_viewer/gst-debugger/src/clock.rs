@@ -0,0 +1,99 @@
+//! The clock a `--mode in-process` pipeline selected, and how its running
+//! time drifts from the monotonic system time.
+
+use std::time::Instant;
+
+use gst_debugger_core::ClockStats;
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+
+/// What kind of clock `clock` is, from the first of its types GStreamer
+/// ships a known clock for.
+fn clock_kind(clock: &gst::Clock) -> String {
+    let mut type_ = clock.type_();
+    while type_.is_valid() && type_ != gst::Clock::static_type() {
+        let kind = match type_.name() {
+            "GstSystemClock" => Some("system"),
+            "GstAudioClock" => Some("audio"),
+            "GstPtpClock" => Some("PTP"),
+            "GstNtpClock" => Some("NTP"),
+            "GstNetClientClock" => Some("network"),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            return kind.to_string();
+        }
+        type_ = type_.parent().unwrap_or(glib::Type::INVALID);
+    }
+    clock.type_().name().to_string()
+}
+
+/// The element whose clock the pipeline uses, if any provides it.
+fn clock_provider(pipeline: &gst::Pipeline, clock: &gst::Clock) -> Option<String> {
+    pipeline
+        .iterate_recurse()
+        .into_iter()
+        .flatten()
+        .filter(|element| element.element_flags().contains(gst::ElementFlags::PROVIDE_CLOCK))
+        .find(|element| element.provide_clock().as_ref() == Some(clock))
+        .map(|element| element.name().to_string())
+}
+
+/// Where the drift is measured from: the clock, its running time and the
+/// system time when it was selected or the pipeline started playing.
+struct Baseline {
+    clock: String,
+    running_time_ns: u64,
+    at: Instant,
+}
+
+#[derive(Default)]
+pub(crate) struct ClockTracker {
+    baseline: Option<Baseline>,
+}
+
+impl ClockTracker {
+    pub(crate) fn stats(
+        &mut self,
+        pipeline: &gst::Pipeline,
+        timestamp_ns: u64,
+        playing: bool,
+    ) -> Option<ClockStats> {
+        let clock = pipeline.clock()?;
+        let name = clock.name().to_string();
+        let running_time_ns = clock
+            .time()
+            .zip(pipeline.base_time())
+            .map_or(0, |(time, base_time)| time.nseconds().saturating_sub(base_time.nseconds()));
+
+        // Running time stands still while paused, so the drift starts over
+        // once the pipeline plays again.
+        if !playing {
+            self.baseline = None;
+        }
+        let now = Instant::now();
+        if self.baseline.as_ref().is_none_or(|baseline| baseline.clock != name) {
+            self.baseline = Some(Baseline {
+                clock: name.clone(),
+                running_time_ns,
+                at: now,
+            });
+        }
+        let baseline = self.baseline.as_ref()?;
+        let elapsed_ns = now.duration_since(baseline.at).as_nanos() as i64;
+        let drift_ns = (running_time_ns as i64 - baseline.running_time_ns as i64) - elapsed_ns;
+
+        let (internal, external, rate_num, rate_denom) = clock.calibration();
+        Some(ClockStats {
+            timestamp_ns,
+            kind: clock_kind(&clock),
+            provider: clock_provider(pipeline, &clock),
+            name,
+            rate: rate_num.nseconds() as f64 / rate_denom.nseconds().max(1) as f64,
+            offset_ns: external.nseconds() as i64 - internal.nseconds() as i64,
+            running_time_ns,
+            drift_ns,
+        })
+    }
+}
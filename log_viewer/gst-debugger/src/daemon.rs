@@ -0,0 +1,334 @@
+//! `--daemon` mode: run headless and take commands over a Unix domain socket.
+//! Each connection sends one command line and reads the reply until the
+//! daemon closes it; replies to rejected commands start with `error: `.
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::time::Instant;
+
+#[cfg(unix)]
+use gst_debugger_core::{
+    format_clock_time, format_rollups, format_stats_table, AlertRule, Annotation, MetricStore,
+    PipelineCommand, PipelineEvent, PipelineState, RuleSet,
+};
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use tokio::sync::{mpsc, oneshot};
+
+use crate::cli::Session;
+
+/// The control socket in the user's runtime directory, which only they can
+/// reach, or in the temporary directory where there is none.
+pub fn default_socket() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(std::env::temp_dir)
+        .join("gst_debugger.sock")
+}
+
+#[cfg(unix)]
+const HELP: &str = "\
+status                 pipeline state, uptime and alert count
+stats                  per-element statistics since the start
+rollups                hourly summaries of every element's metrics
+alerts                 rules that are failing
+rules                  list the threshold and alert rules
+rules load FILE        replace the rules with those in a TOML file
+rules add CONDITION    add an alert, e.g. `rules add fps(sink) < 25 for 5s`
+rules clear            remove every rule
+annotate TEXT          mark the timeline of the log, e.g. `annotate switched to WiFi`
+play | pause | restart | eos
+stop                   stop the pipeline and the daemon
+";
+
+#[cfg(unix)]
+type Request = (String, oneshot::Sender<String>);
+
+#[cfg(unix)]
+struct Daemon {
+    store: MetricStore,
+    rules: RuleSet,
+    state: PipelineState,
+    last_error: Option<String>,
+    started: Instant,
+    control: mpsc::Sender<PipelineCommand>,
+}
+
+#[cfg(unix)]
+enum Reply {
+    Text(String),
+    Stop,
+}
+
+#[cfg(unix)]
+impl Daemon {
+    async fn handle(&mut self, line: &str) -> Reply {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        let control = match command {
+            "status" => return Reply::Text(self.status()),
+            "stats" => {
+                let stats = self.store.element_stats();
+                return Reply::Text(if stats.is_empty() {
+                    "No samples received\n".to_string()
+                } else {
+                    format_stats_table(&stats)
+                });
+            }
+            "rollups" => {
+                let rollups = self.store.rollups();
+                return Reply::Text(if rollups.is_empty() {
+                    "No hour has passed yet\n".to_string()
+                } else {
+                    format_rollups(rollups)
+                });
+            }
+            "alerts" => {
+                let mut reply = String::new();
+                for alert in self.rules.evaluate(&self.store).iter().filter(|result| !result.passed()) {
+                    reply.push_str(&format!("{}\n", alert.describe()));
+                }
+                if reply.is_empty() {
+                    reply.push_str("No alerts\n");
+                }
+                return Reply::Text(reply);
+            }
+            "rules" => return Reply::Text(self.change_rules(argument)),
+            "annotate" if argument.is_empty() => {
+                return Reply::Text("error: `annotate` needs the marker text\n".to_string());
+            }
+            "annotate" => PipelineCommand::Annotate(Annotation {
+                timestamp_ns: self.store.latest_timestamp(),
+                text: argument.to_string(),
+            }),
+            "play" => PipelineCommand::Play,
+            "pause" => PipelineCommand::Pause,
+            "restart" => PipelineCommand::Restart,
+            "eos" => PipelineCommand::SendEos,
+            "stop" => return Reply::Stop,
+            "help" | "" => return Reply::Text(HELP.to_string()),
+            _ => return Reply::Text(format!("error: unknown command `{}`, try `help`\n", command)),
+        };
+        let _ = self.control.send(control).await;
+        Reply::Text("ok\n".to_string())
+    }
+
+    fn status(&self) -> String {
+        let results = self.rules.evaluate(&self.store);
+        let failing = results.iter().filter(|result| !result.passed()).count();
+        let active = results.iter().filter(|result| result.active).count();
+        let mut status = format!(
+            "{:?} | up {} | {} samples, {} latencies | {} alerts, {} active\n",
+            self.state,
+            format_clock_time(self.started.elapsed().as_nanos() as u64),
+            self.store.samples().len(),
+            self.store.latencies().len(),
+            failing,
+            active
+        );
+        if let Some(error) = &self.last_error {
+            status.push_str(&format!("Last error: {}\n", error));
+        }
+        status
+    }
+
+    fn change_rules(&mut self, argument: &str) -> String {
+        let (action, value) = argument.split_once(' ').unwrap_or((argument, ""));
+        match action {
+            "" => {
+                let mut reply = String::new();
+                for rule in &self.rules.rules {
+                    let element = rule.element.as_deref().unwrap_or("*");
+                    for limit in rule.limits() {
+                        reply.push_str(&format!(
+                            "{}: {} {}\n",
+                            element,
+                            rule.aggregate.label(),
+                            limit.describe()
+                        ));
+                    }
+                }
+                for alert in &self.rules.alerts {
+                    reply.push_str(&format!("{}\n", alert.source));
+                }
+                if let Some(anomaly) = &self.rules.anomaly {
+                    reply.push_str(&format!("anomaly: {}\n", anomaly.describe()));
+                }
+                if let Some(trend) = &self.rules.trend {
+                    reply.push_str(&format!("trend: {}\n", trend.describe()));
+                }
+                if let Some(stall) = &self.rules.stall {
+                    reply.push_str(&format!("stall: {}\n", stall.describe()));
+                }
+                if reply.is_empty() {
+                    reply.push_str("No rules\n");
+                }
+                reply
+            }
+            "load" => match RuleSet::load(Path::new(value.trim())) {
+                Ok(rules) => {
+                    self.rules = rules;
+                    "ok\n".to_string()
+                }
+                Err(err) => format!("error: {}\n", err),
+            },
+            "add" => match value.parse::<AlertRule>() {
+                Ok(alert) => {
+                    self.rules.alerts.push(alert);
+                    "ok\n".to_string()
+                }
+                Err(err) => format!("error: {}\n", err),
+            },
+            "clear" => {
+                self.rules = RuleSet::default();
+                "ok\n".to_string()
+            }
+            _ => format!("error: unknown rules action `{}`\n", action),
+        }
+    }
+}
+
+/// Read one command from a client, pass it to the daemon and write back the
+/// reply.
+#[cfg(unix)]
+async fn serve_client(stream: UnixStream, requests: mpsc::Sender<Request>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    if BufReader::new(reader).read_line(&mut line).await.is_err() {
+        return;
+    }
+    let (reply_tx, reply_rx) = oneshot::channel();
+    if requests.send((line, reply_tx)).await.is_err() {
+        return;
+    }
+    if let Ok(reply) = reply_rx.await {
+        let _ = writer.write_all(reply.as_bytes()).await;
+        let _ = writer.shutdown().await;
+    }
+}
+
+/// Bind the control socket, replacing a stale one left by a daemon that did
+/// not shut down cleanly. Only the user running the daemon may connect to
+/// it, since its commands control the pipeline.
+#[cfg(unix)]
+async fn bind(socket: &Path) -> Result<UnixListener, String> {
+    if socket.exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(format!("A daemon is already listening on {}", socket.display()));
+        }
+        let _ = std::fs::remove_file(socket);
+    }
+    let listener = UnixListener::bind(socket)
+        .map_err(|err| format!("Failed to bind {}: {}", socket.display(), err))?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+        .map_err(|err| format!("Failed to restrict {}: {}", socket.display(), err))?;
+    Ok(listener)
+}
+
+/// Run the session without a viewer, answering commands on `socket` until
+/// a client sends `stop`, the pipeline stops or Ctrl+C is pressed.
+#[cfg(unix)]
+pub async fn serve(session: Session, socket: &Path) -> Result<(), String> {
+    let Session {
+        engine,
+        mut output,
+        restart_on_failure,
+        rules,
+        view,
+        ..
+    } = session;
+
+    let listener = bind(socket).await?;
+    eprintln!("Listening for commands on {}", socket.display());
+
+    let (request_tx, mut request_rx) = mpsc::channel::<Request>(16);
+    let mut store = MetricStore::default();
+    store.set_raw_retention(view.raw_retention_ns());
+    let mut daemon = Daemon {
+        store,
+        rules,
+        state: PipelineState::Playing,
+        last_error: None,
+        started: Instant::now(),
+        control: engine.control(),
+    };
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            accepted = listener.accept() => {
+                if let Ok((stream, _)) = accepted {
+                    tokio::spawn(serve_client(stream, request_tx.clone()));
+                }
+            }
+            Some((line, reply_tx)) = request_rx.recv() => match daemon.handle(&line).await {
+                Reply::Text(reply) => {
+                    let _ = reply_tx.send(reply);
+                }
+                Reply::Stop => {
+                    let _ = reply_tx.send("stopping\n".to_string());
+                    break;
+                }
+            },
+            Some(sample) = output.data.recv() => daemon.store.push_sample(sample),
+            Some(latency) = output.latency.recv() => daemon.store.push_latency(latency),
+            Some(_) = output.log.recv() => {}
+            event = output.events.recv() => match event {
+                Some(PipelineEvent::State(PipelineState::Stopped)) if !restart_on_failure => break,
+                Some(PipelineEvent::State(state)) => daemon.state = state,
+                Some(PipelineEvent::Error(error)) => {
+                    eprintln!("Error: {}", error.message);
+                    daemon.last_error = Some(error.message);
+                }
+                Some(PipelineEvent::Notice(message)) => eprintln!("{}", message),
+                Some(PipelineEvent::Restarting { attempt, delay }) => {
+                    daemon.state = PipelineState::Restarting;
+                    eprintln!("Restarting in {:.1}s (attempt {})", delay.as_secs_f32(), attempt);
+                }
+                Some(_) => {}
+                None => break,
+            },
+        }
+    }
+
+    drop(listener);
+    let _ = std::fs::remove_file(socket);
+    engine.shutdown().await;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn serve(session: Session, _socket: &Path) -> Result<(), String> {
+    session.engine.shutdown().await;
+    Err("--daemon needs Unix domain sockets and is not supported on this platform".to_string())
+}
+
+/// Send one command to a running daemon and return its reply.
+#[cfg(unix)]
+pub(crate) async fn send_command(socket: &Path, command: &str) -> Result<String, String> {
+    let mut stream = UnixStream::connect(socket)
+        .await
+        .map_err(|err| format!("No daemon listening on {}: {}", socket.display(), err))?;
+    stream
+        .write_all(format!("{}\n", command).as_bytes())
+        .await
+        .map_err(|err| format!("Failed to send command: {}", err))?;
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .await
+        .map_err(|err| format!("Failed to read reply: {}", err))?;
+    Ok(reply)
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn send_command(_socket: &Path, _command: &str) -> Result<String, String> {
+    Err("ctl needs Unix domain sockets and is not supported on this platform".to_string())
+}
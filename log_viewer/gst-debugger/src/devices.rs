@@ -0,0 +1,65 @@
+//! Capture devices for the GUI's "New pipeline" dialog, found with
+//! GstDeviceMonitor.
+
+use gst_debugger_core::CaptureDevice;
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+
+/// Properties that only identify the element, not the device.
+const SKIPPED_PROPERTIES: [&str; 2] = ["name", "parent"];
+
+/// Every video and audio source GStreamer can capture from.
+pub fn capture_devices() -> Result<Vec<CaptureDevice>, String> {
+    gst::init().map_err(|err| format!("Failed to initialize GStreamer: {}", err))?;
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Video/Source"), None);
+    monitor.add_filter(Some("Audio/Source"), None);
+    monitor
+        .start()
+        .map_err(|err| format!("Failed to start the device monitor: {}", err))?;
+    let devices = monitor.devices();
+    monitor.stop();
+
+    Ok(devices
+        .into_iter()
+        .filter_map(|device| {
+            Some(CaptureDevice {
+                name: device.display_name().to_string(),
+                class: device.device_class().to_string(),
+                source: source_description(&device)?,
+            })
+        })
+        .collect())
+}
+
+/// The element the device creates, written as it would appear in a
+/// gst-launch line: the factory and every property that differs from its
+/// default.
+fn source_description(device: &gst::Device) -> Option<String> {
+    let element = device.create_element(None).ok()?;
+    let mut description = element.factory()?.name().to_string();
+    for pspec in element.list_properties() {
+        let name = pspec.name();
+        if !pspec.flags().contains(glib::ParamFlags::READWRITE)
+            || SKIPPED_PROPERTIES.contains(&name)
+        {
+            continue;
+        }
+        let Ok(value) = element.property_value(name).serialize() else {
+            continue;
+        };
+        if pspec.default_value().serialize().ok().as_ref() == Some(&value) {
+            continue;
+        }
+        description.push(' ');
+        description.push_str(name);
+        description.push('=');
+        if value.contains(|c: char| c.is_whitespace() || c == '!' || c == '"') {
+            description.push_str(&format!("\"{}\"", value.replace('"', "\\\"")));
+        } else {
+            description.push_str(&value);
+        }
+    }
+    Some(description)
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+
+/// SMTP settings read from a TOML file:
+///
+/// ```toml
+/// host = "smtp.example.com"
+/// username = "ci"
+/// password = "secret"
+/// from = "gst_debugger <ci@example.com>"
+/// to = ["oncall@example.com"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EmailConfig {
+    host: String,
+    /// Defaults to 587 with STARTTLS, or 465 when `tls` is `"implicit"`.
+    port: Option<u16>,
+    #[serde(default)]
+    tls: TlsMode,
+    username: Option<String>,
+    password: Option<String>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TlsMode {
+    #[default]
+    Starttls,
+    Implicit,
+    /// Plain text, only for a relay on the local network.
+    None,
+}
+
+impl EmailConfig {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let config: EmailConfig = toml::from_str(&text)
+            .map_err(|err| format!("Invalid email settings in {}: {}", path.display(), err))?;
+        if config.to.is_empty() {
+            return Err(format!("No recipients in {}", path.display()));
+        }
+        Ok(config)
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+        let mut builder = match self.tls {
+            TlsMode::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host),
+            TlsMode::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host),
+            TlsMode::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &self.host,
+            )),
+        }
+        .map_err(|err| format!("Invalid SMTP host {}: {}", self.host, err))?;
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(builder.build())
+    }
+
+    /// Send a plain-text message to every recipient.
+    pub(crate) async fn send(&self, subject: &str, body: String) -> Result<(), String> {
+        let mut message = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|err| format!("Failed to build email: {}", err))?;
+        self.transport()?
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|err| format!("Failed to send email via {}: {}", self.host, err))
+    }
+}
@@ -0,0 +1,115 @@
+//! What a run's metadata records about where it ran: the GStreamer version
+//! and tracers installed on the target and the machine itself, so a replayed
+//! or shared log can be read with them in mind.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+use crate::LaunchConfig;
+
+/// How long one probe of the target may take before it is left out.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunEnvironment {
+    /// The version `gst-launch-1.0 --version` reports, e.g. `1.22.0`.
+    pub gstreamer: Option<String>,
+    /// The tracers gst-inspect lists, such as `latency` or `proctime`.
+    pub tracers: Vec<String>,
+    /// Kernel name, release and machine, as `uname -srm` prints them.
+    pub os: Option<String>,
+    pub distribution: Option<String>,
+    pub cpu: Option<String>,
+}
+
+impl RunEnvironment {
+    /// Ask the target of `config` what it runs, all at once. `tracers` is
+    /// what [`installed_tracers`] found. Probes that fail, such as
+    /// `/proc/cpuinfo` off Linux, leave their field empty.
+    pub async fn probe(config: &LaunchConfig, tracers: Vec<String>) -> Self {
+        let (launch_version, os, release, cpuinfo) = tokio::join!(
+            probe(config, &config.gst_launch, &["--version"]),
+            probe(config, "uname", &["-srm"]),
+            probe(config, "cat", &["/etc/os-release"]),
+            probe(config, "cat", &["/proc/cpuinfo"]),
+        );
+        let gstreamer = launch_version.as_deref().and_then(|output| {
+            let line = output.lines().find(|line| line.starts_with("GStreamer "))?;
+            Some(line.trim_start_matches("GStreamer ").trim().to_string())
+        });
+        let os = os.map(|output| output.trim().to_string());
+        let distribution = release.and_then(|output| {
+            let name = output.lines().find_map(|line| line.strip_prefix("PRETTY_NAME="))?;
+            Some(name.trim_matches('"').to_string())
+        });
+        let cpu = cpuinfo.and_then(|output| {
+            ["model name", "Hardware", "Processor"].into_iter().find_map(|key| {
+                output.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    (name.trim() == key).then(|| value.trim().to_string())
+                })
+            })
+        });
+        RunEnvironment {
+            gstreamer,
+            tracers,
+            os,
+            distribution,
+            cpu,
+        }
+    }
+
+    /// One line for a report, e.g. `GStreamer 1.22.0 on Linux 6.1.0 x86_64
+    /// (Debian GNU/Linux 12, Intel(R) Core(TM) i7-8700)`.
+    pub fn describe(&self) -> String {
+        let mut text = format!("GStreamer {}", self.gstreamer.as_deref().unwrap_or("unknown"));
+        if let Some(os) = &self.os {
+            text.push_str(&format!(" on {}", os));
+        }
+        let details: Vec<&str> =
+            [&self.distribution, &self.cpu].into_iter().flatten().map(String::as_str).collect();
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        text
+    }
+}
+
+/// The tracers gst-inspect lists on the target, `None` when it cannot be
+/// run there.
+pub(crate) async fn installed_tracers(
+    gst_inspect: &str,
+    config: &LaunchConfig,
+) -> Option<Vec<String>> {
+    let output = probe(config, gst_inspect, &[]).await?;
+    Some(output.lines().filter_map(parse_tracer_feature).map(str::to_string).collect())
+}
+
+/// The tracer name of a gst-inspect feature line such as
+/// `coretracers:  latency (GstTracerFactory)`.
+fn parse_tracer_feature(line: &str) -> Option<&str> {
+    let feature = line.strip_suffix(" (GstTracerFactory)")?;
+    let (_, name) = feature.split_once(':')?;
+    Some(name.trim())
+}
+
+/// The output of `program` run with `args` on the target, if it succeeded
+/// in time.
+async fn probe(config: &LaunchConfig, program: &str, args: &[&str]) -> Option<String> {
+    let mut command = config.target.command(
+        program,
+        args.iter().map(|arg| arg.to_string()),
+        &config.env,
+    );
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = time::timeout(PROBE_TIMEOUT, output).await.ok()?.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,595 @@
+//! Runs the pipeline inside the debugger (`--mode in-process`) and measures
+//! buffer flow with pad probes, so the core metrics do not depend on GstShark
+//! being installed. Owning the pipeline also gives the viewers its bus, state
+//! control, position and seeking, and live property editing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use gstreamer_app as gst_app;
+use tokio::sync::mpsc;
+use tokio::time;
+
+use gst_debugger_core::{
+    inject_level, inject_preview, inject_recording, inter_arrival_jitter, pad_sample_name,
+    split_pipeline_args, AudioLevel, BusMessage, BusMessageKind, ElementProperty, PipelineCommand,
+    PipelineError, PipelineEvent, PipelineState, TracingData, VideoFrame, PREVIEW_SINK,
+};
+
+use crate::avsync::AvSyncMonitor;
+use crate::clock::ClockTracker;
+use crate::latency;
+use crate::negotiation;
+use crate::rtp;
+use crate::rtsp::RtspMonitor;
+use crate::runner::RunOutcome;
+use crate::segments::SegmentTracker;
+use crate::sinks::SinkFrameTracker;
+use crate::streams;
+use crate::webrtc::{self, StatsTracker};
+use crate::{LaunchConfig, MetricSenders};
+
+const METRIC_INTERVAL: Duration = Duration::from_secs(1);
+
+type Counters = Arc<Mutex<HashMap<(String, String), PadCounters>>>;
+
+#[derive(Debug, Default)]
+struct PadCounters {
+    /// Whether this was the first source pad of its element to carry a
+    /// buffer.
+    first: bool,
+    buffers: u64,
+    bytes: u64,
+    last_arrival: Option<Instant>,
+    intervals_ns: Vec<u64>,
+}
+
+impl PadCounters {
+    fn record(&mut self, buffers: u64, bytes: u64) {
+        let now = Instant::now();
+        if let Some(last) = self.last_arrival {
+            self.intervals_ns.push(now.duration_since(last).as_nanos() as u64);
+        }
+        self.last_arrival = Some(now);
+        self.buffers += buffers;
+        self.bytes += bytes;
+    }
+
+    /// Turn the counts gathered since the previous sample into rates and
+    /// reset them for the next interval.
+    fn take_sample(&mut self, element: &str, elapsed: Duration, timestamp_ns: u64) -> TracingData {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let sample = TracingData {
+            timestamp_ns,
+            element: element.to_string(),
+            bitrate: Some((self.bytes as f64 * 8.0 / seconds) as u64),
+            framerate: Some(self.buffers as f64 / seconds),
+            proctime_ns: None,
+            jitter_ns: inter_arrival_jitter(&self.intervals_ns),
+        };
+        self.buffers = 0;
+        self.bytes = 0;
+        self.intervals_ns.clear();
+        sample
+    }
+}
+
+fn attach_probe(element_name: &str, pad: &gst::Pad, counters: &Counters) {
+    if pad.direction() != gst::PadDirection::Src {
+        return;
+    }
+
+    let key = (element_name.to_string(), pad.name().to_string());
+    let counters = counters.clone();
+    pad.add_probe(
+        gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+        move |_, info| {
+            let (buffers, bytes) = match &info.data {
+                Some(gst::PadProbeData::Buffer(buffer)) => (1, buffer.size()),
+                Some(gst::PadProbeData::BufferList(list)) => (list.len(), list.calculate_size()),
+                _ => (0, 0),
+            };
+            if buffers > 0 {
+                let mut pads = counters.lock().unwrap();
+                let first = !pads.contains_key(&key)
+                    && pads.keys().all(|(element, _)| *element != key.0);
+                pads.entry(key.clone())
+                    .or_insert_with(|| PadCounters {
+                        first,
+                        ..PadCounters::default()
+                    })
+                    .record(buffers as u64, bytes as u64);
+            }
+            gst::PadProbeReturn::Ok
+        },
+    );
+}
+
+/// Probe every source pad in the pipeline, including pads that appear later
+/// (e.g. from demuxers and decodebin).
+fn attach_probes(pipeline: &gst::Pipeline, counters: &Counters) {
+    for element in pipeline.iterate_recurse().into_iter().flatten() {
+        let element_name = element.name().to_string();
+        for pad in element.src_pads() {
+            attach_probe(&element_name, &pad, counters);
+        }
+
+        let counters = counters.clone();
+        element.connect_pad_added(move |element, pad| {
+            attach_probe(&element.name(), pad, &counters);
+        });
+    }
+}
+
+pub(crate) fn init() -> Result<(), glib::Error> {
+    gst::init()
+}
+
+fn property_string(element: &gst::Element, name: &str) -> String {
+    let value = element.property_value(name);
+    match value.serialize() {
+        Ok(text) => text.to_string(),
+        Err(_) => format!("{:?}", value),
+    }
+}
+
+fn element_properties(element: &gst::Element) -> Vec<ElementProperty> {
+    element
+        .list_properties()
+        .iter()
+        .filter(|pspec| pspec.flags().contains(glib::ParamFlags::READABLE))
+        .map(|pspec| ElementProperty {
+            name: pspec.name().to_string(),
+            type_name: pspec.value_type().name().to_string(),
+            value: property_string(element, pspec.name()),
+            writable: pspec.flags().contains(glib::ParamFlags::WRITABLE),
+        })
+        .collect()
+}
+
+fn find_element(pipeline: &gst::Pipeline, name: &str) -> Result<gst::Element, String> {
+    pipeline
+        .by_name(name)
+        .ok_or_else(|| format!("No element named `{}` in the pipeline", name))
+}
+
+/// Set `name` on `element` from its string form, the way gst-launch parses
+/// property values, and return the value the element ended up with.
+fn set_property(
+    pipeline: &gst::Pipeline,
+    element: &str,
+    name: &str,
+    value: &str,
+) -> Result<String, String> {
+    let element = find_element(pipeline, element)?;
+    let pspec = element
+        .find_property(name)
+        .ok_or_else(|| format!("`{}` has no property `{}`", element.name(), name))?;
+    if !pspec.flags().contains(glib::ParamFlags::WRITABLE) {
+        return Err(format!("`{}.{}` is read-only", element.name(), name));
+    }
+    // Setting a value the property does not accept panics, so it is checked
+    // against the property's type and range first.
+    let invalid = || format!("`{}` is not a valid value for `{}.{}`", value, element.name(), name);
+    let parsed = glib::Value::deserialize_with_pspec(value, &pspec).map_err(|_| invalid())?;
+    if !pspec.value_is_valid(&parsed) {
+        return Err(invalid());
+    }
+    element.set_property_from_value(name, &parsed);
+    Ok(property_string(&element, name))
+}
+
+fn pipeline_state(state: gst::State) -> Option<PipelineState> {
+    match state {
+        gst::State::Playing => Some(PipelineState::Playing),
+        gst::State::Paused => Some(PipelineState::Paused),
+        _ => None,
+    }
+}
+
+/// Map a bus message to the event the viewers show, if any. Errors and EOS
+/// end the run and are handled by the caller.
+/// The timeline entry for `message`, if it is one of the kinds that help
+/// explain changes in the metrics.
+fn bus_message(message: &gst::Message, timestamp_ns: u64) -> Option<BusMessage> {
+    let (kind, detail) = match message.view() {
+        gst::MessageView::StateChanged(changed) => (
+            BusMessageKind::StateChanged,
+            format!("{:?} -> {:?}", changed.old(), changed.current()),
+        ),
+        gst::MessageView::NewClock(clock) => (
+            BusMessageKind::NewClock,
+            clock.clock().map(|clock| clock.name().to_string()).unwrap_or_default(),
+        ),
+        gst::MessageView::ClockLost(_) => (BusMessageKind::ClockLost, String::new()),
+        gst::MessageView::Tag(tag) => (BusMessageKind::Tag, tag.tags().to_string()),
+        gst::MessageView::Buffering(buffering) => {
+            (BusMessageKind::Buffering, format!("{}%", buffering.percent()))
+        }
+        gst::MessageView::Latency(_) => (BusMessageKind::Latency, String::new()),
+        gst::MessageView::Warning(warning) => {
+            (BusMessageKind::Warning, warning.error().to_string())
+        }
+        gst::MessageView::Eos(_) => (BusMessageKind::Eos, String::new()),
+        _ => return None,
+    };
+    Some(BusMessage {
+        timestamp_ns,
+        kind,
+        source: message.src().map(|src| src.name().to_string()).unwrap_or_default(),
+        detail,
+    })
+}
+
+/// The levels in a `level` element's message, one value per channel.
+fn audio_level(message: &gst::Message, timestamp_ns: u64) -> Option<AudioLevel> {
+    let gst::MessageView::Element(element) = message.view() else {
+        return None;
+    };
+    let structure = element.structure().filter(|s| s.name() == "level")?;
+    let channels = |field: &str| -> Option<Vec<f64>> {
+        let values = structure.get::<glib::ValueArray>(field).ok()?;
+        Some(values.iter().filter_map(|value| value.get::<f64>().ok()).collect())
+    };
+    Some(AudioLevel {
+        timestamp_ns,
+        element: message.src().map(|src| src.name().to_string()).unwrap_or_default(),
+        rms_db: channels("rms")?,
+        peak_db: channels("peak")?,
+    })
+}
+
+fn bus_event(pipeline: &gst::Pipeline, message: &gst::Message) -> Option<PipelineEvent> {
+    let source = message.src().map(|src| src.name().to_string());
+    match message.view() {
+        gst::MessageView::StateChanged(changed)
+            if message.src() == Some(pipeline.upcast_ref::<gst::Object>()) =>
+        {
+            pipeline_state(changed.current()).map(PipelineEvent::State)
+        }
+        gst::MessageView::Warning(warning) => Some(PipelineEvent::Notice(format!(
+            "Warning from {}: {}",
+            source.unwrap_or_default(),
+            warning.error()
+        ))),
+        gst::MessageView::ClockLost(_) => {
+            // Picking a new clock needs a round trip through PAUSED.
+            let _ = pipeline.set_state(gst::State::Paused);
+            let _ = pipeline.set_state(gst::State::Playing);
+            Some(PipelineEvent::Notice("Clock lost, selected a new one".to_string()))
+        }
+        // Redistribute the latency when an element's changes, as gst-launch
+        // does.
+        gst::MessageView::Latency(_) => {
+            let _ = pipeline.recalculate_latency();
+            None
+        }
+        _ => None,
+    }
+}
+
+fn launch(pipeline: &str, config: &LaunchConfig) -> Result<gst::Pipeline, String> {
+    let mut args = split_pipeline_args(pipeline);
+    if config.levels {
+        args = inject_level(args);
+    }
+    if config.preview {
+        args = inject_preview(args);
+    }
+    if let Some(recording) = &config.record {
+        args = inject_recording(args, recording);
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    gst::parse_launchv(&args)
+        .map_err(|err| err.to_string())?
+        .downcast::<gst::Pipeline>()
+        .map_err(|_| "The pipeline description must contain more than one element".to_string())
+}
+
+/// Copy the RGBA frame out of a sample from the preview appsink.
+fn video_frame(sample: &gst::Sample) -> Option<VideoFrame> {
+    let structure = sample.caps()?.structure(0)?;
+    let width = structure.get::<i32>("width").ok()? as u32;
+    let height = structure.get::<i32>("height").ok()? as u32;
+    let map = sample.buffer()?.map_readable().ok()?;
+    // RGBA rows are a multiple of four bytes, so they carry no padding.
+    let rgba = map.as_slice().get(..(width * height * 4) as usize)?.to_vec();
+    Some(VideoFrame { width, height, rgba })
+}
+
+fn attach_preview(pipeline: &gst::Pipeline, senders: &MetricSenders) {
+    let Some(sink) = pipeline
+        .by_name(PREVIEW_SINK)
+        .and_then(|sink| sink.downcast::<gst_app::AppSink>().ok())
+    else {
+        return;
+    };
+    let preview = senders.preview.clone();
+    sink.set_callbacks(
+        gst_app::AppSinkCallbacks::builder()
+            .new_sample(move |sink| {
+                let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                if let Some(frame) = video_frame(&sample) {
+                    preview.send_replace(Some(Arc::new(frame)));
+                }
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+}
+
+pub(crate) async fn run_pipeline(
+    pipeline: &str,
+    config: &mut LaunchConfig,
+    time_base_ns: &mut u64,
+    senders: &MetricSenders,
+    control_rx: &mut mpsc::Receiver<PipelineCommand>,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> RunOutcome {
+    let gst_pipeline = match launch(pipeline, config) {
+        Ok(gst_pipeline) => gst_pipeline,
+        Err(message) => {
+            let _ = event_tx
+                .send(PipelineEvent::Error(PipelineError {
+                    message: format!("Failed to build pipeline: {}", message),
+                    debug: pipeline.to_string(),
+                    element: None,
+                    recent_lines: Vec::new(),
+                    caps: None,
+                }))
+                .await;
+            let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+            return RunOutcome::Failed;
+        }
+    };
+
+    let counters = Counters::default();
+    attach_probes(&gst_pipeline, &counters);
+    attach_preview(&gst_pipeline, senders);
+    let webrtcbins = webrtc::webrtcbins(&gst_pipeline);
+    let (stats_tx, mut stats_rx) = mpsc::unbounded_channel();
+    let mut stats_tracker = StatsTracker::default();
+    let mut rtsp = RtspMonitor::new(&gst_pipeline);
+    let mut clock = ClockTracker::default();
+    let mut av_sync = AvSyncMonitor::new(&gst_pipeline, config.av_sync_limit);
+    let mut sink_frames = SinkFrameTracker::new(config.drop_rate_limit);
+    let mut segments = SegmentTracker::default();
+
+    let bus = gst_pipeline.bus().expect("Pipeline without a bus");
+    let mut messages = bus.stream();
+
+    if let Err(err) = gst_pipeline.set_state(gst::State::Playing) {
+        let _ = gst_pipeline.set_state(gst::State::Null);
+        let _ = event_tx
+            .send(PipelineEvent::Error(PipelineError {
+                message: format!("Failed to start pipeline: {}", err),
+                debug: pipeline.to_string(),
+                element: None,
+                recent_lines: Vec::new(),
+                caps: None,
+            }))
+            .await;
+        let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+        return RunOutcome::Failed;
+    }
+
+    let base_ns = *time_base_ns;
+    let started = Instant::now();
+    let mut last_sample = started;
+    let mut ticker = time::interval(METRIC_INTERVAL);
+    ticker.tick().await;
+
+    let mut error = None;
+    let mut next = None;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let now = Instant::now();
+                let elapsed = now.duration_since(last_sample);
+                last_sample = now;
+                let timestamp_ns = base_ns + now.duration_since(started).as_nanos() as u64;
+
+                let samples: Vec<TracingData> = {
+                    let mut pads = counters.lock().unwrap();
+                    pads.iter_mut()
+                        .map(|((element, pad_name), pad)| {
+                            let (buffers, bytes) = (pad.buffers, pad.bytes);
+                            rtsp.record(element, pad_name, buffers, bytes, pad.last_arrival);
+                            let name = pad_sample_name(element, pad_name, pad.first);
+                            pad.take_sample(&name, elapsed, timestamp_ns)
+                        })
+                        .collect()
+                };
+                for sample in samples {
+                    let _ = senders.data.send(sample).await;
+                }
+
+                if let (Some(position), Some(duration)) = (
+                    gst_pipeline.query_position::<gst::ClockTime>(),
+                    gst_pipeline.query_duration::<gst::ClockTime>(),
+                ) {
+                    let _ = event_tx
+                        .send(PipelineEvent::Position {
+                            position_ns: position.nseconds(),
+                            duration_ns: duration.nseconds(),
+                        })
+                        .await;
+                }
+
+                for jitterbuffer in rtp::elements_of(&gst_pipeline, "rtpjitterbuffer") {
+                    let stats = rtp::jitter_buffer_stats(&jitterbuffer, timestamp_ns);
+                    let _ = event_tx.send(PipelineEvent::JitterBuffer(stats)).await;
+                }
+                let playing = gst_pipeline.current_state() == gst::State::Playing;
+                for (stats, notice) in rtsp.stats(elapsed, timestamp_ns, playing) {
+                    if let Some(notice) = notice {
+                        let _ = event_tx.send(PipelineEvent::Notice(notice)).await;
+                    }
+                    let _ = event_tx.send(PipelineEvent::Rtsp(stats)).await;
+                }
+                if let Some(stats) = clock.stats(&gst_pipeline, timestamp_ns, playing) {
+                    let _ = event_tx.send(PipelineEvent::Clock(stats)).await;
+                }
+                if let Some((sync, notice)) = av_sync.sample(&gst_pipeline, timestamp_ns) {
+                    if let Some(notice) = notice {
+                        let _ = event_tx.send(PipelineEvent::Notice(notice)).await;
+                    }
+                    let _ = event_tx.send(PipelineEvent::AvSync(sync)).await;
+                }
+                for (frames, notice) in sink_frames.stats(&gst_pipeline, timestamp_ns) {
+                    if let Some(notice) = notice {
+                        let _ = event_tx.send(PipelineEvent::Notice(notice)).await;
+                    }
+                    let _ = event_tx.send(PipelineEvent::SinkFrames(frames)).await;
+                }
+                if let Some(report) = latency::latency_report(&gst_pipeline, timestamp_ns) {
+                    let _ = event_tx.send(PipelineEvent::Latency(report)).await;
+                }
+                for session in rtp::elements_of(&gst_pipeline, "rtpsession") {
+                    let stats = rtp::rtp_session_stats(&session, timestamp_ns);
+                    let _ = event_tx.send(PipelineEvent::RtpSession(stats)).await;
+                }
+                for webrtcbin in &webrtcbins {
+                    webrtc::request_stats(webrtcbin, &stats_tx);
+                }
+            }
+            Some((element, stats)) = stats_rx.recv() => {
+                let timestamp_ns = base_ns + started.elapsed().as_nanos() as u64;
+                let stats = stats_tracker.condense(element, &stats, timestamp_ns);
+                let _ = event_tx.send(PipelineEvent::WebRtcStats(stats)).await;
+            }
+            message = messages.next() => {
+                let Some(message) = message else { break };
+                let timestamp_ns = base_ns + started.elapsed().as_nanos() as u64;
+                if let Some(bus) = bus_message(&message, timestamp_ns) {
+                    let _ = event_tx.send(PipelineEvent::Bus(bus)).await;
+                }
+                if let Some(level) = audio_level(&message, timestamp_ns) {
+                    let _ = event_tx.send(PipelineEvent::AudioLevel(level)).await;
+                }
+                if let Some(collection) = streams::stream_collection(&message, timestamp_ns) {
+                    let _ = event_tx.send(PipelineEvent::Streams(collection)).await;
+                }
+                if let Some(segment) = segments.segment(&message, timestamp_ns) {
+                    let _ = event_tx.send(PipelineEvent::Segment(segment)).await;
+                }
+                rtsp.progress(&message);
+                match message.view() {
+                    gst::MessageView::Eos(_) => break,
+                    gst::MessageView::Error(err) => {
+                        let debug = err.debug().map(|debug| debug.to_string()).unwrap_or_default();
+                        let element = err.src().map(|src| src.name().to_string());
+                        let caps = if negotiation::is_not_negotiated(&debug) {
+                            negotiation::caps_mismatch(&gst_pipeline, element.as_deref())
+                        } else {
+                            None
+                        };
+                        error = Some(PipelineError {
+                            message: err.error().to_string(),
+                            debug,
+                            element,
+                            recent_lines: Vec::new(),
+                            caps,
+                        });
+                        break;
+                    }
+                    _ => {
+                        if let Some(event) = bus_event(&gst_pipeline, &message) {
+                            let _ = event_tx.send(event).await;
+                        }
+                    }
+                }
+            }
+            // State changes are reported from the bus once they complete.
+            command = control_rx.recv() => match command {
+                Some(PipelineCommand::Play) => {
+                    let _ = gst_pipeline.set_state(gst::State::Playing);
+                }
+                Some(PipelineCommand::Pause) => {
+                    let _ = gst_pipeline.set_state(gst::State::Paused);
+                }
+                Some(PipelineCommand::SendEos) => {
+                    gst_pipeline.send_event(gst::event::Eos::new());
+                }
+                Some(PipelineCommand::Seek(position_ns)) => {
+                    let flags = gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT;
+                    let position = gst::ClockTime::from_nseconds(position_ns);
+                    if let Err(err) = gst_pipeline.seek_simple(flags, position) {
+                        let _ = event_tx
+                            .send(PipelineEvent::Notice(format!("Seek failed: {}", err)))
+                            .await;
+                    }
+                }
+                Some(PipelineCommand::QueryProperties(element)) => {
+                    let event = match find_element(&gst_pipeline, &element) {
+                        Ok(found) => PipelineEvent::Properties {
+                            properties: element_properties(&found),
+                            element,
+                        },
+                        Err(message) => PipelineEvent::Notice(message),
+                    };
+                    let _ = event_tx.send(event).await;
+                }
+                Some(PipelineCommand::SetProperty { element, name, value }) => {
+                    let event = match set_property(&gst_pipeline, &element, &name, &value) {
+                        Ok(value) => PipelineEvent::PropertyChanged { element, name, value },
+                        Err(message) => PipelineEvent::Notice(message),
+                    };
+                    let _ = event_tx.send(event).await;
+                }
+                Some(PipelineCommand::SelectStreams { element, stream_ids }) => {
+                    let selected = find_element(&gst_pipeline, &element)
+                        .and_then(|found| streams::select_streams(&found, &stream_ids));
+                    if let Err(message) = selected {
+                        let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+                    }
+                }
+                Some(PipelineCommand::Annotate(_)) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice(
+                            "An in-process run writes no log, so the bookmark stays in the viewer \
+                             only"
+                                .to_string(),
+                        ))
+                        .await;
+                }
+                Some(PipelineCommand::Restart) => {
+                    next = Some(pipeline.to_string());
+                    break;
+                }
+                Some(PipelineCommand::Relaunch(pipeline)) => {
+                    next = Some(pipeline);
+                    break;
+                }
+                Some(PipelineCommand::SetTracers(tracing)) => {
+                    config.tracing = tracing;
+                    next = Some(pipeline.to_string());
+                    break;
+                }
+                Some(PipelineCommand::Stop) | None => break,
+            },
+        }
+    }
+
+    let _ = gst_pipeline.set_state(gst::State::Null);
+    senders.preview.send_replace(None);
+    *time_base_ns = base_ns + started.elapsed().as_nanos() as u64;
+
+    let failed = error.is_some();
+    if let Some(error) = error {
+        let _ = event_tx.send(PipelineEvent::Error(error)).await;
+    }
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+
+    match next {
+        Some(next) => RunOutcome::Relaunch(next),
+        None if failed => RunOutcome::Failed,
+        None => RunOutcome::Finished,
+    }
+}
@@ -0,0 +1,83 @@
+//! Element documentation from the GStreamer registry, the same details
+//! gst-inspect prints, for the GUI's element panel and builder palette.
+
+use gst_debugger_core::{ElementInfo, FactoryEntry, PadTemplateInfo, PropertyInfo};
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+
+/// Every element factory in the registry, sorted by name.
+pub fn element_factories() -> Result<Vec<FactoryEntry>, String> {
+    gst::init().map_err(|err| format!("Failed to initialize GStreamer: {}", err))?;
+    let mut factories: Vec<FactoryEntry> =
+        gst::ElementFactory::factories_with_type(gst::ElementFactoryType::ANY, gst::Rank::None)
+            .iter()
+            .map(|factory| FactoryEntry {
+                name: factory.name().to_string(),
+                long_name: factory.longname().to_string(),
+                klass: factory.klass().to_string(),
+            })
+            .collect();
+    factories.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(factories)
+}
+
+/// Look `factory` up in the registry. The properties are read from an
+/// instance of the element, as gst-inspect does.
+pub fn element_info(factory: &str) -> Result<ElementInfo, String> {
+    gst::init().map_err(|err| format!("Failed to initialize GStreamer: {}", err))?;
+    let element_factory = gst::ElementFactory::find(factory)
+        .ok_or_else(|| format!("No element called {} is installed", factory))?;
+
+    let pad_templates = element_factory
+        .static_pad_templates()
+        .iter()
+        .map(|template| PadTemplateInfo {
+            name: template.name_template().to_string(),
+            direction: match template.direction() {
+                gst::PadDirection::Src => "src",
+                gst::PadDirection::Sink => "sink",
+                _ => "unknown",
+            }
+            .to_string(),
+            presence: match template.presence() {
+                gst::PadPresence::Always => "always",
+                gst::PadPresence::Sometimes => "sometimes",
+                gst::PadPresence::Request => "request",
+            }
+            .to_string(),
+            caps: template.caps().to_string(),
+        })
+        .collect();
+
+    let element = element_factory
+        .create()
+        .build()
+        .map_err(|err| format!("Failed to create {}: {}", factory, err))?;
+    let properties = element
+        .list_properties()
+        .iter()
+        .map(|pspec| PropertyInfo {
+            name: pspec.name().to_string(),
+            type_name: pspec.value_type().name().to_string(),
+            default: pspec
+                .default_value()
+                .serialize()
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
+            blurb: pspec.blurb().unwrap_or_default().to_string(),
+            writable: pspec.flags().contains(glib::ParamFlags::WRITABLE),
+        })
+        .collect();
+
+    Ok(ElementInfo {
+        factory: factory.to_string(),
+        long_name: element_factory.longname().to_string(),
+        klass: element_factory.klass().to_string(),
+        description: element_factory.description().to_string(),
+        author: element_factory.author().to_string(),
+        plugin: element_factory.plugin_name().map(|name| name.to_string()),
+        pad_templates,
+        properties,
+    })
+}
@@ -0,0 +1,255 @@
+//! `receive --listen`: take GST_DEBUG output that devices forward over the
+//! network, e.g. `gst-launch-1.0 ... 2>&1 | socat - UDP:host:5555`, and
+//! parse it as if the pipeline ran locally.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use gst_debugger_core::{
+    parse_annotation_line, parse_caps_notify, parse_eos_event, parse_gst_tracer_output,
+    parse_interlatency, parse_queue_level, PipelineCommand, PipelineEvent, PipelineState,
+    ScheduleJitter,
+};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+
+use crate::{spawn_engine, EngineHandle, EngineOutput, MetricSenders};
+
+/// Largest datagram a device can send.
+const MAX_DATAGRAM: usize = 65536;
+/// Longest line waited for; a sender that goes on without a line break is
+/// passed on in pieces, so it cannot use up memory.
+const MAX_LINE: usize = 64 * 1024;
+/// How long a UDP sender may stay quiet before it counts as gone and its
+/// unfinished line is dropped.
+const PEER_TIMEOUT: Duration = Duration::from_secs(60);
+/// UDP senders followed at once; the one quiet the longest makes room for a
+/// new one.
+const MAX_PEERS: usize = 256;
+
+/// Where to listen, written as `udp://HOST:PORT` or `tcp://HOST:PORT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Udp(String),
+    Tcp(String),
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.split_once("://") {
+            Some(("udp", addr)) if !addr.is_empty() => Ok(ListenAddr::Udp(addr.to_string())),
+            Some(("tcp", addr)) if !addr.is_empty() => Ok(ListenAddr::Tcp(addr.to_string())),
+            _ => Err(format!(
+                "expected udp://HOST:PORT or tcp://HOST:PORT, got `{}`",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Udp(addr) => write!(f, "udp://{}", addr),
+            ListenAddr::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+enum Socket {
+    Udp(UdpSocket),
+    Tcp(TcpListener),
+}
+
+/// Bind `addr` and start an engine that parses every line received on it.
+pub(crate) async fn spawn_listener(
+    addr: &ListenAddr,
+) -> Result<(EngineHandle, EngineOutput), String> {
+    let socket = match addr {
+        ListenAddr::Udp(host) => UdpSocket::bind(host.as_str()).await.map(Socket::Udp),
+        ListenAddr::Tcp(host) => TcpListener::bind(host.as_str()).await.map(Socket::Tcp),
+    }
+    .map_err(|err| format!("Failed to listen on {}: {}", addr, err))?;
+    eprintln!("Listening for GST_DEBUG output on {}", addr);
+
+    Ok(spawn_engine(move |senders, control_rx, event_tx| {
+        receive(socket, senders, control_rx, event_tx)
+    }))
+}
+
+async fn receive(
+    socket: Socket,
+    senders: MetricSenders,
+    mut control_rx: mpsc::Receiver<PipelineCommand>,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    let (line_tx, mut line_rx) = mpsc::channel::<String>(1000);
+    let (peer_tx, mut peer_rx) = mpsc::channel::<SocketAddr>(16);
+    let reader = tokio::spawn(async move {
+        match socket {
+            Socket::Udp(socket) => read_datagrams(socket, line_tx, peer_tx).await,
+            Socket::Tcp(listener) => accept_streams(listener, line_tx, peer_tx).await,
+        }
+    });
+
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+
+    // Lines that arrive while paused are dropped, as the sender cannot be
+    // paused from here.
+    let mut paused = false;
+    let mut schedule_jitter = ScheduleJitter::default();
+    loop {
+        tokio::select! {
+            line = line_rx.recv() => {
+                let Some(line) = line else { break };
+                if !paused {
+                    forward_line(line, &mut schedule_jitter, &senders, &event_tx).await;
+                }
+            }
+            Some(peer) = peer_rx.recv() => {
+                let _ = event_tx
+                    .send(PipelineEvent::Notice(format!("Receiving from {}", peer)))
+                    .await;
+            }
+            command = control_rx.recv() => match command {
+                Some(PipelineCommand::Play) => {
+                    paused = false;
+                    let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+                }
+                Some(PipelineCommand::Pause) => {
+                    paused = true;
+                    let _ = event_tx.send(PipelineEvent::State(PipelineState::Paused)).await;
+                }
+                Some(PipelineCommand::Stop) | None => break,
+                Some(PipelineCommand::Annotate(_)) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice(
+                            "A forwarded log is not saved, so the bookmark stays in the viewer only"
+                                .to_string(),
+                        ))
+                        .await;
+                }
+                Some(_) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice(
+                            "Only play, pause and stop are available for a forwarded log"
+                                .to_string(),
+                        ))
+                        .await;
+                }
+            },
+        }
+    }
+
+    reader.abort();
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+}
+
+async fn forward_line(
+    line: String,
+    schedule_jitter: &mut ScheduleJitter,
+    senders: &MetricSenders,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) {
+    let _ = senders.log.try_send(line.clone());
+    if let Some(entry) = parse_gst_tracer_output(&line) {
+        let _ = senders.data.send(entry).await;
+    } else if let Some(latency) = parse_interlatency(&line) {
+        let _ = senders.latency.send(latency).await;
+    } else if let Some(entry) = schedule_jitter.push_line(&line) {
+        let _ = senders.data.send(entry).await;
+    } else if let Some(element) = parse_eos_event(&line) {
+        let _ = event_tx.send(PipelineEvent::ElementEos(element)).await;
+    } else if let Some((element, pad, caps)) = parse_caps_notify(&line) {
+        let _ = event_tx.send(PipelineEvent::Caps { element, pad, caps }).await;
+    } else if let Some(level) = parse_queue_level(&line) {
+        let _ = event_tx.send(PipelineEvent::QueueLevel(level)).await;
+    } else if let Some(annotation) = parse_annotation_line(&line) {
+        let _ = event_tx.send(PipelineEvent::Annotation(annotation)).await;
+    }
+}
+
+/// A UDP sender and the unfinished line it sent last.
+struct Peer {
+    pending: String,
+    seen: Instant,
+}
+
+/// Split datagrams into lines. A line may span datagrams, so the unfinished
+/// tail is kept per sender until its newline arrives.
+async fn read_datagrams(
+    socket: UdpSocket,
+    line_tx: mpsc::Sender<String>,
+    peer_tx: mpsc::Sender<SocketAddr>,
+) {
+    let mut buf = vec![0; MAX_DATAGRAM];
+    let mut peers: HashMap<SocketAddr, Peer> = HashMap::new();
+    let mut swept = Instant::now();
+    while let Ok((len, addr)) = socket.recv_from(&mut buf).await {
+        let now = Instant::now();
+        if now.duration_since(swept) >= PEER_TIMEOUT {
+            peers.retain(|_, peer| now.duration_since(peer.seen) < PEER_TIMEOUT);
+            swept = now;
+        }
+        if !peers.contains_key(&addr) {
+            if peers.len() >= MAX_PEERS
+                && let Some(quietest) =
+                    peers.iter().min_by_key(|(_, peer)| peer.seen).map(|(addr, _)| *addr)
+            {
+                peers.remove(&quietest);
+            }
+            let _ = peer_tx.try_send(addr);
+        }
+        let peer = peers.entry(addr).or_insert_with(|| Peer {
+            pending: String::new(),
+            seen: now,
+        });
+        peer.seen = now;
+        peer.pending.push_str(&String::from_utf8_lossy(&buf[..len]));
+        while let Some(end) = peer.pending.find('\n') {
+            let line: String = peer.pending.drain(..=end).collect();
+            if line_tx.send(line.trim_end().to_string()).await.is_err() {
+                return;
+            }
+        }
+        if peer.pending.len() > MAX_LINE {
+            let line = std::mem::take(&mut peer.pending);
+            if line_tx.send(line.trim_end().to_string()).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn accept_streams(
+    listener: TcpListener,
+    line_tx: mpsc::Sender<String>,
+    peer_tx: mpsc::Sender<SocketAddr>,
+) {
+    while let Ok((stream, peer)) = listener.accept().await {
+        let _ = peer_tx.try_send(peer);
+        tokio::spawn(read_stream(stream, line_tx.clone()));
+    }
+}
+
+async fn read_stream(stream: TcpStream, line_tx: mpsc::Sender<String>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = (&mut reader).take(MAX_LINE as u64).read_until(b'\n', &mut line).await;
+        if !matches!(read, Ok(len) if len > 0) {
+            return;
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        if line_tx.send(text).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Latency queries on a `--mode in-process` pipeline and on the chain of
+//! elements upstream of each of its sinks.
+
+use gst_debugger_core::{ChainLatency, LatencyReport};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// Whether the query is live, its minimum and its maximum, `None` when
+/// unbounded.
+fn query_result(query: &gst::query::Latency) -> (bool, u64, Option<u64>) {
+    let (live, min, max) = query.result();
+    (live, min.nseconds(), max.map(|max| max.nseconds()))
+}
+
+/// Query the whole pipeline, then the peer of every sink pad of its leaf
+/// sinks. `None` when the pipeline does not answer, as before prerolling.
+pub(crate) fn latency_report(pipeline: &gst::Pipeline, timestamp_ns: u64) -> Option<LatencyReport> {
+    let mut query = gst::query::Latency::new();
+    if !pipeline.query(&mut query) {
+        return None;
+    }
+    let (live, min_ns, max_ns) = query_result(&query);
+
+    let sinks = pipeline.iterate_recurse().into_iter().flatten().filter(|element| {
+        element.element_flags().contains(gst::ElementFlags::SINK) && !element.is::<gst::Bin>()
+    });
+    let mut chains = Vec::new();
+    for sink in sinks {
+        for pad in sink.sink_pads() {
+            let mut query = gst::query::Latency::new();
+            if !pad.peer_query(&mut query) {
+                continue;
+            }
+            let (live, min_ns, max_ns) = query_result(&query);
+            chains.push(ChainLatency {
+                sink: sink.name().to_string(),
+                live,
+                min_ns,
+                max_ns,
+            });
+        }
+    }
+    Some(LatencyReport {
+        timestamp_ns,
+        live,
+        min_ns,
+        max_ns,
+        configured_ns: pipeline.latency().map(|latency| latency.nseconds()),
+        chains,
+    })
+}
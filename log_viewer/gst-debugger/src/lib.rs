@@ -0,0 +1,625 @@
+//! Launches a GStreamer pipeline with tracers enabled and streams the parsed
+//! metrics and pipeline events over channels. Front ends (the headless CLI and
+//! the GUI) drive it through [`cli::start`] or [`spawn`].
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gst_debugger_core::{
+    default_record_encoder, InterLatencyData, PipelineCommand, PipelineEvent, Recording,
+    TracingData, VideoFrame, GSTSHARK_TRACERS,
+};
+use tokio::sync::{mpsc, watch};
+use tokio::task::{self, JoinHandle};
+use tokio::time;
+
+use crate::environment::{installed_tracers, RunEnvironment};
+
+pub mod bugreport;
+pub mod cli;
+pub mod daemon;
+pub mod devices;
+pub mod environment;
+pub mod inspect;
+pub mod remote;
+pub mod tui;
+
+mod api;
+mod avsync;
+mod check;
+mod child;
+mod clock;
+mod email;
+mod in_process;
+mod intake;
+mod latency;
+mod logfile;
+mod native;
+mod negotiation;
+mod notify;
+mod offline;
+mod replay;
+mod rtp;
+mod rtsp;
+mod runner;
+mod script;
+mod segments;
+mod sinks;
+mod streams;
+mod tap;
+mod target;
+mod tls;
+mod webrtc;
+
+pub use intake::ListenAddr;
+pub use logfile::{is_archive, LogNaming, Rotation};
+pub use offline::{export_range, recorded_pipeline, recorded_start};
+pub use target::Target;
+pub use tls::SecurityArgs;
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// How the pipeline is run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LaunchMode {
+    /// Run gst-launch as a child process and parse its tracer output
+    #[default]
+    External,
+    /// Build the pipeline with gstreamer-rs inside the debugger, which adds
+    /// bus messages, position and seeking, and property editing
+    InProcess,
+}
+
+/// Options shared by every front end that launches a pipeline.
+#[derive(clap::Args, Debug, Clone)]
+pub struct LaunchArgs {
+    #[arg(short, long)]
+    pub pipeline: String,
+
+    #[arg(short, long, default_value = "")]
+    pub tracing: String,
+
+    /// Where the pipeline runs; in-process measures it with pad probes
+    /// instead of GstShark tracers
+    #[arg(long, value_enum, default_value_t = LaunchMode::External)]
+    pub mode: LaunchMode,
+
+    /// Same as --mode in-process
+    #[arg(long)]
+    pub in_process: bool,
+
+    /// Measure with the gstdebugger tracer plugin, which sends binary samples
+    /// over a Unix socket instead of tracer lines to parse
+    #[arg(long, conflicts_with_all = ["container", "adb"])]
+    pub native_tracer: bool,
+
+    /// GST_DEBUG categories and levels for the pipeline, e.g.
+    /// `rtspsrc:5,*:3`; the tracer output stays enabled whatever they set
+    #[arg(long, value_name = "SPEC", value_parser = parse_gst_debug)]
+    pub gst_debug: Option<String>,
+
+    /// Log GST_EVENT messages so EOS can be followed through the pipeline
+    #[arg(long)]
+    pub trace_eos: bool,
+
+    /// Relaunch the pipeline with exponential backoff whenever it fails
+    #[arg(long)]
+    pub restart_on_failure: bool,
+
+    /// gst-launch binary used to run the pipeline
+    #[arg(long, default_value = "gst-launch-1.0")]
+    pub gst_launch: String,
+
+    /// GST_PLUGIN_PATH for the pipeline process
+    #[arg(long)]
+    pub gst_plugin_path: Option<String>,
+
+    /// LD_LIBRARY_PATH for the pipeline process
+    #[arg(long)]
+    pub ld_library_path: Option<String>,
+
+    /// Extra KEY=VALUE environment variables for the pipeline process
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    pub env: Vec<(String, String)>,
+
+    /// gst-inspect binary used to verify the requested tracers exist
+    #[arg(long, default_value = "gst-inspect-1.0")]
+    pub gst_inspect: String,
+
+    /// Launch even if some requested tracers are not installed
+    #[arg(long)]
+    pub skip_tracer_check: bool,
+
+    /// Tee the stream before the sink and record it to this file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<String>,
+
+    /// Elements that convert and encode the recorded branch [default:
+    /// `videoconvert ! vp8enc deadline=1` for .webm, else `videoconvert !
+    /// x264enc tune=zerolatency`]
+    #[arg(long)]
+    pub record_encoder: Option<String>,
+
+    /// Tee the raw video before the sink into a preview panel (in-process only)
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Insert a `level` element before the sink and show audio meters
+    /// (in-process only; existing `level` elements are shown regardless)
+    #[arg(long)]
+    pub levels: bool,
+
+    /// Report audio and video as out of sync when the offset between them
+    /// at the sinks exceeds this (in-process only)
+    #[arg(long, default_value = "40ms", value_parser = cli::parse_duration_arg)]
+    pub av_sync_limit: Duration,
+
+    /// Report a video sink when it drops more than this percentage of its
+    /// frames (in-process only)
+    #[arg(long, value_name = "PERCENT", default_value_t = 5.0)]
+    pub drop_rate_limit: f64,
+
+    /// Report a leak when the resident set of gst-launch grows steadily by
+    /// more than this many MB per minute
+    #[arg(long, value_name = "MB_PER_MIN", default_value_t = 1.0)]
+    pub memory_growth_limit: f64,
+
+    /// Nice level for the pipeline process
+    #[arg(long, allow_hyphen_values = true)]
+    pub nice: Option<i32>,
+
+    /// Pin the pipeline process to these CPUs, e.g. `0,2-3`
+    #[arg(long, value_name = "LIST", value_parser = parse_cpu_list)]
+    pub cpus: Option<Vec<usize>>,
+
+    /// Run gst-launch inside this running container instead of on the host
+    #[arg(long, value_name = "NAME", conflicts_with = "in_process")]
+    pub container: Option<String>,
+
+    /// Container runtime used with --container, e.g. `podman`
+    #[arg(long, default_value = "docker")]
+    pub container_runtime: String,
+
+    /// Run gst-launch on the Android device with this serial through `adb shell`
+    #[arg(long, value_name = "SERIAL", conflicts_with_all = ["in_process", "container"])]
+    pub adb: Option<String>,
+
+    /// Collapse runs of identical log lines into one `last message repeated
+    /// N times` line in the tracer log and the raw log panel
+    #[arg(long)]
+    pub dedup_lines: bool,
+
+    /// Keep at most this many lines a second of each debug category in the
+    /// tracer log and the raw log panel; tracer lines are always kept
+    #[arg(long, value_name = "LINES")]
+    pub rate_limit: Option<u32>,
+
+    /// Write no tracer log or metadata file, for quick interactive sessions
+    #[arg(
+        long,
+        conflicts_with_all = ["latest_link", "metrics_log", "split_tracers", "archive"]
+    )]
+    pub no_log_file: bool,
+
+    /// Directory the tracer logs and their metadata files are written to
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// File name of each tracer log without the extension, where
+    /// `{pipeline}`, `{date}` and `{counter}` are replaced
+    #[arg(long, value_name = "TEMPLATE", default_value = logfile::DEFAULT_LOG_NAME)]
+    pub log_name: String,
+
+    /// Keep `latest.log` and `latest.json` links in the output directory
+    /// pointing at the newest tracer log
+    #[arg(long)]
+    pub latest_link: bool,
+
+    /// Roll the tracer log over to a numbered, compressed file once it grows
+    /// past this size, e.g. `500MB`
+    #[arg(long, value_name = "SIZE", value_parser = cli::parse_size_arg)]
+    pub max_log_size: Option<u64>,
+
+    /// Roll the tracer log over once it has been written to for this long,
+    /// e.g. `1h`
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration_arg)]
+    pub max_log_age: Option<Duration>,
+
+    /// How many rolled-over tracer logs to keep, deleting the oldest
+    #[arg(long, value_name = "COUNT")]
+    pub max_log_files: Option<usize>,
+
+    /// Also write the parsed samples as JSON lines of timestamp, element,
+    /// metric and value to `<log name>.metrics.jsonl`
+    #[arg(long)]
+    pub metrics_log: bool,
+
+    /// Also write each tracer's lines to a file of its own next to the tracer
+    /// log, e.g. `<log name>.interlatency.log`
+    #[arg(long)]
+    pub split_tracers: bool,
+
+    /// Once the pipeline exits, pack the tracer log and every file written
+    /// next to it into `<log name>.tar.zst` and delete them
+    #[arg(long, conflicts_with = "latest_link")]
+    pub archive: bool,
+}
+
+/// CPUs past this do not fit in the affinity mask the child is pinned with.
+#[cfg(target_os = "linux")]
+const MAX_CPUS: usize = libc::CPU_SETSIZE as usize;
+#[cfg(not(target_os = "linux"))]
+const MAX_CPUS: usize = 1024;
+
+fn parse_cpu_list(value: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in value.split(',') {
+        let part = part.trim();
+        let parse = |cpu: &str| match cpu.trim().parse::<usize>() {
+            Ok(cpu) if cpu < MAX_CPUS => Ok(cpu),
+            Ok(_) => Err(format!("CPU `{}` in `{}` is past CPU {}", cpu, value, MAX_CPUS - 1)),
+            Err(_) => Err(format!("invalid CPU `{}` in `{}`", cpu, value)),
+        };
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("reversed CPU range `{}` in `{}`", part, value));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// Check a GST_DEBUG value: comma-separated `category:level` entries, where
+/// the category may hold `*` wildcards, or a level alone for every category.
+fn parse_gst_debug(value: &str) -> Result<String, String> {
+    const LEVELS: [&str; 9] = [
+        "none", "error", "warning", "fixme", "info", "debug", "log", "trace", "memdump",
+    ];
+    for entry in value.split(',').map(str::trim) {
+        let (category, level) = entry.split_once(':').unwrap_or(("*", entry));
+        let valid_category = !category.is_empty()
+            && category.chars().all(|c| c.is_ascii_alphanumeric() || "_-*".contains(c));
+        let valid_level = level.parse::<u8>().is_ok_and(|level| level <= 9)
+            || LEVELS.contains(&level.to_ascii_lowercase().as_str());
+        if !valid_category || !valid_level {
+            return Err(format!(
+                "invalid GST_DEBUG entry `{}`, expected CATEGORY:LEVEL such as `rtspsrc:5`",
+                entry
+            ));
+        }
+    }
+    Ok(value.split(',').map(str::trim).collect::<Vec<_>>().join(","))
+}
+
+fn parse_env_var(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{}`", value)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchConfig {
+    pub gst_launch: String,
+    pub tracing: String,
+    pub gst_debug: String,
+    pub env: Vec<(String, String)>,
+    pub restart_on_failure: bool,
+    pub in_process: bool,
+    pub native_tracer: bool,
+    pub record: Option<Recording>,
+    pub preview: bool,
+    pub levels: bool,
+    pub av_sync_limit: Duration,
+    pub drop_rate_limit: f64,
+    pub memory_growth_limit: f64,
+    pub nice: Option<i32>,
+    pub cpus: Vec<usize>,
+    pub target: Target,
+    pub log: LogNaming,
+    pub dedup_lines: bool,
+    pub rate_limit: Option<u32>,
+    /// What the target runs, for the metadata file and bug reports; probed
+    /// by [`prepare`] when a log is written.
+    pub environment: Option<RunEnvironment>,
+}
+
+impl LaunchConfig {
+    pub fn from_args(args: &LaunchArgs) -> Self {
+        // Later entries win, so the tracer categories go last. The warnings
+        // about refused caps, for the caps diff of not-negotiated errors, go
+        // first so `--gst-debug` can change their level.
+        let mut gst_debug = "GST_CAPS:2,".to_string();
+        if let Some(spec) = args.gst_debug.as_deref().filter(|spec| !spec.is_empty()) {
+            gst_debug.push_str(spec);
+            gst_debug.push(',');
+        }
+        gst_debug.push_str("GST_TRACER:7");
+        if args.trace_eos {
+            gst_debug.push_str(",GST_EVENT:6");
+        }
+
+        let mut env = Vec::new();
+        if let Some(path) = &args.gst_plugin_path {
+            env.push(("GST_PLUGIN_PATH".to_string(), path.clone()));
+        }
+        if let Some(path) = &args.ld_library_path {
+            env.push(("LD_LIBRARY_PATH".to_string(), path.clone()));
+        }
+        env.extend(args.env.iter().cloned());
+
+        Self {
+            gst_launch: args.gst_launch.clone(),
+            tracing: args.tracing.clone(),
+            gst_debug,
+            env,
+            restart_on_failure: args.restart_on_failure,
+            in_process: args.in_process || args.mode == LaunchMode::InProcess,
+            native_tracer: args.native_tracer,
+            record: args.record.as_ref().map(|path| Recording {
+                path: path.clone(),
+                encoder: args
+                    .record_encoder
+                    .clone()
+                    .unwrap_or_else(|| default_record_encoder(path).to_string()),
+            }),
+            preview: args.preview,
+            levels: args.levels,
+            av_sync_limit: args.av_sync_limit,
+            drop_rate_limit: args.drop_rate_limit,
+            memory_growth_limit: args.memory_growth_limit,
+            nice: args.nice,
+            cpus: args.cpus.clone().unwrap_or_default(),
+            target: match (&args.container, &args.adb) {
+                (Some(name), _) => Target::Container {
+                    runtime: args.container_runtime.clone(),
+                    name: name.clone(),
+                },
+                (None, Some(serial)) => Target::Adb {
+                    serial: serial.clone(),
+                },
+                (None, None) => Target::Local,
+            },
+            log: LogNaming {
+                enabled: !args.no_log_file,
+                dir: args.output_dir.clone(),
+                template: args.log_name.clone(),
+                latest_link: args.latest_link,
+                rotation: Rotation {
+                    max_size: args.max_log_size,
+                    max_age: args.max_log_age,
+                    max_files: args.max_log_files,
+                },
+                metrics_log: args.metrics_log,
+                split_tracers: args.split_tracers,
+                archive: args.archive,
+            },
+            dedup_lines: args.dedup_lines,
+            rate_limit: args.rate_limit,
+            environment: None,
+        }
+    }
+}
+
+/// Make sure the tracers `config` asks for are among the `installed` ones
+/// gst-inspect listed, `None` when it could not be run.
+pub fn check_tracers(
+    gst_inspect: &str,
+    config: &LaunchConfig,
+    installed: Option<&[String]>,
+) -> Result<(), String> {
+    let Some(installed) = installed else {
+        eprintln!(
+            "Warning: could not run {} on {} to verify tracers, launching anyway",
+            gst_inspect,
+            config.target.describe(),
+        );
+        return Ok(());
+    };
+    let native = config.native_tracer.then_some("gstdebugger");
+    let missing: Vec<String> = config
+        .tracing
+        .split(';')
+        .chain(native)
+        .map(|tracer| tracer.split('(').next().unwrap_or(tracer).trim())
+        .filter(|name| !name.is_empty() && !installed.iter().any(|tracer| tracer == name))
+        .map(str::to_string)
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("Missing GStreamer tracers: {}\n", missing.join(", "));
+    if missing.iter().any(|name| GSTSHARK_TRACERS.contains(&name.as_str())) {
+        message.push_str(
+            "\nThese tracers are provided by GstShark. Install it from your distribution \
+             (e.g. `gst-shark` packages) or build it from https://github.com/RidgeRun/gst-shark, \
+             then point GStreamer at the install with --gst-plugin-path <dir>/lib/gstreamer-1.0.\n",
+        );
+    }
+    if missing.iter().any(|name| name == "gstdebugger") {
+        message.push_str(
+            "\nThe gstdebugger tracer is built from this workspace with \
+             `cargo build -p gst-debugger-tracer --release`; pass --gst-plugin-path target/release \
+             so GStreamer finds libgstdebugger.\n",
+        );
+    }
+    message.push_str(&format!(
+        "\nCheck with `{} <tracer>`, or pass --skip-tracer-check to launch anyway.",
+        gst_inspect
+    ));
+    Err(message)
+}
+
+/// Validate the launch options before anything is spawned: initialize
+/// GStreamer for in-process runs, warn about settings that do not apply, and
+/// make sure the requested tracers are installed.
+pub async fn prepare(args: &LaunchArgs) -> Result<LaunchConfig, String> {
+    let mut config = LaunchConfig::from_args(args);
+
+    if config.in_process && !config.target.is_local() {
+        return Err(format!(
+            "An in-process pipeline runs on this machine and cannot run on {}",
+            config.target.describe()
+        ));
+    }
+
+    // gst-launch may still work where the GStreamer libraries the debugger
+    // was built against cannot be loaded.
+    if config.in_process
+        && let Err(err) = in_process::init()
+    {
+        eprintln!("Failed to initialize GStreamer ({}), running gst-launch instead", err);
+        config.in_process = false;
+    }
+
+    if config.native_tracer && config.in_process {
+        eprintln!("--native-tracer only applies to a gst-launch child and is ignored in-process");
+        config.native_tracer = false;
+    } else if config.native_tracer && cfg!(not(unix)) {
+        return Err(
+            "--native-tracer needs Unix domain sockets and is not supported on this platform"
+                .to_string(),
+        );
+    }
+
+    if config.preview && !config.in_process {
+        eprintln!("--preview needs --mode in-process and is ignored");
+        config.preview = false;
+    }
+    if config.levels && !config.in_process {
+        eprintln!("--levels needs --mode in-process and is ignored");
+        config.levels = false;
+    }
+
+    if config.in_process && (config.nice.is_some() || !config.cpus.is_empty()) {
+        eprintln!("--nice and --cpus only apply to a gst-launch child and are ignored in-process");
+    } else if !config.target.is_local() && (config.nice.is_some() || !config.cpus.is_empty()) {
+        eprintln!("--nice and --cpus only apply to a local gst-launch child and are ignored");
+    } else if !config.cpus.is_empty() && cfg!(not(target_os = "linux")) {
+        eprintln!("--cpus is only supported on Linux and is ignored");
+    } else if config.nice.is_some() && cfg!(not(unix)) {
+        eprintln!("--nice is not supported on this platform and is ignored");
+    }
+
+    // Only a gst-launch child writes a metadata file for the environment.
+    let record_environment = !config.in_process && config.log.enabled;
+    let check = !args.skip_tracer_check && !config.in_process;
+    let installed = if check || record_environment {
+        installed_tracers(&args.gst_inspect, &config).await
+    } else {
+        None
+    };
+    if check {
+        check_tracers(&args.gst_inspect, &config, installed.as_deref())?;
+    }
+    if record_environment {
+        let tracers = installed.unwrap_or_default();
+        config.environment = Some(RunEnvironment::probe(&config, tracers).await);
+    }
+
+    Ok(config)
+}
+
+/// Receiving ends of the engine's data and event channels.
+pub struct EngineOutput {
+    pub data: mpsc::Receiver<TracingData>,
+    pub latency: mpsc::Receiver<InterLatencyData>,
+    pub events: mpsc::Receiver<PipelineEvent>,
+    /// Raw output lines of the pipeline. Lines are dropped rather than
+    /// queued when nobody keeps up with them.
+    pub log: mpsc::Receiver<String>,
+    /// The latest `--preview` frame, if any.
+    pub preview: watch::Receiver<Option<Arc<VideoFrame>>>,
+}
+
+/// Sending ends of the engine's metric and log channels.
+pub(crate) struct MetricSenders {
+    pub data: mpsc::Sender<TracingData>,
+    pub latency: mpsc::Sender<InterLatencyData>,
+    pub log: mpsc::Sender<String>,
+    pub preview: watch::Sender<Option<Arc<VideoFrame>>>,
+}
+
+/// Controls a running engine task.
+pub struct EngineHandle {
+    control: mpsc::Sender<PipelineCommand>,
+    task: JoinHandle<()>,
+}
+
+impl EngineHandle {
+    pub fn control(&self) -> mpsc::Sender<PipelineCommand> {
+        self.control.clone()
+    }
+
+    /// Stop the pipeline and wait for the child to shut down.
+    pub async fn shutdown(self) {
+        let _ = self.control.send(PipelineCommand::Stop).await;
+        drop(self.control);
+        let _ = time::timeout(SHUTDOWN_TIMEOUT * 3, self.task).await;
+    }
+}
+
+pub fn spawn(pipeline: String, config: LaunchConfig) -> (EngineHandle, EngineOutput) {
+    spawn_engine(|senders, control_rx, event_tx| {
+        runner::run_pipeline_with_tracing(pipeline, config, senders, control_rx, event_tx)
+    })
+}
+
+fn spawn_engine<F, Fut>(run: F) -> (EngineHandle, EngineOutput)
+where
+    F: FnOnce(MetricSenders, mpsc::Receiver<PipelineCommand>, mpsc::Sender<PipelineEvent>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(100);
+    let (lat_tx, lat_rx) = mpsc::channel(100);
+    let (log_tx, log_rx) = mpsc::channel(500);
+    let (control_tx, control_rx) = mpsc::channel(16);
+    let (event_tx, event_rx) = mpsc::channel(16);
+    let (preview_tx, preview_rx) = watch::channel(None);
+
+    let senders = MetricSenders {
+        data: tx,
+        latency: lat_tx,
+        log: log_tx,
+        preview: preview_tx,
+    };
+    let task = task::spawn(run(senders, control_rx, event_tx));
+
+    (
+        EngineHandle {
+            control: control_tx,
+            task,
+        },
+        EngineOutput {
+            data: rx,
+            latency: lat_rx,
+            events: event_rx,
+            log: log_rx,
+            preview: preview_rx,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0,2-4"), Ok(vec![0, 2, 3, 4]));
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list(&MAX_CPUS.to_string()).is_err());
+        assert!(parse_cpu_list("0-x").is_err());
+    }
+}
@@ -0,0 +1,511 @@
+//! Where each run's tracer log goes: a file named from a template in the
+//! output directory, with its metadata file next to it and optionally a
+//! `latest.log` link to the newest one. A long run's log rolls over to
+//! numbered, gzip-compressed files once it gets too big or too old. The
+//! parsed samples can also go to a JSONL file, so they can be analyzed
+//! without parsing the raw log again, and each tracer's lines to a file of
+//! their own.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use gst_debugger_core::{
+    parse_duration_to_ns, parse_tracer_record, pipeline_elements, InterLatencyData, Metric,
+    TracingData,
+};
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::task::{self, JoinHandle};
+
+/// The file name template of tracer logs when none is given.
+pub const DEFAULT_LOG_NAME: &str = "tracer_output_{date}";
+/// Longest stretch of a file name that `{pipeline}` fills.
+const PIPELINE_NAME_LIMIT: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct LogNaming {
+    /// Whether a run writes any files at all.
+    pub enabled: bool,
+    pub dir: PathBuf,
+    /// File name without the extension, where `{pipeline}`, `{date}` and
+    /// `{counter}` are replaced.
+    pub template: String,
+    /// Keep `latest.log` and `latest.json` pointing at the newest log.
+    pub latest_link: bool,
+    pub rotation: Rotation,
+    /// Also write the parsed samples to `<name>.metrics.jsonl`.
+    pub metrics_log: bool,
+    /// Also write each tracer's lines to `<name>.<record>.log`.
+    pub split_tracers: bool,
+    /// Pack the files of a run into `<name>.tar.zst` once it exits.
+    pub archive: bool,
+}
+
+/// When a tracer log rolls over and how many rolled-over files are kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rotation {
+    pub max_size: Option<u64>,
+    pub max_age: Option<Duration>,
+    /// The oldest files beyond this many are deleted; all are kept when
+    /// `None`.
+    pub max_files: Option<usize>,
+}
+
+impl Rotation {
+    fn is_enabled(&self) -> bool {
+        self.max_size.is_some() || self.max_age.is_some()
+    }
+}
+
+/// A tracer log being written, with the run's metrics log and per-tracer
+/// logs when asked for. Rolling over renames `name.log` to `name.1.log.gz`,
+/// compressed in the background, after shifting the older files up by one;
+/// the metrics log and per-tracer logs roll over along with it, such as
+/// `name.metrics.jsonl` to `name.metrics.1.jsonl.gz`.
+pub struct TracerLog {
+    path: PathBuf,
+    file: tokio::fs::File,
+    rotation: Rotation,
+    written: u64,
+    opened: Instant,
+    /// Compression of the last rolled-over file, which the next roll-over
+    /// waits for before it shifts the files.
+    compressing: Option<JoinHandle<()>>,
+    /// The file of each tracer record seen so far, when split by tracer.
+    records: Option<HashMap<String, BufWriter<tokio::fs::File>>>,
+    metrics: Option<MetricsLog>,
+}
+
+impl TracerLog {
+    pub async fn open(path: PathBuf, rotation: Rotation, split_tracers: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path).await?;
+        let written = file.metadata().await?.len();
+        Ok(TracerLog {
+            path,
+            file,
+            rotation,
+            written,
+            opened: Instant::now(),
+            compressing: None,
+            records: split_tracers.then(HashMap::new),
+            metrics: None,
+        })
+    }
+
+    /// Also write the parsed samples to `<name>.metrics.jsonl`.
+    pub async fn open_metrics(&mut self) -> io::Result<()> {
+        self.metrics = Some(MetricsLog::create(&self.path).await?);
+        Ok(())
+    }
+
+    pub async fn write_sample(&mut self, sample: &TracingData) -> io::Result<()> {
+        match &mut self.metrics {
+            Some(metrics) => metrics.write_sample(sample).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn write_latency(&mut self, latency: &InterLatencyData) -> io::Result<()> {
+        match &mut self.metrics {
+            Some(metrics) => metrics.write_latency(latency).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Append a line, rolling the log over first when it is due.
+    pub async fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let due = self.rotation.max_size.is_some_and(|max| self.written >= max)
+            || self.rotation.max_age.is_some_and(|max| self.opened.elapsed() >= max);
+        if self.rotation.is_enabled() && due && self.written > 0 {
+            self.roll_over().await?;
+        }
+        self.file.write_all(format!("{}\n", line).as_bytes()).await?;
+        self.written += line.len() as u64 + 1;
+        if let Some(records) = &mut self.records
+            && let Some(record) = parse_tracer_record(line)
+        {
+            if !records.contains_key(record) {
+                let path = record_path(&self.path, record);
+                let file = OpenOptions::new().create(true).append(true).open(path).await?;
+                records.insert(record.to_string(), BufWriter::new(file));
+            }
+            if let Some(file) = records.get_mut(record) {
+                file.write_all(format!("{}\n", line).as_bytes()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> io::Result<()> {
+        for file in self.records.iter_mut().flat_map(HashMap::values_mut) {
+            file.flush().await?;
+        }
+        if let Some(metrics) = &mut self.metrics {
+            metrics.flush().await?;
+        }
+        self.file.flush().await
+    }
+
+    /// Close the log and pack it into `<name>.tar.zst` next to it, with
+    /// every other file of the run: rolled-over logs, the metadata file, the
+    /// metrics log and the per-tracer logs. The packed files are deleted.
+    pub async fn archive(mut self) -> io::Result<PathBuf> {
+        self.flush().await?;
+        if let Some(compressing) = self.compressing.take() {
+            let _ = compressing.await;
+        }
+        let path = self.path.clone();
+        drop(self);
+        task::spawn_blocking(move || pack_run(&path)).await?
+    }
+
+    async fn roll_over(&mut self) -> io::Result<()> {
+        self.flush().await?;
+        if let Some(compressing) = self.compressing.take() {
+            let _ = compressing.await;
+        }
+        let max_files = self.rotation.max_files;
+        let mut rolled = vec![shift_rolled(&self.path, max_files).await?];
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        if let Some(metrics) = &mut self.metrics {
+            rolled.push(shift_rolled(&metrics.path, max_files).await?);
+            *metrics = MetricsLog::create(&self.path).await?;
+        }
+        for (record, file) in self.records.iter_mut().flatten() {
+            let path = record_path(&self.path, record);
+            rolled.push(shift_rolled(&path, max_files).await?);
+            let opened = OpenOptions::new().create(true).append(true).open(path).await?;
+            *file = BufWriter::new(opened);
+        }
+        self.written = 0;
+        self.opened = Instant::now();
+        if max_files == Some(0) {
+            for (path, _) in rolled {
+                tokio::fs::remove_file(path).await?;
+            }
+            return Ok(());
+        }
+        self.compressing = Some(task::spawn_blocking(move || {
+            for (path, compressed) in rolled {
+                if let Err(err) = compress(&path, &compressed) {
+                    eprintln!("Failed to compress {}: {}", path.display(), err);
+                }
+            }
+        }));
+        Ok(())
+    }
+}
+
+/// The log of one tracer's records next to the tracer log at `log`, such
+/// as `<name>.proctime.log`.
+fn record_path(log: &Path, record: &str) -> PathBuf {
+    let stem = log.file_stem().unwrap_or_default().to_string_lossy();
+    log.with_file_name(format!("{}.{}.log", stem, record))
+}
+
+/// The rolled-over file `number` of `path`, such as `<name>.1.log` for
+/// `<name>.log`, before it is compressed to `<name>.1.log.gz`.
+fn numbered(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.{}", stem, number, extension))
+}
+
+/// Shift the compressed rolled-over files of `path` up by one, deleting
+/// those beyond `max_files`, and move `path` to the first one's place. The
+/// moved file and where it goes compressed are returned.
+async fn shift_rolled(path: &Path, max_files: Option<usize>) -> io::Result<(PathBuf, PathBuf)> {
+    let compressed = |number: usize| {
+        let mut name = numbered(path, number).into_os_string();
+        name.push(".gz");
+        PathBuf::from(name)
+    };
+    let mut count = 0;
+    while compressed(count + 1).exists() {
+        count += 1;
+    }
+    for number in (1..=count).rev() {
+        if max_files.is_some_and(|max| number >= max) {
+            tokio::fs::remove_file(compressed(number)).await?;
+        } else {
+            tokio::fs::rename(compressed(number), compressed(number + 1)).await?;
+        }
+    }
+    let rolled = numbered(path, 1);
+    tokio::fs::rename(path, &rolled).await?;
+    Ok((rolled, compressed(1)))
+}
+
+/// The parsed samples of a run as JSON lines of one metric each, such as
+/// `{"timestamp_ns":5000000,"element":"x264enc0","metric":"proctime","value":1250000}`.
+/// Interlatency is the `from` element's, with the other end in `to`.
+struct MetricsLog {
+    path: PathBuf,
+    file: BufWriter<tokio::fs::File>,
+}
+
+impl MetricsLog {
+    /// The metrics log that goes with the tracer log at `log`.
+    async fn create(log: &Path) -> io::Result<Self> {
+        let path = log.with_extension("metrics.jsonl");
+        let file = tokio::fs::File::create(&path).await?;
+        Ok(MetricsLog {
+            path,
+            file: BufWriter::new(file),
+        })
+    }
+
+    async fn write_sample(&mut self, sample: &TracingData) -> io::Result<()> {
+        for line in sample_records(sample) {
+            self.file.write_all(format!("{}\n", line).as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_latency(&mut self, latency: &InterLatencyData) -> io::Result<()> {
+        match latency_record(latency) {
+            Some(line) => self.file.write_all(format!("{}\n", line).as_bytes()).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        self.file.flush().await
+    }
+}
+
+/// The metrics log lines of a sample, one for each metric it has.
+pub(crate) fn sample_records(
+    sample: &TracingData,
+) -> impl Iterator<Item = serde_json::Value> + '_ {
+    Metric::ALL.into_iter().filter_map(|metric| {
+        let value = metric.value(sample)?;
+        Some(serde_json::json!({
+            "timestamp_ns": sample.timestamp_ns,
+            "element": sample.element,
+            "metric": metric.name(),
+            "value": value,
+        }))
+    })
+}
+
+/// The metrics log line of an interlatency sample.
+pub(crate) fn latency_record(latency: &InterLatencyData) -> Option<serde_json::Value> {
+    let value = parse_duration_to_ns(&latency.time)?;
+    Some(serde_json::json!({
+        "timestamp_ns": latency.timestamp_ns,
+        "element": latency.from,
+        "to": latency.to,
+        "metric": Metric::Interlatency.name(),
+        "value": value,
+    }))
+}
+
+/// The files a rolled-over log at `log` was written to, oldest first: the
+/// numbered `<name>.N.log.gz` files from the highest number down, then the
+/// log itself. A file still being compressed is taken as `<name>.N.log`.
+pub(crate) fn log_segments(log: &Path) -> Vec<PathBuf> {
+    let stem = log.file_stem().unwrap_or_default().to_string_lossy();
+    let mut segments = Vec::new();
+    for number in 1.. {
+        let compressed = log.with_file_name(format!("{}.{}.log.gz", stem, number));
+        let rolled = log.with_file_name(format!("{}.{}.log", stem, number));
+        match (compressed.exists(), rolled.exists()) {
+            (true, _) => segments.push(compressed),
+            (false, true) => segments.push(rolled),
+            (false, false) => break,
+        }
+    }
+    segments.reverse();
+    segments.push(log.to_path_buf());
+    segments
+}
+
+/// Whether `path` is the archive a run was packed into.
+pub fn is_archive(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(".tar.zst")
+}
+
+/// Pack the tracer log at `log` and the files named after it, such as
+/// `<name>.json` or `<name>.1.log.gz`, into `<name>.tar.zst` and delete them.
+/// An archive of an earlier run by the same name is kept, and this one
+/// numbered `<name>.2.tar.zst` and so on.
+fn pack_run(log: &Path) -> io::Result<PathBuf> {
+    let stem = log.file_stem().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{}.", stem);
+    let dir = log.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with(&prefix) && path.is_file() && !is_archive(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    let mut number = 1;
+    let (archive, file) = loop {
+        let archive = match number {
+            1 => log.with_extension("tar.zst"),
+            _ => log.with_file_name(format!("{}.{}.tar.zst", stem, number)),
+        };
+        match File::create_new(&archive) {
+            Ok(file) => break (archive, file),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => number += 1,
+            Err(err) => return Err(err),
+        }
+    };
+    let mut tar = tar::Builder::new(zstd::Encoder::new(file, 0)?);
+    for file in &files {
+        tar.append_path_with_name(file, file.file_name().unwrap_or_default())?;
+    }
+    tar.into_inner()?.finish()?;
+    for file in &files {
+        std::fs::remove_file(file)?;
+    }
+    Ok(archive)
+}
+
+/// Gzip `from` into `to` and delete `from`.
+fn compress(from: &Path, to: &Path) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(to)?, Compression::default());
+    io::copy(&mut File::open(from)?, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(from)
+}
+
+impl LogNaming {
+    /// The path of a new tracer log for `pipeline`. `{counter}` is the
+    /// lowest number from 1 whose log does not exist yet. A name without
+    /// one that is taken, such as a fixed name or a restart within the
+    /// same second, gets `_2`, `_3` and so on appended, so an earlier run's
+    /// files are never appended to or overwritten.
+    pub fn next_path(&self, pipeline: &str) -> PathBuf {
+        let date = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let name = self
+            .template
+            .replace("{pipeline}", &pipeline_name(pipeline))
+            .replace("{date}", &date);
+        if !name.contains("{counter}") {
+            let path = self.dir.join(format!("{}.log", name));
+            if !is_taken(&path) {
+                return path;
+            }
+            let mut counter = 2;
+            loop {
+                let path = self.dir.join(format!("{}_{}.log", name, counter));
+                if !is_taken(&path) {
+                    return path;
+                }
+                counter += 1;
+            }
+        }
+        let mut counter = 1;
+        loop {
+            let numbered = name.replace("{counter}", &counter.to_string());
+            let path = self.dir.join(format!("{}.log", numbered));
+            if !is_taken(&path) {
+                return path;
+            }
+            counter += 1;
+        }
+    }
+
+    /// Point `latest.log` and `latest.json` in the output directory at the
+    /// tracer log and its metadata file.
+    #[cfg(unix)]
+    pub fn link_latest(&self, log: &Path) -> Result<(), String> {
+        let targets = [
+            ("latest.log", log.to_path_buf()),
+            ("latest.json", log.with_extension("json")),
+        ];
+        for (link, target) in targets {
+            let link = self.dir.join(link);
+            let Some(target) = target.file_name() else {
+                continue;
+            };
+            let _ = std::fs::remove_file(&link);
+            std::os::unix::fs::symlink(target, &link)
+                .map_err(|err| format!("Failed to link {}: {}", link.display(), err))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn link_latest(&self, _log: &Path) -> Result<(), String> {
+        Err("--latest-link needs symbolic links and is not supported on this platform".to_string())
+    }
+}
+
+/// Whether a run already wrote files by the name of the log at `path`.
+fn is_taken(path: &Path) -> bool {
+    ["log", "json", "tar.zst"].into_iter().any(|extension| path.with_extension(extension).exists())
+}
+
+/// The pipeline's element names joined by dashes for a file name, e.g.
+/// `videotestsrc-x264enc-fakesink`.
+fn pipeline_name(pipeline: &str) -> String {
+    let name: String = pipeline_elements(pipeline)
+        .join("-")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(PIPELINE_NAME_LIMIT)
+        .collect();
+    if name.is_empty() {
+        "pipeline".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn metrics_and_tracer_logs_roll_over_with_the_log() {
+        let name = format!("gst_debugger_rotation_{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.log");
+        let rotation = Rotation {
+            max_size: Some(1),
+            ..Rotation::default()
+        };
+        let line = "0:00:01.000000000 1 0x1 TRACE GST_TRACER :0:: proctime, element=(string)enc0, \
+                    time=(string)0:00:00.001000000;";
+        let mut log = TracerLog::open(path.clone(), rotation, true).await.unwrap();
+        log.open_metrics().await.unwrap();
+        log.write_line(line).await.unwrap();
+        log.write_line(line).await.unwrap();
+        log.flush().await.unwrap();
+        if let Some(compressing) = log.compressing.take() {
+            compressing.await.unwrap();
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            names,
+            [
+                "run.1.log.gz",
+                "run.log",
+                "run.metrics.1.jsonl.gz",
+                "run.metrics.jsonl",
+                "run.proctime.1.log.gz",
+                "run.proctime.log",
+            ]
+        );
+    }
+}
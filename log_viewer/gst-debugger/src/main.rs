@@ -0,0 +1,30 @@
+use clap::Parser;
+use gst_debugger::cli::{self, Cli};
+use gst_debugger::{daemon, tui};
+
+#[tokio::main]
+async fn main() {
+    let args = Cli::parse();
+    match cli::start(args.command).await {
+        Ok(Some(session)) if session.view.headless => cli::print_summaries(session).await,
+        Ok(Some(session)) if session.view.daemon => {
+            let socket = session.view.socket.clone();
+            if let Err(message) = daemon::serve(session, &socket).await {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
+        Ok(Some(session)) if session.view.tui => {
+            if let Err(err) = tui::run(session).await {
+                eprintln!("Terminal UI failed: {}", err);
+                std::process::exit(1);
+            }
+        }
+        Ok(Some(session)) => cli::print_stream(session).await,
+        Ok(None) => {}
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! `--native-tracer`: receive samples from the `gstdebugger` tracer plugin
+//! (the `gst-debugger-tracer` crate) over a Unix socket instead of parsing
+//! tracer lines from the child's stderr.
+
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use gst_debugger_core::TracingData;
+#[cfg(unix)]
+use gst_debugger_core::wire::{decode_sample, MAX_FRAME_LEN};
+#[cfg(unix)]
+use tokio::io::AsyncReadExt;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc;
+#[cfg(unix)]
+use tokio::task::JoinHandle;
+
+/// Socket the tracer of one pipeline run connects to. The socket file is
+/// removed when this is dropped.
+#[cfg(unix)]
+pub(crate) struct NativeTracer {
+    path: PathBuf,
+    task: JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl NativeTracer {
+    /// Listen on a fresh socket and forward every sample received on it.
+    pub(crate) fn listen(samples: mpsc::Sender<TracingData>) -> Result<Self, String> {
+        let path = std::env::temp_dir().join(format!("gst_debugger_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)
+            .map_err(|err| format!("Failed to listen on {}: {}", path.display(), err))?;
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(read_samples(stream, samples.clone()));
+            }
+        });
+        Ok(NativeTracer { path, task })
+    }
+
+    /// The GST_TRACERS entry that loads the tracer and points it here.
+    pub(crate) fn tracer(&self) -> String {
+        format!("gstdebugger(socket={})", self.path.display())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for NativeTracer {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+async fn read_samples(mut stream: UnixStream, samples: mpsc::Sender<TracingData>) {
+    let mut payload = Vec::new();
+    loop {
+        let Ok(len) = stream.read_u32_le().await else { return };
+        let len = len as usize;
+        if len > MAX_FRAME_LEN {
+            return;
+        }
+        payload.resize(len, 0);
+        if stream.read_exact(&mut payload).await.is_err() {
+            return;
+        }
+        // Frames from a newer tracer are skipped rather than misread.
+        if let Some(sample) = decode_sample(&payload)
+            && samples.send(sample).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) struct NativeTracer;
+
+#[cfg(not(unix))]
+impl NativeTracer {
+    pub(crate) fn listen(_samples: mpsc::Sender<TracingData>) -> Result<Self, String> {
+        Err("--native-tracer needs Unix domain sockets and is not supported on this platform"
+            .to_string())
+    }
+
+    pub(crate) fn tracer(&self) -> String {
+        String::new()
+    }
+}
@@ -0,0 +1,68 @@
+//! Finds the link a not-negotiated error stopped at, with the caps on both
+//! of its ends: by querying the pads of an in-process pipeline, or from the
+//! pad templates for what a gst-launch run did not print.
+
+use gst_debugger_core::CapsMismatch;
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+fn pad_name(pad: &gst::Pad) -> String {
+    match pad.parent_element() {
+        Some(element) => format!("{}:{}", element.name(), pad.name()),
+        None => pad.name().to_string(),
+    }
+}
+
+/// The caps of the first pad template of `factory` in `direction`, `src` or
+/// `sink`. They come from the registry of this machine, which for a run on
+/// another target may not have the same version of the element.
+pub(crate) fn template_caps(factory: &str, direction: &str) -> Option<String> {
+    gst::init().ok()?;
+    let direction = match direction {
+        "src" => gst::PadDirection::Src,
+        _ => gst::PadDirection::Sink,
+    };
+    let factory = gst::ElementFactory::find(factory)?;
+    let templates = factory.static_pad_templates();
+    let template = templates.iter().find(|template| template.direction() == direction)?;
+    Some(template.caps().to_string())
+}
+
+/// Whether the debug details of an error report a not-negotiated flow.
+pub(crate) fn is_not_negotiated(debug: &str) -> bool {
+    debug.contains("not-negotiated")
+}
+
+/// The first link whose source pad cannot produce caps its peer accepts.
+/// The links of `element`, the one that posted the error, are checked
+/// first, since it is usually the one whose output nobody would take.
+pub(crate) fn caps_mismatch(
+    pipeline: &gst::Pipeline,
+    element: Option<&str>,
+) -> Option<CapsMismatch> {
+    let mut links = Vec::new();
+    for child in pipeline.iterate_recurse().into_iter().flatten() {
+        for pad in child.src_pads() {
+            if let Some(peer) = pad.peer() {
+                let posted = element.is_some_and(|element| child.name() == element);
+                links.push((!posted, pad, peer));
+            }
+        }
+    }
+    links.sort_by_key(|(other, _, _)| *other);
+
+    links.into_iter().find_map(|(_, pad, peer)| {
+        // A source pad whose upstream fails to negotiate too reports no caps;
+        // its template still tells what it could produce.
+        let offered = Some(pad.query_caps(None))
+            .filter(|caps| !caps.is_empty())
+            .unwrap_or_else(|| pad.pad_template_caps());
+        let accepted = peer.query_caps(None);
+        (!offered.can_intersect(&accepted)).then(|| CapsMismatch {
+            src_pad: pad_name(&pad),
+            sink_pad: pad_name(&peer),
+            offered: offered.to_string(),
+            accepted: accepted.to_string(),
+        })
+    })
+}
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Local;
+use gst_debugger_core::{
+    format_caps_mismatch, format_clock_time, format_markdown_summary, MetricStore, PipelineError,
+    PipelineEvent, PipelineState, RuleResult, RuleSet,
+};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::email::EmailConfig;
+use crate::tap::{Outbox, SharedStore, Tap};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body POSTed to the webhook when an alert fires. `text` makes it usable
+/// as is with Slack and Teams incoming webhooks.
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    text: String,
+    pipeline: &'a str,
+    element: &'a str,
+    rule: &'a str,
+    metric: &'static str,
+    value: Option<f64>,
+    threshold: f64,
+    /// Pipeline time the alert fired at.
+    timestamp: String,
+    timestamp_ns: u64,
+}
+
+/// Where fired alerts are sent.
+pub(crate) struct Notifier {
+    pub pipeline: String,
+    pub rules: RuleSet,
+    pub webhook: Option<String>,
+    /// Show alerts and pipeline errors as desktop notifications.
+    pub desktop: bool,
+    pub summary: Option<SummaryReport>,
+}
+
+/// A Markdown summary of the run so far, written or mailed every `every`
+/// and right away when the pipeline reports an error.
+pub(crate) struct SummaryReport {
+    pub every: Duration,
+    pub file: Option<PathBuf>,
+    pub email: Option<EmailConfig>,
+}
+
+impl Notifier {
+    fn alert_fired(
+        &self,
+        alert: &RuleResult,
+        client: &reqwest::Client,
+        out: &Outbox,
+    ) {
+        if self.desktop {
+            tokio::spawn(show_desktop_notification(
+                format!("Alert: {}", alert.check),
+                alert.describe(),
+                out.sender(),
+            ));
+        }
+        if let Some(url) = &self.webhook
+            && let Ok(body) = serde_json::to_string(&self.payload(alert))
+        {
+            tokio::spawn(post_webhook(client.clone(), url.clone(), body, out.sender()));
+        }
+    }
+
+    fn send_summary(
+        &self,
+        store: &MetricStore,
+        error: Option<&PipelineError>,
+        out: &mut Outbox,
+    ) {
+        let Some(summary) = &self.summary else {
+            return;
+        };
+        let results = self.rules.evaluate(store);
+        let failed = results.iter().filter(|result| !result.passed()).count();
+        let subject = match error {
+            Some(error) => format!("gst_debugger: pipeline error: {}", error.message),
+            None if failed > 0 => {
+                format!("gst_debugger: summary, {} of {} checks failing", failed, results.len())
+            }
+            None => "gst_debugger: summary".to_string(),
+        };
+
+        let mut report = format!("{}\n\n", Local::now().format("%Y-%m-%d %H:%M:%S"));
+        if let Some(error) = error {
+            report.push_str(&format!("Pipeline error: {}\n", error.message));
+            if !error.debug.is_empty() {
+                report.push_str(&format!("{}\n", error.debug));
+            }
+            if let Some(caps) = &error.caps {
+                report.push_str(&format_caps_mismatch(caps));
+            }
+            report.push('\n');
+        }
+        report.push_str(&format_markdown_summary(&self.pipeline, store, &results));
+
+        if let Some(path) = &summary.file
+            && let Err(err) = std::fs::write(path, &report)
+        {
+            out.push(PipelineEvent::Notice(format!(
+                "Failed to write summary to {}: {}",
+                path.display(),
+                err
+            )));
+        }
+        if let Some(email) = summary.email.clone() {
+            let event_tx = out.sender();
+            tokio::spawn(async move {
+                if let Err(err) = email.send(&subject, report).await {
+                    let _ = event_tx.send(PipelineEvent::Notice(err)).await;
+                }
+            });
+        }
+    }
+
+    fn payload<'a>(&'a self, alert: &'a RuleResult) -> WebhookPayload<'a> {
+        WebhookPayload {
+            text: format!("gst_debugger alert: {}", alert.describe()),
+            pipeline: &self.pipeline,
+            element: &alert.element,
+            rule: &alert.check,
+            metric: alert.metric.label(),
+            value: alert.value,
+            threshold: alert.threshold,
+            timestamp: format_clock_time(alert.timestamp_ns),
+            timestamp_ns: alert.timestamp_ns,
+        }
+    }
+}
+
+/// Failures are reported as notices, since printing them would garble
+/// the TUI.
+async fn show_desktop_notification(
+    summary: String,
+    body: String,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    let shown = tokio::task::spawn_blocking(move || {
+        notify_rust::Notification::new()
+            .appname("gst_debugger")
+            .summary(&summary)
+            .body(&body)
+            .show()
+            .map(|_| ())
+    })
+    .await;
+    if let Ok(Err(err)) = shown {
+        let _ = event_tx
+            .send(PipelineEvent::Notice(format!(
+                "Failed to show desktop notification: {}",
+                err
+            )))
+            .await;
+    }
+}
+
+async fn post_webhook(
+    client: reqwest::Client,
+    url: String,
+    body: String,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    let result = client
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        let _ = event_tx
+            .send(PipelineEvent::Notice(format!("Webhook {} failed: {}", url, err)))
+            .await;
+    }
+}
+
+/// Evaluates the rules once a second against everything received so far
+/// and sends each alert when it becomes active. An alert that clears and
+/// fires again is sent again. Pipeline errors also raise a desktop
+/// notification and an immediate summary, and a last one is sent when the
+/// pipeline stops.
+struct Notifying {
+    notifier: Notifier,
+    store: SharedStore,
+    client: reqwest::Client,
+    active: HashSet<(String, String)>,
+    next_summary: Option<Instant>,
+    /// The error of the current run, repeated in its last summary.
+    error: Option<PipelineError>,
+}
+
+pub(crate) fn tap(notifier: Notifier, store: SharedStore) -> Box<dyn Tap> {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let next_summary = notifier.summary.as_ref().map(|summary| Instant::now() + summary.every);
+    Box::new(Notifying {
+        notifier,
+        store,
+        client,
+        active: HashSet::new(),
+        next_summary,
+        error: None,
+    })
+}
+
+impl Tap for Notifying {
+    fn event(&mut self, event: &PipelineEvent, out: &mut Outbox) {
+        match event {
+            PipelineEvent::Error(error) => {
+                if self.notifier.desktop {
+                    tokio::spawn(show_desktop_notification(
+                        "Pipeline error".to_string(),
+                        error.message.clone(),
+                        out.sender(),
+                    ));
+                }
+                self.notifier.send_summary(&self.store.read().unwrap(), Some(error), out);
+                self.error = Some(error.clone());
+            }
+            PipelineEvent::State(PipelineState::Stopped) => {
+                let error = self.error.take();
+                self.notifier.send_summary(&self.store.read().unwrap(), error.as_ref(), out);
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, out: &mut Outbox) {
+        let store = self.store.read().unwrap();
+        let results = self.notifier.rules.evaluate(&store);
+        let now_active: HashSet<(String, String)> = results
+            .iter()
+            .filter(|result| result.active)
+            .map(|result| (result.element.clone(), result.check.clone()))
+            .collect();
+        for alert in results.iter().filter(|result| result.active) {
+            if self.active.contains(&(alert.element.clone(), alert.check.clone())) {
+                continue;
+            }
+            self.notifier.alert_fired(alert, &self.client, out);
+        }
+        self.active = now_active;
+
+        if let Some(due) = self.next_summary
+            && let Some(summary) = &self.notifier.summary
+            && Instant::now() >= due
+        {
+            self.next_summary = Some(Instant::now() + summary.every);
+            self.notifier.send_summary(&store, None, out);
+        }
+    }
+}
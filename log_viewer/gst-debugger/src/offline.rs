@@ -0,0 +1,374 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use flate2::read::GzDecoder;
+use gst_debugger_core::{
+    format_clock_time, format_folded, format_markdown_summary, format_rules_toml,
+    format_stats_table, parse_caps_notify, parse_duration_to_ns, parse_object_path,
+    parse_schedule_time, proctime_stacks, render_flamegraph_svg, suggest_rules, BottleneckReport,
+    CapsChange, HtmlReport, InterLatencyData, MetricStore, RuleSet, SpeedscopeFile, TestCase,
+    ThreadTracker, TracingData,
+};
+use serde::Serialize;
+
+use crate::check::{report_rule_results, write_junit};
+use crate::cli::{AnalyzeArgs, AnalyzeFormat, ExportArgs, ExportFormat};
+use crate::environment::RunEnvironment;
+use crate::logfile::{latency_record, log_segments, sample_records};
+
+/// A tracer log with what its `gst-launch-1.0 -v` output tells besides the
+/// metrics.
+struct RecordedLog {
+    store: MetricStore,
+    /// Caps notifications, each timed by the last sample before it.
+    caps_changes: Vec<CapsChange>,
+    /// The names from the pipeline down to each element's parent.
+    ancestors: HashMap<String, Vec<String>>,
+    /// Every pad's `scheduletime` intervals as (timestamp, interval) pairs.
+    schedule: BTreeMap<String, Vec<(u64, u64)>>,
+    threads: ThreadTracker,
+}
+
+fn load_recorded(path: &Path) -> Result<RecordedLog, String> {
+    let read_error = |err: io::Error| format!("Failed to read {}: {}", path.display(), err);
+    let mut log = RecordedLog {
+        store: MetricStore::default(),
+        caps_changes: Vec::new(),
+        ancestors: HashMap::new(),
+        schedule: BTreeMap::new(),
+        threads: ThreadTracker::new(None),
+    };
+    for line in BufReader::new(File::open(path).map_err(read_error)?).lines() {
+        let line = line.map_err(read_error)?;
+        if let Some(mut names) = parse_object_path(&line)
+            && let Some(element) = names.pop()
+            && !names.is_empty()
+        {
+            log.ancestors.insert(element, names);
+        }
+        if let Some((pad, timestamp_ns, interval_ns)) = parse_schedule_time(&line) {
+            log.schedule.entry(pad).or_default().push((timestamp_ns, interval_ns));
+        }
+        log.threads.observe_line(&line);
+        match parse_caps_notify(&line) {
+            Some((element, pad, caps)) => log.caps_changes.push(CapsChange {
+                timestamp_ns: log.store.latest_timestamp(),
+                element,
+                pad,
+                caps,
+            }),
+            None => log.store.push_line(&line),
+        }
+    }
+    Ok(log)
+}
+
+#[derive(Serialize)]
+struct AnalyzeJson<'a> {
+    log: String,
+    environment: Option<RunEnvironment>,
+    #[serde(flatten)]
+    report: &'a BottleneckReport,
+    rule_violations: Vec<String>,
+}
+
+pub(crate) fn analyze(args: &AnalyzeArgs) -> Result<(), String> {
+    let rules = args.rules.as_deref().map(RuleSet::load).transpose()?;
+    let RecordedLog {
+        store,
+        caps_changes,
+        mut threads,
+        ..
+    } = load_recorded(&args.log)?;
+    let (threads, _) = threads.snapshot(store.latest_timestamp());
+    let report = BottleneckReport::new(&store, threads.threads, caps_changes);
+    let results = rules.map(|rules| rules.evaluate(&store)).unwrap_or_default();
+    let environment = recorded_environment(&args.log);
+
+    if args.format == AnalyzeFormat::Json {
+        let json = AnalyzeJson {
+            log: args.log.display().to_string(),
+            environment,
+            report: &report,
+            rule_violations: results
+                .iter()
+                .filter(|result| !result.passed())
+                .map(|result| result.describe())
+                .collect(),
+        };
+        let text = serde_json::to_string_pretty(&json)
+            .map_err(|err| format!("Failed to serialize the report: {}", err))?;
+        println!("{}", text);
+    } else {
+        println!("{}", args.log.display());
+        if let Some(environment) = &environment {
+            println!("{}", environment.describe());
+        }
+        println!(
+            "{} metric samples, {} latencies over {}",
+            report.samples,
+            report.latencies,
+            format_clock_time(report.duration_ns)
+        );
+        println!();
+        print!("{}", format_stats_table(&store.element_stats()));
+        println!();
+        print!("{}", report.format_text());
+    }
+
+    if let Some(path) = &args.junit {
+        let cases: Vec<TestCase> = results.iter().map(TestCase::from).collect();
+        write_junit(path, &args.log.display().to_string(), &cases)?;
+    }
+    if let Some(path) = &args.html {
+        let title = args.log.display().to_string();
+        let pipeline = recorded_pipeline(&args.log).unwrap_or_default();
+        let html = HtmlReport {
+            title: &title,
+            pipeline: &pipeline,
+            store: &store,
+            results: &results,
+        };
+        std::fs::write(path, html.render())
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+    }
+    if let Some(path) = &args.suggest_rules {
+        let rules = suggest_rules(&store);
+        std::fs::write(path, format_rules_toml(&rules))
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+        eprintln!("Wrote {} suggested rule(s) to {}", rules.len(), path.display());
+    }
+    if args.format == AnalyzeFormat::Json {
+        let violations = results.iter().filter(|result| !result.passed()).count();
+        return match violations {
+            0 => Ok(()),
+            count => Err(format!("{} rule check(s) violated", count)),
+        };
+    }
+    report_rule_results(&results)
+}
+
+/// Cut the lines of a tracer log from `start_ns` to `end_ns` into a log of
+/// their own at `out`, with the metrics parsed from them in
+/// `<out>.metrics.jsonl` and a copy of the metadata file, so the excerpt
+/// replays and analyzes like a whole run. The files the log rolled over to
+/// are read too. Lines without a timestamp go with the last one before
+/// them. Returns how many lines were kept.
+pub fn export_range(log: &Path, start_ns: u64, end_ns: u64, out: &Path) -> Result<usize, String> {
+    let write_error = |err: io::Error| format!("Failed to write {}: {}", out.display(), err);
+    let mut excerpt = BufWriter::new(File::create(out).map_err(write_error)?);
+    let mut store = MetricStore::default();
+    let mut line_ns = 0;
+    let mut kept = 0;
+    for segment in log_segments(log) {
+        let read_error = |err: io::Error| format!("Failed to read {}: {}", segment.display(), err);
+        let file = File::open(&segment).map_err(read_error)?;
+        let reader: Box<dyn BufRead> = match segment.extension() {
+            Some(extension) if extension == "gz" => Box::new(BufReader::new(GzDecoder::new(file))),
+            _ => Box::new(BufReader::new(file)),
+        };
+        for line in reader.lines() {
+            let line = line.map_err(read_error)?;
+            let timestamp_ns = line.split_whitespace().next().and_then(parse_duration_to_ns);
+            if let Some(timestamp_ns) = timestamp_ns {
+                line_ns = timestamp_ns;
+            }
+            if line_ns < start_ns || line_ns > end_ns {
+                continue;
+            }
+            writeln!(excerpt, "{}", line).map_err(write_error)?;
+            store.push_line(&line);
+            kept += 1;
+        }
+    }
+    excerpt.flush().map_err(write_error)?;
+
+    let metrics_path = out.with_extension("metrics.jsonl");
+    let metrics_error =
+        |err: io::Error| format!("Failed to write {}: {}", metrics_path.display(), err);
+    let mut metrics = BufWriter::new(File::create(&metrics_path).map_err(metrics_error)?);
+    let samples = store.samples().iter().flat_map(sample_records);
+    let latencies = store.latencies().iter().filter_map(latency_record);
+    for record in samples.chain(latencies) {
+        writeln!(metrics, "{}", record).map_err(metrics_error)?;
+    }
+    metrics.flush().map_err(metrics_error)?;
+
+    if log.with_extension("json").exists() {
+        std::fs::copy(log.with_extension("json"), out.with_extension("json"))
+            .map_err(write_error)?;
+    }
+    Ok(kept)
+}
+
+/// The metadata file written next to a tracer log.
+fn recorded_metadata(log: &Path) -> Option<serde_json::Value> {
+    let metadata = std::fs::read_to_string(log.with_extension("json")).ok()?;
+    serde_json::from_str(&metadata).ok()
+}
+
+/// The pipeline recorded in the metadata file written next to a tracer log.
+pub fn recorded_pipeline(log: &Path) -> Option<String> {
+    recorded_metadata(log)?["pipeline"].as_str().map(str::to_string)
+}
+
+/// Where the run of a tracer log ran, from its metadata file.
+pub fn recorded_environment(log: &Path) -> Option<RunEnvironment> {
+    serde_json::from_value(recorded_metadata(log)?.get("environment")?.clone()).ok()
+}
+
+/// When the run of a tracer log started, from its metadata file; logs
+/// written before the start was recorded have none.
+pub fn recorded_start(log: &Path) -> Option<DateTime<Local>> {
+    let started = recorded_metadata(log)?["started"].as_str()?.to_string();
+    let started = DateTime::parse_from_rfc3339(&started).ok()?;
+    Some(started.with_timezone(&Local))
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum Record<'a> {
+    Sample(&'a TracingData),
+    Latency(&'a InterLatencyData),
+}
+
+impl Record<'_> {
+    fn timestamp_ns(&self) -> u64 {
+        match self {
+            Record::Sample(sample) => sample.timestamp_ns,
+            Record::Latency(latency) => latency.timestamp_ns,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        fn field<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+
+        match self {
+            Record::Sample(sample) => format!(
+                "sample,{},{},,{},{},{},{},",
+                sample.timestamp_ns,
+                sample.element,
+                field(sample.bitrate),
+                field(sample.framerate),
+                field(sample.proctime_ns),
+                field(sample.jitter_ns),
+            ),
+            Record::Latency(latency) => format!(
+                "latency,{},{},{},,,,,{}",
+                latency.timestamp_ns,
+                latency.from,
+                latency.to,
+                field(parse_duration_to_ns(&latency.time)),
+            ),
+        }
+    }
+}
+
+pub(crate) fn export(args: &ExportArgs) -> Result<(), String> {
+    let rules = args.rules.as_deref().map(RuleSet::load).transpose()?;
+    let RecordedLog {
+        store,
+        ancestors,
+        schedule,
+        ..
+    } = load_recorded(&args.log)?;
+
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(
+            File::create(path).map_err(|err| format!("Failed to create {}: {}", path.display(), err))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+
+    if matches!(args.format, ExportFormat::Folded | ExportFormat::Flamegraph) {
+        // Elements the log has no path for go under the same top-level
+        // pipeline as the rest.
+        let pipeline = ancestors
+            .values()
+            .find_map(|names| names.first())
+            .map_or("pipeline", String::as_str);
+        let stacks = proctime_stacks(&store, &ancestors, pipeline);
+        let text = match args.format {
+            ExportFormat::Folded => format_folded(&stacks),
+            _ => render_flamegraph_svg(
+                &stacks,
+                &format!("Processing time of {}", args.log.display()),
+            ),
+        };
+        return out
+            .write_all(text.as_bytes())
+            .and_then(|_| out.flush())
+            .map_err(|err| format!("Failed to write export: {}", err));
+    }
+
+    if args.format == ExportFormat::Speedscope {
+        let mut proctimes: BTreeMap<String, Vec<(u64, u64)>> = BTreeMap::new();
+        for sample in store.samples() {
+            if let Some(proctime_ns) = sample.proctime_ns {
+                let spans = proctimes.entry(sample.element.clone()).or_default();
+                spans.push((sample.timestamp_ns, proctime_ns));
+            }
+        }
+        let name = args.log.display().to_string();
+        let profile = SpeedscopeFile::new(&name, &proctimes, &schedule);
+        return serde_json::to_writer(&mut out, &profile)
+            .map_err(io::Error::from)
+            .and_then(|_| out.flush())
+            .map_err(|err| format!("Failed to write export: {}", err));
+    }
+
+    if args.format == ExportFormat::Markdown {
+        let pipeline = recorded_pipeline(&args.log).unwrap_or_default();
+        let results = rules.map(|rules| rules.evaluate(&store)).unwrap_or_default();
+        return out
+            .write_all(format_markdown_summary(&pipeline, &store, &results).as_bytes())
+            .and_then(|_| out.flush())
+            .map_err(|err| format!("Failed to write export: {}", err));
+    }
+
+    let mut records: Vec<Record> = store
+        .samples()
+        .iter()
+        .map(Record::Sample)
+        .chain(store.latencies().iter().map(Record::Latency))
+        .collect();
+    records.sort_by_key(Record::timestamp_ns);
+    write_records(out, &records, args.format)
+        .map_err(|err| format!("Failed to write export: {}", err))
+}
+
+fn write_records(mut out: impl Write, records: &[Record], format: ExportFormat) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            writeln!(
+                out,
+                "kind,timestamp_ns,element,to,bitrate,framerate,proctime_ns,jitter_ns,interlatency_ns"
+            )?;
+            for record in records {
+                writeln!(out, "{}", record.csv_row())?;
+            }
+        }
+        ExportFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(&mut out, record)?;
+                writeln!(out)?;
+            }
+        }
+        ExportFormat::Markdown
+        | ExportFormat::Folded
+        | ExportFormat::Flamegraph
+        | ExportFormat::Speedscope => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "summaries and profiles are not written per record",
+            ));
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,1077 @@
+//! The agent/viewer split: `gst-debugger-agent` runs the pipeline on the
+//! target device and streams what the engine reports over gRPC, and
+//! `connect` turns that stream back into an engine for any viewer.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use gst_debugger_core::{
+    Annotation, AudioLevel, AvSync, BusMessage, BusMessageKind, CapsMismatch, ChainLatency,
+    ClockStats, ElementProperty, InterLatencyData, JitterBufferStats, LatencyReport, MediaStream,
+    MemoryUsage, PipelineCommand, PipelineError, PipelineEvent, PipelineState, QueueLevel,
+    RtpSessionStats, RtpSource, RtspSourceStats, RtspStream, Segment, SinkFrames, StreamCollection,
+    StreamDirection, StreamingThread, StreamingThreads, ThreadElement, TracingData, WebRtcStats,
+    WebRtcStream,
+};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::{InterceptedService, Interceptor};
+use tonic::transport::{Channel, Endpoint, Server};
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::tls::{self, Security, SecurityArgs, TlsConnect};
+use crate::{spawn_engine, EngineHandle, EngineOutput, MetricSenders};
+
+mod proto {
+    tonic::include_proto!("gst_debugger.agent");
+}
+
+use proto::agent_client::AgentClient;
+use proto::agent_server::{Agent, AgentServer};
+use proto::agent_message::Message;
+use proto::command::Command;
+use proto::event::Event;
+
+/// Messages a slow viewer may fall behind by before it misses some.
+const VIEWER_BACKLOG: usize = 4096;
+/// Samples, latencies and events kept for viewers that connect later.
+const HISTORY_LIMIT: usize = 100_000;
+/// Log lines kept for viewers that connect later.
+const LOG_HISTORY: usize = 500;
+
+impl From<TracingData> for proto::Sample {
+    fn from(sample: TracingData) -> Self {
+        proto::Sample {
+            timestamp_ns: sample.timestamp_ns,
+            element: sample.element,
+            bitrate: sample.bitrate,
+            framerate: sample.framerate,
+            proctime_ns: sample.proctime_ns,
+            jitter_ns: sample.jitter_ns,
+        }
+    }
+}
+
+impl From<proto::Sample> for TracingData {
+    fn from(sample: proto::Sample) -> Self {
+        TracingData {
+            timestamp_ns: sample.timestamp_ns,
+            element: sample.element,
+            bitrate: sample.bitrate,
+            framerate: sample.framerate,
+            proctime_ns: sample.proctime_ns,
+            jitter_ns: sample.jitter_ns,
+        }
+    }
+}
+
+impl From<InterLatencyData> for proto::Latency {
+    fn from(latency: InterLatencyData) -> Self {
+        proto::Latency {
+            timestamp_ns: latency.timestamp_ns,
+            from: latency.from,
+            to: latency.to,
+            time: latency.time,
+        }
+    }
+}
+
+impl From<proto::Latency> for InterLatencyData {
+    fn from(latency: proto::Latency) -> Self {
+        InterLatencyData {
+            timestamp_ns: latency.timestamp_ns,
+            from: latency.from,
+            to: latency.to,
+            time: latency.time,
+        }
+    }
+}
+
+impl From<Annotation> for proto::Annotation {
+    fn from(annotation: Annotation) -> Self {
+        proto::Annotation {
+            timestamp_ns: annotation.timestamp_ns,
+            text: annotation.text,
+        }
+    }
+}
+
+impl From<proto::Annotation> for Annotation {
+    fn from(annotation: proto::Annotation) -> Self {
+        Annotation {
+            timestamp_ns: annotation.timestamp_ns,
+            text: annotation.text,
+        }
+    }
+}
+
+fn state_to_proto(state: PipelineState) -> proto::State {
+    match state {
+        PipelineState::Playing => proto::State::Playing,
+        PipelineState::Paused => proto::State::Paused,
+        PipelineState::Stopped => proto::State::Stopped,
+        PipelineState::Restarting => proto::State::Restarting,
+    }
+}
+
+fn state_from_proto(state: proto::State) -> PipelineState {
+    match state {
+        proto::State::Playing => PipelineState::Playing,
+        proto::State::Paused => PipelineState::Paused,
+        proto::State::Stopped => PipelineState::Stopped,
+        proto::State::Restarting => PipelineState::Restarting,
+    }
+}
+
+fn bus_kind_to_proto(kind: BusMessageKind) -> proto::BusMessageKind {
+    match kind {
+        BusMessageKind::StateChanged => proto::BusMessageKind::StateChanged,
+        BusMessageKind::NewClock => proto::BusMessageKind::NewClock,
+        BusMessageKind::ClockLost => proto::BusMessageKind::ClockLost,
+        BusMessageKind::Tag => proto::BusMessageKind::Tag,
+        BusMessageKind::Buffering => proto::BusMessageKind::Buffering,
+        BusMessageKind::Latency => proto::BusMessageKind::Latency,
+        BusMessageKind::Warning => proto::BusMessageKind::Warning,
+        BusMessageKind::Eos => proto::BusMessageKind::Eos,
+    }
+}
+
+fn bus_kind_from_proto(kind: proto::BusMessageKind) -> BusMessageKind {
+    match kind {
+        proto::BusMessageKind::StateChanged => BusMessageKind::StateChanged,
+        proto::BusMessageKind::NewClock => BusMessageKind::NewClock,
+        proto::BusMessageKind::ClockLost => BusMessageKind::ClockLost,
+        proto::BusMessageKind::Tag => BusMessageKind::Tag,
+        proto::BusMessageKind::Buffering => BusMessageKind::Buffering,
+        proto::BusMessageKind::Latency => BusMessageKind::Latency,
+        proto::BusMessageKind::Warning => BusMessageKind::Warning,
+        proto::BusMessageKind::Eos => BusMessageKind::Eos,
+    }
+}
+
+impl From<WebRtcStream> for proto::WebRtcStream {
+    fn from(stream: WebRtcStream) -> Self {
+        let direction = match stream.direction {
+            StreamDirection::Inbound => proto::StreamDirection::Inbound,
+            StreamDirection::Outbound => proto::StreamDirection::Outbound,
+        };
+        proto::WebRtcStream {
+            direction: direction as i32,
+            ssrc: stream.ssrc,
+            kind: stream.kind,
+            bitrate: stream.bitrate,
+            packets: stream.packets,
+            packets_lost: stream.packets_lost,
+            fraction_lost: stream.fraction_lost,
+            jitter_ns: stream.jitter_ns,
+            rtt_ns: stream.rtt_ns,
+        }
+    }
+}
+
+impl From<proto::WebRtcStream> for WebRtcStream {
+    fn from(stream: proto::WebRtcStream) -> Self {
+        let direction = match proto::StreamDirection::try_from(stream.direction) {
+            Ok(proto::StreamDirection::Outbound) => StreamDirection::Outbound,
+            _ => StreamDirection::Inbound,
+        };
+        WebRtcStream {
+            direction,
+            ssrc: stream.ssrc,
+            kind: stream.kind,
+            bitrate: stream.bitrate,
+            packets: stream.packets,
+            packets_lost: stream.packets_lost,
+            fraction_lost: stream.fraction_lost,
+            jitter_ns: stream.jitter_ns,
+            rtt_ns: stream.rtt_ns,
+        }
+    }
+}
+
+impl From<RtpSource> for proto::RtpSource {
+    fn from(source: RtpSource) -> Self {
+        proto::RtpSource {
+            ssrc: source.ssrc,
+            internal: source.internal,
+            cname: source.cname,
+            bitrate: source.bitrate,
+            packets: source.packets,
+            packets_lost: source.packets_lost,
+            fraction_lost: source.fraction_lost,
+            jitter_ns: source.jitter_ns,
+            rtt_ns: source.rtt_ns,
+        }
+    }
+}
+
+impl From<proto::RtpSource> for RtpSource {
+    fn from(source: proto::RtpSource) -> Self {
+        RtpSource {
+            ssrc: source.ssrc,
+            internal: source.internal,
+            cname: source.cname,
+            bitrate: source.bitrate,
+            packets: source.packets,
+            packets_lost: source.packets_lost,
+            fraction_lost: source.fraction_lost,
+            jitter_ns: source.jitter_ns,
+            rtt_ns: source.rtt_ns,
+        }
+    }
+}
+
+impl From<RtspStream> for proto::RtspStream {
+    fn from(stream: RtspStream) -> Self {
+        proto::RtspStream {
+            pad: stream.pad,
+            media: stream.media,
+            bitrate: stream.bitrate,
+            packets: stream.packets,
+            idle_ns: stream.idle_ns,
+        }
+    }
+}
+
+impl From<proto::RtspStream> for RtspStream {
+    fn from(stream: proto::RtspStream) -> Self {
+        RtspStream {
+            pad: stream.pad,
+            media: stream.media,
+            bitrate: stream.bitrate,
+            packets: stream.packets,
+            idle_ns: stream.idle_ns,
+        }
+    }
+}
+
+impl From<MediaStream> for proto::MediaStream {
+    fn from(stream: MediaStream) -> Self {
+        proto::MediaStream {
+            stream_id: stream.stream_id,
+            kind: stream.kind,
+            codec: stream.codec,
+            language: stream.language,
+            selected: stream.selected,
+        }
+    }
+}
+
+impl From<proto::MediaStream> for MediaStream {
+    fn from(stream: proto::MediaStream) -> Self {
+        MediaStream {
+            stream_id: stream.stream_id,
+            kind: stream.kind,
+            codec: stream.codec,
+            language: stream.language,
+            selected: stream.selected,
+        }
+    }
+}
+
+impl From<ChainLatency> for proto::ChainLatency {
+    fn from(chain: ChainLatency) -> Self {
+        proto::ChainLatency {
+            sink: chain.sink,
+            live: chain.live,
+            min_ns: chain.min_ns,
+            max_ns: chain.max_ns,
+        }
+    }
+}
+
+impl From<proto::ChainLatency> for ChainLatency {
+    fn from(chain: proto::ChainLatency) -> Self {
+        ChainLatency {
+            sink: chain.sink,
+            live: chain.live,
+            min_ns: chain.min_ns,
+            max_ns: chain.max_ns,
+        }
+    }
+}
+
+impl From<StreamingThread> for proto::StreamingThread {
+    fn from(thread: StreamingThread) -> Self {
+        proto::StreamingThread {
+            thread: thread.thread,
+            elements: thread
+                .elements
+                .into_iter()
+                .map(|element| proto::ThreadElement {
+                    element: element.element,
+                    busy: element.busy,
+                    heavy: element.heavy,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::StreamingThread> for StreamingThread {
+    fn from(thread: proto::StreamingThread) -> Self {
+        StreamingThread {
+            thread: thread.thread,
+            elements: thread
+                .elements
+                .into_iter()
+                .map(|element| ThreadElement {
+                    element: element.element,
+                    busy: element.busy,
+                    heavy: element.heavy,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<CapsMismatch> for proto::CapsMismatch {
+    fn from(caps: CapsMismatch) -> Self {
+        proto::CapsMismatch {
+            src_pad: caps.src_pad,
+            sink_pad: caps.sink_pad,
+            offered: caps.offered,
+            accepted: caps.accepted,
+        }
+    }
+}
+
+impl From<proto::CapsMismatch> for CapsMismatch {
+    fn from(caps: proto::CapsMismatch) -> Self {
+        CapsMismatch {
+            src_pad: caps.src_pad,
+            sink_pad: caps.sink_pad,
+            offered: caps.offered,
+            accepted: caps.accepted,
+        }
+    }
+}
+
+fn event_to_proto(event: PipelineEvent) -> Event {
+    match event {
+        PipelineEvent::State(state) => Event::State(state_to_proto(state) as i32),
+        PipelineEvent::Position {
+            position_ns,
+            duration_ns,
+        } => Event::Position(proto::Position {
+            position_ns,
+            duration_ns,
+        }),
+        PipelineEvent::ElementEos(element) => Event::Eos(element),
+        PipelineEvent::Properties {
+            element,
+            properties,
+        } => Event::Properties(proto::Properties {
+            element,
+            properties: properties
+                .into_iter()
+                .map(|property| proto::Property {
+                    name: property.name,
+                    type_name: property.type_name,
+                    value: property.value,
+                    writable: property.writable,
+                })
+                .collect(),
+        }),
+        PipelineEvent::PropertyChanged {
+            element,
+            name,
+            value,
+        } => Event::PropertyChanged(proto::PropertyChanged {
+            element,
+            name,
+            value,
+        }),
+        PipelineEvent::Notice(message) => Event::Notice(message),
+        PipelineEvent::Error(error) => Event::Error(proto::Error {
+            message: error.message,
+            debug: error.debug,
+            element: error.element,
+            recent_lines: error.recent_lines,
+            caps: error.caps.map(Into::into),
+        }),
+        PipelineEvent::Restarting { attempt, delay } => Event::Restarting(proto::Restarting {
+            attempt,
+            delay_ms: delay.as_millis() as u64,
+        }),
+        PipelineEvent::Bus(message) => Event::Bus(proto::BusMessage {
+            timestamp_ns: message.timestamp_ns,
+            kind: bus_kind_to_proto(message.kind) as i32,
+            source: message.source,
+            detail: message.detail,
+        }),
+        PipelineEvent::AudioLevel(level) => Event::AudioLevel(proto::AudioLevel {
+            timestamp_ns: level.timestamp_ns,
+            element: level.element,
+            rms_db: level.rms_db,
+            peak_db: level.peak_db,
+        }),
+        PipelineEvent::WebRtcStats(stats) => Event::WebrtcStats(proto::WebRtcStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            streams: stats.streams.into_iter().map(Into::into).collect(),
+        }),
+        PipelineEvent::JitterBuffer(stats) => Event::JitterBuffer(proto::JitterBufferStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            pushed: stats.pushed,
+            lost: stats.lost,
+            late: stats.late,
+            duplicates: stats.duplicates,
+            avg_jitter_ns: stats.avg_jitter_ns,
+        }),
+        PipelineEvent::RtpSession(stats) => Event::RtpSession(proto::RtpSessionStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            sources: stats.sources.into_iter().map(Into::into).collect(),
+        }),
+        PipelineEvent::Rtsp(stats) => Event::Rtsp(proto::RtspSourceStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            location: stats.location,
+            state: stats.state,
+            transport: stats.transport,
+            sdp: stats.sdp,
+            streams: stats.streams.into_iter().map(Into::into).collect(),
+            stalled: stats.stalled,
+        }),
+        PipelineEvent::Streams(collection) => Event::Streams(proto::StreamCollection {
+            timestamp_ns: collection.timestamp_ns,
+            element: collection.element,
+            streams: collection.streams.into_iter().map(Into::into).collect(),
+        }),
+        PipelineEvent::Clock(stats) => Event::Clock(proto::ClockStats {
+            timestamp_ns: stats.timestamp_ns,
+            name: stats.name,
+            kind: stats.kind,
+            provider: stats.provider,
+            rate: stats.rate,
+            offset_ns: stats.offset_ns,
+            running_time_ns: stats.running_time_ns,
+            drift_ns: stats.drift_ns,
+        }),
+        PipelineEvent::AvSync(sync) => Event::AvSync(proto::AvSync {
+            timestamp_ns: sync.timestamp_ns,
+            video_sink: sync.video_sink,
+            audio_sink: sync.audio_sink,
+            offset_ns: sync.offset_ns,
+            limit_ns: sync.limit_ns,
+        }),
+        PipelineEvent::SinkFrames(frames) => Event::SinkFrames(proto::SinkFrames {
+            timestamp_ns: frames.timestamp_ns,
+            sink: frames.sink,
+            rendered: frames.rendered,
+            dropped: frames.dropped,
+            drop_percent: frames.drop_percent,
+            limit_percent: frames.limit_percent,
+        }),
+        PipelineEvent::Latency(report) => Event::Latency(proto::LatencyReport {
+            timestamp_ns: report.timestamp_ns,
+            live: report.live,
+            min_ns: report.min_ns,
+            max_ns: report.max_ns,
+            configured_ns: report.configured_ns,
+            chains: report.chains.into_iter().map(Into::into).collect(),
+        }),
+        PipelineEvent::Segment(segment) => Event::Segment(proto::Segment {
+            timestamp_ns: segment.timestamp_ns,
+            element: segment.element,
+            location: segment.location,
+            running_time_ns: segment.running_time_ns,
+            duration_ns: segment.duration_ns,
+            size_bytes: segment.size_bytes,
+        }),
+        PipelineEvent::Memory(usage) => Event::Memory(proto::MemoryUsage {
+            timestamp_ns: usage.timestamp_ns,
+            rss_bytes: usage.rss_bytes,
+            live_objects: usage.live_objects.into_iter().collect(),
+            growth_bytes_per_s: usage.growth_bytes_per_s,
+            limit_bytes_per_s: usage.limit_bytes_per_s,
+        }),
+        PipelineEvent::QueueLevel(level) => Event::QueueLevel(proto::QueueLevel {
+            timestamp_ns: level.timestamp_ns,
+            queue: level.queue,
+            buffers: level.buffers,
+            bytes: level.bytes,
+            time_ns: level.time_ns,
+            fill: level.fill,
+        }),
+        PipelineEvent::Annotation(annotation) => Event::Annotation(annotation.into()),
+        PipelineEvent::LogFile { path, base_ns } => Event::LogFile(proto::LogFile {
+            path: path.display().to_string(),
+            base_ns,
+        }),
+        PipelineEvent::Threads(threads) => Event::Threads(proto::StreamingThreads {
+            timestamp_ns: threads.timestamp_ns,
+            threads: threads.threads.into_iter().map(Into::into).collect(),
+        }),
+        PipelineEvent::Caps { element, pad, caps } => {
+            Event::Caps(proto::Caps { element, pad, caps })
+        }
+    }
+}
+
+fn event_from_proto(event: Event) -> PipelineEvent {
+    match event {
+        Event::State(state) => PipelineEvent::State(state_from_proto(
+            proto::State::try_from(state).unwrap_or(proto::State::Stopped),
+        )),
+        Event::Position(position) => PipelineEvent::Position {
+            position_ns: position.position_ns,
+            duration_ns: position.duration_ns,
+        },
+        Event::Eos(element) => PipelineEvent::ElementEos(element),
+        Event::Properties(properties) => PipelineEvent::Properties {
+            element: properties.element,
+            properties: properties
+                .properties
+                .into_iter()
+                .map(|property| ElementProperty {
+                    name: property.name,
+                    type_name: property.type_name,
+                    value: property.value,
+                    writable: property.writable,
+                })
+                .collect(),
+        },
+        Event::PropertyChanged(changed) => PipelineEvent::PropertyChanged {
+            element: changed.element,
+            name: changed.name,
+            value: changed.value,
+        },
+        Event::Notice(message) => PipelineEvent::Notice(message),
+        Event::Error(error) => PipelineEvent::Error(PipelineError {
+            message: error.message,
+            debug: error.debug,
+            element: error.element,
+            recent_lines: error.recent_lines,
+            caps: error.caps.map(Into::into),
+        }),
+        Event::Restarting(restarting) => PipelineEvent::Restarting {
+            attempt: restarting.attempt,
+            delay: Duration::from_millis(restarting.delay_ms),
+        },
+        Event::Bus(message) => PipelineEvent::Bus(BusMessage {
+            timestamp_ns: message.timestamp_ns,
+            kind: bus_kind_from_proto(
+                proto::BusMessageKind::try_from(message.kind)
+                    .unwrap_or(proto::BusMessageKind::Warning),
+            ),
+            source: message.source,
+            detail: message.detail,
+        }),
+        Event::AudioLevel(level) => PipelineEvent::AudioLevel(AudioLevel {
+            timestamp_ns: level.timestamp_ns,
+            element: level.element,
+            rms_db: level.rms_db,
+            peak_db: level.peak_db,
+        }),
+        Event::WebrtcStats(stats) => PipelineEvent::WebRtcStats(WebRtcStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            streams: stats.streams.into_iter().map(Into::into).collect(),
+        }),
+        Event::JitterBuffer(stats) => PipelineEvent::JitterBuffer(JitterBufferStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            pushed: stats.pushed,
+            lost: stats.lost,
+            late: stats.late,
+            duplicates: stats.duplicates,
+            avg_jitter_ns: stats.avg_jitter_ns,
+        }),
+        Event::RtpSession(stats) => PipelineEvent::RtpSession(RtpSessionStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            sources: stats.sources.into_iter().map(Into::into).collect(),
+        }),
+        Event::Rtsp(stats) => PipelineEvent::Rtsp(RtspSourceStats {
+            timestamp_ns: stats.timestamp_ns,
+            element: stats.element,
+            location: stats.location,
+            state: stats.state,
+            transport: stats.transport,
+            sdp: stats.sdp,
+            streams: stats.streams.into_iter().map(Into::into).collect(),
+            stalled: stats.stalled,
+        }),
+        Event::Streams(collection) => PipelineEvent::Streams(StreamCollection {
+            timestamp_ns: collection.timestamp_ns,
+            element: collection.element,
+            streams: collection.streams.into_iter().map(Into::into).collect(),
+        }),
+        Event::Clock(stats) => PipelineEvent::Clock(ClockStats {
+            timestamp_ns: stats.timestamp_ns,
+            name: stats.name,
+            kind: stats.kind,
+            provider: stats.provider,
+            rate: stats.rate,
+            offset_ns: stats.offset_ns,
+            running_time_ns: stats.running_time_ns,
+            drift_ns: stats.drift_ns,
+        }),
+        Event::AvSync(sync) => PipelineEvent::AvSync(AvSync {
+            timestamp_ns: sync.timestamp_ns,
+            video_sink: sync.video_sink,
+            audio_sink: sync.audio_sink,
+            offset_ns: sync.offset_ns,
+            limit_ns: sync.limit_ns,
+        }),
+        Event::SinkFrames(frames) => PipelineEvent::SinkFrames(SinkFrames {
+            timestamp_ns: frames.timestamp_ns,
+            sink: frames.sink,
+            rendered: frames.rendered,
+            dropped: frames.dropped,
+            drop_percent: frames.drop_percent,
+            limit_percent: frames.limit_percent,
+        }),
+        Event::Latency(report) => PipelineEvent::Latency(LatencyReport {
+            timestamp_ns: report.timestamp_ns,
+            live: report.live,
+            min_ns: report.min_ns,
+            max_ns: report.max_ns,
+            configured_ns: report.configured_ns,
+            chains: report.chains.into_iter().map(Into::into).collect(),
+        }),
+        Event::Segment(segment) => PipelineEvent::Segment(Segment {
+            timestamp_ns: segment.timestamp_ns,
+            element: segment.element,
+            location: segment.location,
+            running_time_ns: segment.running_time_ns,
+            duration_ns: segment.duration_ns,
+            size_bytes: segment.size_bytes,
+        }),
+        Event::Memory(usage) => PipelineEvent::Memory(MemoryUsage {
+            timestamp_ns: usage.timestamp_ns,
+            rss_bytes: usage.rss_bytes,
+            live_objects: usage.live_objects.into_iter().collect(),
+            growth_bytes_per_s: usage.growth_bytes_per_s,
+            limit_bytes_per_s: usage.limit_bytes_per_s,
+        }),
+        Event::QueueLevel(level) => PipelineEvent::QueueLevel(QueueLevel {
+            timestamp_ns: level.timestamp_ns,
+            queue: level.queue,
+            buffers: level.buffers,
+            bytes: level.bytes,
+            time_ns: level.time_ns,
+            fill: level.fill,
+        }),
+        Event::Annotation(annotation) => PipelineEvent::Annotation(annotation.into()),
+        Event::LogFile(log) => PipelineEvent::LogFile {
+            path: log.path.into(),
+            base_ns: log.base_ns,
+        },
+        Event::Threads(threads) => PipelineEvent::Threads(StreamingThreads {
+            timestamp_ns: threads.timestamp_ns,
+            threads: threads.threads.into_iter().map(Into::into).collect(),
+        }),
+        Event::Caps(caps) => PipelineEvent::Caps {
+            element: caps.element,
+            pad: caps.pad,
+            caps: caps.caps,
+        },
+    }
+}
+
+/// The command to forward to the agent, `None` for those a viewer keeps to
+/// itself.
+fn command_to_proto(command: PipelineCommand) -> Option<Command> {
+    Some(match command {
+        PipelineCommand::Play => Command::Play(proto::Empty {}),
+        PipelineCommand::Pause => Command::Pause(proto::Empty {}),
+        PipelineCommand::Stop => return None,
+        PipelineCommand::Restart => Command::Restart(proto::Empty {}),
+        PipelineCommand::Relaunch(tracing) => Command::Relaunch(tracing),
+        PipelineCommand::Seek(position_ns) => Command::SeekNs(position_ns),
+        PipelineCommand::SendEos => Command::SendEos(proto::Empty {}),
+        PipelineCommand::SetTracers(tracing) => Command::SetTracers(tracing),
+        PipelineCommand::QueryProperties(element) => Command::QueryProperties(element),
+        PipelineCommand::SetProperty {
+            element,
+            name,
+            value,
+        } => Command::SetProperty(proto::PropertyChanged {
+            element,
+            name,
+            value,
+        }),
+        PipelineCommand::SelectStreams {
+            element,
+            stream_ids,
+        } => Command::SelectStreams(proto::SelectStreams {
+            element,
+            stream_ids,
+        }),
+        PipelineCommand::Annotate(annotation) => Command::Annotate(annotation.into()),
+    })
+}
+
+fn command_from_proto(command: Command) -> PipelineCommand {
+    match command {
+        Command::Play(_) => PipelineCommand::Play,
+        Command::Pause(_) => PipelineCommand::Pause,
+        Command::Restart(_) => PipelineCommand::Restart,
+        Command::SendEos(_) => PipelineCommand::SendEos,
+        Command::SeekNs(position_ns) => PipelineCommand::Seek(position_ns),
+        Command::Relaunch(tracing) => PipelineCommand::Relaunch(tracing),
+        Command::SetTracers(tracing) => PipelineCommand::SetTracers(tracing),
+        Command::QueryProperties(element) => PipelineCommand::QueryProperties(element),
+        Command::SetProperty(property) => PipelineCommand::SetProperty {
+            element: property.element,
+            name: property.name,
+            value: property.value,
+        },
+        Command::SelectStreams(selection) => PipelineCommand::SelectStreams {
+            element: selection.element,
+            stream_ids: selection.stream_ids,
+        },
+        Command::Annotate(annotation) => PipelineCommand::Annotate(annotation.into()),
+    }
+}
+
+/// What the agent has streamed so far, replayed to every viewer that
+/// connects so it starts from the same history as the others.
+#[derive(Default)]
+struct History {
+    messages: VecDeque<proto::AgentMessage>,
+    log: VecDeque<proto::AgentMessage>,
+}
+
+impl History {
+    fn push(&mut self, message: proto::AgentMessage) {
+        let (queue, limit) = match message.message {
+            Some(Message::Log(_)) => (&mut self.log, LOG_HISTORY),
+            _ => (&mut self.messages, HISTORY_LIMIT),
+        };
+        if queue.len() == limit {
+            queue.pop_front();
+        }
+        queue.push_back(message);
+    }
+
+    fn snapshot(&self) -> Vec<proto::AgentMessage> {
+        self.messages.iter().chain(&self.log).cloned().collect()
+    }
+}
+
+struct AgentService {
+    hello: proto::Hello,
+    history: Arc<Mutex<History>>,
+    live: broadcast::Sender<proto::AgentMessage>,
+    control: mpsc::Sender<PipelineCommand>,
+}
+
+type WatchStream = Pin<Box<dyn Stream<Item = Result<proto::AgentMessage, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Agent for AgentService {
+    type WatchStream = WatchStream;
+
+    async fn watch(
+        &self,
+        request: Request<proto::WatchRequest>,
+    ) -> Result<Response<WatchStream>, Status> {
+        if let Some(peer) = request.remote_addr() {
+            eprintln!("Viewer connected from {}", peer);
+        }
+        let hello = proto::AgentMessage {
+            message: Some(Message::Hello(self.hello.clone())),
+        };
+        // Subscribe while holding the history so no message is missed or
+        // sent twice between the snapshot and the live stream.
+        let (history, live) = {
+            let history = self.history.lock().unwrap();
+            (history.snapshot(), self.live.subscribe())
+        };
+        let history = futures::stream::iter(history.into_iter().map(Ok));
+        let live = futures::stream::unfold(live, |mut live| async move {
+            let message = match live.recv().await {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(missed)) => proto::AgentMessage {
+                    message: Some(Message::Event(proto::Event {
+                        event: Some(Event::Notice(format!(
+                            "Viewer fell behind, {} messages were dropped",
+                            missed
+                        ))),
+                    })),
+                },
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            Some((Ok(message), live))
+        });
+        let stream = futures::stream::once(async { Ok(hello) }).chain(history).chain(live);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn control(
+        &self,
+        request: Request<proto::Command>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let command = request
+            .into_inner()
+            .command
+            .ok_or_else(|| Status::invalid_argument("empty command"))?;
+        self.control
+            .send(command_from_proto(command))
+            .await
+            .map_err(|_| Status::unavailable("the pipeline has stopped"))?;
+        Ok(Response::new(proto::CommandReply {}))
+    }
+}
+
+/// Rejects calls without the agent's token.
+#[derive(Clone)]
+struct RequireToken(Option<String>);
+
+impl Interceptor for RequireToken {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(token) = &self.0 else {
+            return Ok(request);
+        };
+        let header = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        if tls::bearer_matches(header, token) {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("missing or wrong token"))
+        }
+    }
+}
+
+/// Adds the viewer's agent token to every call.
+#[derive(Clone)]
+struct SendToken(Option<MetadataValue<Ascii>>);
+
+impl Interceptor for SendToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = &self.0 {
+            request.metadata_mut().insert("authorization", value.clone());
+        }
+        Ok(request)
+    }
+}
+
+type Client = AgentClient<InterceptedService<Channel, SendToken>>;
+
+/// What the agent announces to every viewer before streaming.
+pub struct AgentInfo {
+    pub pipeline: String,
+    pub tracing: String,
+    pub restart_on_failure: bool,
+}
+
+/// Stream everything the engine reports to the viewers connected on `addr`
+/// until the pipeline stops or Ctrl+C is pressed.
+pub async fn serve_agent(
+    addr: SocketAddr,
+    security: &SecurityArgs,
+    info: AgentInfo,
+    engine: EngineHandle,
+    mut output: EngineOutput,
+) -> Result<(), String> {
+    let Security { tls, token } = Security::load(security)?;
+    let (live, _) = broadcast::channel(VIEWER_BACKLOG);
+    let history = Arc::new(Mutex::new(History::default()));
+    let service = AgentService {
+        hello: proto::Hello {
+            pipeline: info.pipeline,
+            tracing: info.tracing,
+            restart_on_failure: info.restart_on_failure,
+        },
+        history: history.clone(),
+        live: live.clone(),
+        control: engine.control(),
+    };
+    let service = InterceptedService::new(AgentServer::new(service), RequireToken(token));
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let shutdown = async {
+        let _ = shutdown_rx.await;
+    };
+    let server = match tls {
+        Some(acceptor) => {
+            let listener = TcpListener::bind(addr)
+                .await
+                .map_err(|err| format!("Failed to listen on {}: {}", addr, err))?;
+            tokio::spawn(async move {
+                tls::serve(listener, acceptor, service, shutdown).await;
+                Ok(())
+            })
+        }
+        None => tokio::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_shutdown(addr, shutdown),
+        ),
+    };
+    eprintln!("Waiting for viewers on {}", addr);
+
+    let publish = |message: Message| {
+        let message = proto::AgentMessage {
+            message: Some(message),
+        };
+        let mut history = history.lock().unwrap();
+        history.push(message.clone());
+        let _ = live.send(message);
+    };
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            Some(sample) = output.data.recv() => publish(Message::Sample(sample.into())),
+            Some(latency) = output.latency.recv() => publish(Message::Latency(latency.into())),
+            Some(line) = output.log.recv() => publish(Message::Log(line)),
+            event = output.events.recv() => {
+                let Some(event) = event else { break };
+                let stopped = matches!(event, PipelineEvent::State(PipelineState::Stopped))
+                    && !info.restart_on_failure;
+                if let PipelineEvent::Error(error) = &event {
+                    eprintln!("Error: {}", error.message);
+                }
+                publish(Message::Event(proto::Event {
+                    event: Some(event_to_proto(event)),
+                }));
+                if stopped {
+                    break;
+                }
+            }
+        }
+    }
+
+    engine.shutdown().await;
+    let _ = shutdown_tx.send(());
+    match server.await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(err)) => Err(format!("Agent server on {} failed: {}", addr, err)),
+        Err(err) => Err(format!("Agent server on {} failed: {}", addr, err)),
+    }
+}
+
+/// `err` and its causes, since a transport error alone only says
+/// "transport error".
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.ends_with(&cause_message) {
+            message.push_str(&format!(": {}", cause_message));
+        }
+        source = cause.source();
+    }
+    message
+}
+
+/// Connect to an agent and present its stream as a local engine. Stopping
+/// the engine only disconnects; the pipeline keeps running on the agent.
+pub(crate) async fn connect(
+    addr: &str,
+    tls_ca: Option<&Path>,
+    token: Option<String>,
+) -> Result<(AgentInfo, EngineHandle, EngineOutput), String> {
+    let uri = if addr.contains("://") {
+        addr.to_string()
+    } else {
+        format!("http://{}", addr)
+    };
+    let endpoint = Endpoint::from_shared(uri)
+        .map_err(|err| format!("Invalid agent address {}: {}", addr, err))?;
+    let channel = if endpoint.uri().scheme_str() == Some("https") {
+        endpoint.connect_with_connector(TlsConnect::new(tls_ca)?).await
+    } else {
+        endpoint.connect().await
+    }
+    .map_err(|err| format!("Failed to connect to agent at {}: {}", addr, error_chain(&err)))?;
+    let token = token
+        .map(|token| format!("Bearer {}", token).parse())
+        .transpose()
+        .map_err(|_| "The agent token must be printable ASCII".to_string())?;
+    let mut client = AgentClient::with_interceptor(channel, SendToken(token));
+    let mut stream = client
+        .watch(proto::WatchRequest {})
+        .await
+        .map_err(|status| format!("Agent at {} refused to stream: {}", addr, status.message()))?
+        .into_inner();
+    let hello = match stream.message().await {
+        Ok(Some(proto::AgentMessage {
+            message: Some(Message::Hello(hello)),
+        })) => hello,
+        _ => return Err(format!("{} did not answer like a gst-debugger-agent", addr)),
+    };
+
+    let info = AgentInfo {
+        pipeline: hello.pipeline,
+        tracing: hello.tracing,
+        restart_on_failure: hello.restart_on_failure,
+    };
+    let (engine, output) = spawn_engine(move |senders, control_rx, event_tx| {
+        follow_agent(client, stream, senders, control_rx, event_tx)
+    });
+    Ok((info, engine, output))
+}
+
+async fn follow_agent(
+    mut client: Client,
+    mut stream: Streaming<proto::AgentMessage>,
+    senders: MetricSenders,
+    mut control_rx: mpsc::Receiver<PipelineCommand>,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    loop {
+        tokio::select! {
+            message = stream.message() => match message {
+                Ok(Some(proto::AgentMessage { message: Some(message) })) => match message {
+                    Message::Sample(sample) => {
+                        let _ = senders.data.send(sample.into()).await;
+                    }
+                    Message::Latency(latency) => {
+                        let _ = senders.latency.send(latency.into()).await;
+                    }
+                    Message::Log(line) => {
+                        let _ = senders.log.try_send(line);
+                    }
+                    Message::Event(proto::Event { event: Some(event) }) => {
+                        let _ = event_tx.send(event_from_proto(event)).await;
+                    }
+                    Message::Event(_) | Message::Hello(_) => {}
+                },
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice("The agent closed the connection".to_string()))
+                        .await;
+                    break;
+                }
+                Err(status) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Error(PipelineError {
+                            message: format!("Lost the connection to the agent: {}", status.message()),
+                            debug: String::new(),
+                            element: None,
+                            recent_lines: Vec::new(),
+                            caps: None,
+                        }))
+                        .await;
+                    break;
+                }
+            },
+            command = control_rx.recv() => {
+                let Some(command) = command.and_then(command_to_proto) else {
+                    return;
+                };
+                let request = proto::Command { command: Some(command) };
+                if let Err(status) = client.control(request).await {
+                    let message = format!("Agent rejected the command: {}", status.message());
+                    let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+                }
+            }
+        }
+    }
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+}
@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use gst_debugger_core::{
+    parse_annotation_line, parse_caps_notify, parse_eos_event, parse_gst_tracer_output,
+    parse_interlatency, parse_line_timestamp, parse_queue_level, Annotation, PipelineCommand,
+    PipelineError, PipelineEvent, PipelineState, ScheduleJitter,
+};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::cli::ReplayArgs;
+use crate::offline::recorded_pipeline;
+use crate::{spawn_engine, EngineHandle, EngineOutput, MetricSenders};
+
+enum Flow {
+    Continue,
+    Restart,
+    Stop,
+    Closed,
+}
+
+/// The pipeline to draw for a replayed log: `--pipeline` if given, otherwise
+/// the one recorded in the metadata file written next to the log.
+pub(crate) fn replay_pipeline(args: &ReplayArgs) -> String {
+    args.pipeline
+        .clone()
+        .or_else(|| recorded_pipeline(&args.log))
+        .unwrap_or_default()
+}
+
+pub(crate) fn spawn_replay(args: &ReplayArgs) -> (EngineHandle, EngineOutput) {
+    let path = args.log.clone();
+    let speed = args.speed;
+    spawn_engine(move |senders, control_rx, event_tx| {
+        replay_log(path, speed, senders, control_rx, event_tx)
+    })
+}
+
+/// Where the markers placed while replaying `log` are kept, as
+/// `<name>.bookmarks.jsonl` next to it, since the log itself is left as
+/// recorded.
+fn bookmarks_path(log: &Path) -> PathBuf {
+    log.with_extension("bookmarks.jsonl")
+}
+
+async fn load_bookmarks(log: &Path) -> Vec<Annotation> {
+    let text = tokio::fs::read_to_string(bookmarks_path(log)).await.unwrap_or_default();
+    text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+async fn save_bookmark(
+    log: &Path,
+    annotation: &Annotation,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) {
+    let path = bookmarks_path(log);
+    let Ok(mut line) = serde_json::to_string(annotation) else {
+        return;
+    };
+    line.push('\n');
+    let written = async {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path).await?;
+        file.write_all(line.as_bytes()).await
+    };
+    if let Err(err) = written.await {
+        let message = format!("Failed to save the bookmark to {}: {}", path.display(), err);
+        let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+    }
+}
+
+async fn replay_log(
+    path: PathBuf,
+    speed: f64,
+    senders: MetricSenders,
+    mut control_rx: mpsc::Receiver<PipelineCommand>,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    loop {
+        let flow = replay_once(&path, speed, &senders, &mut control_rx, &event_tx).await;
+        let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+
+        let restart = match flow {
+            Flow::Restart => true,
+            Flow::Closed => false,
+            Flow::Continue | Flow::Stop => loop {
+                match control_rx.recv().await {
+                    Some(PipelineCommand::Restart) => break true,
+                    Some(PipelineCommand::Annotate(annotation)) => {
+                        save_bookmark(&path, &annotation, &event_tx).await;
+                    }
+                    Some(_) => {}
+                    None => break false,
+                }
+            },
+        };
+        if !restart {
+            return;
+        }
+    }
+}
+
+async fn apply_command(
+    command: Option<PipelineCommand>,
+    log: &Path,
+    paused: &mut bool,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> Flow {
+    match command {
+        Some(PipelineCommand::Play) => {
+            *paused = false;
+            let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+            Flow::Continue
+        }
+        Some(PipelineCommand::Pause) => {
+            *paused = true;
+            let _ = event_tx.send(PipelineEvent::State(PipelineState::Paused)).await;
+            Flow::Continue
+        }
+        Some(PipelineCommand::Restart) => Flow::Restart,
+        Some(PipelineCommand::Stop) => Flow::Stop,
+        Some(PipelineCommand::Annotate(annotation)) => {
+            save_bookmark(log, &annotation, event_tx).await;
+            Flow::Continue
+        }
+        Some(_) => {
+            let _ = event_tx
+                .send(PipelineEvent::Notice(
+                    "Only play, pause, stop and restart are available while replaying a log"
+                        .to_string(),
+                ))
+                .await;
+            Flow::Continue
+        }
+        None => Flow::Closed,
+    }
+}
+
+async fn replay_once(
+    path: &Path,
+    speed: f64,
+    senders: &MetricSenders,
+    control_rx: &mut mpsc::Receiver<PipelineCommand>,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> Flow {
+    let file = match File::open(path).await {
+        Ok(file) => file,
+        Err(err) => {
+            let _ = event_tx
+                .send(PipelineEvent::Error(PipelineError {
+                    message: format!("Failed to open {}: {}", path.display(), err),
+                    debug: String::new(),
+                    element: None,
+                    recent_lines: Vec::new(),
+                    caps: None,
+                }))
+                .await;
+            return Flow::Stop;
+        }
+    };
+    let mut lines = BufReader::new(file).lines();
+
+    let _ = event_tx.send(PipelineEvent::LogFile {
+            path: path.to_path_buf(),
+            base_ns: 0,
+        }).await;
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+    for annotation in load_bookmarks(path).await {
+        let _ = event_tx.send(PipelineEvent::Annotation(annotation)).await;
+    }
+
+    let mut paused = false;
+    let mut previous_ns = None;
+    let mut schedule_jitter = ScheduleJitter::default();
+    loop {
+        while paused {
+            match apply_command(control_rx.recv().await, path, &mut paused, event_tx).await {
+                Flow::Continue => {}
+                flow => return flow,
+            }
+        }
+
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            command = control_rx.recv() => {
+                match apply_command(command, path, &mut paused, event_tx).await {
+                    Flow::Continue => continue,
+                    flow => return flow,
+                }
+            }
+        };
+        let Ok(Some(line)) = line else {
+            return Flow::Stop;
+        };
+
+        // Keep the original pacing between timestamped lines.
+        let timestamp_ns = parse_line_timestamp(&line);
+        if timestamp_ns > 0 {
+            if speed > 0.0
+                && let Some(previous) = previous_ns
+                && timestamp_ns > previous
+            {
+                let delay = Duration::from_nanos(((timestamp_ns - previous) as f64 / speed) as u64);
+                tokio::select! {
+                    _ = time::sleep(delay) => {}
+                    command = control_rx.recv() => {
+                        match apply_command(command, path, &mut paused, event_tx).await {
+                            Flow::Continue => {}
+                            flow => return flow,
+                        }
+                    }
+                }
+            }
+            previous_ns = Some(timestamp_ns);
+        }
+
+        let _ = senders.log.try_send(line.clone());
+        if let Some(entry) = parse_gst_tracer_output(&line) {
+            let _ = senders.data.send(entry).await;
+        } else if let Some(latency) = parse_interlatency(&line) {
+            let _ = senders.latency.send(latency).await;
+        } else if let Some(entry) = schedule_jitter.push_line(&line) {
+            let _ = senders.data.send(entry).await;
+        } else if let Some(element) = parse_eos_event(&line) {
+            let _ = event_tx.send(PipelineEvent::ElementEos(element)).await;
+        } else if let Some((element, pad, caps)) = parse_caps_notify(&line) {
+            let _ = event_tx.send(PipelineEvent::Caps { element, pad, caps }).await;
+        } else if let Some(level) = parse_queue_level(&line) {
+            let _ = event_tx.send(PipelineEvent::QueueLevel(level)).await;
+        } else if let Some(annotation) = parse_annotation_line(&line) {
+            let _ = event_tx.send(PipelineEvent::Annotation(annotation)).await;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! RTP statistics for `--mode in-process`, read from the `stats` property
+//! of the jitterbuffers and RTP sessions once a second.
+
+use gst_debugger_core::{JitterBufferStats, RtpSessionStats, RtpSource};
+use gstreamer as gst;
+use gstreamer::glib;
+use gstreamer::prelude::*;
+
+/// Every element made by `factory`, including those inside bins. rtpbin
+/// creates its jitterbuffers only once streams arrive, so this is looked up
+/// on every poll.
+pub(crate) fn elements_of(pipeline: &gst::Pipeline, factory: &str) -> Vec<gst::Element> {
+    pipeline
+        .iterate_recurse()
+        .into_iter()
+        .flatten()
+        .filter(|element| {
+            element
+                .factory()
+                .is_some_and(|element_factory| element_factory.name().as_str() == factory)
+        })
+        .collect()
+}
+
+pub(crate) fn jitter_buffer_stats(
+    jitterbuffer: &gst::Element,
+    timestamp_ns: u64,
+) -> JitterBufferStats {
+    let stats = jitterbuffer.property::<gst::Structure>("stats");
+    let count = |field: &str| stats.get::<u64>(field).unwrap_or(0);
+    JitterBufferStats {
+        timestamp_ns,
+        element: jitterbuffer.name().to_string(),
+        pushed: count("num-pushed"),
+        lost: count("num-lost"),
+        late: count("num-late"),
+        duplicates: count("num-duplicates"),
+        avg_jitter_ns: count("avg-jitter"),
+    }
+}
+
+/// RTCP round-trip times are in 1/65536 seconds.
+fn round_trip_ns(units: u32) -> u64 {
+    (units as u64 * 1_000_000_000) >> 16
+}
+
+fn rtp_source(stats: &gst::StructureRef) -> Option<RtpSource> {
+    let ssrc = stats.get::<u32>("ssrc").ok()?;
+    let flag = |field: &str| stats.get::<bool>(field).unwrap_or(false);
+    let count = |field: &str| stats.get::<u64>(field).unwrap_or(0);
+    let fraction = |field: &str| stats.get::<u32>(field).ok().map(|value| value as f64 / 256.0);
+    let internal = flag("internal");
+
+    let (packets, packets_lost, fraction_lost, jitter_ns, rtt_ns) = if internal {
+        // What the receivers of our stream reported back.
+        let have_rb = flag("have-rb");
+        (
+            count("packets-sent"),
+            stats.get::<i32>("rb-packetslost").ok().filter(|_| have_rb).map(i64::from),
+            fraction("rb-fractionlost").filter(|_| have_rb),
+            None,
+            stats.get::<u32>("rb-round-trip").ok().filter(|_| have_rb).map(round_trip_ns),
+        )
+    } else {
+        // The jitter is in RTP clock units, so it needs the clock rate.
+        let jitter_ns = stats
+            .get::<i32>("clock-rate")
+            .ok()
+            .filter(|&rate| rate > 0)
+            .zip(stats.get::<u32>("jitter").ok())
+            .map(|(rate, jitter)| jitter as u64 * 1_000_000_000 / rate as u64);
+        (
+            count("packets-received"),
+            stats.get::<i32>("packets-lost").ok().map(i64::from),
+            fraction("sent-rb-fractionlost").filter(|_| flag("sent-rb")),
+            jitter_ns,
+            None,
+        )
+    };
+
+    Some(RtpSource {
+        ssrc,
+        internal,
+        cname: stats.get::<String>("cname").ok(),
+        bitrate: count("bitrate"),
+        packets,
+        packets_lost,
+        fraction_lost,
+        jitter_ns,
+        rtt_ns,
+    })
+}
+
+pub(crate) fn rtp_session_stats(session: &gst::Element, timestamp_ns: u64) -> RtpSessionStats {
+    let stats = session.property::<gst::Structure>("stats");
+    let mut sources: Vec<RtpSource> = stats
+        .get::<glib::ValueArray>("source-stats")
+        .map(|sources| {
+            sources
+                .iter()
+                .filter_map(|value| value.get::<gst::Structure>().ok())
+                .filter_map(|source| rtp_source(&source))
+                .collect()
+        })
+        .unwrap_or_default();
+    sources.sort_by_key(|source| (!source.internal, source.ssrc));
+    RtpSessionStats {
+        timestamp_ns,
+        element: session.name().to_string(),
+        sources,
+    }
+}
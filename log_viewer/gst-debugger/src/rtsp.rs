@@ -0,0 +1,203 @@
+//! RTSP client diagnostics for `--mode in-process`: the SDP and progress of
+//! every `rtspsrc`, the transport it ended up with and how much each of its
+//! streams receives.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gst_debugger_core::{RtspSourceStats, RtspStream};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use gstreamer_sdp as gst_sdp;
+
+use crate::rtp;
+
+/// How long a playing rtspsrc may go without receiving anything before the
+/// server is reported as stalled.
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+struct StreamCounters {
+    /// Bytes since the previous stats, packets since the start.
+    bytes: u64,
+    packets: u64,
+    last_arrival: Option<Instant>,
+}
+
+struct RtspClient {
+    element: gst::Element,
+    sdp: Arc<Mutex<Option<String>>>,
+    state: String,
+    streams: BTreeMap<String, StreamCounters>,
+    stalled: bool,
+}
+
+impl RtspClient {
+    /// rtspsrc creates a udpsrc per stream for UDP transports and none when
+    /// the data is interleaved in the TCP connection.
+    fn transport(&self) -> Option<String> {
+        if self.streams.is_empty() {
+            return None;
+        }
+        let bin = self.element.downcast_ref::<gst::Bin>()?;
+        let udp = bin.iterate_recurse().into_iter().flatten().any(|element| {
+            element.factory().is_some_and(|factory| factory.name().as_str() == "udpsrc")
+        });
+        Some(if udp { "UDP" } else { "TCP" }.to_string())
+    }
+
+    fn media(&self, pad: &str) -> String {
+        let Some(caps) = self.element.static_pad(pad).and_then(|pad| pad.current_caps()) else {
+            return String::new();
+        };
+        let Some(structure) = caps.structure(0) else {
+            return String::new();
+        };
+        [structure.get::<&str>("media"), structure.get::<&str>("encoding-name")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Follows every rtspsrc of a pipeline run.
+pub(crate) struct RtspMonitor {
+    clients: Vec<RtspClient>,
+    playing_since: Option<Instant>,
+}
+
+impl RtspMonitor {
+    pub(crate) fn new(pipeline: &gst::Pipeline) -> Self {
+        let clients = rtp::elements_of(pipeline, "rtspsrc")
+            .into_iter()
+            .map(|element| {
+                let sdp = Arc::new(Mutex::new(None));
+                let received = sdp.clone();
+                element.connect("on-sdp", false, move |values| {
+                    if let Ok(message) = values[1].get::<gst_sdp::SDPMessage>() {
+                        *received.lock().unwrap() = message.as_text().ok();
+                    }
+                    None
+                });
+                RtspClient {
+                    element,
+                    sdp,
+                    state: "Connecting".to_string(),
+                    streams: BTreeMap::new(),
+                    stalled: false,
+                }
+            })
+            .collect();
+        RtspMonitor {
+            clients,
+            playing_since: None,
+        }
+    }
+
+    /// Count the buffers a pad of an rtspsrc received since the last call.
+    pub(crate) fn record(
+        &mut self,
+        element: &str,
+        pad: &str,
+        packets: u64,
+        bytes: u64,
+        last_arrival: Option<Instant>,
+    ) {
+        let Some(client) = self.clients.iter_mut().find(|client| client.element.name() == element)
+        else {
+            return;
+        };
+        let stream = client.streams.entry(pad.to_string()).or_default();
+        stream.bytes += bytes;
+        stream.packets += packets;
+        stream.last_arrival = last_arrival;
+    }
+
+    /// Remember the progress an rtspsrc posted, e.g. `Opened Stream`.
+    pub(crate) fn progress(&mut self, message: &gst::Message) {
+        let gst::MessageView::Progress(progress) = message.view() else {
+            return;
+        };
+        let Some(client) = self
+            .clients
+            .iter_mut()
+            .find(|client| message.src() == Some(client.element.upcast_ref::<gst::Object>()))
+        else {
+            return;
+        };
+        let (_, _, text) = progress.get();
+        client.state = text.to_string();
+    }
+
+    /// The stats of every rtspsrc for the interval since the previous call,
+    /// with a notice for each one that stalled or recovered.
+    pub(crate) fn stats(
+        &mut self,
+        elapsed: Duration,
+        timestamp_ns: u64,
+        playing: bool,
+    ) -> Vec<(RtspSourceStats, Option<String>)> {
+        let now = Instant::now();
+        self.playing_since = if playing { Some(self.playing_since.unwrap_or(now)) } else { None };
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        let mut reports = Vec::new();
+        for client in &mut self.clients {
+            let mut streams = Vec::new();
+            for (pad, counters) in &client.streams {
+                streams.push(RtspStream {
+                    pad: pad.clone(),
+                    media: client.media(pad),
+                    bitrate: (counters.bytes as f64 * 8.0 / seconds) as u64,
+                    packets: counters.packets,
+                    idle_ns: counters
+                        .last_arrival
+                        .map_or(0, |last| now.duration_since(last).as_nanos() as u64),
+                });
+            }
+            for counters in client.streams.values_mut() {
+                counters.bytes = 0;
+            }
+
+            // Time spent paused does not count towards a stall.
+            let stalled = self.playing_since.is_some_and(|playing_since| {
+                !client.streams.is_empty()
+                    && client.streams.values().all(|counters| {
+                        let last = counters.last_arrival.map_or(playing_since, |last| {
+                            last.max(playing_since)
+                        });
+                        now.duration_since(last) >= STALL_TIMEOUT
+                    })
+            });
+            let name = client.element.name();
+            let notice = match (client.stalled, stalled) {
+                (false, true) => Some(format!(
+                    "{}: the RTSP server sent no data for {} s",
+                    name,
+                    STALL_TIMEOUT.as_secs()
+                )),
+                (true, false) => Some(format!("{}: the RTSP server sends data again", name)),
+                _ => None,
+            };
+            client.stalled = stalled;
+
+            let stats = RtspSourceStats {
+                timestamp_ns,
+                element: name.to_string(),
+                location: client
+                    .element
+                    .property::<Option<String>>("location")
+                    .unwrap_or_default(),
+                state: client.state.clone(),
+                transport: client.transport(),
+                sdp: client.sdp.lock().unwrap().clone(),
+                streams,
+                stalled,
+            };
+            reports.push((stats, notice));
+        }
+        reports
+    }
+}
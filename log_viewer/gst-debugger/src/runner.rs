@@ -0,0 +1,641 @@
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use gst_debugger_core::{
+    format_annotation_line, format_native_sample_line, inject_recording, parse_caps_notify,
+    parse_eos_event, parse_gst_tracer_output, parse_interlatency, parse_logcat_line,
+    parse_queue_level, split_pipeline_args, ErrorTracker, LineFilter, MemoryTracker,
+    NegotiationLog, PipelineCommand, PipelineError, PipelineEvent, PipelineState, ScheduleJitter,
+    ThreadTracker, TracingData, STDOUT_TAG, TRACER_CATEGORY,
+};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{ChildStderr, ChildStdout};
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::child::{set_scheduling, unsupported_on_platform, PipelineChild};
+use crate::logfile::TracerLog;
+use crate::native::NativeTracer;
+use crate::{
+    in_process, negotiation, LaunchConfig, MetricSenders, RESTART_BACKOFF_BASE,
+    RESTART_BACKOFF_MAX,
+};
+
+/// How often the memory of gst-launch is sampled.
+const MEMORY_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the leaks tracer is asked for its live objects; every dump logs
+/// a line per object, which would swamp the log at each memory sample.
+const LEAKS_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the elements are grouped by streaming thread.
+const THREAD_INTERVAL: Duration = Duration::from_secs(2);
+/// How far back the busy share of an element in its thread is measured.
+const THREAD_WINDOW_NS: u64 = 10_000_000_000;
+
+pub(crate) enum RunOutcome {
+    Relaunch(String),
+    Finished,
+    Failed,
+}
+
+pub(crate) async fn run_pipeline_with_tracing(
+    pipeline: String,
+    mut config: LaunchConfig,
+    senders: MetricSenders,
+    mut control_rx: mpsc::Receiver<PipelineCommand>,
+    event_tx: mpsc::Sender<PipelineEvent>,
+) {
+    let mut pipeline = pipeline;
+    let mut time_base_ns = 0;
+    let mut failures = 0u32;
+    loop {
+        let started = Instant::now();
+        let outcome = if config.in_process {
+            in_process::run_pipeline(
+                &pipeline,
+                &mut config,
+                &mut time_base_ns,
+                &senders,
+                &mut control_rx,
+                &event_tx,
+            )
+            .await
+        } else {
+            run_pipeline_once(
+                &pipeline,
+                &mut config,
+                &mut time_base_ns,
+                &senders,
+                &mut control_rx,
+                &event_tx,
+            )
+            .await
+        };
+        if started.elapsed() > RESTART_BACKOFF_MAX {
+            failures = 0;
+        }
+
+        let next = match outcome {
+            RunOutcome::Relaunch(next) => {
+                failures = 0;
+                Some(next)
+            }
+            RunOutcome::Failed if config.restart_on_failure => {
+                failures += 1;
+                let delay = RESTART_BACKOFF_BASE
+                    .saturating_mul(1 << (failures - 1).min(6))
+                    .min(RESTART_BACKOFF_MAX);
+                let _ = event_tx
+                    .send(PipelineEvent::Restarting { attempt: failures, delay })
+                    .await;
+                backoff_before_restart(delay, &pipeline, &mut config, &mut control_rx, &event_tx)
+                    .await
+            }
+            RunOutcome::Failed | RunOutcome::Finished => {
+                wait_for_restart(&pipeline, &mut config, &mut control_rx).await
+            }
+        };
+        match next {
+            Some(next) => pipeline = next,
+            None => return,
+        }
+    }
+}
+
+fn restart_target(
+    command: PipelineCommand,
+    pipeline: &str,
+    config: &mut LaunchConfig,
+) -> Option<String> {
+    match command {
+        PipelineCommand::Restart => Some(pipeline.to_string()),
+        PipelineCommand::Relaunch(next) => Some(next),
+        PipelineCommand::SetTracers(tracing) => {
+            config.tracing = tracing;
+            Some(pipeline.to_string())
+        }
+        _ => None,
+    }
+}
+
+async fn wait_for_restart(
+    pipeline: &str,
+    config: &mut LaunchConfig,
+    control_rx: &mut mpsc::Receiver<PipelineCommand>,
+) -> Option<String> {
+    while let Some(command) = control_rx.recv().await {
+        if let Some(next) = restart_target(command, pipeline, config) {
+            return Some(next);
+        }
+    }
+    None
+}
+
+async fn backoff_before_restart(
+    delay: Duration,
+    pipeline: &str,
+    config: &mut LaunchConfig,
+    control_rx: &mut mpsc::Receiver<PipelineCommand>,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> Option<String> {
+    let sleep = time::sleep(delay);
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return Some(pipeline.to_string()),
+            command = control_rx.recv() => match command {
+                Some(PipelineCommand::Stop) => {
+                    let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+                    return wait_for_restart(pipeline, config, control_rx).await;
+                }
+                Some(command) => {
+                    if let Some(next) = restart_target(command, pipeline, config) {
+                        return Some(next);
+                    }
+                }
+                None => return None,
+            },
+        }
+    }
+}
+
+async fn run_pipeline_once(
+    pipeline: &str,
+    config: &mut LaunchConfig,
+    time_base_ns: &mut u64,
+    senders: &MetricSenders,
+    control_rx: &mut mpsc::Receiver<PipelineCommand>,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> RunOutcome {
+    let mut pipeline_args = split_pipeline_args(pipeline);
+    if let Some(recording) = &config.record {
+        pipeline_args = inject_recording(pipeline_args, recording);
+    }
+
+    // Samples from the native tracer skip the stderr parsing below.
+    let (native_tx, mut native_rx) = mpsc::channel(1000);
+    let mut tracing = config.tracing.clone();
+    let native = if config.native_tracer {
+        match NativeTracer::listen(native_tx) {
+            Ok(native) => {
+                if !tracing.is_empty() {
+                    tracing.push(';');
+                }
+                tracing.push_str(&native.tracer());
+                Some(native)
+            }
+            Err(err) => {
+                let _ = event_tx.send(PipelineEvent::Notice(err)).await;
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // The leaks tracer logs every live object on SIGUSR1 once asked to.
+    let leaks = tracing.split(';').any(|tracer| tracer.trim().starts_with("leaks"));
+
+    // `-e` turns SIGINT into an EOS so the pipeline can drain before exiting,
+    // which also lets the muxer finalize a recording. `-v` prints the caps
+    // every pad negotiates.
+    let mut env = vec![
+        ("GST_TRACERS".to_string(), tracing),
+        ("GST_DEBUG".to_string(), config.gst_debug.clone()),
+    ];
+    if leaks {
+        env.push(("GST_LEAKS_TRACER_SIG".to_string(), "1".to_string()));
+    }
+    env.extend(config.env.iter().cloned());
+    let args = ["-e".to_string(), "-v".to_string()].into_iter().chain(pipeline_args);
+    let mut command = config.target.command(&config.gst_launch, args, &env);
+    if config.target.is_local() {
+        set_scheduling(&mut command, config);
+    }
+    let command_line: Vec<String> = std::iter::once(command.as_std().get_program())
+        .chain(command.as_std().get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let spawned = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => PipelineChild::new(child, config.target.clone()),
+        Err(err) => {
+            let _ = event_tx
+                .send(PipelineEvent::Error(PipelineError {
+                    message: format!("Failed to launch GStreamer: {}", err),
+                    debug: command_line.join(" "),
+                    element: None,
+                    recent_lines: Vec::new(),
+                    caps: None,
+                }))
+                .await;
+            let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+            return RunOutcome::Failed;
+        }
+    };
+
+    let stderr = child.child.stderr.take().expect("No stderr");
+    let reader = BufReader::new(stderr);
+    let mut lines = reader.lines();
+    let stdout = child.child.stdout.take().expect("No stdout");
+    let mut output_lines = BufReader::new(stdout).lines();
+    let mut output_open = true;
+    let logcat = config.target.logcat().map(|mut command| {
+        command.stdout(Stdio::piped()).stderr(Stdio::null()).kill_on_drop(true).spawn()
+    });
+    // Kept so logcat is killed along with the run.
+    let (_logcat, mut logcat_lines) = match logcat {
+        Some(Ok(mut logcat)) => {
+            let lines = logcat.stdout.take().map(|stdout| BufReader::new(stdout).lines());
+            (Some(logcat), lines)
+        }
+        Some(Err(err)) => {
+            let message = format!("Failed to follow logcat, reading stderr only: {}", err);
+            let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+            (None, None)
+        }
+        None => (None, None),
+    };
+
+    let mut log = if config.log.enabled {
+        let native_tracer = native.is_some();
+        open_log(pipeline, config, &command_line, &env, native_tracer, *time_base_ns, event_tx)
+            .await
+    } else {
+        None
+    };
+
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+
+    // Offset timestamps by the previous runs so the timeline keeps moving
+    // forward across restarts.
+    let base_ns = *time_base_ns;
+    let mut last_ns = base_ns;
+    let mut errors = ErrorTracker::default();
+    let mut caps_log = NegotiationLog::default();
+    let mut line_filter = LineFilter::new(config.dedup_lines, config.rate_limit);
+    let mut schedule_jitter = ScheduleJitter::default();
+    let mut memory = MemoryTracker::new(config.memory_growth_limit);
+    let first_sample = time::Instant::now() + MEMORY_INTERVAL;
+    let mut memory_tick = time::interval_at(first_sample, MEMORY_INTERVAL);
+    // The tracers are all set up together as GStreamer initializes, so the
+    // first tracer record shows the leaks tracer handles SIGUSR1 by now.
+    let mut leaks_ready = false;
+    let mut last_dump: Option<time::Instant> = None;
+    let mut threads = ThreadTracker::new(Some(THREAD_WINDOW_NS));
+    let mut thread_tick = time::interval(THREAD_INTERVAL);
+    let mut stop_requested = false;
+    let mut next = None;
+    loop {
+        tokio::select! {
+            line = next_line(&mut lines, &mut logcat_lines) => {
+                let Some(line) = line else { break };
+                if child.observe(&line) {
+                    continue;
+                }
+                leaks_ready |= line.contains(TRACER_CATEGORY);
+
+                // Held back lines still count for the metrics below.
+                for kept in line_filter.push(&line) {
+                    if let Some(log) = &mut log {
+                        let _ = log.write_line(&kept).await;
+                    }
+                    let _ = senders.log.try_send(kept);
+                }
+                errors.observe(&line);
+                caps_log.observe(&line);
+                if memory.observe_line(&line) {
+                    continue;
+                }
+                threads.observe_line(&line);
+
+                if let Some(entry) = parse_gst_tracer_output(&line) {
+                    send_sample(entry, false, &mut log, base_ns, &mut last_ns, senders).await;
+                } else if let Some(mut latency) = parse_interlatency(&line) {
+                    if let Some(log) = &mut log {
+                        let _ = log.write_latency(&latency).await;
+                    }
+                    latency.timestamp_ns += base_ns;
+                    last_ns = last_ns.max(latency.timestamp_ns);
+                    let _ = senders.latency.send(latency).await;
+                } else if let Some(entry) = schedule_jitter.push_line(&line) {
+                    send_sample(entry, false, &mut log, base_ns, &mut last_ns, senders).await;
+                } else if let Some(element) = parse_eos_event(&line) {
+                    let _ = event_tx.send(PipelineEvent::ElementEos(element)).await;
+                } else if let Some(mut level) = parse_queue_level(&line) {
+                    level.timestamp_ns += base_ns;
+                    last_ns = last_ns.max(level.timestamp_ns);
+                    let _ = event_tx.send(PipelineEvent::QueueLevel(level)).await;
+                }
+            }
+            line = output_lines.next_line(), if output_open => {
+                let Ok(Some(line)) = line else {
+                    output_open = false;
+                    continue;
+                };
+                // What the pipeline prints goes with its debug output, tagged
+                // to tell them apart; `analyze` and `export` take the caps
+                // from the log.
+                let tagged = format!("{}{}", STDOUT_TAG, line);
+                for kept in line_filter.push(&tagged) {
+                    if let Some(log) = &mut log {
+                        let _ = log.write_line(&kept).await;
+                    }
+                    let _ = senders.log.try_send(kept);
+                }
+                errors.observe(&tagged);
+                caps_log.observe(&line);
+                if let Some((element, pad, caps)) = parse_caps_notify(&line) {
+                    let _ = event_tx.send(PipelineEvent::Caps { element, pad, caps }).await;
+                }
+            }
+            _ = memory_tick.tick() => {
+                let (usage, notice) = memory.sample(last_ns, child.rss_bytes());
+                if usage.rss_bytes.is_some() || !usage.live_objects.is_empty() {
+                    let _ = event_tx.send(PipelineEvent::Memory(usage)).await;
+                }
+                if let Some(notice) = notice {
+                    let _ = event_tx.send(PipelineEvent::Notice(notice)).await;
+                }
+                // The objects arrive over the next interval and are counted
+                // into the next sample.
+                let due = last_dump.is_none_or(|dumped| dumped.elapsed() >= LEAKS_INTERVAL);
+                if leaks && leaks_ready && due {
+                    child.dump_live_objects().await;
+                    last_dump = Some(time::Instant::now());
+                }
+            }
+            _ = thread_tick.tick() => {
+                // Only the tracers logged from streaming threads tell them.
+                if threads.is_empty() {
+                    continue;
+                }
+                let (grouped, notice) = threads.snapshot(last_ns);
+                let _ = event_tx.send(PipelineEvent::Threads(grouped)).await;
+                if let Some(notice) = notice {
+                    let _ = event_tx.send(PipelineEvent::Notice(notice)).await;
+                }
+            }
+            Some(entry) = native_rx.recv() => {
+                send_sample(entry, true, &mut log, base_ns, &mut last_ns, senders).await;
+            }
+            command = control_rx.recv() => match command {
+                // gst-launch exposes no position query or seek interface,
+                // so the seek bar stays hidden for external pipelines.
+                Some(PipelineCommand::Seek(_)) => {}
+                Some(PipelineCommand::Annotate(mut annotation)) => {
+                    // The log keeps the child's own clock.
+                    annotation.timestamp_ns = annotation.timestamp_ns.saturating_sub(base_ns);
+                    if let Some(log) = &mut log {
+                        let _ = log.write_line(&format_annotation_line(&annotation)).await;
+                    } else {
+                        let _ = event_tx
+                            .send(PipelineEvent::Notice(
+                                "This run writes no log, so the bookmark stays in the viewer only"
+                                    .to_string(),
+                            ))
+                            .await;
+                    }
+                }
+                Some(PipelineCommand::QueryProperties(_) | PipelineCommand::SetProperty { .. }) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice(
+                            "Element properties are not accessible in a gst-launch child".to_string(),
+                        ))
+                        .await;
+                }
+                Some(PipelineCommand::SelectStreams { .. }) => {
+                    let _ = event_tx
+                        .send(PipelineEvent::Notice(
+                            "Streams cannot be selected in a gst-launch child".to_string(),
+                        ))
+                        .await;
+                }
+                Some(PipelineCommand::SendEos) => {
+                    if !child.interrupt().await {
+                        let _ = event_tx.send(unsupported_on_platform("Sending EOS")).await;
+                    }
+                }
+                Some(PipelineCommand::Pause) => {
+                    if child.pause().await {
+                        let _ = event_tx.send(PipelineEvent::State(PipelineState::Paused)).await;
+                    } else {
+                        let _ = event_tx.send(unsupported_on_platform("Pausing")).await;
+                    }
+                }
+                Some(PipelineCommand::Play) => {
+                    if child.resume().await {
+                        let _ = event_tx.send(PipelineEvent::State(PipelineState::Playing)).await;
+                    }
+                }
+                Some(PipelineCommand::Restart) => {
+                    stop_requested = true;
+                    child.shutdown().await;
+                    next = Some(pipeline.to_string());
+                    break;
+                }
+                Some(PipelineCommand::Relaunch(pipeline)) => {
+                    stop_requested = true;
+                    child.shutdown().await;
+                    next = Some(pipeline);
+                    break;
+                }
+                Some(PipelineCommand::SetTracers(tracing)) => {
+                    stop_requested = true;
+                    child.shutdown().await;
+                    config.tracing = tracing;
+                    next = Some(pipeline.to_string());
+                    break;
+                }
+                Some(PipelineCommand::Stop) | None => {
+                    stop_requested = true;
+                    child.shutdown().await;
+                    break;
+                }
+            },
+        }
+    }
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Some(log) = &mut log {
+            for kept in line_filter.push(&line) {
+                let _ = log.write_line(&kept).await;
+            }
+        }
+        errors.observe(&line);
+        caps_log.observe(&line);
+    }
+
+    // The tracer sends its last samples as gst-launch exits.
+    if native.is_some() {
+        while let Ok(Some(entry)) =
+            time::timeout(Duration::from_millis(200), native_rx.recv()).await
+        {
+            send_sample(entry, true, &mut log, base_ns, &mut last_ns, senders).await;
+        }
+    }
+    if let Some(log) = &mut log {
+        for kept in line_filter.finish() {
+            let _ = log.write_line(&kept).await;
+        }
+        let _ = log.flush().await;
+    }
+
+    drop(native);
+
+    *time_base_ns = last_ns;
+
+    let exit = child.child.wait().await.ok();
+    let mut error = errors.finish(exit, stop_requested);
+    // gst-launch's pads are out of reach, so the link is found from its
+    // output and the pipeline description.
+    if let Some(error) = &mut error
+        && negotiation::is_not_negotiated(&error.debug)
+    {
+        let element = error.element.as_deref();
+        error.caps = caps_log.mismatch(pipeline, element, negotiation::template_caps);
+    }
+    let failed = error.is_some();
+    if let Some(error) = error {
+        let _ = event_tx.send(PipelineEvent::Error(error)).await;
+    }
+    if config.log.archive
+        && let Some(log) = log
+    {
+        // The log is gone now; a viewer of the run is pointed at the
+        // archive instead.
+        let message = match log.archive().await {
+            Ok(archive) => {
+                let message = format!("Archived the run to {}", archive.display());
+                let _ = event_tx.send(PipelineEvent::LogFile { path: archive, base_ns }).await;
+                message
+            }
+            Err(err) => format!("Failed to archive the run: {}", err),
+        };
+        let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+    }
+    let _ = event_tx.send(PipelineEvent::State(PipelineState::Stopped)).await;
+
+    match next {
+        Some(next) => RunOutcome::Relaunch(next),
+        None if failed => RunOutcome::Failed,
+        None => RunOutcome::Finished,
+    }
+}
+
+/// Log `entry`, timed by the child's clock, and pass it on timed by the
+/// session's. The native tracer's samples come over its socket, so gst-launch
+/// never printed them and they get a line of their own in the tracer log.
+async fn send_sample(
+    mut entry: TracingData,
+    native: bool,
+    log: &mut Option<TracerLog>,
+    base_ns: u64,
+    last_ns: &mut u64,
+    senders: &MetricSenders,
+) {
+    if let Some(log) = log {
+        if native {
+            let _ = log.write_line(&format_native_sample_line(&entry)).await;
+        }
+        let _ = log.write_sample(&entry).await;
+    }
+    entry.timestamp_ns += base_ns;
+    *last_ns = (*last_ns).max(entry.timestamp_ns);
+    let _ = senders.data.send(entry).await;
+}
+
+/// The next line gst-launch writes to stderr or, on Android, GStreamer logs
+/// to logcat, in the layout of a GST_DEBUG line. `None` once stderr closes.
+async fn next_line(
+    stderr: &mut Lines<BufReader<ChildStderr>>,
+    logcat: &mut Option<Lines<BufReader<ChildStdout>>>,
+) -> Option<String> {
+    loop {
+        let Some(logcat_lines) = logcat else {
+            return stderr.next_line().await.ok().flatten();
+        };
+        tokio::select! {
+            line = stderr.next_line() => return line.ok().flatten(),
+            line = logcat_lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if let Some(line) = parse_logcat_line(&line) {
+                        return Some(line);
+                    }
+                }
+                _ => *logcat = None,
+            },
+        }
+    }
+}
+
+/// The run's tracer log, with the metrics log, the metadata file and the
+/// `latest` links written next to it.
+async fn open_log(
+    pipeline: &str,
+    config: &LaunchConfig,
+    command: &[String],
+    env: &[(String, String)],
+    native_tracer: bool,
+    base_ns: u64,
+    event_tx: &mpsc::Sender<PipelineEvent>,
+) -> Option<TracerLog> {
+    let path = config.log.next_path(pipeline);
+    // The run goes on without a log rather than fail over it.
+    let opened = match tokio::fs::create_dir_all(&config.log.dir).await {
+        Ok(()) => {
+            TracerLog::open(path.clone(), config.log.rotation, config.log.split_tracers).await
+        }
+        Err(err) => Err(err),
+    };
+    let mut log = match opened {
+        Ok(log) => log,
+        Err(err) => {
+            let message = format!(
+                "Failed to open the tracer log {}, running without one: {}",
+                path.display(),
+                err
+            );
+            let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+            return None;
+        }
+    };
+    if config.log.metrics_log
+        && let Err(err) = log.open_metrics().await
+    {
+        let message = format!("Failed to create the metrics log: {}", err);
+        let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+    }
+
+    let metadata = serde_json::json!({
+        "pipeline": pipeline,
+        "started": Local::now().to_rfc3339(),
+        "tracers": config.tracing,
+        "native_tracer": native_tracer,
+        "gst_launch": config.gst_launch,
+        "command": command,
+        "env": env.iter().cloned().collect::<BTreeMap<_, _>>(),
+        "environment": config.environment,
+        "target": config.target.describe(),
+        "nice": config.nice,
+        "cpus": config.cpus,
+    });
+    let _ = tokio::fs::write(
+        path.with_extension("json"),
+        serde_json::to_string_pretty(&metadata).unwrap_or_default(),
+    )
+    .await;
+    if config.log.latest_link
+        && let Err(message) = config.log.link_latest(&path)
+    {
+        let _ = event_tx.send(PipelineEvent::Notice(message)).await;
+    }
+    let _ = event_tx.send(PipelineEvent::LogFile { path, base_ns }).await;
+
+    Some(log)
+}
@@ -0,0 +1,306 @@
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gst_debugger_core::{Aggregate, Metric, PipelineEvent};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::tap::{Outbox, SharedStore, Tap};
+
+/// How many operations one callback may run, so an endless loop ends.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// How long one callback may run before it is stopped.
+const CALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Callbacks waiting for the script's thread; further ones are skipped
+/// until it catches up.
+const QUEUED_CALLS: usize = 64;
+
+/// Read-only view of the samples received so far, passed to script
+/// callbacks as `metrics`.
+#[derive(Clone)]
+struct Metrics(SharedStore);
+
+impl Metrics {
+    fn elements(&mut self) -> Array {
+        let store = self.0.read().unwrap();
+        store
+            .element_stats()
+            .into_iter()
+            .map(|stats| Dynamic::from(stats.element))
+            .collect()
+    }
+
+    fn stat(&mut self, element: &str, metric: &str, aggregate: &str) -> Dynamic {
+        let Some(aggregate) = Aggregate::from_name(aggregate) else {
+            return Dynamic::UNIT;
+        };
+        let Some(metric) = Metric::from_name(metric) else {
+            return Dynamic::UNIT;
+        };
+        let store = self.0.read().unwrap();
+        store
+            .element_stats()
+            .into_iter()
+            .find(|stats| stats.element == element)
+            .and_then(|stats| stats.summary(metric).get(aggregate))
+            .map_or(Dynamic::UNIT, Dynamic::from_float)
+    }
+
+    fn series(&mut self, element: &str, metric: &str) -> Array {
+        let Some(metric) = Metric::from_name(metric) else {
+            return Array::new();
+        };
+        let store = self.0.read().unwrap();
+        store
+            .series(element, metric)
+            .into_iter()
+            .map(|(timestamp_ns, value)| {
+                let point: Array = vec![
+                    Dynamic::from_int(timestamp_ns as i64),
+                    Dynamic::from_float(value),
+                ];
+                Dynamic::from_array(point)
+            })
+            .collect()
+    }
+
+    fn latest(&mut self, element: &str, metric: &str) -> Dynamic {
+        self.series(element, metric)
+            .pop()
+            .and_then(|point| point.into_array().ok()?.pop())
+            .unwrap_or(Dynamic::UNIT)
+    }
+
+    fn now(&mut self) -> i64 {
+        self.0.read().unwrap().latest_timestamp() as i64
+    }
+}
+
+/// The event as the map `on_event` gets, with the same fields as the
+/// HTTP API's JSON.
+fn event_map(event: &PipelineEvent) -> Option<Dynamic> {
+    match event {
+        PipelineEvent::Position { .. }
+        | PipelineEvent::Properties { .. }
+        | PipelineEvent::PropertyChanged { .. } => None,
+        event => rhai::serde::to_dynamic(event).ok(),
+    }
+}
+
+/// A callback for the script's thread to run.
+enum Call {
+    Tick,
+    Event(Dynamic),
+}
+
+/// A compiled user script and the state it keeps between callbacks, on the
+/// thread that runs it.
+struct Runner {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Dynamic,
+    metrics: Metrics,
+    messages: Arc<Mutex<Vec<String>>>,
+    /// When the running callback started, for [`CALL_TIMEOUT`].
+    started: Arc<Mutex<Instant>>,
+}
+
+/// A user script, run on a thread of its own so a slow callback does not
+/// hold up the samples on their way to the viewer.
+pub(crate) struct Script {
+    name: String,
+    calls: SyncSender<Call>,
+    notices: Receiver<PipelineEvent>,
+    /// Whether callbacks were skipped since the script last kept up.
+    behind: bool,
+}
+
+impl Script {
+    /// Load the script at `path`, reading metrics from `store`, and start
+    /// the thread that runs it.
+    pub(crate) fn load(path: &Path, store: SharedStore) -> Result<Self, String> {
+        let runner = Runner::load(path, store)?;
+        let name = runner.name.clone();
+        let (calls, queued) = mpsc::sync_channel(QUEUED_CALLS);
+        let (notice_tx, notices) = mpsc::channel();
+        thread::Builder::new()
+            .name(format!("script {}", name))
+            .spawn(move || runner.run(queued, notice_tx))
+            .map_err(|err| format!("Failed to start script {}: {}", path.display(), err))?;
+        Ok(Script {
+            name,
+            calls,
+            notices,
+            behind: false,
+        })
+    }
+
+    /// Hand `call` to the script's thread, or skip it when the thread is
+    /// still busy with earlier ones.
+    fn queue(&mut self, call: Call, out: &mut Outbox) {
+        match self.calls.try_send(call) {
+            Ok(()) => self.behind = false,
+            Err(TrySendError::Full(_)) if !self.behind => {
+                self.behind = true;
+                out.push(PipelineEvent::Notice(format!(
+                    "[{}] The script cannot keep up; skipping callbacks until it does",
+                    self.name
+                )));
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Pass on what the script printed or raised since the last call.
+    fn forward(&self, out: &mut Outbox) {
+        self.notices.try_iter().for_each(|notice| out.push(notice));
+    }
+}
+
+impl Runner {
+    fn load(path: &Path, store: SharedStore) -> Result<Self, String> {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let started = Arc::new(Mutex::new(Instant::now()));
+        let mut engine = Engine::new();
+        let deadline = started.clone();
+        engine.set_max_operations(MAX_OPERATIONS).on_progress(move |operations| {
+            // Checking the clock on every operation would slow scripts down.
+            let expired = operations % 1024 == 0
+                && deadline.lock().unwrap().elapsed() > CALL_TIMEOUT;
+            expired.then_some(Dynamic::UNIT)
+        });
+        engine
+            .register_type_with_name::<Metrics>("Metrics")
+            .register_fn("elements", Metrics::elements)
+            .register_fn("stat", Metrics::stat)
+            .register_fn("series", Metrics::series)
+            .register_fn("latest", Metrics::latest)
+            .register_fn("now", Metrics::now);
+        let printed = messages.clone();
+        engine.on_print(move |text| printed.lock().unwrap().push(text.to_string()));
+        let alerts = messages.clone();
+        engine.register_fn("alert", move |text: &str| {
+            alerts.lock().unwrap().push(format!("ALERT {}", text));
+        });
+
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| format!("Failed to load script {}: {}", path.display(), err))?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|err| format!("Script {} failed: {}", path.display(), err))?;
+
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        Ok(Runner {
+            name,
+            engine,
+            ast,
+            scope,
+            state: Dynamic::from_map(Map::new()),
+            metrics: Metrics(store),
+            messages,
+            started,
+        })
+    }
+
+    /// Run the callbacks from `queued` until the [`Script`] is dropped,
+    /// sending what they print or raise to `notices`.
+    fn run(mut self, queued: Receiver<Call>, notices: Sender<PipelineEvent>) {
+        let send = |runner: &Runner| {
+            runner.take_messages().into_iter().all(|notice| notices.send(notice).is_ok())
+        };
+        // What the script printed while it was loaded.
+        if !send(&self) {
+            return;
+        }
+        for call in queued {
+            let metrics = self.metrics.clone();
+            match call {
+                Call::Tick => self.call("on_tick", (metrics,)),
+                Call::Event(map) => self.call("on_event", (metrics, map)),
+            }
+            if !send(&self) {
+                return;
+            }
+        }
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|function| function.name == name)
+    }
+
+    /// Call `name` if the script defines it, with `this` bound to the map the
+    /// script keeps its state in.
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) {
+        if !self.defines(name) {
+            return;
+        }
+        *self.started.lock().unwrap() = Instant::now();
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, name, args);
+        let message = match result.map_err(|err| *err) {
+            Ok(_) => return,
+            Err(EvalAltResult::ErrorTerminated(..)) => format!(
+                "{} ran longer than {} s and was stopped",
+                name,
+                CALL_TIMEOUT.as_secs()
+            ),
+            Err(err) => format!("{} failed: {}", name, err),
+        };
+        self.messages.lock().unwrap().push(message);
+    }
+
+    fn take_messages(&self) -> Vec<PipelineEvent> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+            .into_iter()
+            .map(|message| PipelineEvent::Notice(format!("[{}] {}", self.name, message)))
+            .collect()
+    }
+}
+
+/// A script alongside the viewer: it gets `on_tick(metrics)` once a second
+/// and `on_event(metrics, event)` for pipeline events, and what it prints or
+/// raises with `alert()` reaches the viewer as notices.
+impl Tap for Script {
+    fn event(&mut self, event: &PipelineEvent, out: &mut Outbox) {
+        if let Some(map) = event_map(event) {
+            self.queue(Call::Event(map), out);
+        }
+        self.forward(out);
+    }
+
+    fn tick(&mut self, out: &mut Outbox) {
+        self.queue(Call::Tick, out);
+        self.forward(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endless_callbacks_are_stopped() {
+        let path = std::env::temp_dir().join(format!("gst_debugger_{}.rhai", std::process::id()));
+        std::fs::write(&path, "fn on_tick(metrics) { loop {} }").unwrap();
+        let runner = Runner::load(&path, SharedStore::default());
+        std::fs::remove_file(&path).unwrap();
+        let mut runner = runner.unwrap();
+
+        runner.call("on_tick", (runner.metrics.clone(),));
+        let notices = runner.take_messages();
+        assert_eq!(notices.len(), 1);
+        assert!(matches!(&notices[0], PipelineEvent::Notice(text) if text.contains("on_tick")));
+    }
+}
@@ -0,0 +1,72 @@
+//! Fragments and segments written by `splitmuxsink` and `hlssink2` in
+//! `--mode in-process`, from the element messages they post.
+
+use std::collections::HashMap;
+
+use gst_debugger_core::Segment;
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// The running time each segmenting element last opened or closed a
+/// fragment at, for segments whose message carries no duration.
+#[derive(Default)]
+pub(crate) struct SegmentTracker {
+    boundaries: HashMap<String, u64>,
+}
+
+impl SegmentTracker {
+    /// The segment a `splitmuxsink-fragment-closed` or `hls-segment-added`
+    /// message reports, with its file size when the file can be read.
+    pub(crate) fn segment(&mut self, message: &gst::Message, timestamp_ns: u64) -> Option<Segment> {
+        let gst::MessageView::Element(element) = message.view() else {
+            return None;
+        };
+        let structure = element.structure()?;
+        let src = message.src()?;
+        let element = src.name().to_string();
+        // hlssink2 reports the fragments of the splitmuxsink inside it itself.
+        let inside_hlssink = src
+            .parent()
+            .and_then(|parent| parent.downcast::<gst::Element>().ok())
+            .and_then(|parent| parent.factory())
+            .is_some_and(|factory| factory.name().starts_with("hlssink"));
+        if inside_hlssink && structure.name().starts_with("splitmuxsink-") {
+            return None;
+        }
+        let running_time_ns = structure.get::<u64>("running-time").ok();
+        let (location, duration_ns) = match structure.name().as_str() {
+            "splitmuxsink-fragment-opened" => {
+                if let Some(running_time_ns) = running_time_ns {
+                    self.boundaries.insert(element, running_time_ns);
+                }
+                return None;
+            }
+            "splitmuxsink-fragment-closed" => (
+                structure.get::<String>("location").ok()?,
+                structure.get::<u64>("fragment-duration").ok(),
+            ),
+            "hls-segment-added" => (
+                structure.get::<String>("location").ok()?,
+                structure.get::<u64>("duration").ok(),
+            ),
+            _ => return None,
+        };
+
+        let previous = match running_time_ns {
+            Some(running_time_ns) => self.boundaries.insert(element.clone(), running_time_ns),
+            None => self.boundaries.get(&element).copied(),
+        };
+        let duration_ns = duration_ns.or_else(|| {
+            let (start, end) = previous.zip(running_time_ns)?;
+            end.checked_sub(start)
+        });
+        Some(Segment {
+            timestamp_ns,
+            size_bytes: std::fs::metadata(&location).ok().map(|metadata| metadata.len()),
+            element,
+            location,
+            running_time_ns,
+            duration_ns,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! Frames the video sinks of a `--mode in-process` pipeline rendered and
+//! dropped, from the `stats` every base sink keeps.
+
+use std::collections::{HashMap, HashSet};
+
+use gst_debugger_core::SinkFrames;
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+/// Leaf video sinks that keep frame stats, not the bins around them.
+fn video_sinks(pipeline: &gst::Pipeline) -> Vec<gst::Element> {
+    pipeline
+        .iterate_recurse()
+        .into_iter()
+        .flatten()
+        .filter(|element| {
+            element.element_flags().contains(gst::ElementFlags::SINK)
+                && !element.is::<gst::Bin>()
+                && element.find_property("stats").is_some()
+                && element
+                    .factory()
+                    .and_then(|factory| factory.metadata(gst::ELEMENT_METADATA_KLASS))
+                    .is_some_and(|klass| klass.contains("Video"))
+        })
+        .collect()
+}
+
+pub(crate) struct SinkFrameTracker {
+    /// Rendered and dropped counts of each sink at the previous call.
+    previous: HashMap<String, (u64, u64)>,
+    /// The drop rate in percent above which a sink is reported.
+    limit_percent: f64,
+    dropping: HashSet<String>,
+}
+
+impl SinkFrameTracker {
+    pub(crate) fn new(limit_percent: f64) -> Self {
+        SinkFrameTracker {
+            previous: HashMap::new(),
+            limit_percent,
+            dropping: HashSet::new(),
+        }
+    }
+
+    /// The counts of every video sink with its drop rate since the previous
+    /// call, and a notice when that rate crossed the limit either way.
+    pub(crate) fn stats(
+        &mut self,
+        pipeline: &gst::Pipeline,
+        timestamp_ns: u64,
+    ) -> Vec<(SinkFrames, Option<String>)> {
+        let mut results = Vec::new();
+        for sink in video_sinks(pipeline) {
+            let stats = sink.property::<gst::Structure>("stats");
+            let rendered = stats.get::<u64>("rendered").unwrap_or(0);
+            let dropped = stats.get::<u64>("dropped").unwrap_or(0);
+            let name = sink.name().to_string();
+
+            let (previous_rendered, previous_dropped) =
+                self.previous.insert(name.clone(), (rendered, dropped)).unwrap_or((0, 0));
+            let new_rendered = rendered.saturating_sub(previous_rendered);
+            let new_dropped = dropped.saturating_sub(previous_dropped);
+            let frames = new_rendered + new_dropped;
+            // Nothing to report while no frames arrive, as when paused.
+            if frames == 0 {
+                continue;
+            }
+            let drop_percent = new_dropped as f64 * 100.0 / frames as f64;
+
+            let dropping = drop_percent > self.limit_percent;
+            let notice = match (self.dropping.contains(&name), dropping) {
+                (false, true) => {
+                    self.dropping.insert(name.clone());
+                    Some(format!(
+                        "{} dropped {:.1}% of its frames (limit {}%)",
+                        name, drop_percent, self.limit_percent
+                    ))
+                }
+                (true, false) => {
+                    self.dropping.remove(&name);
+                    Some(format!("{} stopped dropping frames", name))
+                }
+                _ => None,
+            };
+            let frames = SinkFrames {
+                timestamp_ns,
+                sink: name,
+                rendered,
+                dropped,
+                drop_percent,
+                limit_percent: self.limit_percent,
+            };
+            results.push((frames, notice));
+        }
+        results
+    }
+}
@@ -0,0 +1,90 @@
+//! Stream collections posted by stream-aware elements such as decodebin3,
+//! parsebin and playbin3 in `--mode in-process`, and switching the streams
+//! they play.
+
+use gst_debugger_core::{MediaStream, StreamCollection};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+
+fn stream_kind(stream: &gst::Stream) -> &'static str {
+    let kind = stream.stream_type();
+    if kind.contains(gst::StreamType::VIDEO) {
+        "video"
+    } else if kind.contains(gst::StreamType::AUDIO) {
+        "audio"
+    } else if kind.contains(gst::StreamType::TEXT) {
+        "text"
+    } else if kind.contains(gst::StreamType::CONTAINER) {
+        "container"
+    } else {
+        "unknown"
+    }
+}
+
+fn media_stream(stream: &gst::Stream, selected: &[String]) -> MediaStream {
+    let stream_id = stream.stream_id().map(|id| id.to_string()).unwrap_or_default();
+    let tags = stream.tags();
+    let codec = tags
+        .as_ref()
+        .and_then(|tags| {
+            tags.get::<gst::tags::VideoCodec>()
+                .or_else(|| tags.get::<gst::tags::AudioCodec>())
+                .or_else(|| tags.get::<gst::tags::SubtitleCodec>())
+                .or_else(|| tags.get::<gst::tags::Codec>())
+                .map(|codec| codec.get().to_string())
+        })
+        .or_else(|| {
+            let caps = stream.caps()?;
+            caps.structure(0).map(|structure| structure.name().to_string())
+        });
+    let language = tags.as_ref().and_then(|tags| {
+        tags.get::<gst::tags::LanguageCode>()
+            .or_else(|| tags.get::<gst::tags::LanguageName>())
+            .map(|language| language.get().to_string())
+    });
+    MediaStream {
+        selected: selected.contains(&stream_id),
+        stream_id,
+        kind: stream_kind(stream).to_string(),
+        codec,
+        language,
+    }
+}
+
+/// The collection in a stream-collection or streams-selected message. A new
+/// collection has nothing selected until the element picks its streams.
+pub(crate) fn stream_collection(
+    message: &gst::Message,
+    timestamp_ns: u64,
+) -> Option<StreamCollection> {
+    let (collection, selected) = match message.view() {
+        gst::MessageView::StreamCollection(message) => (message.stream_collection(), Vec::new()),
+        gst::MessageView::StreamsSelected(message) => {
+            let selected = message
+                .streams()
+                .into_iter()
+                .filter_map(|stream| stream.stream_id().map(|id| id.to_string()))
+                .collect();
+            (message.stream_collection(), selected)
+        }
+        _ => return None,
+    };
+    Some(StreamCollection {
+        timestamp_ns,
+        element: message.src()?.name().to_string(),
+        streams: collection
+            .iter()
+            .map(|stream| media_stream(&stream, &selected))
+            .collect(),
+    })
+}
+
+/// Ask `element` to play the streams with `stream_ids`.
+pub(crate) fn select_streams(element: &gst::Element, stream_ids: &[String]) -> Result<(), String> {
+    let ids: Vec<&str> = stream_ids.iter().map(String::as_str).collect();
+    if element.send_event(gst::event::SelectStreams::new(&ids)) {
+        Ok(())
+    } else {
+        Err(format!("{} did not accept the stream selection", element.name()))
+    }
+}
@@ -0,0 +1,126 @@
+//! What watches the engine's output on its way to the viewer: a script, the
+//! notifier and the HTTP API. One task stores each sample once, in a store
+//! they share, and hands samples and events to each of them in turn.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use gst_debugger_core::{InterLatencyData, MetricStore, PipelineEvent, TracingData};
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::EngineOutput;
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The samples received so far, shared by the taps.
+pub(crate) type SharedStore = Arc<RwLock<MetricStore>>;
+
+/// Something that watches the engine's output. Samples reach it after the
+/// shared store has them.
+pub(crate) trait Tap: Send {
+    fn sample(&mut self, _sample: &TracingData) {}
+
+    fn latency(&mut self, _latency: &InterLatencyData) {}
+
+    /// Called for each event before it is passed on.
+    fn event(&mut self, _event: &PipelineEvent, _out: &mut Outbox) {}
+
+    /// Called once a second.
+    fn tick(&mut self, _out: &mut Outbox) {}
+}
+
+/// Where a tap sends events of its own, such as notices.
+pub(crate) struct Outbox {
+    raised: Vec<PipelineEvent>,
+    sender: mpsc::Sender<PipelineEvent>,
+}
+
+impl Outbox {
+    /// Pass `event` on after the one being handled. The taps after this
+    /// one see it too.
+    pub(crate) fn push(&mut self, event: PipelineEvent) {
+        self.raised.push(event);
+    }
+
+    /// For events sent later, from a task of the tap's own.
+    pub(crate) fn sender(&self) -> mpsc::Sender<PipelineEvent> {
+        self.sender.clone()
+    }
+}
+
+/// Run `taps` in order on everything passing through `input`, storing the
+/// samples in `store`.
+pub(crate) fn attach(
+    mut taps: Vec<Box<dyn Tap>>,
+    store: SharedStore,
+    mut input: EngineOutput,
+) -> EngineOutput {
+    if taps.is_empty() {
+        return input;
+    }
+    let (tx, rx) = mpsc::channel(100);
+    let (lat_tx, lat_rx) = mpsc::channel(100);
+    let (log_tx, log_rx) = mpsc::channel(500);
+    let (event_tx, event_rx) = mpsc::channel(16);
+    let preview = input.preview.clone();
+
+    tokio::spawn(async move {
+        let mut out = Outbox {
+            raised: Vec::new(),
+            sender: event_tx.clone(),
+        };
+        let mut ticker = time::interval(TICK_INTERVAL);
+        loop {
+            // Events with the index of the first tap that still has to
+            // see them.
+            let mut events = VecDeque::new();
+            tokio::select! {
+                Some(sample) = input.data.recv() => {
+                    store.write().unwrap().push_sample(sample.clone());
+                    for tap in &mut taps {
+                        tap.sample(&sample);
+                    }
+                    let _ = tx.send(sample).await;
+                }
+                Some(latency) = input.latency.recv() => {
+                    store.write().unwrap().push_latency(latency.clone());
+                    for tap in &mut taps {
+                        tap.latency(&latency);
+                    }
+                    let _ = lat_tx.send(latency).await;
+                }
+                Some(line) = input.log.recv() => {
+                    let _ = log_tx.try_send(line);
+                }
+                _ = ticker.tick() => {
+                    for (index, tap) in taps.iter_mut().enumerate() {
+                        tap.tick(&mut out);
+                        events.extend(out.raised.drain(..).map(|event| (event, index + 1)));
+                    }
+                }
+                event = input.events.recv() => {
+                    let Some(event) = event else { break };
+                    events.push_back((event, 0));
+                }
+            }
+
+            while let Some((event, first)) = events.pop_front() {
+                for (index, tap) in taps.iter_mut().enumerate().skip(first) {
+                    tap.event(&event, &mut out);
+                    events.extend(out.raised.drain(..).map(|event| (event, index + 1)));
+                }
+                let _ = event_tx.send(event).await;
+            }
+        }
+    });
+
+    EngineOutput {
+        data: rx,
+        latency: lat_rx,
+        events: event_rx,
+        log: log_rx,
+        preview,
+    }
+}
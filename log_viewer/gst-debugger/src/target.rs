@@ -0,0 +1,125 @@
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+/// Prefix of the line the wrapper script prints to stderr before it execs
+/// gst-launch on a remote target, so the pipeline can be signalled there.
+pub(crate) const PID_PREFIX: &str = "gst_debugger_pid=";
+
+/// Reports the shell's PID, which `exec` hands over to the program.
+const WRAPPER: &str = "echo gst_debugger_pid=$$ >&2; exec \"$@\"";
+
+/// Where gst-launch and gst-inspect run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    Local,
+    /// A running container, entered with `docker exec` or `podman exec`.
+    Container { runtime: String, name: String },
+    /// An Android device, entered with `adb -s SERIAL shell`.
+    Adb { serial: String },
+}
+
+/// Quote `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+impl Target {
+    pub fn is_local(&self) -> bool {
+        *self == Target::Local
+    }
+
+    /// Build the command that runs `program` with `args` and `env` on the
+    /// target. On a remote target the command first reports the PID of the
+    /// program on stderr as `gst_debugger_pid=N`.
+    pub(crate) fn command(
+        &self,
+        program: &str,
+        args: impl IntoIterator<Item = String>,
+        env: &[(String, String)],
+    ) -> Command {
+        match self {
+            Target::Local => {
+                let mut command = Command::new(program);
+                command.args(args).envs(env.iter().cloned());
+                command
+            }
+            Target::Container { runtime, name } => {
+                let mut command = Command::new(runtime);
+                command.args(["exec", "-i"]);
+                for (key, value) in env {
+                    command.arg("-e").arg(format!("{}={}", key, value));
+                }
+                command
+                    .arg(name)
+                    .args(["sh", "-c", WRAPPER, "sh", program])
+                    .args(args);
+                command
+            }
+            // `adb shell` takes one command line, so the environment and the
+            // arguments are quoted into it.
+            Target::Adb { serial } => {
+                let mut line = String::new();
+                for (key, value) in env {
+                    line.push_str(&format!("export {}={}; ", key, shell_quote(value)));
+                }
+                line.push_str(&format!("echo {}$$ >&2; exec ", PID_PREFIX));
+                line.push_str(&shell_quote(program));
+                for arg in args {
+                    line.push(' ');
+                    line.push_str(&shell_quote(&arg));
+                }
+                let mut command = Command::new("adb");
+                command.args(["-s", serial.as_str(), "shell", line.as_str()]);
+                command
+            }
+        }
+    }
+
+    /// The command that follows the target's system log, where GStreamer
+    /// logs on Android when it is set up to use logcat. `None` on targets
+    /// without one.
+    pub(crate) fn logcat(&self) -> Option<Command> {
+        let Target::Adb { serial } = self else {
+            return None;
+        };
+        // `-T 1` skips what was logged before the run, except one line.
+        let mut command = Command::new("adb");
+        command.args(["-s", serial.as_str(), "logcat", "-v", "threadtime", "-T", "1"]);
+        Some(command)
+    }
+
+    /// Send `signal`, e.g. `INT`, to `pid` on a remote target.
+    pub(crate) async fn signal(&self, pid: u32, signal: &str) -> bool {
+        let mut command = match self {
+            Target::Local => return false,
+            Target::Container { runtime, name } => {
+                let mut command = Command::new(runtime);
+                command.args(["exec", name.as_str()]);
+                command
+            }
+            Target::Adb { serial } => {
+                let mut command = Command::new("adb");
+                command.args(["-s", serial.as_str(), "shell"]);
+                command
+            }
+        };
+        command
+            .args(["kill", "-s", signal, &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Short description for messages, e.g. `container app (docker)`.
+    pub fn describe(&self) -> String {
+        match self {
+            Target::Local => "this machine".to_string(),
+            Target::Container { runtime, name } => format!("container {} ({})", name, runtime),
+            Target::Adb { serial } => format!("Android device {}", serial),
+        }
+    }
+}
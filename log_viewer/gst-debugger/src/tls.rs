@@ -0,0 +1,221 @@
+//! TLS and shared-token authentication for the networked modes: the HTTP
+//! API with its WebSocket stream, and the gRPC agent.
+
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use hyper::body::{Body, Incoming};
+use hyper::{Request, Response, Uri};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::{client, TlsAcceptor, TlsConnector};
+use tower_service::Service;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// How long a client may take to finish the TLS handshake before it is
+/// dropped, so idle connections cannot pile up.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the shared token is read from when there is no token file, since
+/// an argument would show in `ps`.
+pub(crate) const TOKEN_VAR: &str = "GST_DEBUGGER_TOKEN";
+
+/// Options for serving over TLS and requiring a token.
+#[derive(clap::Args, Debug, Clone)]
+pub struct SecurityArgs {
+    /// PEM certificate chain to serve over TLS with
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key of --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// File holding the shared token clients must send as `Authorization:
+    /// Bearer TOKEN`. Without it, the token comes from GST_DEBUGGER_TOKEN
+    #[arg(long, value_name = "FILE")]
+    pub token_file: Option<PathBuf>,
+}
+
+/// What [`SecurityArgs`] load to.
+#[derive(Clone, Default)]
+pub(crate) struct Security {
+    pub tls: Option<TlsAcceptor>,
+    pub token: Option<String>,
+}
+
+impl Security {
+    pub(crate) fn load(args: &SecurityArgs) -> Result<Self, String> {
+        let tls = match (&args.tls_cert, &args.tls_key) {
+            (Some(cert), Some(key)) => Some(acceptor(cert, key)?),
+            _ => None,
+        };
+        Ok(Security {
+            tls,
+            token: load_token(args.token_file.as_deref())?,
+        })
+    }
+}
+
+/// The shared token in `file`, or in [`TOKEN_VAR`] without one. `None`
+/// when neither sets one.
+pub(crate) fn load_token(file: Option<&Path>) -> Result<Option<String>, String> {
+    let token = match file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read the token from {}: {}", path.display(), err))?,
+        None => std::env::var(TOKEN_VAR).unwrap_or_default(),
+    };
+    let token = token.trim();
+    match (token.is_empty(), file) {
+        (true, Some(path)) => Err(format!("{} holds no token", path.display())),
+        (true, None) => Ok(None),
+        (false, _) => Ok(Some(token.to_string())),
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| format!("Failed to read certificates from {}: {}", path.display(), err))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates in {}", path.display()));
+    }
+    Ok(certs)
+}
+
+fn acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor, String> {
+    let certs = load_certs(cert)?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|err| format!("Failed to read private key from {}: {}", key.display(), err))?;
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|err| err.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| format!("Invalid certificate or key: {}", err))?;
+    // gRPC needs HTTP/2; browsers and curl may use either.
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Whether `header`, the value of an `Authorization` header, carries
+/// `token`. The comparison takes the same time wherever the values differ.
+pub(crate) fn bearer_matches(header: Option<&str>, token: &str) -> bool {
+    let Some(given) = header.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Serve `service` over TLS on `listener` until `shutdown` resolves.
+pub(crate) async fn serve<S, B>(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    service: S,
+    shutdown: impl Future<Output = ()>,
+) where
+    S: Service<Request<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    tokio::pin!(shutdown);
+    loop {
+        let stream = tokio::select! {
+            _ = &mut shutdown => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+        };
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(service.clone());
+        tokio::spawn(async move {
+            let Ok(Ok(stream)) = time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+            else {
+                return;
+            };
+            let _ = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+/// Opens TLS connections for a gRPC channel to an `https://` agent.
+#[derive(Clone)]
+pub(crate) struct TlsConnect(TlsConnector);
+
+impl TlsConnect {
+    /// Trust the certificates in `ca`, e.g. a lab's self-signed one, or the
+    /// public web roots when `None`.
+    pub(crate) fn new(ca: Option<&Path>) -> Result<Self, String> {
+        let mut roots = RootCertStore::empty();
+        match ca {
+            Some(path) => {
+                for cert in load_certs(path)? {
+                    roots.add(cert).map_err(|err| {
+                        format!("Invalid certificate in {}: {}", path.display(), err)
+                    })?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|err| err.to_string())?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(TlsConnect(TlsConnector::from(Arc::new(config))))
+    }
+}
+
+type TlsIo = TokioIo<client::TlsStream<TcpStream>>;
+type Connecting = Pin<Box<dyn Future<Output = io::Result<TlsIo>> + Send>>;
+
+impl Service<Uri> for TlsConnect {
+    type Response = TlsIo;
+    type Error = io::Error;
+    type Future = Connecting;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Connecting {
+        let connector = self.0.clone();
+        Box::pin(async move {
+            let host = uri
+                .host()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no host in address"))?
+                .trim_matches(['[', ']'])
+                .to_string();
+            let port = uri.port_u16().unwrap_or(443);
+            let stream = TcpStream::connect((host.as_str(), port)).await?;
+            let name = ServerName::try_from(host)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            Ok(TokioIo::new(connector.connect(name, stream).await?))
+        })
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+
+use gst_debugger_core::{
+    format_clock_time, parse_debug_line, pipeline_elements, Aggregate, ElementStats, Metric,
+    MetricStore, PipelineCommand, PipelineEvent, PipelineState, RuleSet, TRACER_CATEGORY,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::cli::Session;
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+const LOG_LINES: usize = 500;
+/// The least severe level the log shows, cycled through with `l`: all
+/// levels, then down to errors only.
+const LOG_FILTERS: [(Option<u8>, &str); 5] = [
+    (None, "all levels"),
+    (Some(5), "DEBUG and above"),
+    (Some(4), "INFO and above"),
+    (Some(2), "WARN and above"),
+    (Some(1), "ERROR only"),
+];
+
+struct Tui {
+    elements: Vec<String>,
+    store: MetricStore,
+    log: VecDeque<String>,
+    /// Index into `LOG_FILTERS`.
+    log_filter: usize,
+    state: PipelineState,
+    eos: HashSet<String>,
+    status: Option<String>,
+    rules: RuleSet,
+    table: TableState,
+    started: Instant,
+}
+
+/// Show the pipeline graph, per-element statistics and the raw log in the
+/// terminal until the user quits or the pipeline stops.
+pub async fn run(session: Session) -> io::Result<()> {
+    let Session {
+        pipeline,
+        engine,
+        mut output,
+        restart_on_failure,
+        rules,
+        ..
+    } = session;
+
+    let control = engine.control();
+    let mut tui = Tui {
+        elements: pipeline_elements(&pipeline),
+        store: MetricStore::default(),
+        log: VecDeque::with_capacity(LOG_LINES),
+        log_filter: 0,
+        state: PipelineState::Playing,
+        eos: HashSet::new(),
+        status: None,
+        rules,
+        table: TableState::default().with_selected(Some(0)),
+        started: Instant::now(),
+    };
+
+    let mut terminal = ratatui::init();
+    let mut ticker = time::interval(REDRAW_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if let Err(err) = terminal.draw(|frame| tui.draw(frame)) {
+                    break Err(err);
+                }
+                match tui.handle_input(&control).await {
+                    Ok(true) => break Ok(()),
+                    Ok(false) => {}
+                    Err(err) => break Err(err),
+                }
+            }
+            Some(sample) = output.data.recv() => tui.store.push_sample(sample),
+            Some(latency) = output.latency.recv() => tui.store.push_latency(latency),
+            Some(line) = output.log.recv() => {
+                if tui.log.len() == LOG_LINES {
+                    tui.log.pop_front();
+                }
+                tui.log.push_back(line);
+            }
+            event = output.events.recv() => match event {
+                Some(PipelineEvent::State(PipelineState::Stopped)) if !restart_on_failure => {
+                    break Ok(());
+                }
+                Some(PipelineEvent::State(state)) => {
+                    if state == PipelineState::Playing && tui.state != PipelineState::Paused {
+                        tui.eos.clear();
+                    }
+                    tui.state = state;
+                }
+                Some(PipelineEvent::ElementEos(element)) => {
+                    tui.eos.insert(element);
+                }
+                Some(PipelineEvent::Error(error)) => {
+                    tui.status = Some(format!("Error: {}", error.message));
+                }
+                Some(PipelineEvent::Notice(message)) => tui.status = Some(message),
+                Some(PipelineEvent::Restarting { attempt, delay }) => {
+                    tui.state = PipelineState::Restarting;
+                    tui.status = Some(format!(
+                        "Restarting in {:.1}s (attempt {})",
+                        delay.as_secs_f32(),
+                        attempt
+                    ));
+                }
+                Some(_) => {}
+                None => break Ok(()),
+            },
+        }
+    };
+
+    ratatui::restore();
+    engine.shutdown().await;
+    result
+}
+
+fn key_hints() -> Line<'static> {
+    let hints = [
+        ("q", "quit"),
+        ("space", "play/pause"),
+        ("r", "restart"),
+        ("e", "EOS"),
+        ("↑↓", "select"),
+        ("l", "log level"),
+    ];
+    let mut spans = Vec::new();
+    for (key, action) in hints {
+        spans.push(Span::styled(key, Style::new().add_modifier(Modifier::BOLD)));
+        spans.push(Span::raw(format!(" {}  ", action)));
+    }
+    Line::from(spans)
+}
+
+impl Tui {
+    /// Apply pending key presses. Returns true when the user asked to quit.
+    async fn handle_input(&mut self, control: &mpsc::Sender<PipelineCommand>) -> io::Result<bool> {
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let command = match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(true);
+                }
+                KeyCode::Char(' ') | KeyCode::Char('p') => match self.state {
+                    PipelineState::Paused => Some(PipelineCommand::Play),
+                    _ => Some(PipelineCommand::Pause),
+                },
+                KeyCode::Char('r') => Some(PipelineCommand::Restart),
+                KeyCode::Char('e') => Some(PipelineCommand::SendEos),
+                KeyCode::Char('l') => {
+                    self.log_filter = (self.log_filter + 1) % LOG_FILTERS.len();
+                    None
+                }
+                KeyCode::Up => {
+                    self.table.select_previous();
+                    None
+                }
+                KeyCode::Down => {
+                    self.table.select_next();
+                    None
+                }
+                _ => None,
+            };
+            if let Some(command) = command {
+                let _ = control.send(command).await;
+            }
+        }
+        Ok(false)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, graph, stats, log] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(5),
+            Constraint::Min(6),
+            Constraint::Percentage(40),
+        ])
+        .areas(frame.area());
+
+        let stats_rows = self.store.element_stats();
+        let last = stats_rows.len().checked_sub(1);
+        self.table.select(match self.table.selected() {
+            Some(index) => last.map(|last| index.min(last)),
+            None => last.map(|_| 0),
+        });
+        let selected = self
+            .table
+            .selected()
+            .and_then(|index| stats_rows.get(index))
+            .map(|stats| stats.element.clone());
+
+        self.draw_header(frame, header);
+        self.draw_graph(frame, graph, selected.as_deref());
+        self.draw_stats(frame, stats, &stats_rows);
+        self.draw_log(frame, log);
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let mut status = vec![
+            Span::styled(
+                format!("{:?}", self.state),
+                Style::new().fg(state_color(self.state)).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "  {}  {} samples",
+                format_clock_time(self.started.elapsed().as_nanos() as u64),
+                self.store.samples().len()
+            )),
+        ];
+        if let Some(message) = &self.status {
+            status.push(Span::styled(format!("  {}", message), Style::new().fg(Color::Yellow)));
+        }
+        frame.render_widget(Paragraph::new(vec![Line::from(status), key_hints()]), area);
+    }
+
+    /// Draw the elements as a row of boxes linked left to right.
+    fn draw_graph(&self, frame: &mut Frame, area: Rect, selected: Option<&str>) {
+        let mut top = Vec::new();
+        let mut middle = Vec::new();
+        let mut bottom = Vec::new();
+        for (index, element) in self.elements.iter().enumerate() {
+            let width = element.chars().count() + 2;
+            let style = if self.eos.contains(element) {
+                Style::new().fg(Color::DarkGray)
+            } else if selected.is_some_and(|name| name.starts_with(element.as_str())) {
+                Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            };
+
+            if index > 0 {
+                top.push(Span::raw("   "));
+                middle.push(Span::raw("──▶"));
+                bottom.push(Span::raw("   "));
+            }
+            top.push(Span::styled(format!("┌{}┐", "─".repeat(width)), style));
+            middle.push(Span::styled(format!("│ {} │", element), style));
+            bottom.push(Span::styled(format!("└{}┘", "─".repeat(width)), style));
+        }
+
+        let graph = Paragraph::new(vec![Line::from(top), Line::from(middle), Line::from(bottom)])
+            .block(Block::new().borders(Borders::ALL).title(" Pipeline "));
+        frame.render_widget(graph, area);
+    }
+
+    fn draw_stats(&mut self, frame: &mut Frame, area: Rect, stats: &[ElementStats]) {
+        let header = Row::new(
+            std::iter::once(Cell::from("Element"))
+                .chain(Metric::ALL.iter().map(|metric| Cell::from(metric.label()))),
+        )
+        .style(Style::new().add_modifier(Modifier::BOLD));
+
+        let rows = stats.iter().map(|element| {
+            Row::new(std::iter::once(Cell::from(element.element.clone())).chain(
+                Metric::ALL.iter().map(|&metric| {
+                    let Some(value) = element.summary(metric).get(Aggregate::Avg) else {
+                        return Cell::from("-");
+                    };
+                    let violated = self
+                        .rules
+                        .limit_for(&element.element, metric)
+                        .is_some_and(|limit| limit.is_violated_by(value));
+                    let style = if violated {
+                        Style::new().fg(Color::Red)
+                    } else {
+                        Style::new()
+                    };
+                    Cell::from(metric.format(value)).style(style)
+                }),
+            ))
+        });
+
+        let element_width = stats
+            .iter()
+            .map(|element| element.element.len())
+            .chain(["Element".len()])
+            .max()
+            .unwrap_or(0) as u16;
+        let widths = std::iter::once(Constraint::Length(element_width))
+            .chain(Metric::ALL.iter().map(|_| Constraint::Length(16)));
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .block(Block::new().borders(Borders::ALL).title(" Averages "));
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_log(&self, frame: &mut Frame, area: Rect) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let (max_severity, filter) = LOG_FILTERS[self.log_filter];
+        // Lines other than debug lines, such as gst-launch's own, always show.
+        let shown: Vec<&String> = self
+            .log
+            .iter()
+            .filter(|line| {
+                let severity = parse_debug_line(line).map(|debug| debug.severity());
+                max_severity.is_none_or(|max| severity.is_none_or(|severity| severity <= max))
+            })
+            .collect();
+        let lines: Vec<Line> = shown
+            .iter()
+            .skip(shown.len().saturating_sub(visible))
+            .map(|line| log_line(line))
+            .collect();
+        let title = format!(" Log ({}) ", filter);
+        let log = Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(title));
+        frame.render_widget(log, area);
+    }
+}
+
+/// A line of the log panel: debug lines other than the tracers' with their
+/// level colored and category picked out and the process, thread and source
+/// location left out, anything else as it came.
+fn log_line(line: &str) -> Line<'_> {
+    match parse_debug_line(line) {
+        Some(debug) if debug.category != TRACER_CATEGORY => Line::from(vec![
+            Span::styled(debug.timestamp, Style::new().fg(Color::DarkGray)),
+            Span::raw(" "),
+            Span::styled(format!("{:<5}", debug.level), level_style(debug.severity())),
+            Span::raw(" "),
+            Span::styled(debug.category, Style::new().fg(Color::Magenta)),
+            Span::raw(" "),
+            Span::raw(debug.message),
+        ]),
+        _ => Line::raw(line),
+    }
+}
+
+/// The colors of `GST_DEBUG_COLOR_MODE=on`.
+fn level_style(severity: u8) -> Style {
+    let bold = Style::new().add_modifier(Modifier::BOLD);
+    match severity {
+        1 => bold.fg(Color::Red),
+        2 | 3 => bold.fg(Color::Yellow),
+        4 => bold.fg(Color::Green),
+        5 => Style::new().fg(Color::Cyan),
+        _ => Style::new().fg(Color::Gray),
+    }
+}
+
+fn state_color(state: PipelineState) -> Color {
+    match state {
+        PipelineState::Playing => Color::Green,
+        PipelineState::Paused => Color::Yellow,
+        PipelineState::Stopped => Color::Red,
+        PipelineState::Restarting => Color::Magenta,
+    }
+}
@@ -0,0 +1,173 @@
+//! webrtcbin statistics for `--mode in-process`. Every webrtcbin in the
+//! pipeline is asked for its stats once a second with `get-stats`, and the
+//! reply is condensed into per-stream bitrate, loss, jitter and RTT.
+
+use std::collections::HashMap;
+
+use gst_debugger_core::{StreamDirection, WebRtcStats, WebRtcStream};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use tokio::sync::mpsc;
+
+/// A `get-stats` reply and the name of the webrtcbin it came from.
+pub(crate) type StatsReply = (String, gst::Structure);
+
+pub(crate) fn webrtcbins(pipeline: &gst::Pipeline) -> Vec<gst::Element> {
+    pipeline
+        .iterate_recurse()
+        .into_iter()
+        .flatten()
+        .filter(|element| {
+            element
+                .factory()
+                .is_some_and(|factory| factory.name().as_str() == "webrtcbin")
+        })
+        .collect()
+}
+
+/// Ask `webrtcbin` for its stats. The reply is sent to `replies` from a
+/// GStreamer thread once webrtcbin has gathered it.
+pub(crate) fn request_stats(
+    webrtcbin: &gst::Element,
+    replies: &mpsc::UnboundedSender<StatsReply>,
+) {
+    let name = webrtcbin.name().to_string();
+    let replies = replies.clone();
+    let promise = gst::Promise::with_change_func(move |reply| {
+        if let Ok(Some(stats)) = reply {
+            let _ = replies.send((name, stats.to_owned()));
+        }
+    });
+    webrtcbin.emit_by_name::<()>("get-stats", &[&None::<gst::Pad>, &promise]);
+}
+
+// Field types differ between GStreamer versions, so integers are read as
+// whichever type the field has.
+fn unsigned(stat: &gst::StructureRef, field: &str) -> Option<u64> {
+    stat.get::<u64>(field)
+        .ok()
+        .or_else(|| stat.get::<u32>(field).ok().map(u64::from))
+        .or_else(|| stat.get::<i64>(field).ok().and_then(|value| value.try_into().ok()))
+        .or_else(|| stat.get::<i32>(field).ok().and_then(|value| value.try_into().ok()))
+}
+
+fn signed(stat: &gst::StructureRef, field: &str) -> Option<i64> {
+    stat.get::<i64>(field)
+        .ok()
+        .or_else(|| stat.get::<i32>(field).ok().map(i64::from))
+        .or_else(|| stat.get::<u64>(field).ok().and_then(|value| value.try_into().ok()))
+        .or_else(|| stat.get::<u32>(field).ok().map(i64::from))
+}
+
+/// A duration field given in seconds, in nanoseconds.
+fn seconds_ns(stat: &gst::StructureRef, field: &str) -> Option<u64> {
+    stat.get::<f64>(field).ok().map(|seconds| (seconds * 1e9) as u64)
+}
+
+/// What the remote peer reported about one of our outbound streams.
+#[derive(Default)]
+struct ReceiverReport {
+    packets_lost: Option<i64>,
+    fraction_lost: Option<f64>,
+    jitter_ns: Option<u64>,
+    rtt_ns: Option<u64>,
+}
+
+/// Remembers the byte counters of the previous stats to turn them into
+/// bitrates.
+#[derive(Default)]
+pub(crate) struct StatsTracker {
+    bytes: HashMap<(String, StreamDirection, u32), (u64, u64)>,
+}
+
+impl StatsTracker {
+    pub(crate) fn condense(
+        &mut self,
+        element: String,
+        stats: &gst::StructureRef,
+        timestamp_ns: u64,
+    ) -> WebRtcStats {
+        let entries: Vec<gst::Structure> = stats
+            .iter()
+            .filter_map(|(_, value)| value.get::<gst::Structure>().ok())
+            .collect();
+
+        let mut reports = HashMap::new();
+        for stat in entries.iter().filter(|stat| stat.name().as_str() == "remote-inbound-rtp") {
+            let Some(ssrc) = unsigned(stat, "ssrc") else { continue };
+            reports.insert(
+                ssrc as u32,
+                ReceiverReport {
+                    packets_lost: signed(stat, "packets-lost"),
+                    fraction_lost: stat.get::<f64>("fraction-lost").ok(),
+                    jitter_ns: seconds_ns(stat, "jitter"),
+                    rtt_ns: seconds_ns(stat, "round-trip-time"),
+                },
+            );
+        }
+
+        let mut streams = Vec::new();
+        for stat in &entries {
+            let (direction, bytes_field, packets_field) = match stat.name().as_str() {
+                "outbound-rtp" => (StreamDirection::Outbound, "bytes-sent", "packets-sent"),
+                "inbound-rtp" => (StreamDirection::Inbound, "bytes-received", "packets-received"),
+                _ => continue,
+            };
+            let Some(ssrc) = unsigned(stat, "ssrc").map(|ssrc| ssrc as u32) else {
+                continue;
+            };
+            let bytes = unsigned(stat, bytes_field).unwrap_or(0);
+            let packets = unsigned(stat, packets_field).unwrap_or(0);
+            let bitrate = self.bitrate(&element, direction, ssrc, bytes, timestamp_ns);
+
+            let report = match direction {
+                StreamDirection::Outbound => reports.remove(&ssrc).unwrap_or_default(),
+                StreamDirection::Inbound => {
+                    let packets_lost = signed(stat, "packets-lost");
+                    ReceiverReport {
+                        packets_lost,
+                        fraction_lost: packets_lost.map(|lost| {
+                            lost.max(0) as f64 / (packets as f64 + lost.max(0) as f64).max(1.0)
+                        }),
+                        jitter_ns: seconds_ns(stat, "jitter"),
+                        rtt_ns: None,
+                    }
+                }
+            };
+            streams.push(WebRtcStream {
+                direction,
+                ssrc,
+                kind: stat.get::<String>("kind").unwrap_or_default(),
+                bitrate,
+                packets,
+                packets_lost: report.packets_lost,
+                fraction_lost: report.fraction_lost,
+                jitter_ns: report.jitter_ns,
+                rtt_ns: report.rtt_ns,
+            });
+        }
+        streams.sort_by_key(|stream| (stream.direction.label(), stream.ssrc));
+
+        WebRtcStats {
+            timestamp_ns,
+            element,
+            streams,
+        }
+    }
+
+    fn bitrate(
+        &mut self,
+        element: &str,
+        direction: StreamDirection,
+        ssrc: u32,
+        bytes: u64,
+        timestamp_ns: u64,
+    ) -> Option<u64> {
+        let previous = self
+            .bytes
+            .insert((element.to_string(), direction, ssrc), (bytes, timestamp_ns))?;
+        let elapsed_ns = timestamp_ns.checked_sub(previous.1).filter(|&ns| ns > 0)?;
+        let sent = bytes.checked_sub(previous.0)?;
+        Some((sent as f64 * 8.0 * 1e9 / elapsed_ns as f64) as u64)
+    }
+}